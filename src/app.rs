@@ -6,20 +6,44 @@ use self::arg::Args;
 use self::arg::Command;
 
 mod arg;
+mod cdx;
+mod checksum;
 mod common;
+mod digest;
 mod dump_help;
+mod estimate;
+mod exec;
 mod export;
 mod extract;
+mod extract_archive;
 mod filter;
+mod fix;
 mod format;
 mod get;
+mod html;
 mod import;
+mod import_dir;
 mod io;
 mod list;
 mod logging;
+mod members;
+mod mime;
+mod mode;
 mod model;
 mod progress;
+mod record_id;
+mod rewrite;
+mod schema;
+#[cfg(feature = "search")]
+mod search;
 mod self_;
+mod sessions;
+mod split;
+mod summary;
+mod temp;
+mod trace;
+#[cfg(feature = "zstd")]
+mod train_dictionary;
 mod verify;
 
 pub fn run() -> ExitCode {
@@ -46,6 +70,19 @@ fn run_impl() -> anyhow::Result<ExitCode> {
     }
 
     self::logging::set_up_logging(args.log_level, args.log_file.as_deref(), args.log_json)?;
+    self::mode::set_global_mode(args.mode);
+    self::digest::set_global_digest_encoding(args.digest_encoding.into());
+    self::record_id::set_global_deterministic_record_ids(args.deterministic_ids);
+    self::io::set_global_mmap(args.mmap);
+    self::common::set_global_max_header_length(args.header_limit);
+
+    if let Some(line_folding) = args.line_folding {
+        self::common::set_global_line_folding(line_folding.into());
+    }
+
+    if let Some(path) = args.tempdir {
+        self::temp::set_global_temp_dir(path);
+    }
 
     let exit_code = match args.command {
         Command::Export(args) => {
@@ -56,6 +93,10 @@ fn run_impl() -> anyhow::Result<ExitCode> {
             self::import::import(&args)?;
             ExitCode::SUCCESS
         }
+        Command::ImportDir(args) => {
+            self::import_dir::import_dir(&args)?;
+            ExitCode::SUCCESS
+        }
         Command::List(args) => {
             self::list::list(&args)?;
             ExitCode::SUCCESS
@@ -68,7 +109,46 @@ fn run_impl() -> anyhow::Result<ExitCode> {
             self::extract::extract(&args)?;
             ExitCode::SUCCESS
         }
+        Command::Split(args) => {
+            self::split::split(&args)?;
+            ExitCode::SUCCESS
+        }
+        #[cfg(feature = "search")]
+        Command::Index(args) => {
+            self::search::index(&args)?;
+            ExitCode::SUCCESS
+        }
+        #[cfg(feature = "search")]
+        Command::Search(args) => {
+            self::search::search(&args)?;
+            ExitCode::SUCCESS
+        }
         Command::Verify(args) => self::verify::verify(&args)?,
+        Command::Digest(args) => {
+            self::digest::digest(&args)?;
+            ExitCode::SUCCESS
+        }
+        #[cfg(feature = "zstd")]
+        Command::TrainDictionary(args) => {
+            self::train_dictionary::train_dictionary(&args)?;
+            ExitCode::SUCCESS
+        }
+        Command::Members(args) => {
+            self::members::members(&args)?;
+            ExitCode::SUCCESS
+        }
+        Command::Summary(args) => {
+            self::summary::summary(&args)?;
+            ExitCode::SUCCESS
+        }
+        Command::Sessions(args) => {
+            self::sessions::sessions(&args)?;
+            ExitCode::SUCCESS
+        }
+        Command::Trace(args) => {
+            self::trace::trace(&args)?;
+            ExitCode::SUCCESS
+        }
         Command::Self_(args) => {
             self::self_::self_(&args)?;
             ExitCode::SUCCESS
@@ -1,10 +1,19 @@
-use std::path::{Path, PathBuf};
+use std::{
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use clap::{Parser, Subcommand};
 
-use crate::verify::Check;
+use crate::{
+    digest::AlgorithmName,
+    extract::PathLayout,
+    verify::{Check, ProblemKind, Severity},
+};
 
-use super::format::filename_compression_format;
+use super::format::{detect_compression_format, filename_compression_format};
 
 /// WARC archive tool
 #[derive(Parser, Debug)]
@@ -31,16 +40,84 @@ pub struct Args {
     /// Write log messages as JSON sequences instead of a console logging format.
     #[clap(long)]
     pub log_json: bool,
+
+    /// Tolerance of malformed input.
+    ///
+    /// Permissive mode accepts files that end within a record, bare LF line
+    /// endings in headers, and missing or repeated line endings between
+    /// records. It skips corrupt data to the next record and, in commands
+    /// that process records individually, continues past records that
+    /// cannot be processed. Deviations are logged as warnings.
+    #[clap(long, default_value = "strict")]
+    pub mode: super::mode::Mode,
+
+    /// Directory for temporary files.
+    ///
+    /// By default, the directory in the TMPDIR environment variable or the
+    /// system default is used, except that extracted files and split
+    /// records are written to a temporary file in the output directory.
+    /// When this option is given, all temporary files are written to the
+    /// directory and copied to the output directory if they cannot be
+    /// moved.
+    #[clap(long)]
+    pub tempdir: Option<PathBuf>,
+
+    /// Encoding of the digest values written into WARC files.
+    #[clap(long, default_value = "conventional")]
+    pub digest_encoding: DigestEncoding,
+
+    /// Generate record IDs from the record instead of randomly.
+    ///
+    /// The IDs of records created by `import-dir` and of records fixed by
+    /// `verify --fix` are version 5 UUIDs derived from the WARC-Type,
+    /// WARC-Target-URI, WARC-Date, and WARC-Block-Digest fields, so running
+    /// the command again on the same input produces the same IDs.
+    #[clap(long)]
+    pub deterministic_ids: bool,
+
+    /// Read uncompressed input files through a memory map.
+    ///
+    /// Headers and blocks are read directly from the mapped file instead of
+    /// being copied into buffers, which is faster for large uncompressed
+    /// files. Input files must not be modified while they are read.
+    #[clap(long)]
+    pub mmap: bool,
+
+    /// Maximum length of a WARC header in bytes.
+    ///
+    /// Files with longer headers are rejected as malformed.
+    #[clap(long, default_value_t = crate::warc::DEFAULT_HEADER_LENGTH_LIMIT)]
+    pub header_limit: usize,
+
+    /// Handling of WARC header field values continued on folded lines.
+    ///
+    /// By default, folded lines are unfolded in both modes. `verify`
+    /// reports folded lines only when they are preserved.
+    #[clap(long)]
+    pub line_folding: Option<LineFolding>,
 }
 
 #[derive(Debug, Subcommand)]
 pub enum Command {
     Export(ExportCommand),
     Import(ImportCommand),
+    ImportDir(ImportDirCommand),
     List(ListCommand),
     Get(GetCommand),
     Extract(ExtractCommand),
+    Split(SplitCommand),
+    #[cfg(feature = "search")]
+    Index(IndexCommand),
+    #[cfg(feature = "search")]
+    Search(SearchCommand),
     Verify(VerifyCommand),
+    Digest(DigestCommand),
+    #[cfg(feature = "zstd")]
+    TrainDictionary(TrainDictionaryCommand),
+    Members(MembersCommand),
+    Summary(SummaryCommand),
+    Sessions(SessionsCommand),
+    Trace(TraceCommand),
     Self_(SelfCommand),
     #[command(hide(true))]
     DumpHelp,
@@ -72,6 +149,39 @@ pub struct ExportCommand {
     /// Output extract messages.
     #[clap(long)]
     pub extract: bool,
+
+    /// Include the header as it appears in the file in header messages.
+    ///
+    /// Import writes the header verbatim so that the order, case, and
+    /// formatting of the fields are preserved.
+    #[clap(long)]
+    pub raw_header: bool,
+
+    /// Run a command for each record.
+    ///
+    /// The command is run by the system shell. The payload of the record is
+    /// written to the standard input of the command. If the output is
+    /// standard output, the standard output of the command is redirected to
+    /// standard error.
+    /// Header fields are provided as environment variables named with the
+    /// prefix `WARCAT_FIELD_` and the field name in uppercase with
+    /// non-alphanumeric characters replaced by `_` (such as
+    /// `WARCAT_FIELD_WARC_TARGET_URI`). `WARCAT_FILE` and `WARCAT_POSITION`
+    /// contain the path of the WARC file and the position of the record.
+    #[clap(long)]
+    pub exec: Option<String>,
+
+    /// Number of records to skip at the start of each file.
+    #[clap(long, default_value = "0")]
+    pub skip: u64,
+
+    /// Maximum number of records to output from each file.
+    #[clap(long)]
+    pub limit: Option<u64>,
+
+    /// Print the JSON Schema of the output messages instead of exporting.
+    #[clap(long)]
+    pub emit_schema: bool,
 }
 
 /// Encodes a WARC file from messages in a format of the `export` subcommand.
@@ -94,8 +204,156 @@ pub struct ImportCommand {
     pub compression: CompressionFormat,
 
     /// Level of compression for the output.
+    ///
+    /// One of "low", "balanced", "high", or a number specific to the
+    /// compression format, such as 19 for Zstandard.
+    #[clap(long, default_value = "high")]
+    pub compression_level: CompressionLevel,
+
+    /// Write a sidecar checksum file of the output WARC file.
+    ///
+    /// The checksum file is placed next to the output file with the name of
+    /// the algorithm appended (such as "example.warc.gz.sha256").
+    #[clap(long, value_delimiter = ',')]
+    pub container_checksum: Vec<ContainerChecksumAlgorithm>,
+
+    /// Path of a second output to write the WARC file to at the same time.
+    ///
+    /// This can be standard output (`-`) piped to an upload command, for
+    /// example. If writing to one of the outputs fails, writing to the
+    /// other output continues and the failure is reported at the end.
+    #[clap(long)]
+    pub tee: Option<PathBuf>,
+
+    /// Write a seek table at the end of a Zstandard compressed output.
+    ///
+    /// The table allows `get --decompressed` to find records by their
+    /// position in the decompressed data without decompressing the file.
+    #[clap(long)]
+    pub seek_table: bool,
+
+    /// Path of a Zstandard dictionary to embed in the output.
+    ///
+    /// The dictionary is written in a skippable frame at the start of the
+    /// output and is used to compress each record as specified for
+    /// ".warc.zst" files. A dictionary can be created with the
+    /// train-dictionary command. Requires Zstandard compression.
+    #[clap(long)]
+    pub dictionary: Option<PathBuf>,
+
+    /// Number of threads for compressing records with gzip.
+    ///
+    /// Each record is a separate gzip member, so records are compressed in
+    /// parallel and written in order. The default is the number of
+    /// available CPUs.
+    #[clap(long)]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Base 2 logarithm of the Zstandard window size.
+    ///
+    /// A larger window improves the compression of archives with repeated
+    /// content for cold storage. Values greater than 27 require other
+    /// decompressors to raise their memory limit (such as `zstd --long=31`)
+    /// and may not be supported by replay tools.
+    #[clap(long, value_parser = clap::value_parser!(u32).range(10..=31))]
+    pub zstd_window_log: Option<u32>,
+
+    /// Enable Zstandard long distance matching.
+    ///
+    /// The window is increased to 128 MiB unless set with
+    /// `--zstd-window-log`.
+    #[clap(long)]
+    pub zstd_long: bool,
+
+    /// Base 2 logarithm of the Zstandard match search table size.
+    #[clap(long, value_parser = clap::value_parser!(u32).range(6..=30))]
+    pub zstd_chain_log: Option<u32>,
+
+    /// Write the lengths of each gzip member in an "sl" extra field.
+    ///
+    /// The field allows indexing tools to skip records without
+    /// decompressing them.
+    #[clap(long)]
+    pub gzip_member_length: bool,
+}
+
+/// Encodes a WARC file from the files of a directory or an archive file.
+///
+/// Each file is written as a resource record with a target URI composed of
+/// the base URL and the path of the file relative to the input directory or
+/// the path of the entry in the archive file. The date of the record is the
+/// modification time of the file.
+/// The content type is guessed from the filename extension or the contents.
+#[derive(Parser, Debug)]
+pub struct ImportDirCommand {
+    /// Path to the input directory or archive file.
+    ///
+    /// Supported archive files are zip (".zip") and tar (".tar", ".tar.gz",
+    /// ".tgz") files.
+    #[clap(long, required = true)]
+    pub input: PathBuf,
+
+    /// Path of the output WARC file.
+    #[clap(long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// Base URL of the target URI of the records.
+    #[clap(long, default_value = "http://localhost/")]
+    pub base_url: String,
+
+    /// Compression format of the output WARC file.
+    #[clap(long, default_value = "auto")]
+    pub compression: CompressionFormat,
+
+    /// Level of compression for the output.
+    ///
+    /// One of "low", "balanced", "high", or a number specific to the
+    /// compression format, such as 19 for Zstandard.
     #[clap(long, default_value = "high")]
     pub compression_level: CompressionLevel,
+
+    /// Write a seek table at the end of a Zstandard compressed output.
+    ///
+    /// The table allows `get --decompressed` to find records by their
+    /// position in the decompressed data without decompressing the file.
+    #[clap(long)]
+    pub seek_table: bool,
+
+    /// Path of a Zstandard dictionary to embed in the output.
+    ///
+    /// See the option of the same name of the import command.
+    #[clap(long)]
+    pub dictionary: Option<PathBuf>,
+
+    /// Number of threads for compressing records with gzip.
+    ///
+    /// See the option of the same name of the import command.
+    #[clap(long)]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Base 2 logarithm of the Zstandard window size.
+    ///
+    /// See the option of the same name of the import command.
+    #[clap(long, value_parser = clap::value_parser!(u32).range(10..=31))]
+    pub zstd_window_log: Option<u32>,
+
+    /// Enable Zstandard long distance matching.
+    ///
+    /// See the option of the same name of the import command.
+    #[clap(long)]
+    pub zstd_long: bool,
+
+    /// Base 2 logarithm of the Zstandard match search table size.
+    ///
+    /// See the option of the same name of the import command.
+    #[clap(long, value_parser = clap::value_parser!(u32).range(6..=30))]
+    pub zstd_chain_log: Option<u32>,
+
+    /// Write the lengths of each gzip member in an "sl" extra field.
+    ///
+    /// See the option of the same name of the import command.
+    #[clap(long)]
+    pub gzip_member_length: bool,
 }
 
 /// Provides a listing of the WARC records.
@@ -110,12 +368,19 @@ pub struct ListCommand {
     pub compression: CompressionFormat,
 
     /// Path to output listings.
+    ///
+    /// The option can be repeated to write the listing in several formats
+    /// while reading the input once.
     #[clap(long, default_value = "-")]
-    pub output: PathBuf,
+    pub output: Vec<PathBuf>,
 
     /// Format of the output.
-    #[clap(long, default_value = "json-seq")]
-    pub format: ListSerializationFormat,
+    ///
+    /// If not specified, the format is detected from the filename extension
+    /// of each output (`.json-seq`, `.jsonl`, `.cbor-seq`, `.csv`, or
+    /// `.html`) and is `json-seq` otherwise.
+    #[clap(long)]
+    pub format: Option<ListFormat>,
 
     /// Fields to include in the listing.
     ///
@@ -123,12 +388,89 @@ pub struct ListCommand {
     ///
     /// The pseudo-name `:position` represents the position in the file.
     /// `:file` represents the path of the file.
+    /// `:protocols` represents the values of all WARC-Protocol fields
+    /// separated by spaces.
+    /// `:tls-protocol` represents the TLS version of WARC-Protocol.
+    /// `:timing` represents the timing extension fields, such as
+    /// WARC-Fetch-Time-Ms, as a map of field names to milliseconds.
+    /// `:extensions` represents all extension fields declared with
+    /// `--extension-fields` and the timing fields as a map.
+    ///
+    /// The following pseudo-names are computed from the record block:
+    /// `:http-status` represents the status code of a HTTP response.
+    /// `:payload-type` represents the Content-Type of the HTTP message or
+    /// the record when the record is not HTTP.
+    /// `:decoded-size` represents the length of the decompressed block.
+    /// `:compressed-size` represents the length of the record in the file.
+    /// `:cert-subject`, `:cert-issuer`, `:cert-not-before`, and
+    /// `:cert-not-after` represent the first TLS certificate of a
+    /// certificate record.
     #[clap(
         long,
         value_delimiter = ',',
         default_value = ":position,WARC-Record-ID,WARC-Type,Content-Type,WARC-Target-URI"
     )]
     pub field: Vec<String>,
+
+    /// Number of records to skip at the start of each file.
+    #[clap(long, default_value = "0")]
+    pub skip: u64,
+
+    /// Maximum number of records to output from each file.
+    #[clap(long)]
+    pub limit: Option<u64>,
+
+    /// Output only the first record of each unique value.
+    ///
+    /// The number of records sharing the value is appended as a `:count`
+    /// column. The listing is output after all input files are read.
+    #[clap(long)]
+    pub unique_by: Option<UniqueBy>,
+
+    /// Normalize the percent-encoding and host of WARC-Target-URI values.
+    ///
+    /// Escaped unreserved characters are decoded, the remaining escapes are
+    /// uppercased, and internationalized hosts are converted to Punycode.
+    /// Applies to the WARC-Target-URI field and `--unique-by target-uri`.
+    #[clap(long)]
+    pub normalize_uri: bool,
+
+    /// Database filename for storing temporary intermediate data.
+    ///
+    /// Used by `--unique-by`. If not given, a temporary file is used.
+    #[clap(long)]
+    pub database: Option<PathBuf>,
+
+    /// Predict the memory and database size needed by `--unique-by` instead
+    /// of listing.
+    ///
+    /// The first records of each input file are read and the size of the
+    /// data stored for them is scaled to the file size. The estimate is
+    /// printed to standard output as a JSON object.
+    #[clap(long)]
+    pub estimate: bool,
+
+    /// Print the JSON Schema of the rows of JSON and CBOR listings instead
+    /// of listing.
+    #[clap(long)]
+    pub emit_schema: bool,
+
+    /// Path to a file declaring extension fields for `:extensions`.
+    ///
+    /// Each line contains a field name optionally followed by a space and
+    /// its kind: `text` (default), `integer`, or `duration`. Fields of kind
+    /// `duration` are also included in `:timing`. Lines starting with `#`
+    /// are ignored.
+    #[clap(long)]
+    pub extension_fields: Option<PathBuf>,
+
+    /// Number of threads for decompressing gzip members.
+    ///
+    /// For files with record-at-time compression, the records are
+    /// decompressed in parallel and listed in order. Each record is read
+    /// into memory. The default is 1.
+    #[clap(long)]
+    pub jobs: Option<NonZeroUsize>,
 }
 
 /// Returns a single WARC record.
@@ -159,7 +501,15 @@ pub struct GetExportSubcommand {
     #[clap(long, required = true)]
     pub position: u64,
 
+    /// The position is in the decompressed data instead of the file.
+    ///
+    /// Requires an uncompressed file or a Zstandard file with a seek table.
+    #[clap(long)]
+    pub decompressed: bool,
+
     /// The ID of the record to extract.
+    ///
+    /// The ID may be given with or without the `<` and `>` deliminators.
     #[clap(long, required = true)]
     pub id: String,
 
@@ -195,7 +545,15 @@ pub struct GetExtractSubcommand {
     #[clap(long, required = true)]
     pub position: u64,
 
+    /// The position is in the decompressed data instead of the file.
+    ///
+    /// Requires an uncompressed file or a Zstandard file with a seek table.
+    #[clap(long)]
+    pub decompressed: bool,
+
     /// The ID of the record to extract.
+    ///
+    /// The ID may be given with or without the `<` and `>` deliminators.
     #[clap(long, required = true)]
     pub id: String,
 
@@ -206,11 +564,12 @@ pub struct GetExtractSubcommand {
 
 /// Extracts resources for casual viewing of the WARC contents.
 ///
-/// Files are extracted to a directory structure similar to the archived
-/// URL.
+/// By default, files are extracted to a directory structure similar to the
+/// archived URL.
 ///
 /// This operation does not automatically permit offline viewing of archived
-/// websites; no content conversion or link-rewriting is performed.
+/// websites; no content conversion or link-rewriting is performed unless
+/// requested.
 #[derive(Parser, Debug)]
 pub struct ExtractCommand {
     /// Path to the WARC file.
@@ -222,16 +581,94 @@ pub struct ExtractCommand {
     pub compression: CompressionFormat,
 
     /// Path to the output directory.
+    ///
+    /// When the output format is zip or tar, the path of the archive file.
+    /// A tar archive may be written to standard output with "-".
     #[clap(long, default_value = "./")]
     pub output: PathBuf,
 
+    /// Format of the output.
+    #[clap(long, default_value = "directory")]
+    pub output_format: ExtractOutputFormat,
+
+    /// Write a metadata file next to each extracted file.
+    ///
+    /// The metadata file is named with ".meta.json" appended and contains
+    /// the record ID, target URI, date, HTTP status code, and HTTP response
+    /// header fields.
+    #[clap(long)]
+    pub metadata: bool,
+
+    /// Do not set the modification time of extracted files to the WARC-Date.
+    ///
+    /// This option only applies when extracting to a directory.
+    #[clap(long)]
+    pub no_mtime: bool,
+
+    /// Handling of files with the same content as a previously extracted
+    /// file.
+    ///
+    /// Files are compared by their XXH3 digest and size. By default,
+    /// duplicate content is written as usual. This option only applies when
+    /// extracting to a directory.
+    #[clap(long)]
+    pub dedupe: Option<ExtractDedupe>,
+
+    /// Print the files that would be extracted without writing anything.
+    ///
+    /// Each file is printed as a JSON line with the record ID, target URI,
+    /// path, and status. The status indicates whether the file would be
+    /// written, renamed due to a conflict, or not written because the path
+    /// already exists or the content is a duplicate. This option only
+    /// applies when extracting to a directory.
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Directory structure of the extracted files.
+    ///
+    /// When the layout is not url, a "manifest.jsonl" file in the output
+    /// directory is appended with a JSON line for each extracted file
+    /// containing the record ID, target URI, and path.
+    #[clap(long, default_value = "url")]
+    pub layout: ExtractLayout,
+
+    /// Extract only records containing TLS certificates.
+    ///
+    /// Records are recognized by a certificate Content-Type such as
+    /// "application/pkix-cert" or by a ".pem", ".crt", ".cer", or ".der"
+    /// filename extension in the WARC-Target-URI. Use the `:cert-subject`
+    /// and related pseudo-fields of the list command to view the
+    /// certificates.
+    #[clap(long)]
+    pub certificates: bool,
+
+    /// Also extract the given types of records.
+    ///
+    /// Files of these records have a suffix such as "⬧request" appended to
+    /// the name. Requests without a body are skipped.
+    #[clap(long, value_delimiter = ',')]
+    pub extra_records: Vec<ExtractRecordType>,
+
+    /// Do not decode the Content-Encoding of HTTP messages.
+    ///
+    /// Files are written with the bytes as sent by the server, such as
+    /// gzip compressed data. The Transfer-Encoding is still decoded.
+    #[clap(long)]
+    pub no_content_decoding: bool,
+
     /// Whether to ignore errors.
+    ///
+    /// This is enabled by the permissive mode.
     #[clap(long)]
     pub continue_on_error: bool,
 
     /// Select only records with a field.
     ///
     /// Rule format is "NAME" or "NAME:VALUE".
+    ///
+    /// Prefix the value with `~` ("NAME:~VALUE") to compare
+    /// case-insensitively and ignore surrounding whitespace. A value that
+    /// starts with `~` can be matched exactly with a pattern rule.
     #[clap(long)]
     pub include: Vec<String>,
 
@@ -244,6 +681,10 @@ pub struct ExtractCommand {
     /// Do not select records with a field.
     ///
     /// Rule format is "NAME" or "NAME:VALUE".
+    ///
+    /// Prefix the value with `~` ("NAME:~VALUE") to compare
+    /// case-insensitively and ignore surrounding whitespace. A value that
+    /// starts with `~` can be matched exactly with a pattern rule.
     #[clap(long)]
     pub exclude: Vec<String>,
 
@@ -252,11 +693,57 @@ pub struct ExtractCommand {
     /// Rule format is "NAME:VALUEPATTERN".
     #[clap(long)]
     pub exclude_pattern: Vec<String>,
+
+    /// Select only content with a media type.
+    ///
+    /// Rule format is "TYPE/SUBTYPE" or "TYPE/*" (such as "image/*").
+    ///
+    /// The media type is the declared Content-Type of the content. If the
+    /// Content-Type is missing or generic, the media type is detected from
+    /// the contents.
+    #[clap(long)]
+    pub include_mime: Vec<String>,
+
+    /// Do not select content with a media type.
+    ///
+    /// Rule format is "TYPE/SUBTYPE" or "TYPE/*" (such as "image/*").
+    #[clap(long)]
+    pub exclude_mime: Vec<String>,
+
+    /// Append a filename extension for the media type when the URL path
+    /// does not have an extension.
+    #[clap(long)]
+    pub fix_extension: bool,
+
+    /// Rewrite links in HTML and CSS documents to relative paths of the
+    /// extracted files.
+    ///
+    /// Links are rewritten to the path where the linked URL would be
+    /// extracted, so links to resources that are not in the WARC files or
+    /// that were renamed due to conflicts will be broken. This is intended
+    /// for browsing simple sites offline.
+    #[clap(long)]
+    pub rewrite_links: bool,
+
+    /// Run a command for each record.
+    ///
+    /// The command is run by the system shell. The extracted resource is
+    /// written to the standard input of the command instead of a file.
+    /// Header fields are provided as environment variables named with the
+    /// prefix `WARCAT_FIELD_` and the field name in uppercase with
+    /// non-alphanumeric characters replaced by `_` (such as
+    /// `WARCAT_FIELD_WARC_TARGET_URI`). `WARCAT_FILE` and `WARCAT_POSITION`
+    /// contain the path of the WARC file and the position of the record.
+    #[clap(long)]
+    pub exec: Option<String>,
 }
 
-/// Perform specification and integrity checks on WARC files.
+/// Writes each record to its own WARC file.
+///
+/// Each output file contains a single record compressed with gzip and is
+/// named by the record ID or the block digest of the record.
 #[derive(Parser, Debug)]
-pub struct VerifyCommand {
+pub struct SplitCommand {
     /// Path to the WARC file.
     #[clap(long, default_value = "-")]
     pub input: Vec<PathBuf>,
@@ -265,59 +752,532 @@ pub struct VerifyCommand {
     #[clap(long, default_value = "auto")]
     pub compression: CompressionFormat,
 
-    /// Path to output problems.
-    #[clap(long, default_value = "-")]
+    /// Path to the output directory.
+    #[clap(long, default_value = "./")]
     pub output: PathBuf,
 
-    /// Format of the output.
-    #[clap(long, default_value = "json-seq")]
-    pub format: ListSerializationFormat,
+    /// Value used to name the output files.
+    #[clap(long, default_value = "record-id")]
+    pub name_by: SplitName,
 
-    /// Do not perform check.
-    #[clap(long, value_delimiter = ',')]
-    pub exclude_check: Vec<VerifyCheck>,
+    /// Level of compression for the output.
+    ///
+    /// One of "low", "balanced", "high", or a number specific to the
+    /// compression format, such as 19 for Zstandard.
+    #[clap(long, default_value = "high")]
+    pub compression_level: CompressionLevel,
 
-    /// Database filename for storing temporary intermediate data.
+    /// Select only records with a field.
+    ///
+    /// Rule format is "NAME" or "NAME:VALUE".
+    ///
+    /// Prefix the value with `~` ("NAME:~VALUE") to compare
+    /// case-insensitively and ignore surrounding whitespace. A value that
+    /// starts with `~` can be matched exactly with a pattern rule.
     #[clap(long)]
-    pub database: Option<PathBuf>,
-}
+    pub include: Vec<String>,
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
-pub enum VerifyCheck {
-    MandatoryFields,
-    KnownRecordType,
-    ContentType,
-    ConcurrentTo,
-    BlockDigest,
-    PayloadDigest,
-    IpAddress,
-    RefersTo,
-    RefersToTargetUri,
-    RefersToDate,
-    TargetUri,
-    Truncated,
-    WarcinfoId,
-    Filename,
-    Profile,
-    // IdentifiedPayloadType,
-    Segment,
-    RecordAtTimeCompression,
+    /// Select only records matching a regular expression.
+    ///
+    /// Rule format is "NAME:VALUEPATTERN".
+    #[clap(long)]
+    pub include_pattern: Vec<String>,
+
+    /// Do not select records with a field.
+    ///
+    /// Rule format is "NAME" or "NAME:VALUE".
+    ///
+    /// Prefix the value with `~` ("NAME:~VALUE") to compare
+    /// case-insensitively and ignore surrounding whitespace. A value that
+    /// starts with `~` can be matched exactly with a pattern rule.
+    #[clap(long)]
+    pub exclude: Vec<String>,
+
+    /// Do not select records matching a regular expression.
+    ///
+    /// Rule format is "NAME:VALUEPATTERN".
+    #[clap(long)]
+    pub exclude_pattern: Vec<String>,
+
+    /// Do not write resource and metadata records without content.
+    ///
+    /// Records with an empty block or a block of only line endings, spaces,
+    /// or NUL bytes are skipped.
+    #[clap(long)]
+    pub drop_empty: bool,
 }
 
-impl From<VerifyCheck> for Check {
-    fn from(value: VerifyCheck) -> Self {
-        match value {
-            VerifyCheck::MandatoryFields => Self::MandatoryFields,
-            VerifyCheck::KnownRecordType => Self::KnownRecordType,
-            VerifyCheck::ContentType => Self::ContentType,
-            VerifyCheck::ConcurrentTo => Self::ConcurrentTo,
-            VerifyCheck::BlockDigest => Self::BlockDigest,
-            VerifyCheck::PayloadDigest => Self::PayloadDigest,
-            VerifyCheck::IpAddress => Self::IpAddress,
-            VerifyCheck::RefersTo => Self::RefersTo,
-            VerifyCheck::RefersToTargetUri => Self::RefersToTargetUri,
-            VerifyCheck::RefersToDate => Self::RefersToDate,
-            VerifyCheck::TargetUri => Self::TargetUri,
+/// Build a full-text search index of the text content of WARC records.
+///
+/// Text and HTML payloads are indexed by the file and the position of the
+/// record. Indexing a file again replaces its previous entries.
+#[cfg(feature = "search")]
+#[derive(Parser, Debug)]
+pub struct IndexCommand {
+    /// Path to the WARC file.
+    #[clap(long, default_value = "-")]
+    pub input: Vec<PathBuf>,
+
+    /// Compression format of the input WARC file.
+    #[clap(long, default_value = "auto")]
+    pub compression: CompressionFormat,
+
+    /// Path to the directory of the index.
+    ///
+    /// The directory is created if it does not exist.
+    #[clap(long)]
+    pub index: PathBuf,
+
+    /// Normalize the percent-encoding and host of WARC-Target-URI values.
+    ///
+    /// Escaped unreserved characters are decoded, the remaining escapes are
+    /// uppercased, and internationalized hosts are converted to Punycode.
+    #[clap(long)]
+    pub normalize_uri: bool,
+
+    /// Number of threads for decompressing gzip members.
+    ///
+    /// See the option of the same name of the list command.
+    #[clap(long)]
+    pub jobs: Option<NonZeroUsize>,
+}
+
+/// Search an index created by the index command.
+#[cfg(feature = "search")]
+#[derive(Parser, Debug)]
+pub struct SearchCommand {
+    /// Path to the directory of the index.
+    #[clap(long)]
+    pub index: PathBuf,
+
+    /// Search query.
+    ///
+    /// Terms are matched against the title and the text of the content.
+    /// The query syntax supports phrases ("...") and the `AND`, `OR`, `+`,
+    /// and `-` operators.
+    #[clap(long)]
+    pub query: String,
+
+    /// Maximum number of results.
+    #[clap(long, default_value = "10")]
+    pub limit: usize,
+
+    /// Path to output results.
+    #[clap(long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// Format of the output.
+    #[clap(long, default_value = "json-seq")]
+    pub format: ListFormat,
+}
+
+/// Perform specification and integrity checks on WARC files.
+#[derive(Parser, Debug)]
+pub struct VerifyCommand {
+    /// Path to the WARC file.
+    #[clap(long, default_value = "-")]
+    pub input: Vec<PathBuf>,
+
+    /// Compression format of the input WARC file.
+    #[clap(long, default_value = "auto")]
+    pub compression: CompressionFormat,
+
+    /// Path to output problems.
+    #[clap(long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// Format of the output.
+    #[clap(long, default_value = "json-seq")]
+    pub format: ListSerializationFormat,
+
+    /// Do not perform check.
+    #[clap(long, value_delimiter = ',')]
+    pub exclude_check: Vec<VerifyCheck>,
+
+    /// Output only problems of the given kinds.
+    ///
+    /// Problems that are not output do not affect the exit status.
+    #[clap(long, value_delimiter = ',')]
+    pub only_kind: Vec<VerifyProblemKind>,
+
+    /// Do not output problems of the given kinds.
+    ///
+    /// Problems that are not output do not affect the exit status.
+    #[clap(long, value_delimiter = ',')]
+    pub ignore_kind: Vec<VerifyProblemKind>,
+
+    /// Output only the given fields of each problem.
+    ///
+    /// When specified, each problem is output as a list of values in the
+    /// given order instead of an object.
+    #[clap(long, value_delimiter = ',')]
+    pub field: Vec<VerifyField>,
+
+    /// Minimum severity of problems that cause a failure exit status.
+    #[clap(long, default_value = "info")]
+    pub fail_on: VerifySeverity,
+
+    /// Output a summary message after the problems of each file.
+    ///
+    /// The message is an object with a `summary` key containing the file,
+    /// the number of records, the decoded size of the record blocks, the
+    /// number of output problems by kind, the number of problems that fail
+    /// the verification according to `--fail-on`, and the duration in
+    /// seconds.
+    #[clap(long)]
+    pub summary: bool,
+
+    /// Database filename for storing temporary intermediate data.
+    ///
+    /// The progress of each file is also saved so that an interrupted
+    /// verification can be continued with `--resume`.
+    #[clap(long)]
+    pub database: Option<PathBuf>,
+
+    /// Continue an interrupted verification using the `--database` file.
+    ///
+    /// Files that were verified completely are skipped and other files are
+    /// verified from the last saved position. Problems are appended to the
+    /// output file. The same input paths must be given as before.
+    #[clap(long)]
+    pub resume: bool,
+
+    /// Predict the memory and database size needed instead of verifying.
+    ///
+    /// The first records of each input file are read and the size of the
+    /// data stored for them is scaled to the file size. The data is kept in
+    /// memory unless `--database` is given. The estimate is printed to
+    /// standard output as a JSON object.
+    #[clap(long)]
+    pub estimate: bool,
+
+    /// Print the JSON Schema of the output problems and summaries instead
+    /// of verifying.
+    #[clap(long)]
+    pub emit_schema: bool,
+
+    /// Path of a CDX or CDXJ index of the WARC files to check against.
+    ///
+    /// Each entry must point at the start of a record with the same length
+    /// and payload digest. Response, resource, and revisit records missing
+    /// from the index and entries of the input files that do not point at
+    /// a record are reported. Entries are matched to the input files by
+    /// filename. The index can be gzip compressed.
+    ///
+    /// Record positions are exact only in files with record-at-time
+    /// compression, so uncompressed WARC files cannot be checked.
+    #[clap(long)]
+    pub cdx: Option<PathBuf>,
+
+    /// Check the WARC files against sidecar checksum files first.
+    ///
+    /// Files that do not match their checksum are not checked further.
+    #[clap(long)]
+    pub container_checksums: bool,
+
+    /// Number of threads for checking block and payload digests.
+    ///
+    /// If given, gzip members are also decompressed in parallel as with
+    /// the option of the same name of the list command. The default is the
+    /// number of available CPUs for checking digests only.
+    #[clap(long)]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Check the block and payload digests of only a sample of the records.
+    ///
+    /// The value is a percentage of the records, such as `5%`, or a number
+    /// of records per file. A percentage selects records by a hash of their
+    /// WARC-Record-ID so that the same records are selected with the same
+    /// `--sample-seed`. A number selects records at even intervals of the
+    /// file size, or the first records if the size is unknown. Other checks
+    /// are performed on every record. Segmented records are always checked.
+    #[clap(long)]
+    pub sample: Option<SampleSize>,
+
+    /// Seed for selecting records by a `--sample` percentage.
+    #[clap(long, default_value = "0")]
+    pub sample_seed: u64,
+
+    /// Range of plausible WARC-Date values, such as
+    /// `2024-01-01..2024-07-01`.
+    ///
+    /// Either end can be omitted. Each end is a date or an RFC 3339 date
+    /// and time; dates are the start of the day in UTC. Dates before 1993
+    /// and dates in the future are always reported.
+    #[clap(long)]
+    pub date_range: Option<DateRange>,
+
+    /// Report records with a WARC-Date earlier than a previous record in
+    /// the same file by more than the given number of hours.
+    #[clap(long)]
+    pub date_order_tolerance: Option<u32>,
+
+    /// Report header field values longer than the given number of bytes.
+    #[clap(long, default_value_t = crate::verify::DEFAULT_MAX_FIELD_LENGTH)]
+    pub max_field_length: u64,
+
+    /// Report headers longer than the given number of bytes.
+    ///
+    /// Headers longer than the global `--header-limit` option cannot
+    /// be read.
+    #[clap(long, default_value_t = crate::verify::DEFAULT_MAX_HEADER_LENGTH)]
+    pub max_header_length: u64,
+
+    /// Path of a WARC file to write the records with problems corrected.
+    ///
+    /// Wrong block and payload digests, wrong Content-Length values, and
+    /// missing WARC-Record-ID fields are corrected. Other records are
+    /// written unchanged. A record cut off by the end of the file is
+    /// written with the WARC-Truncated field. Digests are checked on a
+    /// single thread when this option is used.
+    #[clap(long)]
+    pub fix: Option<PathBuf>,
+
+    /// Compression format of the fixed WARC file.
+    #[clap(long, default_value = "auto")]
+    pub fix_compression: CompressionFormat,
+
+    /// Do not write resource and metadata records without content to the
+    /// fixed WARC file.
+    ///
+    /// Records with an empty block or a block of only line endings, spaces,
+    /// or NUL bytes are skipped.
+    #[clap(long)]
+    pub fix_drop_empty: bool,
+}
+
+/// List the compression members of a gzip compressed WARC file.
+///
+/// Each member is listed with the number of records that start within it
+/// and whether it contains exactly one whole record as required for
+/// record-at-time compression. This is intended for diagnosing files that
+/// are not correctly compressed.
+#[derive(Parser, Debug)]
+pub struct MembersCommand {
+    /// Path to the WARC file.
+    #[clap(long, default_value = "-")]
+    pub input: Vec<PathBuf>,
+
+    /// Compression format of the input WARC file.
+    #[clap(long, default_value = "auto")]
+    pub compression: CompressionFormat,
+
+    /// Path to output the listing.
+    #[clap(long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// Format of the output.
+    #[clap(long, default_value = "json-seq")]
+    pub format: ListSerializationFormat,
+}
+
+/// Compute the block and payload digests of the records.
+///
+/// Each record is output as a row of the WARC-Record-ID, the
+/// WARC-Target-URI, the block digest of each algorithm, and the payload
+/// digest of each algorithm. Payload digests are empty for records without
+/// a payload. Digests are computed from the data; the digest fields of the
+/// records are not checked. This is intended for building fixity manifests.
+#[derive(Parser, Debug)]
+pub struct DigestCommand {
+    /// Path to the WARC file.
+    #[clap(long, default_value = "-")]
+    pub input: Vec<PathBuf>,
+
+    /// Compression format of the input WARC file.
+    #[clap(long, default_value = "auto")]
+    pub compression: CompressionFormat,
+
+    /// Hashing algorithms, such as `sha256,blake3`.
+    #[clap(long, value_delimiter = ',', default_value = "sha256")]
+    pub algorithm: Vec<AlgorithmName>,
+
+    /// Path to output the digests.
+    #[clap(long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// Format of the output.
+    #[clap(long, default_value = "json-seq")]
+    pub format: ListSerializationFormat,
+}
+
+/// Create a Zstandard dictionary from the records of WARC files.
+///
+/// The start of each record, including the header, is used as a sample for
+/// training the dictionary. The dictionary can be embedded in a ".warc.zst"
+/// file with the `--dictionary` option of import and import-dir.
+#[cfg(feature = "zstd")]
+#[derive(Parser, Debug)]
+pub struct TrainDictionaryCommand {
+    /// Path to the WARC file.
+    #[clap(long, default_value = "-")]
+    pub input: Vec<PathBuf>,
+
+    /// Compression format of the input WARC file.
+    #[clap(long, default_value = "auto")]
+    pub compression: CompressionFormat,
+
+    /// Path of the output dictionary.
+    #[clap(long, required = true)]
+    pub output: PathBuf,
+
+    /// Maximum size of the dictionary in bytes.
+    #[clap(long, default_value = "112640")]
+    pub max_size: usize,
+
+    /// Maximum number of bytes of each record used as a sample.
+    #[clap(long, default_value = "131072")]
+    pub sample_size: usize,
+
+    /// Maximum number of records to sample from each file.
+    #[clap(long)]
+    pub limit: Option<u64>,
+}
+
+/// List the crawl sessions of the records in WARC files.
+///
+/// Records are grouped by their WARC-Warcinfo-ID, or by the last warcinfo
+/// record in the same file when the field is absent. Each session is
+/// output with the software and isPartOf values of its warcinfo record,
+/// the files containing its records, the number of records of each type,
+/// the range of record dates, the total Content-Length, and the total
+/// compressed size. This is intended for attributing the records of merged
+/// archives to the crawls that produced them.
+#[derive(Parser, Debug)]
+pub struct SessionsCommand {
+    /// Path to the WARC file.
+    #[clap(long, default_value = "-")]
+    pub input: Vec<PathBuf>,
+
+    /// Compression format of the input WARC file.
+    #[clap(long, default_value = "auto")]
+    pub compression: CompressionFormat,
+
+    /// Path to output the listing.
+    #[clap(long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// Format of the output.
+    #[clap(long, default_value = "json-seq")]
+    pub format: ListSerializationFormat,
+}
+
+/// Output the records connected to a record by references.
+///
+/// The references of the WARC-Refers-To, WARC-Concurrent-To,
+/// WARC-Warcinfo-ID, and WARC-Segment-Origin-ID fields are followed in both
+/// directions from the given record, except that the records referring to a
+/// warcinfo record are not followed from it. Each connected record is
+/// output as a `record` message with its distance from the given record,
+/// and its type, file, and position if it is in the input. Each reference
+/// is output as a `reference` message.
+#[derive(Parser, Debug)]
+pub struct TraceCommand {
+    /// Path to the WARC file.
+    ///
+    /// The files are read twice, so standard input is not supported.
+    #[clap(long, required = true)]
+    pub input: Vec<PathBuf>,
+
+    /// Compression format of the input WARC file.
+    #[clap(long, default_value = "auto")]
+    pub compression: CompressionFormat,
+
+    /// WARC-Record-ID of the record to start from, such as
+    /// `<urn:uuid:...>`.
+    #[clap(long)]
+    pub id: String,
+
+    /// Path to output the records and references.
+    #[clap(long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// Format of the output.
+    #[clap(long, default_value = "json-seq")]
+    pub format: ListSerializationFormat,
+
+    /// Database filename for storing temporary intermediate data.
+    #[clap(long)]
+    pub database: Option<PathBuf>,
+}
+
+/// Print an overview of a WARC file.
+///
+/// The overview includes the software that created the file, the range of
+/// record dates, the number of records of each type, the schemes of the
+/// captured URIs, sizes, compression, and the number of problems found by
+/// verifying the first records.
+///
+/// For files with `dns:` records, such as those written by Heritrix, the
+/// captured hosts without a DNS record and the DNS records of hosts that
+/// were not captured are also shown.
+#[derive(Parser, Debug)]
+pub struct SummaryCommand {
+    /// Path to the WARC file.
+    #[clap(long, default_value = "-")]
+    pub input: Vec<PathBuf>,
+
+    /// Compression format of the input WARC file.
+    #[clap(long, default_value = "auto")]
+    pub compression: CompressionFormat,
+
+    /// Path to output the overview.
+    #[clap(long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// Number of records at the start of each file to verify.
+    ///
+    /// Checks that involve other records, such as missing referenced
+    /// records, are not performed.
+    #[clap(long, default_value = "1000")]
+    pub sample: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum VerifyCheck {
+    MandatoryFields,
+    KnownRecordType,
+    ContentType,
+    ConcurrentTo,
+    BlockDigest,
+    PayloadDigest,
+    IpAddress,
+    RefersTo,
+    RefersToTargetUri,
+    RefersToDate,
+    TargetUri,
+    Truncated,
+    WarcinfoId,
+    Filename,
+    Profile,
+    // IdentifiedPayloadType,
+    Segment,
+    RecordAtTimeCompression,
+    Protocol,
+    ContentLengthActual,
+    DuplicateRecordId,
+    Revisit,
+    DuplicateField,
+    Date,
+    HeaderLength,
+    FieldEncoding,
+    LineFolding,
+}
+
+impl From<VerifyCheck> for Check {
+    fn from(value: VerifyCheck) -> Self {
+        match value {
+            VerifyCheck::MandatoryFields => Self::MandatoryFields,
+            VerifyCheck::KnownRecordType => Self::KnownRecordType,
+            VerifyCheck::ContentType => Self::ContentType,
+            VerifyCheck::ConcurrentTo => Self::ConcurrentTo,
+            VerifyCheck::BlockDigest => Self::BlockDigest,
+            VerifyCheck::PayloadDigest => Self::PayloadDigest,
+            VerifyCheck::IpAddress => Self::IpAddress,
+            VerifyCheck::RefersTo => Self::RefersTo,
+            VerifyCheck::RefersToTargetUri => Self::RefersToTargetUri,
+            VerifyCheck::RefersToDate => Self::RefersToDate,
+            VerifyCheck::TargetUri => Self::TargetUri,
             VerifyCheck::WarcinfoId => Self::WarcinfoId,
             VerifyCheck::Truncated => Self::Truncated,
             VerifyCheck::Filename => Self::Filename,
@@ -325,13 +1285,250 @@ impl From<VerifyCheck> for Check {
             // VerifyCheck::IdentifiedPayloadType => Self::IdentifiedPayloadType,
             VerifyCheck::Segment => Self::Segment,
             VerifyCheck::RecordAtTimeCompression => Self::RecordAtTimeCompression,
+            VerifyCheck::Protocol => Self::Protocol,
+            VerifyCheck::ContentLengthActual => Self::ContentLengthActual,
+            VerifyCheck::DuplicateRecordId => Self::DuplicateRecordId,
+            VerifyCheck::Revisit => Self::Revisit,
+            VerifyCheck::DuplicateField => Self::DuplicateField,
+            VerifyCheck::Date => Self::Date,
+            VerifyCheck::HeaderLength => Self::HeaderLength,
+            VerifyCheck::FieldEncoding => Self::FieldEncoding,
+            VerifyCheck::LineFolding => Self::LineFolding,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum VerifyProblemKind {
+    UnknownRecordType,
+    RequiredFieldMissing,
+    ProhibitedField,
+    ReferencedRecordMissing,
+    UnknownDigest,
+    BadSpecUri,
+    ParseInt,
+    InvalidDate,
+    InvalidUrl,
+    InvalidIpAddress,
+    InvalidMediaType,
+    InvalidTruncatedReason,
+    InvalidSegment,
+    MissingSegment,
+    MismatchedSegmentLength,
+    DigestMismatch,
+    PayloadDigestMismatch,
+    ParsePayload,
+    NotRecordAtTimeCompression,
+    ContainerChecksumMissing,
+    ContainerChecksumMismatch,
+    InvalidRecordId,
+    TruncatedFile,
+    UnknownProtocol,
+    InvalidCipherSuite,
+    ContentLengthMismatch,
+    DuplicateRecordId,
+    UnknownProfile,
+    RevisitDigestMismatch,
+    DuplicateField,
+    CdxRecordMissing,
+    CdxEntryNotFound,
+    CdxLengthMismatch,
+    CdxDigestMismatch,
+    DateOutOfRange,
+    DateOutOfOrder,
+    FieldTooLong,
+    HeaderTooLong,
+    NonUtf8Field,
+    FoldedField,
+}
+
+impl From<&ProblemKind> for VerifyProblemKind {
+    fn from(value: &ProblemKind) -> Self {
+        match value {
+            ProblemKind::UnknownRecordType(_) => Self::UnknownRecordType,
+            ProblemKind::RequiredFieldMissing(_) => Self::RequiredFieldMissing,
+            ProblemKind::ProhibitedField(_) => Self::ProhibitedField,
+            ProblemKind::ReferencedRecordMissing(_) => Self::ReferencedRecordMissing,
+            ProblemKind::UnknownDigest(_) => Self::UnknownDigest,
+            ProblemKind::BadSpecUri(_) => Self::BadSpecUri,
+            ProblemKind::ParseInt(_) => Self::ParseInt,
+            ProblemKind::InvalidDate(_) => Self::InvalidDate,
+            ProblemKind::InvalidUrl(_) => Self::InvalidUrl,
+            ProblemKind::InvalidIpAddress(_) => Self::InvalidIpAddress,
+            ProblemKind::InvalidMediaType(_) => Self::InvalidMediaType,
+            ProblemKind::InvalidTruncatedReason => Self::InvalidTruncatedReason,
+            ProblemKind::InvalidSegment => Self::InvalidSegment,
+            ProblemKind::MissingSegment(_) => Self::MissingSegment,
+            ProblemKind::MismatchedSegmentLength { .. } => Self::MismatchedSegmentLength,
+            ProblemKind::DigestMismatch { .. } => Self::DigestMismatch,
+            ProblemKind::PayloadDigestMismatch { .. } => Self::PayloadDigestMismatch,
+            ProblemKind::ParsePayload(_) => Self::ParsePayload,
+            ProblemKind::NotRecordAtTimeCompression => Self::NotRecordAtTimeCompression,
+            ProblemKind::ContainerChecksumMissing => Self::ContainerChecksumMissing,
+            ProblemKind::ContainerChecksumMismatch { .. } => Self::ContainerChecksumMismatch,
+            ProblemKind::InvalidRecordId(_) => Self::InvalidRecordId,
+            ProblemKind::TruncatedFile => Self::TruncatedFile,
+            ProblemKind::UnknownProtocol(_) => Self::UnknownProtocol,
+            ProblemKind::InvalidCipherSuite(_) => Self::InvalidCipherSuite,
+            ProblemKind::ContentLengthMismatch { .. } => Self::ContentLengthMismatch,
+            ProblemKind::DuplicateRecordId => Self::DuplicateRecordId,
+            ProblemKind::UnknownProfile(_) => Self::UnknownProfile,
+            ProblemKind::RevisitDigestMismatch(_) => Self::RevisitDigestMismatch,
+            ProblemKind::DuplicateField(_) => Self::DuplicateField,
+            ProblemKind::CdxRecordMissing => Self::CdxRecordMissing,
+            ProblemKind::CdxEntryNotFound { .. } => Self::CdxEntryNotFound,
+            ProblemKind::CdxLengthMismatch { .. } => Self::CdxLengthMismatch,
+            ProblemKind::CdxDigestMismatch { .. } => Self::CdxDigestMismatch,
+            ProblemKind::DateOutOfRange(_) => Self::DateOutOfRange,
+            ProblemKind::DateOutOfOrder(_) => Self::DateOutOfOrder,
+            ProblemKind::FieldTooLong { .. } => Self::FieldTooLong,
+            ProblemKind::HeaderTooLong { .. } => Self::HeaderTooLong,
+            ProblemKind::NonUtf8Field(_) => Self::NonUtf8Field,
+            ProblemKind::FoldedField(_) => Self::FoldedField,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum VerifyField {
+    /// Path of the WARC file.
+    File,
+    /// ID of the record containing the problem.
+    RecordId,
+    /// Name of the kind of problem.
+    Kind,
+    /// Details of the problem specific to the kind.
+    Detail,
+    /// Severity of the problem.
+    Severity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum VerifySeverity {
+    /// Problems that do not affect validity.
+    Info,
+    /// Questionable values.
+    Warning,
+    /// Invalid or damaged data.
+    Error,
+}
+
+impl From<VerifySeverity> for Severity {
+    fn from(value: VerifySeverity) -> Self {
+        match value {
+            VerifySeverity::Info => Self::Info,
+            VerifySeverity::Warning => Self::Warning,
+            VerifySeverity::Error => Self::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleSize {
+    /// Percentage of the records.
+    Percent(f64),
+    /// Number of records per file.
+    Count(u64),
+}
+
+impl FromStr for SampleSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(value) = s.strip_suffix('%') {
+            match value.parse::<f64>() {
+                Ok(value) if (0.0..=100.0).contains(&value) => Ok(Self::Percent(value)),
+                _ => Err("percentage must be from 0 to 100".to_string()),
+            }
+        } else {
+            s.parse::<u64>()
+                .map(Self::Count)
+                .map_err(|_| "expected a percentage or a number of records".to_string())
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DigestEncoding {
+    /// Base32 for SHA-1 and hexadecimal for other algorithms.
+    Conventional,
+    /// Base32.
+    Base32,
+    /// Lowercase hexadecimal.
+    Hex,
+    /// Standard Base64.
+    Base64,
+}
+
+impl From<DigestEncoding> for crate::digest::DigestEncoding {
+    fn from(value: DigestEncoding) -> Self {
+        match value {
+            DigestEncoding::Conventional => Self::Conventional,
+            DigestEncoding::Base32 => Self::Base32,
+            DigestEncoding::Hex => Self::Hex,
+            DigestEncoding::Base64 => Self::Base64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LineFolding {
+    /// Join the folded lines with a space.
+    Unfold,
+    /// Reject headers with folded lines as malformed.
+    Reject,
+    /// Keep the folded lines in the values and in written files.
+    Preserve,
+}
+
+impl From<LineFolding> for crate::header::LineFolding {
+    fn from(value: LineFolding) -> Self {
+        match value {
+            LineFolding::Unfold => Self::Unfold,
+            LineFolding::Reject => Self::Reject,
+            LineFolding::Preserve => Self::Preserve,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl FromStr for DateRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once("..")
+            .ok_or_else(|| "expected a range in the form START..END".to_string())?;
+        let parse = |value: &str| -> Result<Option<DateTime<Utc>>, String> {
+            if value.is_empty() {
+                return Ok(None);
+            }
+
+            if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                return Ok(Some(date.and_time(NaiveTime::MIN).and_utc()));
+            }
+
+            DateTime::parse_from_rfc3339(value)
+                .map(|date| Some(date.to_utc()))
+                .map_err(|_| format!("invalid date {:?}", value))
+        };
+
+        Ok(Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 pub enum CompressionFormat {
-    /// Automatically detect the format by the filename extension.
+    /// Automatically detect the format by the contents of an input file or
+    /// by the filename extension.
     Auto,
     /// No compression.
     None,
@@ -340,10 +1537,30 @@ pub enum CompressionFormat {
     /// Zstandard format (such as ".warc.zst" files).
     #[cfg(feature = "zstd")]
     Zstandard,
+    /// Bzip2 format (such as legacy ".warc.bz2" files).
+    #[cfg(feature = "bzip2")]
+    Bzip2,
 }
 
 impl CompressionFormat {
+    /// Returns the format of the input file.
+    ///
+    /// The format is detected from the start of the file, or from the
+    /// filename if the contents are not recognized.
     pub fn try_into_native(&self, path: &Path) -> anyhow::Result<crate::compress::Format> {
+        if *self == Self::Auto {
+            if let Some(format) = detect_compression_format(path)? {
+                return Ok(format);
+            }
+        }
+
+        self.try_into_native_output(path)
+    }
+
+    /// Returns the format of the output file.
+    ///
+    /// The format is detected from the filename only.
+    pub fn try_into_native_output(&self, path: &Path) -> anyhow::Result<crate::compress::Format> {
         if *self == Self::Auto {
             Ok(filename_compression_format(path)
                 .ok_or_else(|| anyhow::anyhow!("unsupported compression or file format"))?)
@@ -365,11 +1582,13 @@ impl TryFrom<CompressionFormat> for crate::compress::Format {
             CompressionFormat::Gzip => Ok(Self::Gzip),
             #[cfg(feature = "zstd")]
             CompressionFormat::Zstandard => Ok(Self::Zstandard),
+            #[cfg(feature = "bzip2")]
+            CompressionFormat::Bzip2 => Ok(Self::BZIP2),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressionLevel {
     /// A balance between compression ratio and resource consumption.
     Balanced,
@@ -377,6 +1596,26 @@ pub enum CompressionLevel {
     High,
     /// Fast and low resource usage, but lower compression ratio.
     Low,
+    /// Level specific to the compression format.
+    Precise(i32),
+}
+
+impl FromStr for CompressionLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "balanced" => Ok(Self::Balanced),
+            "high" => Ok(Self::High),
+            "low" => Ok(Self::Low),
+            _ => s.parse().map(Self::Precise).map_err(|_| {
+                format!(
+                    "invalid compression level {:?}: expected low, balanced, high, or a number",
+                    s
+                )
+            }),
+        }
+    }
 }
 
 impl From<CompressionLevel> for crate::compress::Level {
@@ -385,6 +1624,24 @@ impl From<CompressionLevel> for crate::compress::Level {
             CompressionLevel::Balanced => Self::Balanced,
             CompressionLevel::High => Self::High,
             CompressionLevel::Low => Self::Low,
+            CompressionLevel::Precise(value) => Self::Precise(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ContainerChecksumAlgorithm {
+    /// SHA-256.
+    Sha256,
+    /// XXH3 (64-bit).
+    Xxh3,
+}
+
+impl From<ContainerChecksumAlgorithm> for crate::digest::AlgorithmName {
+    fn from(value: ContainerChecksumAlgorithm) -> Self {
+        match value {
+            ContainerChecksumAlgorithm::Sha256 => Self::Sha256,
+            ContainerChecksumAlgorithm::Xxh3 => Self::Xxh3,
         }
     }
 }
@@ -440,6 +1697,137 @@ impl From<ListSerializationFormat> for crate::dataseq::SeqFormat {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListFormat {
+    /// JSON sequences (RFC 7464).
+    /// Each message is a JSON object delimitated by a Record Separator (U+001E)
+    /// and a Line Feed (U+000A).
+    JsonSeq,
+    /// JSON Lines.
+    /// Each message is a JSON object terminated by a Line Feed (U+000A).
+    Jsonl,
+    /// CBOR sequences (RFC 8742).
+    /// Messages are a series of consecutive CBOR data items.
+    CborSeq,
+    /// Comma separated values.
+    Csv,
+    /// HTML document.
+    /// A self-contained file with a sortable table for viewing in a web browser.
+    Html,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExtractOutputFormat {
+    /// Files are written to a directory.
+    Directory,
+    /// Files are written into a zip file without a temporary directory.
+    Zip,
+    /// Files are written as a tar stream.
+    ///
+    /// Each file is buffered in a temporary file before it is written to the
+    /// stream.
+    Tar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExtractDedupe {
+    /// Create a hard link to the previously extracted file.
+    ///
+    /// The file is written as usual if a hard link cannot be created.
+    Hardlink,
+    /// Copy the previously extracted file.
+    ///
+    /// The copy may share storage with the previous file on filesystems
+    /// that support copy-on-write.
+    Copy,
+    /// Do not write the file.
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExtractRecordType {
+    /// Bodies of HTTP requests such as POST payloads.
+    Request,
+    /// Blocks of metadata records.
+    Metadata,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExtractLayout {
+    /// Directories named by the scheme, host, and path of the URL.
+    Url,
+    /// A single directory with files named by the last path segment and
+    /// query of the URL.
+    Flat,
+    /// Files named by the SHA-256 digest of the content in two levels of
+    /// directories named by the first two bytes of the digest.
+    ///
+    /// Files with the same content are written once. This layout is not
+    /// supported with zip or tar output.
+    Sha256Prefix,
+}
+
+impl From<ExtractLayout> for PathLayout {
+    fn from(value: ExtractLayout) -> Self {
+        match value {
+            ExtractLayout::Url => Self::Url,
+            ExtractLayout::Flat => Self::Flat,
+            ExtractLayout::Sha256Prefix => Self::Sha256Prefix,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SplitName {
+    /// The UUID or percent-encoded URI of the WARC-Record-ID.
+    RecordId,
+    /// The algorithm and hex-encoded value of the WARC-Block-Digest.
+    ///
+    /// A SHA-256 digest is computed if the record does not have a digest.
+    Digest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum UniqueBy {
+    /// The value of WARC-Payload-Digest.
+    ///
+    /// Records without the field are not considered duplicates.
+    Digest,
+    /// The value of WARC-Target-URI.
+    ///
+    /// Records without the field are not considered duplicates.
+    TargetUri,
+}
+
+impl ListFormat {
+    /// Returns the format for the filename extension of the path.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+
+        match extension.as_str() {
+            "json-seq" | "jsonseq" => Some(Self::JsonSeq),
+            "jsonl" => Some(Self::Jsonl),
+            "cbor-seq" | "cborseq" | "cbor" => Some(Self::CborSeq),
+            "csv" => Some(Self::Csv),
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    /// Returns the format for serializing the listing as a sequence.
+    ///
+    /// Returns `None` for a document format.
+    pub fn seq_format(&self) -> Option<crate::dataseq::SeqFormat> {
+        match self {
+            Self::JsonSeq => Some(crate::dataseq::SeqFormat::JsonSeq),
+            Self::Jsonl => Some(crate::dataseq::SeqFormat::JsonL),
+            Self::CborSeq => Some(crate::dataseq::SeqFormat::CborSeq),
+            Self::Csv => Some(crate::dataseq::SeqFormat::Csv),
+            Self::Html => None,
+        }
+    }
+}
+
 /// Self-installer and uninstaller.
 #[derive(Debug, Parser)]
 pub struct SelfCommand {
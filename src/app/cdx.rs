@@ -0,0 +1,275 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+};
+
+use anyhow::Context;
+
+use crate::{
+    cdx::{CdxEntry, CdxReader},
+    digest::{AlgorithmName, Digest},
+    header::{fields::FieldsExt, WarcHeader},
+    verify::{Problem, ProblemKind},
+};
+
+/// Record types that are expected to be listed in an index.
+const INDEXED_RECORD_TYPES: [&str; 3] = ["response", "resource", "revisit"];
+
+struct CurrentRecord {
+    record_id: String,
+    is_indexed_type: bool,
+    payload_digest: Option<Digest>,
+    position: u64,
+}
+
+/// Checks records against the entries of a CDX index.
+pub struct CdxChecker {
+    /// Entries by filename and offset.
+    entries: HashMap<String, BTreeMap<u64, Vec<CdxEntry>>>,
+    /// Entries of the current file that were not matched yet.
+    file_entries: BTreeMap<u64, Vec<CdxEntry>>,
+    current: Option<CurrentRecord>,
+}
+
+impl CdxChecker {
+    /// Reads the index.
+    ///
+    /// Entries without a filename or offset cannot be checked and are
+    /// skipped.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("opening CDX file {:?} failed", path))?;
+        let input: Box<dyn BufRead> = if path.extension().is_some_and(|value| value == "gz") {
+            Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file)))
+        } else {
+            Box::new(BufReader::new(file))
+        };
+
+        let mut entries = HashMap::<String, BTreeMap<u64, Vec<CdxEntry>>>::new();
+        let mut skipped_count = 0u64;
+
+        for entry in CdxReader::new(input) {
+            let entry = entry.with_context(|| format!("reading CDX file {:?} failed", path))?;
+
+            match (file_name(entry.filename.as_deref()), entry.offset) {
+                (Some(name), Some(offset)) => entries
+                    .entry(name)
+                    .or_default()
+                    .entry(offset)
+                    .or_default()
+                    .push(entry),
+                _ => skipped_count += 1,
+            }
+        }
+
+        if skipped_count > 0 {
+            tracing::warn!(skipped_count, "CDX entries without filename or offset");
+        }
+
+        Ok(Self {
+            entries,
+            file_entries: BTreeMap::new(),
+            current: None,
+        })
+    }
+
+    /// Starts checking a WARC file.
+    ///
+    /// Entries before the start position are not checked.
+    pub fn begin_file(&mut self, path: &Path, start_position: u64) {
+        let name = file_name(path.to_str()).unwrap_or_default();
+        let mut file_entries = self.entries.remove(&name).unwrap_or_default();
+        self.file_entries = file_entries.split_off(&start_position);
+        self.current = None;
+    }
+
+    pub fn begin_record(&mut self, header: &WarcHeader, position: u64) {
+        let payload_digest = header
+            .fields
+            .get_all("WARC-Payload-Digest")
+            .filter_map(|value| Digest::from_str(value).ok())
+            .find(|digest| digest.algorithm() == AlgorithmName::Sha1);
+
+        self.current = Some(CurrentRecord {
+            record_id: header.fields.get_or_default("WARC-Record-ID").to_string(),
+            is_indexed_type: INDEXED_RECORD_TYPES
+                .contains(&header.fields.get_or_default("WARC-Type")),
+            payload_digest,
+            position,
+        });
+    }
+
+    /// Checks the entries of the record that ended at the position.
+    pub fn end_record(&mut self, position: u64) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        let Some(record) = self.current.take() else {
+            return problems;
+        };
+        let add_problem = |problems: &mut Vec<Problem>, kind| {
+            problems.push(Problem::new(&record.record_id, kind));
+        };
+
+        let Some(entries) = self.file_entries.remove(&record.position) else {
+            if record.is_indexed_type {
+                add_problem(&mut problems, ProblemKind::CdxRecordMissing);
+            }
+
+            return problems;
+        };
+
+        let actual_length = position - record.position;
+
+        for entry in entries {
+            if let Some(expected) = entry.length.filter(|length| *length != actual_length) {
+                add_problem(
+                    &mut problems,
+                    ProblemKind::CdxLengthMismatch {
+                        expected,
+                        actual: actual_length,
+                    },
+                );
+            }
+
+            let expected_digest = entry.digest.as_deref().and_then(parse_digest);
+
+            if let (Some(expected), Some(actual)) = (expected_digest, &record.payload_digest) {
+                if expected.algorithm() == actual.algorithm() && expected.value() != actual.value()
+                {
+                    add_problem(
+                        &mut problems,
+                        ProblemKind::CdxDigestMismatch {
+                            expected: expected.to_string(),
+                            actual: actual.to_string(),
+                        },
+                    );
+                }
+            }
+        }
+
+        problems
+    }
+
+    /// Returns problems for the entries of the file that do not point at
+    /// a record.
+    pub fn end_file(&mut self) -> Vec<Problem> {
+        // The entries of a record cut off by the end of the file point at
+        // a record.
+        if let Some(record) = self.current.take() {
+            self.file_entries.remove(&record.position);
+        }
+
+        std::mem::take(&mut self.file_entries)
+            .into_iter()
+            .flat_map(|(offset, entries)| {
+                entries.into_iter().map(move |entry| {
+                    Problem::new(
+                        "",
+                        ProblemKind::CdxEntryNotFound {
+                            url: entry.url.unwrap_or(entry.url_key),
+                            offset,
+                        },
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+fn file_name(path: Option<&str>) -> Option<String> {
+    Path::new(path?)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+/// Parses a CDX digest which is SHA-1 when there is no algorithm label.
+fn parse_digest(value: &str) -> Option<Digest> {
+    if value.contains(':') {
+        Digest::from_str(value).ok()
+    } else {
+        Digest::from_str(&format!("sha1:{}", value)).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::arg::VerifyProblemKind;
+
+    use super::*;
+
+    fn entry(offset: u64, length: u64, digest: &str) -> CdxEntry {
+        CdxEntry {
+            url_key: format!("key{}", offset),
+            digest: Some(digest.to_string()),
+            length: Some(length),
+            offset: Some(offset),
+            filename: Some("a.warc".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn header(id: &str, record_type: &str, digest: &str) -> WarcHeader {
+        let mut header = WarcHeader::new(0, record_type);
+        header
+            .fields
+            .insert("WARC-Record-ID".to_string(), id.to_string());
+        header
+            .fields
+            .insert("WARC-Payload-Digest".to_string(), digest.to_string());
+        header
+    }
+
+    #[test]
+    fn test_cdx_checker() {
+        let digest = "3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ";
+        let other_digest = "LRPJEK4G7TIGBBHGD5NBDEWCVNMDVNCU";
+        let mut file_entries = BTreeMap::new();
+        file_entries.insert(0, vec![entry(0, 100, digest)]);
+        file_entries.insert(100, vec![entry(100, 60, other_digest)]);
+        file_entries.insert(500, vec![entry(500, 10, digest)]);
+
+        let mut checker = CdxChecker {
+            entries: HashMap::from([("a.warc".to_string(), file_entries)]),
+            file_entries: BTreeMap::new(),
+            current: None,
+        };
+        let sha1 = |value: &str| format!("sha1:{}", value);
+        let kinds = |problems: Vec<Problem>| {
+            problems
+                .iter()
+                .map(|problem| VerifyProblemKind::from(problem.kind()))
+                .collect::<Vec<_>>()
+        };
+
+        checker.begin_file(Path::new("dir/a.warc"), 0);
+
+        checker.begin_record(&header("<urn:example:1>", "response", &sha1(digest)), 0);
+        assert!(checker.end_record(100).is_empty());
+
+        checker.begin_record(&header("<urn:example:2>", "response", &sha1(digest)), 100);
+        assert_eq!(
+            kinds(checker.end_record(150)),
+            vec![
+                VerifyProblemKind::CdxLengthMismatch,
+                VerifyProblemKind::CdxDigestMismatch
+            ]
+        );
+
+        checker.begin_record(&header("<urn:example:3>", "request", &sha1(digest)), 150);
+        assert!(checker.end_record(200).is_empty());
+
+        checker.begin_record(&header("<urn:example:4>", "response", &sha1(digest)), 200);
+        assert_eq!(
+            kinds(checker.end_record(300)),
+            vec![VerifyProblemKind::CdxRecordMissing]
+        );
+
+        assert_eq!(
+            kinds(checker.end_file()),
+            vec![VerifyProblemKind::CdxEntryNotFound]
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    ffi::OsString,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use data_encoding::{HEXLOWER, HEXLOWER_PERMISSIVE};
+
+use crate::{
+    digest::{AlgorithmName, Hasher, MultiHasher},
+    verify::{Problem, ProblemKind},
+};
+
+/// Algorithms that are searched for sidecar checksum files.
+pub const CONTAINER_CHECKSUM_ALGORITHMS: [AlgorithmName; 2] =
+    [AlgorithmName::Sha256, AlgorithmName::Xxh3];
+
+/// Returns the path of a sidecar checksum file such as "example.warc.gz.sha256".
+pub fn sidecar_path(path: &Path, algorithm: AlgorithmName) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".");
+    name.push(algorithm.as_str());
+    PathBuf::from(name)
+}
+
+/// Writes a sidecar checksum file in the format of "sha256sum" and similar tools.
+pub fn write_sidecar(path: &Path, algorithm: AlgorithmName, value: &[u8]) -> anyhow::Result<()> {
+    let filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let sidecar_path = sidecar_path(path, algorithm);
+
+    std::fs::write(
+        &sidecar_path,
+        format_sidecar_line(value, &filename).as_bytes(),
+    )
+    .with_context(|| format!("writing checksum file {:?} failed", sidecar_path))?;
+
+    tracing::info!(path = ?sidecar_path, "wrote checksum file");
+
+    Ok(())
+}
+
+fn format_sidecar_line(value: &[u8], filename: &str) -> String {
+    format!("{}  {}\n", HEXLOWER.encode(value), filename)
+}
+
+fn parse_sidecar_line(text: &str) -> Option<Vec<u8>> {
+    let line = text.lines().next()?;
+    let encoded = line.split_whitespace().next()?;
+
+    HEXLOWER_PERMISSIVE.decode(encoded.as_bytes()).ok()
+}
+
+/// Checks the file against any sidecar checksum files.
+///
+/// Returns the problems found and whether the file content is intact.
+pub fn check_container(path: &Path) -> anyhow::Result<(Vec<Problem>, bool)> {
+    let mut problems = Vec::new();
+    let mut expected_values = Vec::new();
+
+    for algorithm in CONTAINER_CHECKSUM_ALGORITHMS {
+        let sidecar_path = sidecar_path(path, algorithm);
+
+        if !sidecar_path.is_file() {
+            continue;
+        }
+
+        let text = std::fs::read_to_string(&sidecar_path)
+            .with_context(|| format!("reading checksum file {:?} failed", sidecar_path))?;
+        let value = parse_sidecar_line(&text)
+            .with_context(|| format!("invalid checksum file {:?}", sidecar_path))?;
+
+        expected_values.push((algorithm, value));
+    }
+
+    if expected_values.is_empty() {
+        problems.push(Problem::new("", ProblemKind::ContainerChecksumMissing));
+        return Ok((problems, true));
+    }
+
+    let algorithms = expected_values
+        .iter()
+        .map(|(algorithm, _)| *algorithm)
+        .collect::<Vec<_>>();
+    let mut hasher = MultiHasher::new(&algorithms);
+    let mut file = std::fs::File::open(path)?;
+    let mut buf = vec![0u8; crate::io::IO_BUFFER_LENGTH * 16];
+
+    loop {
+        let read_length = file.read(&mut buf)?;
+
+        if read_length == 0 {
+            break;
+        }
+
+        hasher.update(&buf[0..read_length]);
+    }
+
+    let actual_values = hasher.finish();
+    let mut is_intact = true;
+
+    for (algorithm, expected) in expected_values {
+        let actual = &actual_values[&algorithm];
+
+        tracing::debug!(%algorithm, "check container checksum");
+
+        if &expected != actual {
+            is_intact = false;
+            problems.push(Problem::new(
+                "",
+                ProblemKind::ContainerChecksumMismatch {
+                    algorithm: algorithm.to_string(),
+                    expected: HEXLOWER.encode(&expected),
+                    actual: HEXLOWER.encode(actual),
+                },
+            ));
+        }
+    }
+
+    Ok((problems, is_intact))
+}
+
+/// Writer that computes checksums of the data written through it.
+pub struct ChecksumWriter<W: Write> {
+    inner: W,
+    hashers: Vec<Hasher>,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    pub fn new(inner: W, algorithms: &[AlgorithmName]) -> Self {
+        Self {
+            inner,
+            hashers: algorithms.iter().map(|a| Hasher::new(*a)).collect(),
+        }
+    }
+
+    /// Returns the underlying writer and the checksum values.
+    pub fn finish(mut self) -> (W, Vec<(AlgorithmName, Vec<u8>)>) {
+        let values = self
+            .hashers
+            .iter_mut()
+            .map(|hasher| (hasher.algorithm(), hasher.finish()))
+            .collect();
+
+        (self.inner, values)
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let write_length = self.inner.write(buf)?;
+
+        for hasher in &mut self.hashers {
+            hasher.update(&buf[0..write_length]);
+        }
+
+        Ok(write_length)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidecar_line() {
+        let line = format_sidecar_line(b"\xab\xcd", "example.warc");
+
+        assert_eq!(line, "abcd  example.warc\n");
+        assert_eq!(parse_sidecar_line(&line), Some(b"\xab\xcd".to_vec()));
+        assert_eq!(
+            parse_sidecar_line("ABCD *example.warc"),
+            Some(b"\xab\xcd".to_vec())
+        );
+        assert_eq!(parse_sidecar_line(""), None);
+    }
+
+    #[test]
+    fn test_sidecar_path() {
+        assert_eq!(
+            sidecar_path(Path::new("a/example.warc.gz"), AlgorithmName::Sha256),
+            Path::new("a/example.warc.gz.sha256")
+        );
+    }
+}
@@ -1,19 +1,100 @@
-use std::{io::Read, path::Path};
+use std::{
+    io::{Read, Seek, SeekFrom},
+    num::NonZeroUsize,
+    path::Path,
+    sync::OnceLock,
+};
 
 use anyhow::Context;
 use indicatif::ProgressBar;
 
 use crate::{
-    compress::{Dictionary, Format},
-    header::WarcHeader,
-    io::LogicalPosition,
-    warc::{DecStateBlock, DecStateHeader, Decoder, DecoderConfig},
+    compress::{zstd::ZstdParameters, Dictionary, Format},
+    error::GeneralError,
+    header::{LineFolding, WarcHeader},
+    io::{LogicalPosition, MappedFile},
+    warc::{
+        DecStateBlock, DecStateHeader, Decoder, DecoderConfig, EndState, ParallelDecoder,
+        SliceDecoder,
+    },
 };
 
 use super::io::{ProgramInput, ProgramOutput};
 
 const BUFFER_LENGTH: usize = crate::io::IO_BUFFER_LENGTH;
 
+static GLOBAL_MAX_HEADER_LENGTH: OnceLock<usize> = OnceLock::new();
+
+pub fn set_global_max_header_length(value: usize) {
+    GLOBAL_MAX_HEADER_LENGTH.set(value).unwrap();
+}
+
+/// Returns the maximum length of a WARC header given on the command line.
+pub fn max_header_length() -> usize {
+    GLOBAL_MAX_HEADER_LENGTH
+        .get()
+        .copied()
+        .unwrap_or(crate::warc::DEFAULT_HEADER_LENGTH_LIMIT)
+}
+
+static GLOBAL_LINE_FOLDING: OnceLock<LineFolding> = OnceLock::new();
+
+pub fn set_global_line_folding(value: LineFolding) {
+    GLOBAL_LINE_FOLDING.set(value).unwrap();
+}
+
+/// Returns the number of threads given by a `--jobs` option or the number of
+/// available CPUs.
+pub fn job_count(jobs: Option<NonZeroUsize>) -> usize {
+    jobs.or_else(|| std::thread::available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get)
+}
+
+/// Returns the Zstandard parameters given by the `--zstd-*` options.
+pub fn zstd_parameters(
+    window_log: Option<u32>,
+    long_distance_matching: bool,
+    chain_log: Option<u32>,
+) -> ZstdParameters {
+    ZstdParameters {
+        window_log,
+        long_distance_matching,
+        chain_log,
+    }
+}
+
+/// Reads a dictionary file to embed in a Zstandard compressed output.
+pub fn read_dictionary(path: Option<&Path>, format: Format) -> anyhow::Result<Dictionary> {
+    let Some(path) = path else {
+        return Ok(Dictionary::None);
+    };
+
+    #[cfg(feature = "zstd")]
+    if format == Format::Zstandard {
+        let data = std::fs::read(path).with_context(|| format!("failed to read {:?}", path))?;
+
+        return Ok(Dictionary::WarcZstd(data));
+    }
+
+    anyhow::bail!("a dictionary requires Zstandard compression")
+}
+
+/// Returns a decoder config with the options given on the command line.
+pub fn decoder_config() -> DecoderConfig {
+    let mode = super::mode::global_mode();
+
+    DecoderConfig {
+        max_header_length: max_header_length(),
+        line_folding: GLOBAL_LINE_FOLDING
+            .get()
+            .copied()
+            .unwrap_or_else(|| mode.line_folding()),
+        lenient: mode.is_lenient(),
+        recover: mode.recovers_corrupt_data(),
+        ..Default::default()
+    }
+}
+
 pub fn open_input(path: &Path) -> anyhow::Result<ProgramInput> {
     ProgramInput::open(path).context("opening input file failed")
 }
@@ -25,11 +106,16 @@ pub fn open_output(path: &Path) -> anyhow::Result<ProgramOutput> {
 pub enum ReaderEvent<'a> {
     Header {
         header: WarcHeader,
+        /// Header as it appears in the file.
+        raw_header: &'a [u8],
         record_boundary_position: u64,
     },
     Block {
         data: &'a [u8],
     },
+    EndRecord {
+        record_boundary_position: u64,
+    },
 }
 
 #[derive(Debug)]
@@ -37,6 +123,10 @@ enum ReaderState {
     None,
     Header(Decoder<DecStateHeader, ProgramInput>),
     Block(Decoder<DecStateBlock, ProgramInput>),
+    /// Uncompressed file read without copying.
+    Mapped(MappedFile),
+    /// Gzip file with members decompressed on several threads.
+    Parallel(ParallelDecoder<ProgramInput>),
 }
 
 impl ReaderState {
@@ -69,9 +159,16 @@ where
 {
     progress_bar: ProgressBar,
     state: ReaderState,
+    compression_format: Format,
     buf: Vec<u8>,
     callback: C,
+    skip: u64,
+    limit: Option<u64>,
+    no_block: bool,
+    start_position: u64,
+    record_count: u64,
     pub has_record_at_time_compression_fault: bool,
+    pub end_state: EndState,
 }
 
 impl<C> ReaderPipeline<C>
@@ -86,30 +183,168 @@ where
     ) -> anyhow::Result<Self> {
         let progress_bar = super::progress::make_bytes_progress_bar(file_len);
 
-        let mut config = DecoderConfig::default();
+        let mut config = decoder_config();
         config.decompressor.format = compression_format;
         config.decompressor.dictionary = Dictionary::WarcZstd(Vec::new());
+        config.keep_raw_header = true;
+
+        // Only the streaming decoder tolerates malformed input.
+        let is_tolerant = config.lenient || config.recover;
 
-        let reader = Decoder::new(input, config)?;
+        let state = match input.map()? {
+            Some(map) if compression_format == Format::Identity && !is_tolerant => {
+                ReaderState::Mapped(map)
+            }
+            _ => ReaderState::Header(Decoder::new(input, config)?),
+        };
 
         Ok(Self {
             progress_bar,
-            state: ReaderState::Header(reader),
+            state,
+            compression_format,
             buf: Vec::new(),
             callback,
+            skip: 0,
+            limit: None,
+            no_block: false,
+            start_position: 0,
+            record_count: 0,
             has_record_at_time_compression_fault: false,
+            end_state: EndState::NotReached,
         })
     }
 
+    /// Do not output the given number of records at the start of the file.
+    pub fn with_skip(mut self, value: u64) -> Self {
+        self.skip = value;
+        self
+    }
+
+    /// Stop after the given number of records have been output.
+    pub fn with_limit(mut self, value: Option<u64>) -> Self {
+        self.limit = value;
+        self
+    }
+
+    /// Do not read the block data of records.
+    ///
+    /// Only the empty data block event that indicates the end of the block
+    /// is output.
+    pub fn with_no_block(mut self, value: bool) -> Self {
+        self.no_block = value;
+        self
+    }
+
+    /// Start reading at the given record boundary position.
+    ///
+    /// Positions in the events are relative to the start of the file.
+    pub fn with_start_position(mut self, value: u64) -> Self {
+        self.start_position = value;
+        self
+    }
+
+    /// Decompress gzip members on the given number of threads.
+    ///
+    /// Each record is read into memory. Other formats, and the permissive
+    /// mode, are not affected.
+    pub fn with_threads(mut self, value: usize) -> Self {
+        let config = decoder_config();
+
+        if value > 1
+            && self.compression_format == Format::Gzip
+            && !config.lenient
+            && !config.recover
+        {
+            if let ReaderState::Header(reader) = self.state.take() {
+                let mut config = config;
+                config.decompressor.format = Format::Gzip;
+                config.keep_raw_header = true;
+
+                self.state =
+                    ReaderState::Parallel(ParallelDecoder::new(reader.into_inner(), config, value));
+            }
+        }
+
+        self
+    }
+
+    fn is_limit_reached(&self) -> bool {
+        if let Some(limit) = self.limit {
+            self.record_count >= self.skip.saturating_add(limit)
+        } else {
+            false
+        }
+    }
+
     pub fn run(&mut self) -> anyhow::Result<()> {
         super::progress::global_progress_bar().add(self.progress_bar.clone());
 
+        let result = self.run_impl();
+
+        self.progress_bar.finish();
+        super::progress::global_progress_bar().remove(&self.progress_bar);
+
+        match result {
+            Err(error)
+                if self.end_state == EndState::Truncated
+                    && super::mode::global_mode().accepts_truncation() =>
+            {
+                tracing::warn!(?error, "file appears truncated; ignored incomplete record");
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn run_impl(&mut self) -> anyhow::Result<()> {
+        match self.state.take() {
+            ReaderState::Mapped(map) => {
+                let result = self.run_mapped(&map);
+                self.state = ReaderState::Mapped(map);
+
+                return result;
+            }
+            ReaderState::Parallel(mut reader) => {
+                let result = self.run_parallel(&mut reader);
+                self.state = ReaderState::Parallel(reader);
+
+                return result;
+            }
+            state => self.state = state,
+        }
+
+        if self.start_position != 0 {
+            let mut reader = self.state.take().try_into_header().unwrap();
+            reader.prepare_for_seek()?;
+            reader
+                .get_mut()
+                .seek(SeekFrom::Start(self.start_position))?;
+
+            // The position may be the end of the file.
+            let has_more = reader.has_next_record()?;
+            self.end_state = reader.end_state();
+            self.state = ReaderState::Header(reader);
+
+            if !has_more {
+                return Ok(());
+            }
+        }
+
         loop {
-            self.process_header()?;
-            self.process_block()?;
+            if self.is_limit_reached() {
+                tracing::debug!("record limit reached");
+                break;
+            }
+
+            let is_skipped = self.record_count < self.skip;
+
+            self.process_header(is_skipped)?;
+            self.process_block(is_skipped)?;
+            self.record_count += 1;
 
             let mut reader = self.state.take().try_into_header().unwrap();
             let has_more = reader.has_next_record()?;
+            self.end_state = reader.end_state();
             self.state = ReaderState::Header(reader);
 
             if !has_more {
@@ -117,18 +352,148 @@ where
             }
         }
 
-        self.progress_bar.finish();
-        super::progress::global_progress_bar().remove(&self.progress_bar);
+        Ok(())
+    }
+
+    fn run_mapped(&mut self, map: &MappedFile) -> anyhow::Result<()> {
+        let mut config = decoder_config();
+        config.keep_raw_header = true;
+
+        let mut decoder = SliceDecoder::new(map, config);
+        decoder.set_position(self.start_position);
+
+        while decoder.has_next_record() {
+            if self.is_limit_reached() {
+                tracing::debug!("record limit reached");
+                break;
+            }
+
+            let is_skipped = self.record_count < self.skip;
+            let record_boundary_position = decoder.position();
+
+            let (header, block) = decoder
+                .read_record()
+                .inspect_err(|_| self.end_state = decoder.end_state())
+                .context("invalid WARC record")?;
+
+            self.process_record(
+                header,
+                decoder.raw_header().unwrap_or_default(),
+                block,
+                record_boundary_position,
+                decoder.position(),
+                is_skipped,
+            )?;
+            self.record_count += 1;
+        }
+
+        self.end_state = decoder.end_state();
 
         Ok(())
     }
 
-    fn process_header(&mut self) -> anyhow::Result<()> {
+    fn run_parallel(&mut self, reader: &mut ParallelDecoder<ProgramInput>) -> anyhow::Result<()> {
+        if self.start_position != 0 {
+            reader
+                .get_mut()
+                .seek(SeekFrom::Start(self.start_position))?;
+        }
+
+        loop {
+            if self.is_limit_reached() {
+                tracing::debug!("record limit reached");
+                break;
+            }
+
+            let is_skipped = self.record_count < self.skip;
+
+            let record = reader
+                .read_record()
+                .inspect_err(|_| self.end_state = reader.end_state())
+                .context("invalid WARC record")?;
+            self.has_record_at_time_compression_fault =
+                reader.has_record_at_time_compression_fault();
+
+            let Some(record) = record else {
+                break;
+            };
+
+            let (header, block) = record.into_parts();
+
+            self.process_record(
+                header,
+                reader.raw_header().unwrap_or_default(),
+                &block,
+                self.start_position + reader.record_boundary_position(),
+                self.start_position + reader.position(),
+                is_skipped,
+            )?;
+            self.record_count += 1;
+        }
+
+        self.end_state = reader.end_state();
+
+        Ok(())
+    }
+
+    /// Outputs the events of a record read into memory.
+    fn process_record(
+        &mut self,
+        header: WarcHeader,
+        raw_header: &[u8],
+        block: &[u8],
+        record_boundary_position: u64,
+        end_position: u64,
+        is_skipped: bool,
+    ) -> anyhow::Result<()> {
+        let record_id = header
+            .fields
+            .get("WARC-Record-ID")
+            .map(|s| s.as_str())
+            .unwrap_or_default();
+        self.progress_bar
+            .set_message(format!("Processing record {}", record_id));
+        tracing::info!(record_id, "processing record");
+        self.progress_bar.set_position(end_position);
+
+        if !is_skipped {
+            (self.callback)(ReaderEvent::Header {
+                header,
+                raw_header,
+                record_boundary_position,
+            })?;
+
+            if !self.no_block {
+                for data in block.chunks(BUFFER_LENGTH) {
+                    (self.callback)(ReaderEvent::Block { data })?;
+                }
+            }
+
+            (self.callback)(ReaderEvent::Block { data: &[] })?;
+            (self.callback)(ReaderEvent::EndRecord {
+                record_boundary_position: end_position,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn process_header(&mut self, is_skipped: bool) -> anyhow::Result<()> {
         let reader = self.state.take().try_into_header().unwrap();
 
         self.has_record_at_time_compression_fault = reader.has_record_at_time_compression_fault();
 
-        let (header, reader) = reader.read_header().context("invalid WARC header")?;
+        let (header, reader) = reader
+            .read_header()
+            .inspect_err(|error| {
+                // The decoder is not returned on error.
+                if let GeneralError::Io(error) = error {
+                    if error.kind() == std::io::ErrorKind::UnexpectedEof {
+                        self.end_state = EndState::Truncated;
+                    }
+                }
+            })
+            .context("invalid WARC header")?;
 
         let record_id = header
             .fields
@@ -138,40 +503,71 @@ where
         self.progress_bar
             .set_message(format!("Processing record {}", record_id));
         tracing::info!(record_id, "processing record");
-        self.progress_bar.set_position(reader.logical_position());
-
-        (self.callback)(ReaderEvent::Header {
-            header,
-            record_boundary_position: reader.record_boundary_position(),
-        })?;
+        self.progress_bar
+            .set_position(self.start_position + reader.logical_position());
+
+        if !is_skipped {
+            (self.callback)(ReaderEvent::Header {
+                header,
+                raw_header: reader.raw_header().unwrap_or_default(),
+                record_boundary_position: self.start_position + reader.record_boundary_position(),
+            })?;
+        }
 
         self.state = ReaderState::Block(reader);
 
         Ok(())
     }
 
-    fn process_block(&mut self) -> anyhow::Result<()> {
+    fn process_block(&mut self, is_skipped: bool) -> anyhow::Result<()> {
         let mut reader = self.state.take().try_into_block().unwrap();
 
-        loop {
+        if is_skipped {
+            self.state = ReaderState::Header(self.finish_block(reader)?);
+            return Ok(());
+        }
+
+        while !self.no_block {
             self.buf.resize(BUFFER_LENGTH, 0);
 
-            let read_length = reader.read(&mut self.buf)?;
+            let read_length = reader.read(&mut self.buf).inspect_err(|_| {
+                self.end_state = reader.end_state();
+            })?;
             self.buf.truncate(read_length);
 
             if read_length == 0 {
                 break;
             }
 
-            self.progress_bar.set_position(reader.logical_position());
+            self.progress_bar
+                .set_position(self.start_position + reader.logical_position());
 
             (self.callback)(ReaderEvent::Block { data: &self.buf })?;
         }
 
         (self.callback)(ReaderEvent::Block { data: &[] })?;
 
-        self.state = ReaderState::Header(reader.finish_block()?);
+        let reader = self.finish_block(reader)?;
+
+        (self.callback)(ReaderEvent::EndRecord {
+            record_boundary_position: self.start_position + reader.record_boundary_position(),
+        })?;
+
+        self.state = ReaderState::Header(reader);
 
         Ok(())
     }
+
+    fn finish_block(
+        &mut self,
+        mut reader: Decoder<DecStateBlock, ProgramInput>,
+    ) -> anyhow::Result<Decoder<DecStateHeader, ProgramInput>> {
+        // Read the remaining block so that the end state is known if the
+        // file ends within the record.
+        std::io::copy(&mut reader, &mut std::io::sink()).inspect_err(|_| {
+            self.end_state = reader.end_state();
+        })?;
+
+        Ok(reader.finish_block()?)
+    }
 }
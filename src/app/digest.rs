@@ -0,0 +1,225 @@
+use std::sync::OnceLock;
+
+use crate::{
+    dataseq::SeqWriter,
+    digest::{AlgorithmName, Digest, DigestEncoding, Hasher},
+    extract::WarcExtractor,
+    header::WarcHeader,
+};
+
+use super::{
+    arg::DigestCommand,
+    common::{ReaderEvent, ReaderPipeline},
+};
+
+static GLOBAL_DIGEST_ENCODING: OnceLock<DigestEncoding> = OnceLock::new();
+
+pub fn set_global_digest_encoding(encoding: DigestEncoding) {
+    GLOBAL_DIGEST_ENCODING.set(encoding).unwrap();
+}
+
+/// Returns the digest for a WARC header field in the encoding given by the
+/// user.
+pub fn format_digest(digest: &Digest) -> String {
+    let encoding = GLOBAL_DIGEST_ENCODING.get().copied().unwrap_or_default();
+
+    digest.to_string_with_encoding(encoding)
+}
+
+pub fn digest(args: &DigestCommand) -> anyhow::Result<()> {
+    let output = super::common::open_output(&args.output)?;
+    let mut writer = SeqWriter::new(output, args.format.into());
+
+    for input_path in &args.input {
+        let span = tracing::info_span!("digest", path = ?input_path);
+        let _span_guard = span.enter();
+
+        let input = super::common::open_input(input_path)?;
+        let compression_format = args.compression.try_into_native(input_path)?;
+        let file_len = std::fs::metadata(input_path).map(|m| m.len()).ok();
+        let mut record = RecordDigester::new(&args.algorithm);
+
+        ReaderPipeline::new(
+            |event| {
+                match event {
+                    ReaderEvent::Header { header, .. } => record.begin_record(&header),
+                    ReaderEvent::Block { data } => record.block_data(data),
+                    ReaderEvent::EndRecord { .. } => writer.put(record.end_record())?,
+                }
+
+                Ok(())
+            },
+            input,
+            compression_format,
+            file_len,
+        )?
+        .run()?;
+    }
+
+    Ok(())
+}
+
+/// Computes the digests of a record.
+struct RecordDigester {
+    block_hashers: Vec<Hasher>,
+    payload_hashers: Vec<Hasher>,
+    extractor: Option<WarcExtractor>,
+    buf: Vec<u8>,
+    record_id: String,
+    target_uri: Option<String>,
+}
+
+impl RecordDigester {
+    fn new(algorithms: &[AlgorithmName]) -> Self {
+        Self {
+            block_hashers: algorithms.iter().map(|a| Hasher::new(*a)).collect(),
+            payload_hashers: algorithms.iter().map(|a| Hasher::new(*a)).collect(),
+            extractor: None,
+            buf: Vec::new(),
+            record_id: String::new(),
+            target_uri: None,
+        }
+    }
+
+    fn begin_record(&mut self, header: &WarcHeader) {
+        self.record_id = header
+            .fields
+            .get("WARC-Record-ID")
+            .cloned()
+            .unwrap_or_default();
+        self.target_uri = header.fields.get("WARC-Target-URI").cloned();
+
+        // The payload is the data as transferred, so the content encoding
+        // is not decoded.
+        let mut extractor = WarcExtractor::new()
+            .with_requests(true)
+            .with_content_decoding(false);
+
+        self.extractor = match extractor.read_header(header) {
+            Ok(()) if extractor.has_content() => Some(extractor),
+            Ok(()) => None,
+            Err(error) => {
+                tracing::warn!(%error, record_id = self.record_id, "could not parse payload");
+                None
+            }
+        };
+    }
+
+    fn block_data(&mut self, data: &[u8]) {
+        for hasher in &mut self.block_hashers {
+            hasher.update(data);
+        }
+
+        if let Some(extractor) = &mut self.extractor {
+            match extractor.extract_data(data, &mut self.buf) {
+                Ok(_) => {
+                    for hasher in &mut self.payload_hashers {
+                        hasher.update(&self.buf);
+                    }
+                }
+                Err(error) => {
+                    tracing::warn!(%error, record_id = self.record_id, "could not parse payload");
+                    self.extractor = None;
+                }
+            }
+
+            self.buf.clear();
+        }
+    }
+
+    /// Returns the record ID, target URI, block digests, and payload
+    /// digests.
+    fn end_record(&mut self) -> Vec<Option<String>> {
+        let mut row = vec![
+            Some(std::mem::take(&mut self.record_id)),
+            self.target_uri.take(),
+        ];
+        let has_payload = self.extractor.take().is_some();
+
+        for hasher in &mut self.block_hashers {
+            row.push(Some(finish_digest(hasher)));
+        }
+
+        for hasher in &mut self.payload_hashers {
+            let digest = finish_digest(hasher);
+            row.push(Some(digest).filter(|_| has_payload));
+        }
+
+        row
+    }
+}
+
+fn finish_digest(hasher: &mut Hasher) -> String {
+    format_digest(&Digest::new(hasher.algorithm(), hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_digester() {
+        let mut digester = RecordDigester::new(&[AlgorithmName::Sha1]);
+        let mut header = WarcHeader::new(41, "response");
+        header
+            .fields
+            .insert("WARC-Record-ID".to_string(), "<urn:example:1>".to_string());
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "http://example.com/".to_string(),
+        );
+        header.fields.insert(
+            "Content-Type".to_string(),
+            "application/http;msgtype=response".to_string(),
+        );
+
+        digester.begin_record(&header);
+        digester.block_data(b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\n");
+        digester.block_data(b"abc");
+        digester.block_data(b"");
+        let row = digester.end_record();
+
+        assert_eq!(row.len(), 4);
+        assert_eq!(row[0].as_deref(), Some("<urn:example:1>"));
+        assert_eq!(row[1].as_deref(), Some("http://example.com/"));
+        assert_eq!(
+            row[3].as_deref(),
+            Some("sha1:VGMT4NSHA2AWVOR6EVYXQUGCNSONBWE5")
+        );
+
+        let header = WarcHeader::new(0, "warcinfo");
+        digester.begin_record(&header);
+        digester.block_data(b"");
+        let row = digester.end_record();
+
+        assert_eq!(
+            row[2].as_deref(),
+            Some("sha1:3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ")
+        );
+        assert_eq!(row[3], None);
+    }
+
+    #[test]
+    fn test_record_digester_empty_record() {
+        let mut digester = RecordDigester::new(&[AlgorithmName::Sha1]);
+        let mut header = WarcHeader::new(0, "response");
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "http://example.com/".to_string(),
+        );
+        header.fields.insert(
+            "Content-Type".to_string(),
+            "application/http;msgtype=response".to_string(),
+        );
+
+        digester.begin_record(&header);
+        digester.block_data(b"");
+        let row = digester.end_record();
+
+        assert_eq!(
+            row[2].as_deref(),
+            Some("sha1:3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ")
+        );
+        assert_eq!(row[3], None);
+    }
+}
@@ -0,0 +1,178 @@
+use std::{io::Write, path::Path};
+
+use serde::Serialize;
+
+use crate::header::WarcHeader;
+
+use super::{
+    arg::CompressionFormat,
+    common::{ReaderEvent, ReaderPipeline},
+};
+
+/// Number of records read from the start of each file.
+const SAMPLE_RECORD_COUNT: u64 = 1000;
+/// Approximate ratio of the database size to the size of the stored keys
+/// and values, accounting for B-tree pages that are partially filled.
+const DATABASE_OVERHEAD: f64 = 2.0;
+/// Default cache size of the database.
+const DATABASE_CACHE_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Predicted resource usage of a command.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Estimate {
+    /// Total size of the input files.
+    pub input_size: u64,
+    /// Number of input bytes that were read.
+    pub sampled_size: u64,
+    /// Number of records that were read.
+    pub sampled_records: u64,
+    /// Predicted number of records in the input files.
+    pub records: u64,
+    /// Predicted size of the keys and values stored for the records.
+    pub data_size: u64,
+    /// Predicted memory used for the stored data.
+    pub memory: u64,
+    /// Predicted size of the database file.
+    pub temp_disk: u64,
+}
+
+/// Extrapolates the data stored for all records from the records at the
+/// start of each file.
+pub struct Estimator {
+    estimate: Estimate,
+    has_database: bool,
+    data_size: f64,
+    records: f64,
+}
+
+impl Estimator {
+    /// Creates an estimator for data stored in a database file or, if
+    /// `has_database` is false, in memory.
+    pub fn new(has_database: bool) -> Self {
+        Self {
+            estimate: Estimate::default(),
+            has_database,
+            data_size: 0.0,
+            records: 0.0,
+        }
+    }
+
+    /// Reads a sample of the file.
+    ///
+    /// `stored_len` returns the number of bytes stored for a record.
+    pub fn sample_file<F>(
+        &mut self,
+        path: &Path,
+        compression: CompressionFormat,
+        mut stored_len: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(&WarcHeader) -> u64,
+    {
+        let Some(metadata) = std::fs::metadata(path).ok().filter(|m| m.is_file()) else {
+            anyhow::bail!("estimating requires input files with a known size");
+        };
+        let file_len = metadata.len();
+
+        let input = super::common::open_input(path)?;
+        let compression_format = compression.try_into_native(path)?;
+        let mut record_count = 0u64;
+        let mut data_size = 0u64;
+        let mut sampled_len = 0u64;
+
+        ReaderPipeline::new(
+            |event| {
+                match event {
+                    ReaderEvent::Header { header, .. } => {
+                        record_count += 1;
+                        data_size += stored_len(&header);
+                    }
+                    ReaderEvent::Block { .. } => {}
+                    ReaderEvent::EndRecord {
+                        record_boundary_position,
+                    } => sampled_len = record_boundary_position,
+                }
+
+                Ok(())
+            },
+            input,
+            compression_format,
+            Some(file_len),
+        )?
+        .with_limit(Some(SAMPLE_RECORD_COUNT))
+        .with_no_block(true)
+        .run()?;
+
+        self.add_sample(file_len, sampled_len, record_count, data_size);
+
+        Ok(())
+    }
+
+    fn add_sample(&mut self, file_len: u64, sampled_len: u64, record_count: u64, data_size: u64) {
+        let sampled_len = sampled_len.min(file_len);
+        let scale = if record_count < SAMPLE_RECORD_COUNT || sampled_len == 0 {
+            1.0
+        } else {
+            file_len as f64 / sampled_len as f64
+        };
+
+        self.estimate.input_size += file_len;
+        self.estimate.sampled_size += sampled_len;
+        self.estimate.sampled_records += record_count;
+        self.records += record_count as f64 * scale;
+        self.data_size += data_size as f64 * scale;
+    }
+
+    pub fn finish(mut self) -> Estimate {
+        let database_size = (self.data_size * DATABASE_OVERHEAD) as u64;
+
+        self.estimate.records = self.records as u64;
+        self.estimate.data_size = self.data_size as u64;
+
+        if self.has_database {
+            self.estimate.memory = database_size.min(DATABASE_CACHE_SIZE);
+            self.estimate.temp_disk = database_size;
+        } else {
+            self.estimate.memory = database_size;
+        }
+
+        self.estimate
+    }
+}
+
+/// Prints the estimate to standard output as a JSON object.
+pub fn print_estimate(estimate: &Estimate) -> anyhow::Result<()> {
+    let mut output = std::io::stdout().lock();
+    serde_json::to_writer(&mut output, &serde_json::json!({ "estimate": estimate }))?;
+    writeln!(output)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimator() {
+        let mut estimator = Estimator::new(false);
+        estimator.add_sample(10000, 1000, SAMPLE_RECORD_COUNT, 500);
+        estimator.add_sample(300, 300, 3, 30);
+        let estimate = estimator.finish();
+
+        assert_eq!(estimate.input_size, 10300);
+        assert_eq!(estimate.sampled_size, 1300);
+        assert_eq!(estimate.sampled_records, SAMPLE_RECORD_COUNT + 3);
+        assert_eq!(estimate.records, SAMPLE_RECORD_COUNT * 10 + 3);
+        assert_eq!(estimate.data_size, 5030);
+        assert_eq!(estimate.memory, 10060);
+        assert_eq!(estimate.temp_disk, 0);
+
+        let mut estimator = Estimator::new(true);
+        estimator.add_sample(100, 100, 1, 10);
+        let estimate = estimator.finish();
+
+        assert_eq!(estimate.memory, 20);
+        assert_eq!(estimate.temp_disk, 20);
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+use anyhow::Context;
+
+use crate::header::WarcHeader;
+
+const FIELD_ENV_PREFIX: &str = "WARCAT_FIELD_";
+
+/// Runs a user command for each record.
+///
+/// The command is run by the system shell. Data is written to the standard
+/// input of the command and the header fields are provided as environment
+/// variables.
+pub struct RecordCommand {
+    command: String,
+    child: Option<(Child, Option<ChildStdin>)>,
+    stdout_to_stderr: bool,
+}
+
+impl RecordCommand {
+    pub fn new<S: Into<String>>(command: S) -> Self {
+        Self {
+            command: command.into(),
+            child: None,
+            stdout_to_stderr: false,
+        }
+    }
+
+    /// Redirect the standard output of the command to standard error.
+    ///
+    /// This keeps the output of the command from mixing with program output
+    /// written to standard output.
+    pub fn with_stdout_to_stderr(mut self, value: bool) -> Self {
+        self.stdout_to_stderr = value;
+        self
+    }
+
+    /// Starts the command for a record.
+    pub fn begin(&mut self, header: &WarcHeader, file: &Path, position: u64) -> anyhow::Result<()> {
+        self.finish()?;
+
+        let mut command = shell_command(&self.command);
+        command
+            .stdin(Stdio::piped())
+            .env("WARCAT_FILE", file)
+            .env("WARCAT_POSITION", position.to_string());
+
+        if self.stdout_to_stderr {
+            command.stdout(std::io::stderr());
+        }
+
+        for (name, value) in &header.fields {
+            let env_name = field_env_name(name);
+
+            // Only the first occurrence of a repeated field is used.
+            if header.fields.get(name) == Some(value) {
+                command.env(env_name, value);
+            }
+        }
+
+        tracing::debug!(command = self.command, "run record command");
+
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("failed to run command {:?}", self.command))?;
+        let stdin = child.stdin.take();
+
+        self.child = Some((child, stdin));
+
+        Ok(())
+    }
+
+    /// Returns whether a command is running for the current record.
+    pub fn is_running(&self) -> bool {
+        self.child.is_some()
+    }
+
+    /// Writes data to the standard input of the command.
+    ///
+    /// If the command does not read its input, the data is discarded.
+    pub fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        if let Some((_child, stdin)) = &mut self.child {
+            if let Some(writer) = stdin {
+                if let Err(error) = writer.write_all(data) {
+                    if error.kind() == std::io::ErrorKind::BrokenPipe {
+                        tracing::debug!("record command closed standard input");
+                        *stdin = None;
+                    } else {
+                        return Err(error.into());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Closes the standard input of the command and waits for it to exit.
+    pub fn finish(&mut self) -> anyhow::Result<()> {
+        if let Some((mut child, stdin)) = self.child.take() {
+            drop(stdin);
+
+            let status = child.wait()?;
+
+            if !status.success() {
+                anyhow::bail!("command {:?} exited with {}", self.command, status);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn shell_command(command: &str) -> Command {
+    let mut output = Command::new("sh");
+    output.arg("-c").arg(command);
+    output
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut output = Command::new("cmd");
+    output.arg("/C").arg(command);
+    output
+}
+
+/// Returns the environment variable name for a field such as
+/// "WARCAT_FIELD_WARC_TARGET_URI" for "WARC-Target-URI".
+fn field_env_name(name: &str) -> String {
+    let mut output = String::from(FIELD_ENV_PREFIX);
+
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            output.push(c.to_ascii_uppercase());
+        } else {
+            output.push('_');
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_env_name() {
+        assert_eq!(
+            field_env_name("WARC-Target-URI"),
+            "WARCAT_FIELD_WARC_TARGET_URI"
+        );
+        assert_eq!(field_env_name("content-type"), "WARCAT_FIELD_CONTENT_TYPE");
+        assert_eq!(
+            field_env_name("X.Custom Field2"),
+            "WARCAT_FIELD_X_CUSTOM_FIELD2"
+        );
+        assert_eq!(field_env_name("X-Nämé"), "WARCAT_FIELD_X_N_M_");
+    }
+}
@@ -9,16 +9,22 @@ use crate::{
     digest::{AlgorithmName, MultiHasher},
     extract::WarcExtractor,
     header::WarcHeader,
+    segment::SegmentInfo,
 };
 
 use super::{
     arg::ExportCommand,
     common::ReaderPipeline,
+    exec::RecordCommand,
     io::ProgramOutput,
     model::{EndOfFile, ExtractChunk, ExtractEnd, ExtractMetadata},
 };
 
 pub fn export(args: &ExportCommand) -> anyhow::Result<()> {
+    if args.emit_schema {
+        return super::schema::print_schema(&super::schema::export_schema());
+    }
+
     let output_path = &args.output;
     let seq_format = args.format.into();
 
@@ -37,18 +43,31 @@ pub fn export(args: &ExportCommand) -> anyhow::Result<()> {
 
         let mut exporter = Exporter::new(input_path, writer, args.no_block, args.extract);
 
+        if let Some(command) = &args.exec {
+            let is_stdout = output_path.to_str() == Some("-");
+            exporter = exporter
+                .with_command(RecordCommand::new(command).with_stdout_to_stderr(is_stdout));
+        }
+
+        exporter = exporter.with_raw_header(args.raw_header);
+
         ReaderPipeline::new(
             |event| match event {
                 ReaderEvent::Header {
                     header,
+                    raw_header,
                     record_boundary_position,
-                } => exporter.process_header(&header, record_boundary_position),
+                } => exporter.process_header(&header, raw_header, record_boundary_position),
                 ReaderEvent::Block { data } => exporter.process_block(data),
+                ReaderEvent::EndRecord { .. } => Ok(()),
             },
             input,
             compression_format,
             file_len,
         )?
+        .with_skip(args.skip)
+        .with_limit(args.limit)
+        .with_no_block(args.no_block && !args.extract && args.exec.is_none())
         .run()?;
 
         exporter.finish()?;
@@ -67,6 +86,10 @@ pub struct Exporter {
     extractor: Option<WarcExtractor>,
     extract_hasher: MultiHasher,
     buf: Vec<u8>,
+    command: Option<RecordCommand>,
+    command_extractor: WarcExtractor,
+    segment: Option<SegmentInfo>,
+    raw_header: bool,
 }
 
 impl Exporter {
@@ -101,12 +124,29 @@ impl Exporter {
             extractor,
             extract_hasher,
             buf: Vec::new(),
+            command: None,
+            command_extractor: WarcExtractor::new(),
+            segment: None,
+            raw_header: false,
         }
     }
 
+    /// Run the command for each record with the payload as input.
+    pub fn with_command(mut self, command: RecordCommand) -> Self {
+        self.command = Some(command);
+        self
+    }
+
+    /// Include the header as it appears in the file in header messages.
+    pub fn with_raw_header(mut self, value: bool) -> Self {
+        self.raw_header = value;
+        self
+    }
+
     pub fn process_header(
         &mut self,
         header: &WarcHeader,
+        raw_header: &[u8],
         record_boundary_position: u64,
     ) -> anyhow::Result<()> {
         let message = WarcMessage::Metadata(model::Metadata {
@@ -122,15 +162,42 @@ impl Exporter {
                 .iter()
                 .map(|(k, v)| (k.to_string(), v.to_string()))
                 .collect(),
+            raw: self.raw_header.then(|| raw_header.to_vec()),
         });
         self.writer.put(message)?;
 
         self.message_extract_header(header)?;
 
+        if let Some(command) = &mut self.command {
+            self.command_extractor.read_header(header)?;
+            command.begin(header, &self.input_path, record_boundary_position)?;
+        }
+
         Ok(())
     }
 
     fn message_extract_header(&mut self, header: &WarcHeader) -> anyhow::Result<()> {
+        if self.extractor.is_none() {
+            return Ok(());
+        }
+
+        let segment = SegmentInfo::from_header(header)?;
+
+        if let Some(segment) = segment.as_ref().filter(|segment| !segment.is_first()) {
+            if self
+                .segment
+                .as_ref()
+                .is_some_and(|previous| previous.is_continued_by(segment))
+            {
+                // The block continues the content of the previous segment.
+                self.segment = (!segment.is_last()).then(|| segment.clone());
+                return Ok(());
+            }
+        }
+
+        self.end_segmented_record()?;
+        self.segment = segment.filter(|segment| segment.is_first() && !segment.is_last());
+
         if let Some(extractor) = &mut self.extractor {
             extractor.read_header(header)?;
 
@@ -152,6 +219,16 @@ impl Exporter {
 
         self.message_extract_chunk(data)?;
 
+        if let Some(command) = &mut self.command {
+            if data.is_empty() {
+                command.finish()?;
+            } else if self.command_extractor.has_content() {
+                self.command_extractor.extract_data(data, &mut self.buf)?;
+                command.write(&self.buf)?;
+                self.buf.clear();
+            }
+        }
+
         Ok(())
     }
 
@@ -182,6 +259,10 @@ impl Exporter {
             }
 
             if data.is_empty() {
+                if self.segment.is_some() {
+                    return Ok(());
+                }
+
                 let checksum_map = self.extract_hasher.finish_u64();
                 let message = WarcMessage::ExtractEnd(ExtractEnd {
                     crc32: Some(checksum_map[&AlgorithmName::Crc32] as u32),
@@ -205,7 +286,22 @@ impl Exporter {
         Ok(())
     }
 
+    /// Ends the extracted content of a segmented record that is missing its
+    /// remaining segments.
+    fn end_segmented_record(&mut self) -> anyhow::Result<()> {
+        if let Some(segment) = self.segment.take() {
+            tracing::warn!(
+                origin_id = segment.origin_id,
+                "segmented record is missing segments"
+            );
+            self.message_extract_chunk(&[])?;
+        }
+
+        Ok(())
+    }
+
     pub fn finish(&mut self) -> anyhow::Result<()> {
+        self.end_segmented_record()?;
         self.writer.put(WarcMessage::EndOfFile(EndOfFile {}))?;
 
         Ok(())
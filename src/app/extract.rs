@@ -1,26 +1,53 @@
 use std::{
+    collections::{HashMap, HashSet},
     io::{Cursor, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use tempfile::NamedTempFile;
 
 use crate::{
     app::common::{ReaderEvent, ReaderPipeline},
-    error::GeneralError,
-    extract::{WarcExtractor, FILENAME_CONFLICT_MARKER},
-    header::WarcHeader,
+    dataseq::{SeqFormat, SeqWriter},
+    extract::{PathLayout, WarcExtractor, FILENAME_CONFLICT_MARKER},
+    header::{fields::FieldsExt, WarcHeader},
+    segment::SegmentInfo,
 };
 
-use super::{arg::ExtractCommand, filter::FieldFilter};
+use super::{
+    arg::{ExtractCommand, ExtractDedupe, ExtractOutputFormat, ExtractRecordType},
+    exec::RecordCommand,
+    extract_archive::ArchiveOutput,
+    filter::{FieldFilter, MediaTypeFilter},
+    io::ProgramOutput,
+    mime::SNIFF_LENGTH,
+    rewrite::{DocumentKind, LinkRewriter, Location},
+};
 
-// FIXME: continuation records not yet implemented.
+const METADATA_SUFFIX: &str = ".meta.json";
+const MANIFEST_FILENAME: &str = "manifest.jsonl";
 
 pub fn extract(args: &ExtractCommand) -> anyhow::Result<()> {
-    let output_dir = &args.output;
+    let output_path = &args.output;
 
-    if !output_dir.is_dir() {
-        anyhow::bail!("not a directory: {:?}", output_dir)
+    match args.output_format {
+        ExtractOutputFormat::Directory => {
+            if !output_path.is_dir() {
+                anyhow::bail!("not a directory: {:?}", output_path)
+            }
+        }
+        ExtractOutputFormat::Zip => {
+            if output_path.is_dir() || output_path.to_str() == Some("-") {
+                anyhow::bail!("not a file path: {:?}", output_path)
+            }
+        }
+        ExtractOutputFormat::Tar => {
+            if output_path.is_dir() {
+                anyhow::bail!("not a file path: {:?}", output_path)
+            }
+        }
     }
 
     let mut filter = FieldFilter::new();
@@ -38,6 +65,75 @@ pub fn extract(args: &ExtractCommand) -> anyhow::Result<()> {
         filter.add_exclude_pattern(rule)?;
     }
 
+    let mut media_type_filter = MediaTypeFilter::new();
+
+    for rule in &args.include_mime {
+        media_type_filter.add_include(rule);
+    }
+    for rule in &args.exclude_mime {
+        media_type_filter.add_exclude(rule);
+    }
+
+    let layout = PathLayout::from(args.layout);
+
+    if layout != PathLayout::Url && args.rewrite_links {
+        anyhow::bail!("rewriting links is supported only with the url layout")
+    }
+    if layout == PathLayout::Sha256Prefix
+        && args.exec.is_none()
+        && args.output_format != ExtractOutputFormat::Directory
+    {
+        anyhow::bail!("sha256-prefix layout is supported only when extracting to a directory")
+    }
+
+    let mut extractor = Extractor::new(output_path, filter)
+        .with_layout(layout)
+        .with_media_type_filter(media_type_filter)
+        .with_fix_extension(args.fix_extension)
+        .with_metadata_sidecar(args.metadata)
+        .with_modified_time(!args.no_mtime)
+        .with_dedupe(args.dedupe)
+        .with_certificates_only(args.certificates)
+        .with_extra_records(&args.extra_records)
+        .with_content_decoding(!args.no_content_decoding)
+        .with_link_rewriting(args.rewrite_links);
+
+    if let Some(command) = &args.exec {
+        extractor = extractor.with_command(RecordCommand::new(command));
+    } else {
+        match args.output_format {
+            ExtractOutputFormat::Directory => {}
+            ExtractOutputFormat::Zip => {
+                extractor = extractor.with_archive(ArchiveOutput::create_zip(output_path)?);
+            }
+            ExtractOutputFormat::Tar => {
+                let output = super::common::open_output(output_path)?;
+                extractor = extractor.with_archive(ArchiveOutput::create_tar(output));
+            }
+        }
+    }
+
+    if args.dry_run {
+        if args.exec.is_some() || args.output_format != ExtractOutputFormat::Directory {
+            anyhow::bail!("dry run is supported only when extracting to a directory")
+        }
+
+        let output = super::common::open_output(Path::new("-"))?;
+        extractor = extractor.with_dry_run(SeqWriter::new(output, SeqFormat::JsonL));
+    } else if layout != PathLayout::Url
+        && args.exec.is_none()
+        && args.output_format == ExtractOutputFormat::Directory
+    {
+        let file = std::fs::File::options()
+            .create(true)
+            .append(true)
+            .open(output_path.join(MANIFEST_FILENAME))?;
+        extractor = extractor.with_manifest(SeqWriter::new(file, SeqFormat::JsonL));
+    }
+
+    let continue_on_error =
+        args.continue_on_error || super::mode::global_mode().continues_on_error();
+
     for input_path in &args.input {
         let span = tracing::info_span!("extract", path = ?input_path);
         let _span_guard = span.enter();
@@ -49,17 +145,18 @@ pub fn extract(args: &ExtractCommand) -> anyhow::Result<()> {
         let compression_format = args.compression.try_into_native(input_path)?;
         let file_len = std::fs::metadata(input_path).map(|m| m.len()).ok();
 
-        let mut extractor = Extractor::new(output_dir, filter.clone());
+        extractor.set_input_path(input_path);
 
         ReaderPipeline::new(
             |event| match event {
                 ReaderEvent::Header {
                     header,
-                    record_boundary_position: _,
+                    record_boundary_position,
+                    ..
                 } => {
-                    let result = extractor.process_header(&header);
+                    let result = extractor.process_header(&header, record_boundary_position);
 
-                    if args.continue_on_error {
+                    if continue_on_error {
                         if let Err(error) = result {
                             let error = anyhow::anyhow!(error);
                             tracing::error!(?error, "error processing record header");
@@ -73,7 +170,7 @@ pub fn extract(args: &ExtractCommand) -> anyhow::Result<()> {
                 ReaderEvent::Block { data } => {
                     let result = extractor.process_data(data);
 
-                    if args.continue_on_error {
+                    if continue_on_error {
                         if let Err(error) = result {
                             let error = anyhow::anyhow!(error);
                             tracing::error!(?error, "error processing record block");
@@ -84,6 +181,7 @@ pub fn extract(args: &ExtractCommand) -> anyhow::Result<()> {
 
                     Ok(())
                 }
+                ReaderEvent::EndRecord { .. } => Ok(()),
             },
             input,
             compression_format,
@@ -94,6 +192,8 @@ pub fn extract(args: &ExtractCommand) -> anyhow::Result<()> {
         tracing::info!("closed file");
     }
 
+    extractor.finish()?;
+
     Ok(())
 }
 
@@ -104,6 +204,61 @@ struct Extractor {
     hasher: xxhash_rust::xxh3::Xxh3Default,
     output_dir: PathBuf,
     filter: FieldFilter,
+    media_type_filter: MediaTypeFilter,
+    input_path: PathBuf,
+    command: Option<RecordCommand>,
+    archive: Option<ArchiveOutput>,
+    archive_file: Option<String>,
+    pending: Option<(WarcHeader, u64)>,
+    components: Vec<String>,
+    extension: Option<&'static str>,
+    layout: PathLayout,
+    manifest: Option<SeqWriter<std::fs::File>>,
+    date: Option<DateTime<Utc>>,
+    is_metadata_sidecar: bool,
+    is_modified_time: bool,
+    is_fix_extension: bool,
+    is_certificates_only: bool,
+    link_rewriter: Option<LinkRewriter>,
+    rewrite_kind: Option<DocumentKind>,
+    segment: Option<SegmentInfo>,
+    dedupe: Option<ExtractDedupe>,
+    /// Mapping of digest and size => path of extracted files.
+    extracted_files: HashMap<(u64, u64), PathBuf>,
+    dry_run: Option<SeqWriter<ProgramOutput>>,
+    is_planning: bool,
+    planned_len: u64,
+    planned_files: HashSet<PathBuf>,
+    planned_dirs: HashSet<PathBuf>,
+}
+
+/// Whether a file would be written in a dry run.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum PlanStatus {
+    /// The file would be written.
+    New,
+    /// The file would be written with a name changed due to a conflict.
+    Renamed,
+    /// The file would not be written because the path already exists.
+    Exists,
+    /// The file would not be written because it is a duplicate.
+    Duplicate,
+}
+
+#[derive(Debug, Serialize)]
+struct ManifestEntry<'a> {
+    record_id: &'a str,
+    target_uri: &'a str,
+    path: &'a Path,
+}
+
+#[derive(Debug, Serialize)]
+struct PlannedFile<'a> {
+    record_id: &'a str,
+    target_uri: &'a str,
+    path: &'a Path,
+    status: PlanStatus,
 }
 
 impl Extractor {
@@ -111,112 +266,633 @@ impl Extractor {
         Self {
             output_dir: output_dir.into(),
             filter,
+            media_type_filter: MediaTypeFilter::new(),
             extractor: WarcExtractor::new(),
             buf: Vec::new(),
             hasher: xxhash_rust::xxh3::Xxh3Default::new(),
             file: None,
+            input_path: PathBuf::new(),
+            command: None,
+            archive: None,
+            archive_file: None,
+            pending: None,
+            components: Vec::new(),
+            extension: None,
+            layout: PathLayout::default(),
+            manifest: None,
+            date: None,
+            is_metadata_sidecar: false,
+            is_modified_time: false,
+            is_fix_extension: false,
+            is_certificates_only: false,
+            link_rewriter: None,
+            rewrite_kind: None,
+            segment: None,
+            dedupe: None,
+            extracted_files: HashMap::new(),
+            dry_run: None,
+            is_planning: false,
+            planned_len: 0,
+            planned_files: HashSet::new(),
+            planned_dirs: HashSet::new(),
+        }
+    }
+
+    /// Set the directory structure of the extracted files.
+    fn with_layout(mut self, layout: PathLayout) -> Self {
+        self.extractor = std::mem::take(&mut self.extractor).with_layout(layout);
+        self.layout = layout;
+        self
+    }
+
+    /// Append the paths of the extracted files to the manifest.
+    fn with_manifest(mut self, writer: SeqWriter<std::fs::File>) -> Self {
+        self.manifest = Some(writer);
+        self
+    }
+
+    /// Select only content with the given media types.
+    fn with_media_type_filter(mut self, filter: MediaTypeFilter) -> Self {
+        self.media_type_filter = filter;
+        self
+    }
+
+    /// Append a filename extension for the media type if the URL does not have one.
+    fn with_fix_extension(mut self, value: bool) -> Self {
+        self.is_fix_extension = value;
+        self
+    }
+
+    /// Rewrite links in HTML and CSS documents to the paths of extracted files.
+    fn with_link_rewriting(mut self, value: bool) -> Self {
+        self.link_rewriter = value.then(LinkRewriter::new);
+        self
+    }
+
+    /// Set the modification time of extracted files to the WARC-Date.
+    fn with_modified_time(mut self, value: bool) -> Self {
+        self.is_modified_time = value;
+        self
+    }
+
+    /// Link, copy, or skip files with the same content as a previously
+    /// extracted file.
+    fn with_dedupe(mut self, value: Option<ExtractDedupe>) -> Self {
+        self.dedupe = value;
+        self
+    }
+
+    /// Also extract requests or metadata records.
+    fn with_extra_records(mut self, types: &[ExtractRecordType]) -> Self {
+        self.extractor = std::mem::take(&mut self.extractor)
+            .with_requests(types.contains(&ExtractRecordType::Request))
+            .with_metadata_records(types.contains(&ExtractRecordType::Metadata));
+        self
+    }
+
+    /// Decode the Content-Encoding of HTTP messages.
+    fn with_content_decoding(mut self, value: bool) -> Self {
+        self.extractor = std::mem::take(&mut self.extractor).with_content_decoding(value);
+        self
+    }
+
+    /// Select only records containing TLS certificates.
+    fn with_certificates_only(mut self, value: bool) -> Self {
+        self.is_certificates_only = value;
+        self
+    }
+
+    /// Print the paths of the files instead of writing them.
+    fn with_dry_run(mut self, writer: SeqWriter<ProgramOutput>) -> Self {
+        self.dry_run = Some(writer);
+        self
+    }
+
+    /// Write a metadata file next to each extracted file.
+    fn with_metadata_sidecar(mut self, value: bool) -> Self {
+        self.is_metadata_sidecar = value;
+        self
+    }
+
+    /// Run the command for each record instead of writing files.
+    fn with_command(mut self, command: RecordCommand) -> Self {
+        self.command = Some(command);
+        self
+    }
+
+    /// Write files into an archive instead of the output directory.
+    fn with_archive(mut self, archive: ArchiveOutput) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    fn set_input_path(&mut self, path: &Path) {
+        self.input_path = path.to_path_buf();
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        self.end_segmented_record()?;
+
+        if let Some(archive) = self.archive.take() {
+            archive.finish()?;
         }
+
+        Ok(())
     }
 
-    fn process_header(&mut self, header: &WarcHeader) -> anyhow::Result<()> {
+    fn process_header(&mut self, header: &WarcHeader, position: u64) -> anyhow::Result<()> {
+        let segment = SegmentInfo::from_header(header)?;
+
+        if let Some(segment) = segment.as_ref().filter(|segment| !segment.is_first()) {
+            if self
+                .segment
+                .as_ref()
+                .is_some_and(|previous| previous.is_continued_by(segment))
+            {
+                // The block continues the content of the previous segment.
+                self.segment = (!segment.is_last()).then(|| segment.clone());
+                return Ok(());
+            }
+
+            tracing::warn!(
+                origin_id = segment.origin_id,
+                "skipped segment without previous segment"
+            );
+        }
+
+        self.end_segmented_record()?;
+        self.segment = segment.filter(|segment| segment.is_first() && !segment.is_last());
+
         self.extractor.reset();
+        self.pending = None;
+        self.rewrite_kind = None;
+        self.buf.clear();
 
         if !self.filter.is_allow(header) {
             return Ok(());
         }
 
+        if self.is_certificates_only && !crate::cert::is_certificate_record(header) {
+            return Ok(());
+        }
+
         self.extractor.read_header(header)?;
 
-        if self.extractor.has_content() {
-            self.file = Some(
-                tempfile::Builder::new()
-                    .prefix("extract-")
-                    .suffix(".incomplete.tmp")
-                    .tempfile_in(&self.output_dir)?,
+        if !self.extractor.has_content() {
+            return Ok(());
+        }
+
+        self.date = header
+            .fields
+            .get_date("WARC-Date")
+            .and_then(|date| date.ok())
+            .map(|date| date.to_utc());
+
+        // The output is started when the start of the content is available
+        // for determining the media type.
+        self.pending = Some((header.clone(), position));
+
+        Ok(())
+    }
+
+    /// Finishes the output of a segmented record that is missing its
+    /// remaining segments.
+    fn end_segmented_record(&mut self) -> anyhow::Result<()> {
+        if let Some(segment) = self.segment.take() {
+            tracing::warn!(
+                origin_id = segment.origin_id,
+                "segmented record is missing segments"
             );
+            self.process_data(&[])?;
         }
 
         Ok(())
     }
 
     fn process_data(&mut self, data: &[u8]) -> anyhow::Result<()> {
-        self.write_extracted_data(data)?;
-        self.finish_processing_data(data)?;
+        if self.pending.is_none() && !self.is_output_open() {
+            return Ok(());
+        }
+
+        // The content of a segmented record continues in the next segment.
+        let is_end = data.is_empty() && self.segment.is_none();
+
+        if !data.is_empty() {
+            self.extractor.extract_data(data, &mut self.buf)?;
+        }
+
+        if self.pending.is_some() {
+            if !is_end && self.buf.len() < SNIFF_LENGTH {
+                return Ok(());
+            }
+
+            let (header, position) = self.pending.take().unwrap();
+
+            if is_end && self.buf.is_empty() {
+                tracing::debug!("skipped record without content");
+                return Ok(());
+            }
+            let media_type =
+                super::mime::content_media_type(&header, self.extractor.metadata(), &self.buf);
+
+            if !self.media_type_filter.is_allow(&media_type) {
+                tracing::debug!(media_type, "skipped content by media type");
+                self.buf.clear();
+                return Ok(());
+            }
+
+            self.begin_output(&header, position, &media_type)?;
+
+            if self.link_rewriter.is_some() {
+                self.rewrite_kind = DocumentKind::from_media_type(&media_type);
+            }
+        }
+
+        if self.rewrite_kind.is_some() {
+            // The whole document is needed for rewriting.
+            if !is_end {
+                return Ok(());
+            }
+
+            self.rewrite_links();
+        }
+
+        self.write_output()?;
+
+        if is_end {
+            self.finish_output()?;
+        }
 
         Ok(())
     }
 
-    fn write_extracted_data(&mut self, data: &[u8]) -> Result<(), GeneralError> {
-        if let Some(writer) = &mut self.file {
-            self.extractor.extract_data(data, &mut self.buf)?;
+    fn rewrite_links(&mut self) {
+        let (Some(rewriter), Some(kind)) = (&self.link_rewriter, self.rewrite_kind.take()) else {
+            return;
+        };
+
+        if let Some(location) =
+            Location::new(&self.extractor.metadata().target_uri, &self.components)
+        {
+            self.buf = rewriter.rewrite(kind, &location, &self.buf);
+        }
+    }
+
+    fn is_output_open(&self) -> bool {
+        self.command
+            .as_ref()
+            .is_some_and(|command| command.is_running())
+            || self.archive_file.is_some()
+            || self.file.is_some()
+            || self.is_planning
+    }
+
+    fn begin_output(
+        &mut self,
+        header: &WarcHeader,
+        position: u64,
+        media_type: &str,
+    ) -> anyhow::Result<()> {
+        self.extension =
+            if self.is_fix_extension && !has_url_extension(&self.extractor.metadata().target_uri) {
+                super::mime::media_type_extension(media_type)
+            } else {
+                None
+            };
+        self.update_components();
+
+        if self.dry_run.is_some() {
+            self.is_planning = true;
+        } else if let Some(command) = &mut self.command {
+            command.begin(header, &self.input_path, position)?;
+        } else if let Some(archive) = &mut self.archive {
+            let conflict_id = xxhash_rust::xxh3::xxh3_64(
+                header.fields.get_or_default("WARC-Record-ID").as_bytes(),
+            );
+            let is_large = header.content_length().unwrap_or(u64::MAX) >= u32::MAX as u64;
+            let name = archive.start_file(
+                &self.components,
+                self.extractor.is_truncated(),
+                conflict_id,
+                is_large,
+                self.date,
+            )?;
+
+            tracing::info!(name, "extracting file to archive");
+
+            self.archive_file = Some(name);
+        } else {
+            self.file = Some(super::temp::named_tempfile_for(
+                &self.output_dir,
+                "extract-",
+                ".incomplete.tmp",
+            )?);
+        }
+
+        Ok(())
+    }
+
+    /// Sets the path components of the file from the extractor.
+    ///
+    /// For content-addressed layouts, this must be called again once the
+    /// content is extracted.
+    fn update_components(&mut self) {
+        self.components = self.extractor.file_path_components();
+
+        if let (Some(extension), Some(last)) = (self.extension, self.components.last_mut()) {
+            last.push('.');
+            last.push_str(extension);
+        }
+    }
+
+    fn write_output(&mut self) -> anyhow::Result<()> {
+        if let Some(command) = &mut self.command {
+            command.write(&self.buf)?;
+        } else if let Some(archive) = &mut self.archive {
+            archive.write_all(&self.buf)?;
+        } else if let Some(writer) = &mut self.file {
             self.hasher.update(&self.buf);
             std::io::copy(&mut Cursor::new(&self.buf), writer)?;
-            self.buf.clear();
+        } else if self.is_planning {
+            self.hasher.update(&self.buf);
+            self.planned_len += self.buf.len() as u64;
         }
 
+        self.buf.clear();
+
         Ok(())
     }
 
-    fn finish_processing_data(&mut self, data: &[u8]) -> std::io::Result<()> {
-        if self.file.is_some() && data.is_empty() {
-            let digest = self.hasher.digest();
-            self.hasher.reset();
+    fn finish_output(&mut self) -> anyhow::Result<()> {
+        if self.is_planning {
+            self.finish_plan()?;
+        } else if let Some(command) = &mut self.command {
+            command.finish()?;
+        } else if let Some(archive) = &mut self.archive {
+            archive.finish_file()?;
 
-            let file = self.file.take().unwrap();
+            let name = self.archive_file.take().unwrap();
 
-            let target_path = self.create_target_path(digest);
+            if self.is_metadata_sidecar {
+                let data = serde_json::to_vec_pretty(self.extractor.metadata())?;
+                let name = format!("{}{}", name, METADATA_SUFFIX);
+                archive.add_file(&name, &data, self.date)?;
+            }
+        } else {
+            let path = self.finish_file()?;
+
+            if let Some(manifest) = &mut self.manifest {
+                let metadata = self.extractor.metadata();
+                manifest.put(ManifestEntry {
+                    record_id: &metadata.record_id,
+                    target_uri: &metadata.target_uri,
+                    path: path.strip_prefix(&self.output_dir).unwrap_or(&path),
+                })?;
+            }
+        }
 
-            if !target_path.exists() {
-                std::fs::create_dir_all(target_path.parent().unwrap())?;
-                let (mut file, temp_path) = file.keep()?;
+        Ok(())
+    }
+
+    /// Moves the temporary file to the target path and returns the path of
+    /// the file with the content.
+    fn finish_file(&mut self) -> std::io::Result<PathBuf> {
+        let digest = self.hasher.digest();
+        self.hasher.reset();
+
+        let mut file = self.file.take().unwrap();
+        let key = (digest, file.as_file().metadata()?.len());
+
+        if self.layout == PathLayout::Sha256Prefix {
+            self.update_components();
+        }
+
+        let (target_path, _) = self.create_target_path(digest);
+
+        if !target_path.exists() {
+            let original = self
+                .extracted_files
+                .get(&key)
+                .filter(|path| path.is_file())
+                .cloned();
+
+            if let (Some(ExtractDedupe::Skip), Some(original)) = (self.dedupe, &original) {
+                tracing::info!(path = ?target_path, ?original, "skipped duplicate file");
+                return Ok(original.clone());
+            }
+
+            std::fs::create_dir_all(target_path.parent().unwrap())?;
+
+            let is_deduplicated = match original {
+                Some(original) => self.dedupe_file(&original, &target_path)?,
+                None => false,
+            };
+
+            if !is_deduplicated {
                 file.flush()?;
-                std::fs::rename(temp_path, &target_path)?;
+                let file = super::temp::persist(file, &target_path)?;
+
+                if let (true, Some(date)) = (self.is_modified_time, self.date) {
+                    file.set_modified(date.into())?;
+                }
+
+                if self.dedupe.is_some() {
+                    self.extracted_files.insert(key, target_path.clone());
+                }
+            }
 
-                tracing::info!(path = ?target_path, "extracted file");
+            if self.is_metadata_sidecar {
+                let mut metadata_path = target_path.clone().into_os_string();
+                metadata_path.push(METADATA_SUFFIX);
+                let data = serde_json::to_vec_pretty(self.extractor.metadata())?;
+                std::fs::write(metadata_path, data)?;
             }
+
+            tracing::info!(path = ?target_path, "extracted file");
         }
 
-        Ok(())
+        Ok(target_path)
     }
 
-    fn create_target_path(&self, conflict_id: u64) -> PathBuf {
-        let mut target_path = self.output_dir.clone();
-        let components = self.extractor.file_path_components();
+    fn finish_plan(&mut self) -> anyhow::Result<()> {
+        let digest = self.hasher.digest();
+        self.hasher.reset();
+        self.is_planning = false;
 
-        let mut iter = components.iter().peekable();
+        let key = (digest, std::mem::take(&mut self.planned_len));
 
-        while let Some(component) = iter.next() {
-            let is_last_component = iter.peek().is_none();
+        if self.layout == PathLayout::Sha256Prefix {
+            self.update_components();
+        }
 
-            if is_last_component {
-                let mut base_filename = component.to_string();
+        let (target_path, is_renamed) = self.create_target_path(digest);
+
+        let status = if self.path_exists(&target_path) {
+            PlanStatus::Exists
+        } else if self.dedupe == Some(ExtractDedupe::Skip)
+            && self.extracted_files.contains_key(&key)
+        {
+            PlanStatus::Duplicate
+        } else {
+            if self.dedupe.is_some() {
+                self.extracted_files
+                    .entry(key)
+                    .or_insert_with(|| target_path.clone());
+            }
 
-                if self.extractor.is_truncated() {
-                    base_filename.push(FILENAME_CONFLICT_MARKER);
-                    base_filename.push_str("truncated");
+            for path in target_path.ancestors().skip(1) {
+                if path == self.output_dir || !self.planned_dirs.insert(path.to_path_buf()) {
+                    break;
                 }
+            }
+            self.planned_files.insert(target_path.clone());
 
-                target_path.push(&base_filename);
+            if is_renamed {
+                PlanStatus::Renamed
+            } else {
+                PlanStatus::New
+            }
+        };
+
+        let metadata = self.extractor.metadata();
+        self.dry_run.as_mut().unwrap().put(PlannedFile {
+            record_id: &metadata.record_id,
+            target_uri: &metadata.target_uri,
+            path: &target_path,
+            status,
+        })?;
+
+        Ok(())
+    }
 
-                if target_path.exists() {
-                    // File or directory already exists, append a unique ID to the name.
-                    target_path.pop();
-                    target_path.push(format!(
-                        "{}{}{:016x}",
-                        base_filename, FILENAME_CONFLICT_MARKER, conflict_id
-                    ));
+    /// Returns whether a file or directory exists or would exist in a dry run.
+    fn path_exists(&self, path: &Path) -> bool {
+        path.exists() || self.planned_files.contains(path) || self.planned_dirs.contains(path)
+    }
+
+    /// Returns whether a file exists or would exist in a dry run.
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file() || self.planned_files.contains(path)
+    }
+
+    /// Links or copies the previously extracted file to the target path.
+    ///
+    /// Returns false if the file needs to be written instead.
+    fn dedupe_file(&self, original: &Path, target_path: &Path) -> std::io::Result<bool> {
+        match self.dedupe {
+            Some(ExtractDedupe::Hardlink) => {
+                if let Err(error) = std::fs::hard_link(original, target_path) {
+                    tracing::debug!(?error, ?original, "hard link failed");
+                    return Ok(false);
                 }
-            } else {
-                target_path.push(component);
 
-                if target_path.is_file() {
-                    // File exists in place of directory component, append ".d"-style to the name
-                    target_path.pop();
-                    target_path.push(format!("{}{}d", component, FILENAME_CONFLICT_MARKER));
+                tracing::info!(path = ?target_path, ?original, "linked duplicate file");
+            }
+            Some(ExtractDedupe::Copy) => {
+                std::fs::copy(original, target_path)?;
+
+                if let (true, Some(date)) = (self.is_modified_time, self.date) {
+                    std::fs::File::options()
+                        .write(true)
+                        .open(target_path)?
+                        .set_modified(date.into())?;
                 }
+
+                tracing::info!(path = ?target_path, ?original, "copied duplicate file");
             }
+            Some(ExtractDedupe::Skip) | None => return Ok(false),
         }
 
-        target_path
+        Ok(true)
+    }
+
+    /// Returns the path for the file and whether the path was changed due
+    /// to a conflict.
+    fn create_target_path(&self, conflict_id: u64) -> (PathBuf, bool) {
+        let join = |components: &[String]| {
+            let mut path = self.output_dir.clone();
+            path.extend(components);
+            path
+        };
+
+        let (components, is_renamed) = resolve_path_conflicts(
+            &self.components,
+            self.extractor.is_truncated(),
+            conflict_id,
+            // Files with the same digest in a content-addressed layout
+            // have the same content.
+            self.layout != PathLayout::Sha256Prefix,
+            |components| self.is_file(&join(components)),
+            |components| self.path_exists(&join(components)),
+        );
+
+        (join(&components), is_renamed)
+    }
+}
+
+/// Returns the path components of a file with the names changed to avoid
+/// existing files and directories, and whether a name was changed.
+///
+/// The `is_file` and `path_exists` functions are given the components of a
+/// path to check. If `rename_existing` is false, an existing file at the
+/// final path is not avoided.
+pub(super) fn resolve_path_conflicts(
+    components: &[String],
+    is_truncated: bool,
+    conflict_id: u64,
+    rename_existing: bool,
+    is_file: impl Fn(&[String]) -> bool,
+    path_exists: impl Fn(&[String]) -> bool,
+) -> (Vec<String>, bool) {
+    let mut output = Vec::with_capacity(components.len());
+    let mut is_renamed = false;
+    let mut iter = components.iter().peekable();
+
+    while let Some(component) = iter.next() {
+        let is_last_component = iter.peek().is_none();
+
+        if is_last_component {
+            let mut base_filename = component.to_string();
+
+            if is_truncated {
+                base_filename.push(FILENAME_CONFLICT_MARKER);
+                base_filename.push_str("truncated");
+            }
+
+            output.push(base_filename);
+
+            if rename_existing && path_exists(&output) {
+                // File or directory already exists, append a unique ID to the name.
+                is_renamed = true;
+                let base_filename = output.pop().unwrap();
+                output.push(format!(
+                    "{}{}{:016x}",
+                    base_filename, FILENAME_CONFLICT_MARKER, conflict_id
+                ));
+            }
+        } else {
+            output.push(component.clone());
+
+            if is_file(&output) {
+                // File exists in place of directory component, append ".d"-style to the name
+                is_renamed = true;
+                output.pop();
+                output.push(format!("{}{}d", component, FILENAME_CONFLICT_MARKER));
+            }
+        }
     }
+
+    (output, is_renamed)
+}
+
+/// Returns whether the last segment of the URL path has a filename extension.
+fn has_url_extension(url: &str) -> bool {
+    let path = match url::Url::parse(url) {
+        Ok(url) => url.path().to_string(),
+        Err(_) => url.to_string(),
+    };
+
+    path.rsplit('/')
+        .next()
+        .is_some_and(|segment| segment.contains('.'))
 }
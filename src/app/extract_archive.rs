@@ -0,0 +1,313 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{Seek, Write},
+    path::Path,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use super::{extract::resolve_path_conflicts, io::ProgramOutput};
+
+/// Writes extracted files into an archive file.
+///
+/// Files are written into the archive one at a time.
+/// Name conflicts are resolved in the same manner as extracting to a
+/// directory.
+pub struct ArchiveOutput {
+    writer: ArchiveWriter,
+    files: HashSet<String>,
+    dirs: HashSet<String>,
+}
+
+enum ArchiveWriter {
+    Zip(Box<ZipWriter<File>>),
+    Tar {
+        builder: tar::Builder<ProgramOutput>,
+        entry: Option<TarEntry>,
+    },
+}
+
+/// A tar file entry that is buffered to a temporary file because the size
+/// must be known before writing the entry header.
+struct TarEntry {
+    name: String,
+    date: Option<DateTime<Utc>>,
+    file: File,
+}
+
+impl ArchiveOutput {
+    fn new(writer: ArchiveWriter) -> Self {
+        Self {
+            writer,
+            files: HashSet::new(),
+            dirs: HashSet::new(),
+        }
+    }
+
+    /// Creates a zip file.
+    ///
+    /// Files are written directly into the zip file without a temporary
+    /// directory.
+    pub fn create_zip(path: &Path) -> anyhow::Result<Self> {
+        let file = File::create(path).with_context(|| format!("creating {:?} failed", path))?;
+
+        Ok(Self::new(ArchiveWriter::Zip(Box::new(ZipWriter::new(
+            file,
+        )))))
+    }
+
+    /// Creates a tar stream.
+    ///
+    /// The output may be a stream such as standard output.
+    pub fn create_tar(output: ProgramOutput) -> Self {
+        let builder = tar::Builder::new(output);
+
+        Self::new(ArchiveWriter::Tar {
+            builder,
+            entry: None,
+        })
+    }
+
+    /// Begins a new file in the archive and returns the name of the file.
+    ///
+    /// The `conflict_id` is appended to the name when a file of the same
+    /// name already exists. If that name is also taken, such as by a record
+    /// with the same ID, the next ID is used. `is_large` should be set if the file may be
+    /// larger than 4 GB.
+    pub fn start_file(
+        &mut self,
+        components: &[String],
+        is_truncated: bool,
+        conflict_id: u64,
+        is_large: bool,
+        date: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<String> {
+        self.finish_file()?;
+
+        let name = self.create_name(components, is_truncated, conflict_id);
+
+        match &mut self.writer {
+            ArchiveWriter::Zip(writer) => {
+                let mut options = SimpleFileOptions::default()
+                    .compression_method(CompressionMethod::Deflated)
+                    .large_file(is_large);
+
+                if let Some(date) = date.and_then(zip_date_time) {
+                    options = options.last_modified_time(date);
+                }
+
+                writer.start_file(name.as_str(), options)?;
+            }
+            ArchiveWriter::Tar { entry, .. } => {
+                *entry = Some(TarEntry {
+                    name: name.clone(),
+                    date,
+                    file: super::temp::tempfile()?,
+                });
+            }
+        }
+
+        for (index, _) in name.match_indices('/') {
+            self.dirs.insert(name[0..index].to_string());
+        }
+        self.files.insert(name.clone());
+
+        Ok(name)
+    }
+
+    /// Adds a file with the given name and contents to the archive.
+    ///
+    /// The name is used as is without resolving conflicts.
+    pub fn add_file(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        date: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<()> {
+        self.finish_file()?;
+
+        match &mut self.writer {
+            ArchiveWriter::Zip(writer) => {
+                let mut options =
+                    SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+                if let Some(date) = date.and_then(zip_date_time) {
+                    options = options.last_modified_time(date);
+                }
+
+                writer.start_file(name, options)?;
+                writer.write_all(data)?;
+            }
+            ArchiveWriter::Tar { builder, .. } => {
+                let mut header = tar_header(data.len() as u64, date);
+                builder.append_data(&mut header, name, data)?;
+            }
+        }
+
+        self.files.insert(name.to_string());
+
+        Ok(())
+    }
+
+    /// Completes the current file in the archive.
+    pub fn finish_file(&mut self) -> anyhow::Result<()> {
+        if let ArchiveWriter::Tar { builder, entry } = &mut self.writer {
+            if let Some(mut entry) = entry.take() {
+                let size = entry.file.stream_position()?;
+                entry.file.rewind()?;
+
+                let mut header = tar_header(size, entry.date);
+                builder.append_data(&mut header, &entry.name, &mut entry.file)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create_name(&self, components: &[String], is_truncated: bool, conflict_id: u64) -> String {
+        let mut conflict_id = conflict_id;
+
+        loop {
+            let (components, _) = resolve_path_conflicts(
+                components,
+                is_truncated,
+                conflict_id,
+                true,
+                |components| self.files.contains(&components.join("/")),
+                |components| self.path_exists(&components.join("/")),
+            );
+            let name = components.join("/");
+
+            if !self.path_exists(&name) {
+                return name;
+            }
+
+            conflict_id = conflict_id.wrapping_add(1);
+        }
+    }
+
+    fn path_exists(&self, name: &str) -> bool {
+        self.files.contains(name) || self.dirs.contains(name)
+    }
+
+    /// Writes the end of the archive and closes the file.
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        self.finish_file()?;
+
+        match self.writer {
+            ArchiveWriter::Zip(writer) => {
+                let mut file = writer.finish()?;
+                file.flush()?;
+            }
+            ArchiveWriter::Tar { builder, .. } => {
+                let mut output = builder.into_inner()?;
+                output.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for ArchiveOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.writer {
+            ArchiveWriter::Zip(writer) => writer.write(buf),
+            ArchiveWriter::Tar { entry, .. } => match entry {
+                Some(entry) => entry.file.write(buf),
+                None => Ok(buf.len()),
+            },
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.writer {
+            ArchiveWriter::Zip(writer) => writer.flush(),
+            ArchiveWriter::Tar { entry, .. } => match entry {
+                Some(entry) => entry.file.flush(),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+fn tar_header(size: u64, date: Option<DateTime<Utc>>) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_mtime(
+        date.map(|date| date.timestamp().max(0) as u64)
+            .unwrap_or_default(),
+    );
+    header.set_entry_type(tar::EntryType::Regular);
+    header
+}
+
+fn zip_date_time(date: DateTime<Utc>) -> Option<zip::DateTime> {
+    zip::DateTime::from_date_and_time(
+        u16::try_from(date.year()).ok()?,
+        date.month() as u8,
+        date.day() as u8,
+        date.hour() as u8,
+        date.minute() as u8,
+        date.second() as u8,
+    )
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zip_name_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output.zip");
+        let mut archive = ArchiveOutput::create_zip(&path).unwrap();
+        let components = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut names = Vec::new();
+
+        for parts in [
+            &["example.com", "index.html"][..],
+            &["example.com", "index.html"],
+            &["example.com", "index.html"],
+            &["example.com", "index.html", "a.html"],
+        ] {
+            let name = archive
+                .start_file(&components(parts), false, 0xabc, false, None)
+                .unwrap();
+            archive.write_all(name.as_bytes()).unwrap();
+            names.push(name);
+        }
+
+        archive.finish().unwrap();
+
+        assert_eq!(
+            names,
+            [
+                "example.com/index.html",
+                "example.com/index.html⬧0000000000000abc",
+                "example.com/index.html⬧0000000000000abd",
+                "example.com/index.html⬧d/a.html",
+            ]
+        );
+
+        let mut zip = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(zip.len(), 4);
+
+        for name in names {
+            let mut data = String::new();
+            std::io::Read::read_to_string(&mut zip.by_name(&name).unwrap(), &mut data).unwrap();
+            assert_eq!(data, name);
+        }
+    }
+}
@@ -1,11 +1,45 @@
 use regex::Regex;
 
-use crate::header::WarcHeader;
+use crate::header::{fields::FieldsExt, WarcHeader};
+
+/// Value of a name-value rule.
+///
+/// A value prefixed with `~` is compared case-insensitively with
+/// surrounding whitespace ignored. Other values are compared exactly.
+#[derive(Debug, Clone)]
+struct ValueRule {
+    value: String,
+    is_relaxed: bool,
+}
+
+impl ValueRule {
+    fn parse(rule: &str) -> Self {
+        if let Some(value) = rule.strip_prefix('~') {
+            Self {
+                value: value.trim().to_string(),
+                is_relaxed: true,
+            }
+        } else {
+            Self {
+                value: rule.to_string(),
+                is_relaxed: false,
+            }
+        }
+    }
+
+    fn is_match(&self, value: &str) -> bool {
+        if self.is_relaxed {
+            value.trim().eq_ignore_ascii_case(&self.value)
+        } else {
+            value == self.value
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct FieldFilter {
-    includes: Vec<(String, Option<String>)>,
-    excludes: Vec<(String, Option<String>)>,
+    includes: Vec<(String, Option<ValueRule>)>,
+    excludes: Vec<(String, Option<ValueRule>)>,
     include_patterns: Vec<(String, Regex)>,
     exclude_patterns: Vec<(String, Regex)>,
 }
@@ -23,7 +57,7 @@ impl FieldFilter {
     pub fn add_include(&mut self, rule: &str) {
         if let Some((name, value)) = rule.split_once(":") {
             self.includes
-                .push((name.to_string(), Some(value.to_string())));
+                .push((name.to_string(), Some(ValueRule::parse(value))));
         } else {
             self.includes.push((rule.to_string(), None));
         }
@@ -32,7 +66,7 @@ impl FieldFilter {
     pub fn add_exclude(&mut self, rule: &str) {
         if let Some((name, value)) = rule.split_once(":") {
             self.excludes
-                .push((name.to_string(), Some(value.to_string())));
+                .push((name.to_string(), Some(ValueRule::parse(value))));
         } else {
             self.excludes.push((rule.to_string(), None));
         }
@@ -60,7 +94,7 @@ impl FieldFilter {
         for (rule_name, rule_value) in &self.excludes {
             if let Some(rule_value) = rule_value {
                 for value in header.fields.get_all(rule_name) {
-                    if value == rule_value {
+                    if rule_value.is_match(value) {
                         return false;
                     }
                 }
@@ -80,7 +114,7 @@ impl FieldFilter {
         for (rule_name, rule_value) in &self.includes {
             if let Some(rule_value) = rule_value {
                 for value in header.fields.get_all(rule_name) {
-                    if value == rule_value {
+                    if rule_value.is_match(value) {
                         return true;
                     }
                 }
@@ -101,6 +135,79 @@ impl FieldFilter {
     }
 }
 
+/// Filter by media types such as "image/png" or "image/*".
+#[derive(Debug, Clone, Default)]
+pub struct MediaTypeFilter {
+    includes: Vec<String>,
+    excludes: Vec<String>,
+}
+
+impl MediaTypeFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_include(&mut self, rule: &str) {
+        self.includes.push(rule.trim().to_string());
+    }
+
+    pub fn add_exclude(&mut self, rule: &str) {
+        self.excludes.push(rule.trim().to_string());
+    }
+
+    pub fn is_allow(&self, media_type: &str) -> bool {
+        if self
+            .excludes
+            .iter()
+            .any(|rule| super::mime::is_media_type_match(rule, media_type))
+        {
+            return false;
+        }
+
+        self.includes.is_empty()
+            || self
+                .includes
+                .iter()
+                .any(|rule| super::mime::is_media_type_match(rule, media_type))
+    }
+}
+
+/// Bytes that generators write as padding in place of a block.
+const PADDING_BYTES: [u8; 5] = [b'\r', b'\n', b' ', b'\t', 0];
+
+/// Detects resource and metadata records without content.
+///
+/// Some generators write resource and metadata records with an empty block
+/// or a block of only line endings, spaces, or NUL bytes as padding.
+#[derive(Debug, Clone, Default)]
+pub struct EmptyRecordFilter {
+    is_candidate: bool,
+    is_empty: bool,
+}
+
+impl EmptyRecordFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn begin_record(&mut self, header: &WarcHeader) {
+        self.is_candidate = matches!(
+            header.fields.get_or_default("WARC-Type"),
+            "resource" | "metadata"
+        );
+        self.is_empty = true;
+    }
+
+    pub fn block_data(&mut self, data: &[u8]) {
+        self.is_empty = self.is_empty && data.iter().all(|byte| PADDING_BYTES.contains(byte));
+    }
+
+    /// Returns whether the current record has no content.
+    pub fn is_empty_record(&self) -> bool {
+        self.is_candidate && self.is_empty
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,6 +250,46 @@ mod tests {
         assert!(!filter.is_allow(&header2));
     }
 
+    #[test]
+    fn test_filter_relaxed() {
+        let mut header1 = WarcHeader::empty();
+        header1
+            .fields
+            .insert("WARC-Type".to_string(), " Response ".to_string());
+        let mut header2 = WarcHeader::empty();
+        header2
+            .fields
+            .insert("WARC-Type".to_string(), "request".to_string());
+        let mut header3 = WarcHeader::empty();
+        header3
+            .fields
+            .insert("WARC-Type".to_string(), "~response".to_string());
+
+        let mut filter = FieldFilter::new();
+        filter.add_include("warc-type:~response");
+
+        assert!(filter.is_allow(&header1));
+        assert!(!filter.is_allow(&header2));
+        assert!(!filter.is_allow(&header3));
+
+        let mut filter = FieldFilter::new();
+        filter.add_include("WARC-Type:response");
+
+        assert!(!filter.is_allow(&header1));
+
+        let mut filter = FieldFilter::new();
+        filter.add_include("WARC-Type:=response");
+
+        assert!(!filter.is_allow(&header1));
+        assert!(!filter.is_allow(&header3));
+
+        let mut header4 = WarcHeader::empty();
+        header4
+            .fields
+            .insert("WARC-Type".to_string(), "=response".to_string());
+        assert!(filter.is_allow(&header4));
+    }
+
     #[test]
     fn test_filter_regex() {
         let mut header1 = WarcHeader::empty();
@@ -165,4 +312,42 @@ mod tests {
         assert!(!filter.is_allow(&header3));
         assert!(!filter.is_allow(&header4));
     }
+
+    #[test]
+    fn test_media_type_filter() {
+        let mut filter = MediaTypeFilter::new();
+
+        assert!(filter.is_allow("text/html"));
+
+        filter.add_include("image/*");
+        filter.add_include("text/html");
+        filter.add_exclude("image/svg+xml");
+
+        assert!(filter.is_allow("text/html"));
+        assert!(filter.is_allow("image/png"));
+        assert!(!filter.is_allow("image/svg+xml"));
+        assert!(!filter.is_allow("text/plain"));
+    }
+
+    #[test]
+    fn test_empty_record_filter() {
+        let mut filter = EmptyRecordFilter::new();
+
+        filter.begin_record(&WarcHeader::new(0, "resource"));
+        assert!(filter.is_empty_record());
+
+        filter.begin_record(&WarcHeader::new(4, "metadata"));
+        filter.block_data(b"\r\n");
+        filter.block_data(b"\0 ");
+        assert!(filter.is_empty_record());
+
+        filter.begin_record(&WarcHeader::new(3, "resource"));
+        filter.block_data(b"\r\n");
+        filter.block_data(b"a");
+        filter.block_data(b"\n");
+        assert!(!filter.is_empty_record());
+
+        filter.begin_record(&WarcHeader::new(0, "revisit"));
+        assert!(!filter.is_empty_record());
+    }
 }
@@ -0,0 +1,311 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    str::FromStr,
+};
+
+use data_encoding::HEXLOWER;
+
+use crate::{
+    compress::{CompressorConfig, Format},
+    digest::{AlgorithmName, Digest},
+    header::{WarcFields, WarcHeader},
+    verify::{Problem, ProblemKind},
+    warc::{EncStateHeader, Encoder, EncoderConfig},
+};
+
+use super::{filter::EmptyRecordFilter, io::ProgramOutput};
+
+/// Writes records to a WARC file with fixable problems corrected.
+///
+/// The block of the current record is held in a temporary file until the
+/// problems of the record are known.
+pub struct RecordFixer {
+    encoder: Option<Encoder<EncStateHeader, ProgramOutput>>,
+    header: Option<WarcHeader>,
+    block: File,
+    block_len: u64,
+    fixed_count: u64,
+    empty_filter: Option<EmptyRecordFilter>,
+}
+
+impl RecordFixer {
+    pub fn new(output: ProgramOutput, compression: Format) -> anyhow::Result<Self> {
+        let config = EncoderConfig {
+            compressor: CompressorConfig {
+                format: compression,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        Ok(Self {
+            encoder: Some(Encoder::new(output, config)),
+            header: None,
+            block: super::temp::tempfile()?,
+            block_len: 0,
+            fixed_count: 0,
+            empty_filter: None,
+        })
+    }
+
+    /// Skip resource and metadata records without content.
+    ///
+    /// Records cut off by the end of the file are always written.
+    pub fn with_drop_empty(mut self, value: bool) -> Self {
+        self.empty_filter = value.then(EmptyRecordFilter::new);
+        self
+    }
+
+    pub fn begin_record(&mut self, header: &WarcHeader) -> anyhow::Result<()> {
+        self.header = Some(header.clone());
+
+        if let Some(empty_filter) = &mut self.empty_filter {
+            empty_filter.begin_record(header);
+        }
+
+        self.block.set_len(0)?;
+        self.block.seek(SeekFrom::Start(0))?;
+        self.block_len = 0;
+
+        Ok(())
+    }
+
+    pub fn block_data(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        self.block.write_all(data)?;
+        self.block_len += data.len() as u64;
+
+        if let Some(empty_filter) = &mut self.empty_filter {
+            empty_filter.block_data(data);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the current record with the problems corrected.
+    pub fn end_record(&mut self, problems: &[Problem]) -> anyhow::Result<()> {
+        self.write_record(problems, false)
+    }
+
+    /// Writes the current record that was cut off by the end of the file.
+    ///
+    /// The record is marked as truncated. Its digests, which are for the
+    /// original data, are removed.
+    pub fn end_truncated_record(&mut self, problems: &[Problem]) -> anyhow::Result<()> {
+        self.write_record(problems, true)
+    }
+
+    fn write_record(&mut self, problems: &[Problem], is_truncated: bool) -> anyhow::Result<()> {
+        let Some(mut header) = self.header.take() else {
+            return Ok(());
+        };
+
+        if !is_truncated
+            && self
+                .empty_filter
+                .as_ref()
+                .is_some_and(|filter| filter.is_empty_record())
+        {
+            tracing::debug!(
+                record_id = header.fields.get("WARC-Record-ID"),
+                "skipped empty record"
+            );
+            return Ok(());
+        }
+
+        let mut is_fixed = fix_header(&mut header, problems);
+
+        if is_truncated {
+            header.fields.remove("WARC-Block-Digest");
+            header.fields.remove("WARC-Payload-Digest");
+            header.set_content_length(self.block_len);
+
+            if !header.fields.contains_name("WARC-Truncated") {
+                header
+                    .fields
+                    .insert("WARC-Truncated".to_string(), "unspecified".to_string());
+            }
+
+            is_fixed = true;
+        }
+
+        if is_fixed {
+            tracing::debug!(
+                record_id = header.fields.get("WARC-Record-ID"),
+                "fixed record"
+            );
+            self.fixed_count += 1;
+        }
+
+        let encoder = self.encoder.take().unwrap();
+        let mut encoder = encoder.write_header(&header)?;
+
+        self.block.seek(SeekFrom::Start(0))?;
+        std::io::copy(&mut (&self.block).take(self.block_len), &mut encoder)?;
+
+        self.encoder = Some(encoder.finish_block()?);
+
+        Ok(())
+    }
+
+    /// Returns the number of records that were changed.
+    pub fn fixed_count(&self) -> u64 {
+        self.fixed_count
+    }
+
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        let mut output = self.encoder.take().unwrap().finish()?;
+        output.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Corrects the fields of the header according to the problems.
+///
+/// Returns whether the header was changed.
+fn fix_header(header: &mut WarcHeader, problems: &[Problem]) -> bool {
+    let mut block_digests = Vec::new();
+    let mut payload_digests = Vec::new();
+    let mut is_fixed = false;
+
+    for problem in problems {
+        match problem.kind() {
+            ProblemKind::DigestMismatch {
+                algorithm, actual, ..
+            } => {
+                if let Some(digest) = parse_actual_digest(algorithm, actual) {
+                    block_digests.push(digest);
+                }
+            }
+            ProblemKind::PayloadDigestMismatch {
+                algorithm, actual, ..
+            } => {
+                if let Some(digest) = parse_actual_digest(algorithm, actual) {
+                    payload_digests.push(digest);
+                }
+            }
+            ProblemKind::ContentLengthMismatch { actual, .. } => {
+                header.set_content_length(*actual);
+                is_fixed = true;
+            }
+            ProblemKind::RequiredFieldMissing(name) if name == "WARC-Record-ID" => {
+                let record_id = super::record_id::generate_record_id(header);
+                header.fields.insert(name.clone(), record_id.to_string());
+                is_fixed = true;
+            }
+            _ => {}
+        }
+    }
+
+    if !block_digests.is_empty() || !payload_digests.is_empty() {
+        header.fields = replace_digests(&header.fields, &block_digests, &payload_digests);
+        is_fixed = true;
+    }
+
+    is_fixed
+}
+
+fn parse_actual_digest(algorithm: &str, actual: &str) -> Option<Digest> {
+    let algorithm = AlgorithmName::from_str(algorithm).ok()?;
+    let value = HEXLOWER.decode(actual.as_bytes()).ok()?;
+
+    Some(Digest::new(algorithm, value))
+}
+
+/// Replaces the digest values that have the same algorithm in place.
+fn replace_digests(
+    fields: &WarcFields,
+    block_digests: &[Digest],
+    payload_digests: &[Digest],
+) -> WarcFields {
+    fields
+        .iter()
+        .map(|(name, value)| {
+            let replacements = if name.eq_ignore_ascii_case("WARC-Block-Digest") {
+                block_digests
+            } else if name.eq_ignore_ascii_case("WARC-Payload-Digest") {
+                payload_digests
+            } else {
+                &[]
+            };
+            let replacement = Digest::from_str(value).ok().and_then(|digest| {
+                replacements
+                    .iter()
+                    .find(|item| item.algorithm() == digest.algorithm())
+            });
+
+            match replacement {
+                Some(digest) => (name.clone(), super::digest::format_digest(digest)),
+                None => (name.clone(), value.clone()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fix_header() {
+        let mut header = WarcHeader::empty();
+        header
+            .fields
+            .insert("Content-Length".to_string(), "10".to_string());
+        header.fields.insert(
+            "WARC-Block-Digest".to_string(),
+            "sha1:AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA".to_string(),
+        );
+        header
+            .fields
+            .insert("WARC-Type".to_string(), "resource".to_string());
+
+        assert!(!fix_header(&mut header.clone(), &[]));
+
+        let problems = [
+            Problem::new(
+                "",
+                ProblemKind::DigestMismatch {
+                    algorithm: "sha1".to_string(),
+                    expected: "0".repeat(40),
+                    actual: "da39a3ee5e6b4b0d3255bfef95601890afd80709".to_string(),
+                },
+            ),
+            Problem::new(
+                "",
+                ProblemKind::ContentLengthMismatch {
+                    expected: 10,
+                    actual: 5,
+                },
+            ),
+            Problem::new(
+                "",
+                ProblemKind::RequiredFieldMissing("WARC-Record-ID".to_string()),
+            ),
+        ];
+
+        assert!(fix_header(&mut header, &problems));
+
+        let names = header
+            .fields
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            vec![
+                "WARC-Block-Digest",
+                "WARC-Type",
+                "Content-Length",
+                "WARC-Record-ID"
+            ]
+        );
+        assert_eq!(
+            header.fields.get("WARC-Block-Digest").unwrap(),
+            "sha1:3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ"
+        );
+        assert_eq!(header.content_length().unwrap(), 5);
+    }
+}
@@ -1,7 +1,14 @@
-use std::path::Path;
+use std::{
+    fs::File,
+    io::{BufRead, Read},
+    path::Path,
+};
 
 use crate::compress::Format;
 
+/// Number of bytes needed to detect the compression format.
+const MAGIC_BYTES_LENGTH: usize = 5;
+
 pub fn filename_compression_format(path: &Path) -> Option<Format> {
     if let Some(filename) = path.file_name() {
         let filename = filename.to_string_lossy().to_ascii_lowercase();
@@ -16,7 +23,89 @@ pub fn filename_compression_format(path: &Path) -> Option<Format> {
         if filename.ends_with(".warc.zst") {
             return Some(Format::Zstandard);
         }
+        #[cfg(feature = "bzip2")]
+        if filename.ends_with(".warc.bz2") {
+            return Some(Format::BZIP2);
+        }
     }
 
     None
 }
+
+/// Returns the compression format of an input file detected from its first
+/// bytes.
+///
+/// The input is read only if it is standard input, which is peeked without
+/// consuming the data, or a regular file.
+pub fn detect_compression_format(path: &Path) -> std::io::Result<Option<Format>> {
+    if path.to_str() == Some("-") {
+        let mut stdin = std::io::stdin().lock();
+        let buf = stdin.fill_buf()?;
+
+        return Ok(content_compression_format(buf));
+    }
+
+    if !std::fs::metadata(path).is_ok_and(|metadata| metadata.is_file()) {
+        return Ok(None);
+    }
+
+    let mut buf = Vec::with_capacity(MAGIC_BYTES_LENGTH);
+    File::open(path)?
+        .take(MAGIC_BYTES_LENGTH as u64)
+        .read_to_end(&mut buf)?;
+
+    Ok(content_compression_format(&buf))
+}
+
+/// Returns the compression format of a WARC file by the magic bytes at the
+/// start of the data.
+pub fn content_compression_format(data: &[u8]) -> Option<Format> {
+    if data.starts_with(b"WARC/") {
+        return Some(Format::Identity);
+    }
+    if data.starts_with(&[0x1f, 0x8b]) {
+        return Some(Format::Gzip);
+    }
+    #[cfg(feature = "zstd")]
+    if data.len() >= 4 {
+        let magic_number = u32::from_le_bytes(data[0..4].try_into().unwrap());
+
+        if magic_number == 0xFD2FB528 || crate::compress::zstd::is_skippable_frame(magic_number) {
+            return Some(Format::Zstandard);
+        }
+    }
+    #[cfg(feature = "bzip2")]
+    if data.len() >= 4 && data.starts_with(b"BZh") && data[3].is_ascii_digit() {
+        return Some(Format::BZIP2);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_compression_format() {
+        assert_eq!(
+            content_compression_format(b"WARC/1.1\r\n"),
+            Some(Format::Identity)
+        );
+        assert_eq!(
+            content_compression_format(&[0x1f, 0x8b, 0x08, 0x00]),
+            Some(Format::Gzip)
+        );
+        assert_eq!(
+            content_compression_format(&[0x28, 0xb5, 0x2f, 0xfd, 0x00]),
+            Some(Format::Zstandard)
+        );
+        assert_eq!(
+            content_compression_format(&[0x5d, 0x2a, 0x4d, 0x18, 0x00]),
+            Some(Format::Zstandard)
+        );
+        assert_eq!(content_compression_format(b"BZh91"), Some(Format::BZIP2));
+        assert_eq!(content_compression_format(b"WAR"), None);
+        assert_eq!(content_compression_format(b""), None);
+    }
+}
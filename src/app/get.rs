@@ -1,4 +1,4 @@
-use std::io::{Read, Seek, Write};
+use std::io::{Read, Write};
 
 use crate::{
     app::export::Exporter,
@@ -6,8 +6,8 @@ use crate::{
     dataseq::SeqWriter,
     error::{ProtocolError, ProtocolErrorKind},
     extract::WarcExtractor,
-    header::fields::FieldsExt,
-    warc::{Decoder, DecoderConfig},
+    header::{fields::FieldsExt, record_id::RecordId},
+    warc::Decoder,
 };
 
 use super::arg::{GetCommand, GetExportSubcommand, GetExtractSubcommand, GetSubcommand};
@@ -19,6 +19,14 @@ pub fn get(args: &GetCommand) -> anyhow::Result<()> {
     }
 }
 
+/// Returns whether the record ID matches ignoring deliminator and case variations.
+fn is_matching_id(record_id: &str, id: &str) -> bool {
+    match id.parse::<RecordId>() {
+        Ok(id) => id.matches_str(record_id),
+        Err(_) => record_id == id,
+    }
+}
+
 // FIXME: refactor the copypaste boilerplate
 
 fn export(args: &GetExportSubcommand) -> anyhow::Result<()> {
@@ -38,31 +46,35 @@ fn export(args: &GetExportSubcommand) -> anyhow::Result<()> {
 
     let mut exporter = Exporter::new(input_path, writer, args.no_block, args.extract);
 
-    let mut config = DecoderConfig::default();
+    let mut config = super::common::decoder_config();
     config.decompressor.format = compression_format;
     config.decompressor.dictionary = get_dictionary(compression_format);
+    config.keep_raw_header = true;
 
-    let mut decoder = Decoder::new(input, config)?;
-
-    if args.position != 0 {
-        decoder.prepare_for_seek()?;
+    let decoder = if args.decompressed {
+        let mut decoder = Decoder::new(input, config)?;
+        decoder.seek_decompressed(args.position)?;
         decoder
-            .get_mut()
-            .seek(std::io::SeekFrom::Start(args.position))?;
-    }
+    } else {
+        Decoder::open_at(input, args.position, config)?
+    };
 
     let (header, mut decoder) = decoder.read_header()?;
 
     let record_id = header.fields.get_or_default("WARC-Record-ID");
 
-    if record_id != args.id {
+    if !is_matching_id(record_id, &args.id) {
         return Err(ProtocolError::new(ProtocolErrorKind::NotFound).into());
     }
 
     let progress_bar = super::progress::make_bytes_progress_bar(Some(header.content_length()?));
     super::progress::global_progress_bar().add(progress_bar.clone());
 
-    exporter.process_header(&header, decoder.record_boundary_position())?;
+    exporter.process_header(
+        &header,
+        decoder.raw_header().unwrap_or_default(),
+        decoder.record_boundary_position(),
+    )?;
 
     let mut buf = Vec::with_capacity(8192);
 
@@ -106,24 +118,23 @@ fn extract(args: &GetExtractSubcommand) -> anyhow::Result<()> {
 
     let mut extractor = WarcExtractor::new();
 
-    let mut config = DecoderConfig::default();
+    let mut config = super::common::decoder_config();
     config.decompressor.format = compression_format;
     config.decompressor.dictionary = get_dictionary(compression_format);
 
-    let mut decoder = Decoder::new(input, config)?;
-
-    if args.position != 0 {
-        decoder.prepare_for_seek()?;
+    let decoder = if args.decompressed {
+        let mut decoder = Decoder::new(input, config)?;
+        decoder.seek_decompressed(args.position)?;
         decoder
-            .get_mut()
-            .seek(std::io::SeekFrom::Start(args.position))?;
-    }
+    } else {
+        Decoder::open_at(input, args.position, config)?
+    };
 
     let (header, mut decoder) = decoder.read_header()?;
 
     let record_id = header.fields.get_or_default("WARC-Record-ID");
 
-    if record_id != args.id {
+    if !is_matching_id(record_id, &args.id) {
         return Err(ProtocolError::new(ProtocolErrorKind::NotFound).into());
     }
 
@@ -0,0 +1,141 @@
+use std::io::Write;
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 1em; }
+table { border-collapse: collapse; font-size: 0.9em; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.5em; text-align: left; vertical-align: top; }
+th { background: #eee; cursor: pointer; position: sticky; top: 0; user-select: none; }
+th.asc::after { content: " \25B2"; }
+th.desc::after { content: " \25BC"; }
+tr:nth-child(even) td { background: #f8f8f8; }
+td { word-break: break-all; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.sortable").forEach(function (table) {
+  var headers = table.querySelectorAll("th");
+  headers.forEach(function (th, index) {
+    th.addEventListener("click", function () {
+      var ascending = !th.classList.contains("asc");
+      headers.forEach(function (other) { other.classList.remove("asc", "desc"); });
+      th.classList.add(ascending ? "asc" : "desc");
+      var body = table.tBodies[0];
+      var rows = Array.prototype.slice.call(body.rows);
+      rows.sort(function (a, b) {
+        var x = a.cells[index].textContent;
+        var y = b.cells[index].textContent;
+        var nx = Number(x);
+        var ny = Number(y);
+        var result = (x !== "" && y !== "" && !isNaN(nx) && !isNaN(ny))
+          ? nx - ny : x.localeCompare(y);
+        return ascending ? result : -result;
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+});
+"#;
+
+/// Writes a self-contained HTML document containing a sortable table.
+pub struct HtmlTableWriter<W: Write> {
+    output: W,
+}
+
+impl<W: Write> HtmlTableWriter<W> {
+    /// Writes the beginning of the document including the table column names.
+    pub fn new(mut output: W, title: &str, columns: &[String]) -> std::io::Result<Self> {
+        writeln!(output, "<!DOCTYPE html>")?;
+        writeln!(output, "<html>")?;
+        writeln!(output, "<head>")?;
+        writeln!(output, "<meta charset=\"utf-8\">")?;
+        writeln!(output, "<title>{}</title>", escape(title))?;
+        writeln!(output, "<style>{}</style>", STYLE)?;
+        writeln!(output, "</head>")?;
+        writeln!(output, "<body>")?;
+        writeln!(output, "<h1>{}</h1>", escape(title))?;
+        writeln!(output, "<table class=\"sortable\">")?;
+        write!(output, "<thead><tr>")?;
+
+        for column in columns {
+            write!(output, "<th>{}</th>", escape(column))?;
+        }
+
+        writeln!(output, "</tr></thead>")?;
+        writeln!(output, "<tbody>")?;
+
+        Ok(Self { output })
+    }
+
+    /// Writes a table row.
+    pub fn put(&mut self, values: &[serde_json::Value]) -> std::io::Result<()> {
+        write!(self.output, "<tr>")?;
+
+        for value in values {
+            let text = match value {
+                serde_json::Value::Null => String::new(),
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+
+            write!(self.output, "<td>{}</td>", escape(&text))?;
+        }
+
+        writeln!(self.output, "</tr>")?;
+
+        Ok(())
+    }
+
+    /// Writes the end of the document.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        writeln!(self.output, "</tbody>")?;
+        writeln!(self.output, "</table>")?;
+        writeln!(self.output, "<script>{}</script>", SCRIPT)?;
+        writeln!(self.output, "</body>")?;
+        writeln!(self.output, "</html>")?;
+        self.output.flush()?;
+
+        Ok(self.output)
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_table() {
+        let mut writer =
+            HtmlTableWriter::new(Vec::new(), "a & b", &["<x>".to_string(), "y".to_string()])
+                .unwrap();
+        writer
+            .put(&[serde_json::json!("\"q\""), serde_json::json!(123)])
+            .unwrap();
+        writer
+            .put(&[serde_json::Value::Null, serde_json::json!("z")])
+            .unwrap();
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        assert!(output.contains("<title>a &amp; b</title>"));
+        assert!(output.contains("<th>&lt;x&gt;</th><th>y</th>"));
+        assert!(output.contains("<tr><td>&quot;q&quot;</td><td>123</td></tr>"));
+        assert!(output.contains("<tr><td></td><td>z</td></tr>"));
+        assert!(output.ends_with("</html>\n"));
+    }
+}
@@ -3,16 +3,17 @@ use std::io::Write;
 use indicatif::ProgressBar;
 
 use crate::{
-    compress::{CompressorConfig, Format, Level},
+    compress::CompressorConfig,
     dataseq::{SeqFormat, SeqReader},
     digest::{AlgorithmName, MultiHasher},
     header::WarcHeader,
-    io::{BufferReader, LogicalPosition},
+    io::{BufferReader, LogicalPosition, TeeWriter},
     warc::{EncStateBlock, EncStateHeader, Encoder, EncoderConfig},
 };
 
 use super::{
     arg::ImportCommand,
+    checksum::ChecksumWriter,
     io::{ProgramInput, ProgramOutput},
     model::WarcMessage,
 };
@@ -20,8 +21,33 @@ use super::{
 pub fn import(args: &ImportCommand) -> anyhow::Result<()> {
     let output_path = &args.output;
     let seq_format = args.format.into();
-    let format = args.compression.try_into_native(output_path)?;
-    let level = args.compression_level.into();
+    let format = args.compression.try_into_native_output(output_path)?;
+    let compressor = CompressorConfig {
+        format,
+        level: args.compression_level.into(),
+        dictionary: super::common::read_dictionary(args.dictionary.as_deref(), format)?,
+        seek_table: args.seek_table,
+        threads: super::common::job_count(args.jobs),
+        zstd_parameters: super::common::zstd_parameters(
+            args.zstd_window_log,
+            args.zstd_long,
+            args.zstd_chain_log,
+        ),
+        gzip_member_length: args.gzip_member_length,
+        ..Default::default()
+    };
+    let checksum_algorithms = args
+        .container_checksum
+        .iter()
+        .map(|&algorithm| AlgorithmName::from(algorithm))
+        .collect::<Vec<_>>();
+
+    if !checksum_algorithms.is_empty() && output_path.to_str() == Some("-") {
+        anyhow::bail!("container checksums require an output file");
+    }
+    if args.tee.as_ref() == Some(output_path) {
+        anyhow::bail!("tee output must be different from the output");
+    }
 
     for input_path in &args.input {
         let span = tracing::info_span!("import", path = ?input_path);
@@ -29,12 +55,39 @@ pub fn import(args: &ImportCommand) -> anyhow::Result<()> {
 
         let input = super::common::open_input(input_path)?;
         let output = super::common::open_output(output_path)?;
+        let tee_output: Box<dyn Write> = match &args.tee {
+            Some(path) => Box::new(super::common::open_output(path)?),
+            None => Box::new(std::io::sink()),
+        };
+        let output = ChecksumWriter::new(TeeWriter::new(output, tee_output), &checksum_algorithms);
 
         tracing::info!("opened file");
 
         let file_len = std::fs::metadata(input_path).map(|m| m.len()).ok();
 
-        Importer::new(input, output, seq_format, (format, level), file_len)?.run()?;
+        let mut importer = Importer::new(input, output, seq_format, compressor.clone(), file_len)?;
+        importer.run()?;
+
+        if let State::Done(output) = importer.state {
+            let (output, checksums) = output.finish();
+
+            // Either output may have failed alone, so both are reported.
+            if let Some(error) = output.second_error() {
+                tracing::error!(%error, path = ?args.tee, "writing tee output failed");
+            }
+
+            if let Some(error) = output.first_error() {
+                anyhow::bail!("writing output {:?} failed: {}", output_path, error);
+            }
+
+            for (algorithm, value) in checksums {
+                super::checksum::write_sidecar(output_path, algorithm, &value)?;
+            }
+
+            if let Some(error) = output.second_error() {
+                anyhow::bail!("writing tee output {:?} failed: {}", args.tee, error);
+            }
+        }
 
         tracing::info!("closed file");
     }
@@ -42,11 +95,14 @@ pub fn import(args: &ImportCommand) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Output and the optional tee output.
+type ImportOutput = ChecksumWriter<TeeWriter<ProgramOutput, Box<dyn Write>>>;
+
 enum State {
     None,
-    Header(Encoder<EncStateHeader, ProgramOutput>),
-    Block(Encoder<EncStateBlock, ProgramOutput>),
-    Done,
+    Header(Encoder<EncStateHeader, ImportOutput>),
+    Block(Encoder<EncStateBlock, ImportOutput>),
+    Done(ImportOutput),
 }
 
 impl State {
@@ -65,18 +121,15 @@ struct Importer {
 impl Importer {
     fn new(
         input: ProgramInput,
-        output: ProgramOutput,
+        output: ImportOutput,
         seq_format: SeqFormat,
-        (compression, compression_level): (Format, Level),
+        compressor: CompressorConfig,
         file_len: Option<u64>,
     ) -> anyhow::Result<Self> {
         let progress_bar = super::progress::make_bytes_progress_bar(file_len);
         let config = EncoderConfig {
-            compressor: CompressorConfig {
-                format: compression,
-                level: compression_level,
-                ..Default::default()
-            },
+            compressor,
+            ..Default::default()
         };
         let output = Encoder::new(output, config);
 
@@ -108,7 +161,7 @@ impl Importer {
                 break;
             }
 
-            if matches!(self.state, State::Done) {
+            if matches!(self.state, State::Done(_)) {
                 break;
             }
         }
@@ -143,14 +196,17 @@ impl Importer {
 
     fn process_header(
         &mut self,
-        writer: Encoder<EncStateHeader, ProgramOutput>,
+        writer: Encoder<EncStateHeader, ImportOutput>,
         header: super::model::Header,
     ) -> anyhow::Result<()> {
         let mut warc_header = WarcHeader::empty();
         warc_header.version = header.version;
         warc_header.fields.extend(header.fields);
 
-        let writer = writer.write_header(&warc_header)?;
+        let writer = match &header.raw {
+            Some(raw_header) => writer.write_raw_header(raw_header)?,
+            None => writer.write_header(&warc_header)?,
+        };
 
         let record_id = warc_header
             .fields
@@ -165,18 +221,15 @@ impl Importer {
         Ok(())
     }
 
-    fn process_eof(
-        &mut self,
-        writer: Encoder<EncStateHeader, ProgramOutput>,
-    ) -> anyhow::Result<()> {
-        writer.finish()?;
-        self.state = State::Done;
+    fn process_eof(&mut self, writer: Encoder<EncStateHeader, ImportOutput>) -> anyhow::Result<()> {
+        let output = writer.finish()?;
+        self.state = State::Done(output);
         Ok(())
     }
 
     fn process_block(
         &mut self,
-        mut writer: Encoder<EncStateBlock, ProgramOutput>,
+        mut writer: Encoder<EncStateBlock, ImportOutput>,
         chunk: super::model::BlockChunk,
     ) -> anyhow::Result<()> {
         writer.write_all(&chunk.data)?;
@@ -189,7 +242,7 @@ impl Importer {
 
     fn process_block_end(
         &mut self,
-        writer: Encoder<EncStateBlock, ProgramOutput>,
+        writer: Encoder<EncStateBlock, ImportOutput>,
         end: super::model::BlockEnd,
     ) -> anyhow::Result<()> {
         let checksum_map = self.multi_hasher.finish_u64();
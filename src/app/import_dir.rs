@@ -0,0 +1,395 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use indicatif::ProgressBar;
+use percent_encoding::{AsciiSet, CONTROLS};
+
+use crate::{
+    compress::{CompressorConfig, Level},
+    digest::{AlgorithmName, Digest, Hasher},
+    header::WarcHeader,
+    warc::{EncStateHeader, Encoder, EncoderConfig},
+};
+
+use super::{arg::ImportDirCommand, io::ProgramOutput, mime::SNIFF_LENGTH};
+
+const PATH_SEGMENT_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Kind of the input containing the files.
+enum InputKind {
+    Directory,
+    Zip,
+    Tar,
+    TarGzip,
+}
+
+impl InputKind {
+    fn detect(path: &Path) -> anyhow::Result<Self> {
+        if path.is_dir() {
+            return Ok(Self::Directory);
+        }
+
+        let filename = path
+            .file_name()
+            .map(|value| value.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+
+        if filename.ends_with(".zip") {
+            Ok(Self::Zip)
+        } else if filename.ends_with(".tar") {
+            Ok(Self::Tar)
+        } else if filename.ends_with(".tar.gz") || filename.ends_with(".tgz") {
+            Ok(Self::TarGzip)
+        } else {
+            anyhow::bail!("not a directory or a supported archive file: {:?}", path)
+        }
+    }
+}
+
+pub fn import_dir(args: &ImportDirCommand) -> anyhow::Result<()> {
+    let input_path = &args.input;
+    let output_path = &args.output;
+    let input_kind = InputKind::detect(input_path)?;
+
+    let mut base_url = args.base_url.clone();
+
+    if !base_url.ends_with('/') {
+        base_url.push('/');
+    }
+
+    let base_url = url::Url::parse(&base_url).context("invalid base URL")?;
+
+    let format = args.compression.try_into_native_output(output_path)?;
+    let config = EncoderConfig {
+        compressor: CompressorConfig {
+            format,
+            level: Level::from(args.compression_level),
+            dictionary: super::common::read_dictionary(args.dictionary.as_deref(), format)?,
+            seek_table: args.seek_table,
+            threads: super::common::job_count(args.jobs),
+            zstd_parameters: super::common::zstd_parameters(
+                args.zstd_window_log,
+                args.zstd_long,
+                args.zstd_chain_log,
+            ),
+            gzip_member_length: args.gzip_member_length,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let output = super::common::open_output(output_path)?;
+    let mut writer = RecordWriter::new(Encoder::new(output, config), base_url);
+
+    match input_kind {
+        InputKind::Directory => import_directory(input_path, &mut writer)?,
+        InputKind::Zip => import_zip(input_path, &mut writer)?,
+        InputKind::Tar => import_tar(File::open(input_path)?, &mut writer)?,
+        InputKind::TarGzip => import_tar(
+            flate2::read::MultiGzDecoder::new(File::open(input_path)?),
+            &mut writer,
+        )?,
+    }
+
+    writer.finish()?;
+
+    Ok(())
+}
+
+fn import_directory(input_dir: &Path, writer: &mut RecordWriter) -> anyhow::Result<()> {
+    let mut paths = Vec::new();
+    walk_dir(input_dir, &mut paths)?;
+
+    let total_len = paths
+        .iter()
+        .filter_map(|path| path.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    writer.progress_bar.set_length(total_len);
+
+    for path in paths {
+        let span = tracing::info_span!("import_dir", path = ?path);
+        let _span_guard = span.enter();
+
+        let mut file = File::open(&path).with_context(|| format!("opening {:?} failed", path))?;
+        let date = file.metadata()?.modified().ok().map(DateTime::<Utc>::from);
+
+        writer.write(path.strip_prefix(input_dir)?, &mut file, date)?;
+    }
+
+    Ok(())
+}
+
+fn import_zip(input_path: &Path, writer: &mut RecordWriter) -> anyhow::Result<()> {
+    let file =
+        File::open(input_path).with_context(|| format!("opening {:?} failed", input_path))?;
+    let mut archive = zip::ZipArchive::new(file)?;
+
+    let total_len = (0..archive.len())
+        .filter_map(|index| archive.by_index_raw(index).ok().map(|entry| entry.size()))
+        .sum();
+    writer.progress_bar.set_length(total_len);
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+
+        if entry.is_dir() {
+            continue;
+        }
+
+        let span = tracing::info_span!("import_dir", entry = entry.name());
+        let _span_guard = span.enter();
+
+        let Some(path) = entry.enclosed_name() else {
+            tracing::warn!("skipped entry with unsafe path");
+            continue;
+        };
+        let date = entry.last_modified().and_then(zip_date_to_utc);
+
+        let mut temp_file = super::temp::tempfile()?;
+        std::io::copy(&mut entry, &mut temp_file)?;
+        temp_file.rewind()?;
+
+        writer.write(&path, &mut temp_file, date)?;
+    }
+
+    Ok(())
+}
+
+fn import_tar<R: Read>(input: R, writer: &mut RecordWriter) -> anyhow::Result<()> {
+    let mut archive = tar::Archive::new(input);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path()?.into_owned();
+
+        let span = tracing::info_span!("import_dir", entry = ?path);
+        let _span_guard = span.enter();
+
+        let date = entry
+            .header()
+            .mtime()
+            .ok()
+            .and_then(|value| DateTime::from_timestamp(i64::try_from(value).ok()?, 0));
+
+        let mut temp_file = super::temp::tempfile()?;
+        std::io::copy(&mut entry, &mut temp_file)?;
+        temp_file.rewind()?;
+
+        writer.write(&path, &mut temp_file, date)?;
+    }
+
+    Ok(())
+}
+
+/// Collects the paths of files in the directory recursively in sorted order.
+fn walk_dir(dir: &Path, paths: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    let mut entries = std::fs::read_dir(dir)
+        .with_context(|| format!("reading directory {:?} failed", dir))?
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+
+        if path.is_dir() {
+            walk_dir(&path, paths)?;
+        } else if path.is_file() {
+            paths.push(path);
+        } else {
+            tracing::warn!(?path, "skipped entry that is not a file");
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the target URI for the path of a file.
+///
+/// Components of the path that are not names such as `..` are ignored.
+fn target_uri(base_url: &url::Url, relative_path: &Path) -> anyhow::Result<String> {
+    let mut segments = Vec::new();
+
+    for component in relative_path.components() {
+        if let Component::Normal(component) = component {
+            let component = component.to_string_lossy();
+            segments.push(
+                percent_encoding::utf8_percent_encode(&component, PATH_SEGMENT_ENCODE_SET)
+                    .to_string(),
+            );
+        }
+    }
+
+    Ok(base_url.join(&segments.join("/"))?.to_string())
+}
+
+fn zip_date_to_utc(date: zip::DateTime) -> Option<DateTime<Utc>> {
+    let date = NaiveDate::from_ymd_opt(date.year().into(), date.month().into(), date.day().into())?
+        .and_hms_opt(
+            date.hour().into(),
+            date.minute().into(),
+            date.second().into(),
+        )?;
+
+    Some(date.and_utc())
+}
+
+/// Writes files as resource records.
+struct RecordWriter {
+    encoder: Option<Encoder<EncStateHeader, ProgramOutput>>,
+    base_url: url::Url,
+    progress_bar: ProgressBar,
+    buf: Vec<u8>,
+}
+
+impl RecordWriter {
+    fn new(encoder: Encoder<EncStateHeader, ProgramOutput>, base_url: url::Url) -> Self {
+        let progress_bar = super::progress::make_bytes_progress_bar(Some(0));
+        super::progress::global_progress_bar().add(progress_bar.clone());
+
+        Self {
+            encoder: Some(encoder),
+            base_url,
+            progress_bar,
+            buf: vec![0; crate::io::IO_BUFFER_LENGTH],
+        }
+    }
+
+    /// Writes a record for a file.
+    ///
+    /// The file is read twice because the digest must be written in the
+    /// header before the block.
+    fn write<R: Read + Seek>(
+        &mut self,
+        relative_path: &Path,
+        file: &mut R,
+        date: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<()> {
+        let target_uri = target_uri(&self.base_url, relative_path)?;
+
+        self.progress_bar
+            .set_message(format!("Processing file {}", relative_path.display()));
+
+        let mut hasher = Hasher::new(AlgorithmName::Sha1);
+        let mut head = Vec::with_capacity(SNIFF_LENGTH);
+        let mut length = 0u64;
+
+        loop {
+            let read_length = file.read(&mut self.buf)?;
+
+            if read_length == 0 {
+                break;
+            }
+
+            let data = &self.buf[0..read_length];
+
+            if head.len() < SNIFF_LENGTH {
+                let remain = SNIFF_LENGTH - head.len();
+                head.extend_from_slice(&data[0..remain.min(data.len())]);
+            }
+
+            hasher.update(data);
+            length += read_length as u64;
+        }
+
+        let digest =
+            super::digest::format_digest(&Digest::new(AlgorithmName::Sha1, hasher.finish()));
+
+        let mut header = WarcHeader::new(length, "resource");
+        header
+            .fields
+            .insert("WARC-Target-URI".to_string(), target_uri.clone());
+
+        if let Some(date) = date {
+            header.fields.insert(
+                "WARC-Date".to_string(),
+                date.to_rfc3339_opts(SecondsFormat::Secs, true),
+            );
+        }
+
+        header.fields.insert(
+            "Content-Type".to_string(),
+            super::mime::guess_media_type(relative_path, &head).to_string(),
+        );
+        header
+            .fields
+            .insert("WARC-Block-Digest".to_string(), digest.clone());
+        header
+            .fields
+            .insert("WARC-Payload-Digest".to_string(), digest);
+        header.fields.insert(
+            "WARC-Record-ID".to_string(),
+            super::record_id::generate_record_id(&header).to_string(),
+        );
+
+        file.rewind()?;
+
+        let mut encoder = self.encoder.take().unwrap().write_header(&header)?;
+        let copied_length = std::io::copy(file, &mut encoder)?;
+
+        if copied_length != length {
+            anyhow::bail!("file {:?} changed while reading", relative_path);
+        }
+
+        self.encoder = Some(encoder.finish_block()?);
+
+        tracing::info!(target_uri, "wrote record");
+        self.progress_bar.inc(length);
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> anyhow::Result<()> {
+        self.encoder.take().unwrap().finish()?.flush()?;
+
+        self.progress_bar.finish();
+        super::progress::global_progress_bar().remove(&self.progress_bar);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_uri() {
+        let base_url = url::Url::parse("http://example.com/site/").unwrap();
+
+        assert_eq!(
+            target_uri(&base_url, Path::new("a b/c#?.html")).unwrap(),
+            "http://example.com/site/a%20b/c%23%3F.html"
+        );
+        assert_eq!(
+            target_uri(&base_url, Path::new("/../a/./b")).unwrap(),
+            "http://example.com/site/a/b"
+        );
+    }
+}
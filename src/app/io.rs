@@ -2,9 +2,19 @@ use std::{
     fs::File,
     io::{Read, Seek, Stdin, Stdout, Write},
     path::Path,
+    sync::OnceLock,
 };
 
-use crate::error::{ProtocolError, ProtocolErrorKind};
+use crate::{
+    error::{ProtocolError, ProtocolErrorKind},
+    io::MappedFile,
+};
+
+static GLOBAL_MMAP: OnceLock<bool> = OnceLock::new();
+
+pub fn set_global_mmap(value: bool) {
+    GLOBAL_MMAP.set(value).unwrap();
+}
 
 #[derive(Debug)]
 pub enum ProgramInput {
@@ -23,6 +33,18 @@ impl ProgramInput {
             Ok(Self::File(file))
         }
     }
+
+    /// Maps the file into memory if the user asked for memory mapped input.
+    ///
+    /// Returns `None` otherwise or if the input is not a file.
+    pub fn map(&self) -> std::io::Result<Option<MappedFile>> {
+        match self {
+            Self::File(file) if GLOBAL_MMAP.get().copied().unwrap_or_default() => {
+                Ok(Some(MappedFile::from_file(file)?))
+            }
+            _ => Ok(None),
+        }
+    }
 }
 
 impl Read for ProgramInput {
@@ -66,6 +88,18 @@ impl ProgramOutput {
             Ok(Self::File(file))
         }
     }
+
+    /// Opens the file for writing at the end of the existing content.
+    pub fn open_append<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let path = path.as_ref();
+
+        if path.to_str() == Some("-") {
+            Ok(Self::Stdout(std::io::stdout()))
+        } else {
+            let file = File::options().append(true).create(true).open(path)?;
+            Ok(Self::File(file))
+        }
+    }
 }
 
 impl Write for ProgramOutput {
@@ -1,59 +1,696 @@
-use crate::{app::common::ReaderEvent, dataseq::SeqWriter};
+use std::{num::NonZeroUsize, path::Path, str::FromStr};
 
-use super::{arg::ListCommand, common::ReaderPipeline};
+use redb::{Database, ReadableTable, TableDefinition, WriteTransaction};
+
+use crate::{
+    app::common::ReaderEvent,
+    cert::CertificateInfo,
+    dataseq::SeqWriter,
+    digest::Digest,
+    header::{
+        extension::{ExtensionFields, ExtensionKind, ExtensionValue},
+        fields::FieldsExt,
+        uri::normalize_uri,
+        WarcHeader,
+    },
+    http::h1::header::{MessageHeader, StartLine},
+};
+
+use super::{
+    arg::{ListCommand, ListFormat, UniqueBy},
+    common::ReaderPipeline,
+    estimate::{print_estimate, Estimator},
+    html::HtmlTableWriter,
+    io::ProgramOutput,
+};
+
+const MAX_HTTP_HEADER_LENGTH: usize = 32768;
+const MAX_CERTIFICATE_LENGTH: usize = 65536;
+/// Number of rows stored by `--unique-by` in each database transaction.
+const UNIQUE_ROWS_BATCH_SIZE: u64 = 10000;
+
+// mapping of key => (row number, count)
+const UNIQUE_KEYS_TABLE: TableDefinition<&str, (u64, u64)> = TableDefinition::new("unique_keys");
+// mapping of row number => (key, serialized row values)
+const UNIQUE_ROWS_TABLE: TableDefinition<u64, (&str, &[u8])> = TableDefinition::new("unique_rows");
 
 pub fn list(args: &ListCommand) -> anyhow::Result<()> {
-    let output_path = &args.output;
-    let seq_format = args.format.into();
+    let needs_block = args.field.iter().any(|name| is_computed_field(name));
+    let mut unique_rows = match args.unique_by {
+        Some(unique_by) => Some(UniqueRows::new(
+            unique_by,
+            args.normalize_uri,
+            args.database.as_deref(),
+        )?),
+        None => None,
+    };
+    let mut extension_fields = ExtensionFields::default();
+
+    if let Some(path) = &args.extension_fields {
+        let text = std::fs::read_to_string(path)?;
+        extension_fields
+            .add_config(&text)
+            .map_err(|error| error.with_file(path))?;
+    }
+
+    if args.emit_schema {
+        return super::schema::print_schema(&super::schema::list_schema());
+    }
+
+    if args.estimate {
+        return estimate(args, &extension_fields);
+    }
+
+    let title = args
+        .input
+        .iter()
+        .map(|path| path.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut columns = args.field.clone();
+
+    if unique_rows.is_some() {
+        columns.push(":count".to_string());
+    }
+
+    let mut writers = Vec::with_capacity(args.output.len());
+
+    for output_path in &args.output {
+        let format = args
+            .format
+            .or_else(|| ListFormat::from_path(output_path))
+            .unwrap_or(ListFormat::JsonSeq);
+        let output = super::common::open_output(output_path)?;
+
+        writers.push(RowWriter::new(output, format, &title, &columns)?);
+    }
 
     for input_path in &args.input {
         let span = tracing::info_span!("list", path = ?input_path);
         let _span_guard = span.enter();
 
         let input = super::common::open_input(input_path)?;
-        let output = super::common::open_output(output_path)?;
 
         tracing::info!("opened file");
 
         let compression_format = args.compression.try_into_native(input_path)?;
         let file_len = std::fs::metadata(input_path).map(|m| m.len()).ok();
-        let mut writer = SeqWriter::new(output, seq_format);
+        let file = input_path.to_string_lossy().to_string();
+        let mut row = Row::new(&extension_fields, args.normalize_uri);
+        let mut put_row = |row: &Row| -> anyhow::Result<()> {
+            let values = row.values(&args.field, &file);
+
+            if let Some(unique_rows) = &mut unique_rows {
+                unique_rows.put(&row.header, values)
+            } else {
+                put_all(&mut writers, values)
+            }
+        };
 
         ReaderPipeline::new(
             |event| match event {
                 ReaderEvent::Header {
                     header,
                     record_boundary_position,
+                    ..
                 } => {
-                    let mut values = Vec::new();
-
-                    for name in &args.field {
-                        if name == ":position" {
-                            values.push(serde_json::Value::Number(record_boundary_position.into()));
-                        } else if name == ":file" {
-                            values.push(serde_json::Value::String(
-                                input_path.to_string_lossy().to_string(),
-                            ));
-                        } else {
-                            let value = header.fields.get(name).cloned().unwrap_or_default();
-                            values.push(serde_json::Value::String(value));
-                        }
+                    row.begin(header, record_boundary_position);
+
+                    if !needs_block {
+                        put_row(&row)?;
                     }
 
-                    writer.put(values)?;
+                    Ok(())
+                }
+                ReaderEvent::Block { data } => {
+                    if needs_block {
+                        row.block_data(data);
+                    }
+
+                    Ok(())
+                }
+                ReaderEvent::EndRecord {
+                    record_boundary_position,
+                } => {
+                    if needs_block {
+                        row.end(record_boundary_position);
+                        put_row(&row)?;
+                    }
 
                     Ok(())
                 }
-                ReaderEvent::Block { data: _ } => Ok(()),
             },
             input,
             compression_format,
             file_len,
         )?
+        .with_skip(args.skip)
+        .with_limit(args.limit)
+        .with_no_block(!needs_block)
+        .with_threads(args.jobs.map_or(1, NonZeroUsize::get))
         .run()?;
 
         tracing::info!("closed file");
     }
 
+    if let Some(unique_rows) = unique_rows {
+        unique_rows.write(&mut writers)?;
+    }
+
+    for writer in writers {
+        writer.finish()?;
+    }
+
     Ok(())
 }
+
+/// Predicts the size of the data stored by `--unique-by`.
+///
+/// Every record is assumed to have a unique key, so the estimate is an
+/// upper bound.
+fn estimate(args: &ListCommand, extension_fields: &ExtensionFields) -> anyhow::Result<()> {
+    let mut estimator = Estimator::new(true);
+
+    for input_path in &args.input {
+        let file = input_path.to_string_lossy().to_string();
+        let mut row = Row::new(extension_fields, args.normalize_uri);
+
+        estimator.sample_file(input_path, args.compression, |header| {
+            let Some(unique_by) = args.unique_by else {
+                return 0;
+            };
+            let key_len = unique_key(unique_by, args.normalize_uri, header)
+                .map(|key| key.len())
+                .unwrap_or(8) as u64;
+
+            row.begin(header.clone(), 0);
+            let data_len = serde_json::to_vec(&row.values(&args.field, &file))
+                .map(|data| data.len())
+                .unwrap_or_default() as u64;
+
+            // Key, row number, and count in the keys table, and row number,
+            // key, and values in the rows table.
+            key_len * 2 + 24 + data_len
+        })?;
+    }
+
+    print_estimate(&estimator.finish())
+}
+
+/// Writes the row values to each writer.
+fn put_all(writers: &mut [RowWriter], values: Vec<serde_json::Value>) -> anyhow::Result<()> {
+    if let Some((last, writers)) = writers.split_last_mut() {
+        for writer in writers {
+            writer.put(values.clone())?;
+        }
+
+        last.put(values)?;
+    }
+
+    Ok(())
+}
+
+pub enum RowWriter {
+    Seq(SeqWriter<ProgramOutput>),
+    Html(HtmlTableWriter<ProgramOutput>),
+}
+
+impl RowWriter {
+    pub fn new(
+        output: ProgramOutput,
+        format: ListFormat,
+        title: &str,
+        columns: &[String],
+    ) -> anyhow::Result<Self> {
+        match format.seq_format() {
+            Some(seq_format) => Ok(Self::Seq(SeqWriter::new(output, seq_format))),
+            None => Ok(Self::Html(HtmlTableWriter::new(output, title, columns)?)),
+        }
+    }
+
+    pub fn put(&mut self, values: Vec<serde_json::Value>) -> anyhow::Result<()> {
+        match self {
+            Self::Seq(writer) => writer.put(values)?,
+            Self::Html(writer) => writer.put(&values)?,
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> anyhow::Result<()> {
+        if let Self::Html(writer) = self {
+            writer.finish()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether the pseudo-field requires reading the record block.
+fn is_computed_field(name: &str) -> bool {
+    matches!(
+        name,
+        ":http-status"
+            | ":payload-type"
+            | ":decoded-size"
+            | ":compressed-size"
+            | ":cert-subject"
+            | ":cert-issuer"
+            | ":cert-not-before"
+            | ":cert-not-after"
+    )
+}
+
+struct Row<'a> {
+    extension_fields: &'a ExtensionFields,
+    normalize_uri: bool,
+    header: WarcHeader,
+    position: u64,
+    end_position: u64,
+    decoded_size: u64,
+    is_http: bool,
+    http_buf: Vec<u8>,
+    http_header: Option<MessageHeader>,
+    is_certificate: bool,
+    certificate_buf: Vec<u8>,
+    certificate: Option<CertificateInfo>,
+}
+
+impl<'a> Row<'a> {
+    fn new(extension_fields: &'a ExtensionFields, normalize_uri: bool) -> Self {
+        Self {
+            extension_fields,
+            normalize_uri,
+            header: WarcHeader::empty(),
+            position: 0,
+            end_position: 0,
+            decoded_size: 0,
+            is_http: false,
+            http_buf: Vec::new(),
+            http_header: None,
+            is_certificate: false,
+            certificate_buf: Vec::new(),
+            certificate: None,
+        }
+    }
+
+    fn begin(&mut self, header: WarcHeader, position: u64) {
+        self.is_http = header
+            .fields
+            .get("Content-Type")
+            .map(|value| value.starts_with("application/http"))
+            .unwrap_or_default();
+        self.is_certificate = crate::cert::is_certificate_record(&header);
+        self.header = header;
+        self.position = position;
+        self.end_position = position;
+        self.decoded_size = 0;
+        self.http_buf.clear();
+        self.http_header = None;
+        self.certificate_buf.clear();
+        self.certificate = None;
+    }
+
+    fn block_data(&mut self, data: &[u8]) {
+        self.decoded_size += data.len() as u64;
+
+        if self.is_certificate {
+            let remain_len = MAX_CERTIFICATE_LENGTH.saturating_sub(self.certificate_buf.len());
+            self.certificate_buf
+                .extend_from_slice(&data[0..data.len().min(remain_len)]);
+        }
+
+        if !self.is_http || self.http_header.is_some() {
+            return;
+        }
+
+        let remain_len = MAX_HTTP_HEADER_LENGTH.saturating_sub(self.http_buf.len());
+
+        if remain_len == 0 {
+            return;
+        }
+
+        self.http_buf
+            .extend_from_slice(&data[0..data.len().min(remain_len)]);
+
+        if let Some(index) = crate::parse::scan_header_deliminator(&self.http_buf) {
+            match MessageHeader::parse(&self.http_buf[0..index]) {
+                Ok(header) => self.http_header = Some(header),
+                Err(error) => {
+                    tracing::debug!(?error, "could not parse HTTP header");
+                    self.is_http = false;
+                }
+            }
+
+            self.http_buf.clear();
+        }
+    }
+
+    fn end(&mut self, position: u64) {
+        self.end_position = position;
+
+        if self.is_certificate {
+            match crate::cert::parse_certificates(&self.certificate_buf) {
+                Ok(certificates) => self.certificate = certificates.into_iter().next(),
+                Err(error) => tracing::debug!(?error, "could not parse certificate"),
+            }
+
+            self.certificate_buf.clear();
+        }
+    }
+
+    fn http_status(&self) -> Option<u16> {
+        match &self.http_header.as_ref()?.start_line {
+            StartLine::Status(status) => Some(status.status_code),
+            _ => None,
+        }
+    }
+
+    fn certificate_value(&self, name: &str) -> String {
+        let Some(certificate) = &self.certificate else {
+            return String::new();
+        };
+
+        match name {
+            ":cert-subject" => certificate.subject.clone(),
+            ":cert-issuer" => certificate.issuer.clone(),
+            ":cert-not-before" => certificate.not_before.to_rfc3339(),
+            ":cert-not-after" => certificate.not_after.to_rfc3339(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn tls_protocol(&self) -> String {
+        self.header
+            .fields
+            .get_protocols()
+            .into_iter()
+            .flatten()
+            .find(|protocol| protocol.is_tls())
+            .map(|protocol| protocol.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Returns the present extension fields as a map.
+    ///
+    /// Durations are in milliseconds.
+    fn extension_values(&self, only_timing: bool) -> serde_json::Value {
+        let map = self
+            .extension_fields
+            .values(&self.header)
+            .filter(|(field, _)| !only_timing || field.kind == ExtensionKind::Duration)
+            .map(|(field, value)| {
+                let value = match value {
+                    ExtensionValue::Text(value) => serde_json::Value::String(value),
+                    ExtensionValue::Integer(value) => serde_json::Value::Number(value.into()),
+                    ExtensionValue::Duration(value) => {
+                        serde_json::json!(value.as_secs_f64() * 1000.0)
+                    }
+                };
+
+                (field.name.clone(), value)
+            })
+            .collect();
+
+        serde_json::Value::Object(map)
+    }
+
+    fn payload_type(&self) -> String {
+        if let Some(http_header) = &self.http_header {
+            http_header
+                .fields
+                .get("Content-Type")
+                .map(|value| value.to_string_lossy().to_string())
+                .unwrap_or_default()
+        } else if self.is_http {
+            String::new()
+        } else {
+            self.header
+                .fields
+                .get("Content-Type")
+                .cloned()
+                .unwrap_or_default()
+        }
+    }
+
+    fn values(&self, names: &[String], file: &str) -> Vec<serde_json::Value> {
+        let mut values = Vec::new();
+
+        for name in names {
+            let value = match name.as_str() {
+                ":position" => serde_json::Value::Number(self.position.into()),
+                ":file" => serde_json::Value::String(file.to_string()),
+                ":protocols" => serde_json::Value::String(
+                    self.header
+                        .fields
+                        .get_all("WARC-Protocol")
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ),
+                ":tls-protocol" => serde_json::Value::String(self.tls_protocol()),
+                ":timing" => self.extension_values(true),
+                ":extensions" => self.extension_values(false),
+                ":http-status" => self
+                    .http_status()
+                    .map(|status| serde_json::Value::Number(status.into()))
+                    .unwrap_or_default(),
+                ":payload-type" => serde_json::Value::String(self.payload_type()),
+                ":decoded-size" => serde_json::Value::Number(self.decoded_size.into()),
+                ":compressed-size" => serde_json::Value::Number(
+                    (self.end_position.saturating_sub(self.position)).into(),
+                ),
+                ":cert-subject" | ":cert-issuer" | ":cert-not-before" | ":cert-not-after" => {
+                    serde_json::Value::String(self.certificate_value(name))
+                }
+                _ if self.normalize_uri && name.eq_ignore_ascii_case("WARC-Target-URI") => {
+                    serde_json::Value::String(
+                        self.header
+                            .fields
+                            .get(name)
+                            .map(|value| normalize_uri(value))
+                            .unwrap_or_default(),
+                    )
+                }
+                _ => serde_json::Value::String(
+                    self.header.fields.get(name).cloned().unwrap_or_default(),
+                ),
+            };
+
+            values.push(value);
+        }
+
+        values
+    }
+}
+
+fn unique_key(unique_by: UniqueBy, normalize: bool, header: &WarcHeader) -> Option<String> {
+    match unique_by {
+        UniqueBy::Digest => {
+            let value = header.fields.get("WARC-Payload-Digest")?;
+
+            match Digest::from_str(value) {
+                Ok(digest) => Some(digest.to_string()),
+                Err(_) => Some(value.to_string()),
+            }
+        }
+        UniqueBy::TargetUri => {
+            let value = header.fields.get("WARC-Target-URI")?;
+
+            if normalize {
+                Some(normalize_uri(value))
+            } else {
+                Some(value.to_string())
+            }
+        }
+    }
+}
+
+/// Stores the first row of each unique key and counts the rows with the same key.
+///
+/// Without a database path, the rows are stored in a temporary file.
+struct UniqueRows {
+    unique_by: UniqueBy,
+    normalize_uri: bool,
+    db: Database,
+    txn: Option<WriteTransaction>,
+    row_count: u64,
+}
+
+impl UniqueRows {
+    fn new(unique_by: UniqueBy, normalize_uri: bool, path: Option<&Path>) -> anyhow::Result<Self> {
+        let builder = Database::builder();
+        let db = match path {
+            Some(path) => builder.create(path)?,
+            None => builder.create_file(super::temp::tempfile()?)?,
+        };
+        let txn = db.begin_write()?;
+        txn.delete_table(UNIQUE_KEYS_TABLE)?;
+        txn.delete_table(UNIQUE_ROWS_TABLE)?;
+        txn.open_table(UNIQUE_KEYS_TABLE)?;
+        txn.open_table(UNIQUE_ROWS_TABLE)?;
+        txn.commit()?;
+        let txn = db.begin_write()?;
+
+        Ok(Self {
+            unique_by,
+            normalize_uri,
+            db,
+            txn: Some(txn),
+            row_count: 0,
+        })
+    }
+
+    fn put(&mut self, header: &WarcHeader, values: Vec<serde_json::Value>) -> anyhow::Result<()> {
+        let row_number = self.row_count;
+        self.row_count += 1;
+
+        // Rows without a key are never duplicates of each other.
+        let key = unique_key(self.unique_by, self.normalize_uri, header)
+            .unwrap_or_else(|| format!("\0{}", row_number));
+
+        {
+            let txn = self.txn.as_ref().unwrap();
+            let mut keys_table = txn.open_table(UNIQUE_KEYS_TABLE)?;
+
+            let existing = keys_table.get(key.as_str())?.map(|value| value.value());
+
+            if let Some((first_row_number, count)) = existing {
+                keys_table.insert(key.as_str(), (first_row_number, count + 1))?;
+            } else {
+                keys_table.insert(key.as_str(), (row_number, 1))?;
+
+                let mut rows_table = txn.open_table(UNIQUE_ROWS_TABLE)?;
+                let data = serde_json::to_vec(&values)?;
+                rows_table.insert(row_number, (key.as_str(), data.as_slice()))?;
+            }
+        }
+
+        if self.row_count % UNIQUE_ROWS_BATCH_SIZE == 0 {
+            self.txn.take().unwrap().commit()?;
+            self.txn = Some(self.db.begin_write()?);
+        }
+
+        Ok(())
+    }
+
+    fn write(mut self, writers: &mut [RowWriter]) -> anyhow::Result<()> {
+        self.txn.take().unwrap().commit()?;
+
+        let txn = self.db.begin_read()?;
+        let keys_table = txn.open_table(UNIQUE_KEYS_TABLE)?;
+        let rows_table = txn.open_table(UNIQUE_ROWS_TABLE)?;
+
+        for item in rows_table.iter()? {
+            let (_row_number, value) = item?;
+            let (key, data) = value.value();
+            let mut values: Vec<serde_json::Value> = serde_json::from_slice(data)?;
+            let count = keys_table
+                .get(key)?
+                .map(|value| value.value().1)
+                .unwrap_or(1);
+
+            values.push(serde_json::Value::Number(count.into()));
+            put_all(writers, values)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn computed_values(content_type: &str, block: &[&[u8]]) -> Vec<serde_json::Value> {
+        let block_len = block.iter().map(|data| data.len() as u64).sum();
+        let mut header = WarcHeader::new(block_len, "response");
+        header
+            .fields
+            .insert("Content-Type".to_string(), content_type.to_string());
+
+        let extension_fields = ExtensionFields::default();
+        let mut row = Row::new(&extension_fields, false);
+        row.begin(header, 100);
+
+        for data in block {
+            row.block_data(data);
+        }
+
+        row.end(150);
+
+        let names = [
+            ":http-status",
+            ":payload-type",
+            ":decoded-size",
+            ":compressed-size",
+        ];
+        row.values(&names.map(String::from), "a.warc")
+    }
+
+    #[test]
+    fn test_computed_fields_http() {
+        let values = computed_values(
+            "application/http;msgtype=response",
+            &[
+                b"HTTP/1.1 404 Not Found\r\nContent-Type: text/h",
+                b"tml\r\n\r\n<html>",
+            ],
+        );
+
+        assert_eq!(
+            values,
+            [
+                serde_json::json!(404),
+                serde_json::json!("text/html"),
+                serde_json::json!(57),
+                serde_json::json!(50),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_computed_fields_non_http() {
+        let values = computed_values("text/plain", &[b"hello"]);
+
+        assert_eq!(
+            values,
+            [
+                serde_json::Value::Null,
+                serde_json::json!("text/plain"),
+                serde_json::json!(5),
+                serde_json::json!(50),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_computed_fields_missing_payload() {
+        let values = computed_values("application/http;msgtype=response", &[]);
+
+        assert_eq!(
+            values,
+            [
+                serde_json::Value::Null,
+                serde_json::json!(""),
+                serde_json::json!(0),
+                serde_json::json!(50),
+            ]
+        );
+
+        let values = computed_values(
+            "application/http;msgtype=response",
+            &[b"HTTP/1.1 204 No Content\r\n\r\n"],
+        );
+
+        assert_eq!(
+            values,
+            [
+                serde_json::json!(204),
+                serde_json::json!(""),
+                serde_json::json!(27),
+                serde_json::json!(50),
+            ]
+        );
+    }
+}
@@ -0,0 +1,383 @@
+use std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader, Read},
+};
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::{compress::Format, dataseq::SeqWriter, header::WarcHeader};
+
+use super::{arg::MembersCommand, io::ProgramInput};
+
+pub fn members(args: &MembersCommand) -> anyhow::Result<()> {
+    let output = super::common::open_output(&args.output)?;
+    let mut writer = SeqWriter::new(output, args.format.into());
+
+    for input_path in &args.input {
+        let span = tracing::info_span!("members", path = ?input_path);
+        let _span_guard = span.enter();
+
+        let compression_format = args.compression.try_into_native(input_path)?;
+
+        if compression_format != Format::Gzip {
+            anyhow::bail!("only gzip compressed files are supported")
+        }
+
+        let input = super::common::open_input(input_path)?;
+
+        tracing::info!("opened file");
+
+        let file = input_path.to_string_lossy().to_string();
+        let mut lister = MemberLister::new(input, file);
+
+        while let Some(member) = lister.next_member()? {
+            writer.put(member)?;
+        }
+
+        tracing::info!("closed file");
+    }
+
+    Ok(())
+}
+
+/// Alignment of a compression member with the records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum MemberStatus {
+    /// The member contains exactly one whole record.
+    Record,
+    /// The member contains more than one whole record.
+    MultipleRecords,
+    /// The member starts or ends within a record.
+    PartialRecord,
+    /// The member does not contain any data.
+    Empty,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MemberRow {
+    file: String,
+    position: u64,
+    compressed_size: u64,
+    decoded_position: u64,
+    decoded_size: u64,
+    record_count: u64,
+    record_id: String,
+    status: MemberStatus,
+    #[serde(skip)]
+    starts_at_boundary: bool,
+    #[serde(skip)]
+    ends_at_boundary: bool,
+}
+
+impl MemberRow {
+    fn decoded_end(&self) -> u64 {
+        self.decoded_position + self.decoded_size
+    }
+
+    fn update_status(&mut self) {
+        self.status = if self.decoded_size == 0 {
+            MemberStatus::Empty
+        } else if !self.starts_at_boundary || !self.ends_at_boundary {
+            MemberStatus::PartialRecord
+        } else if self.record_count > 1 {
+            MemberStatus::MultipleRecords
+        } else {
+            MemberStatus::Record
+        }
+    }
+}
+
+/// Reads gzip members one at a time and determines the records that start
+/// within each member.
+///
+/// Members are returned once all the record headers that start within them
+/// are read.
+struct MemberLister {
+    file: String,
+    reader: CountingReader<ProgramInput>,
+    scanner: RecordScanner,
+    pending: VecDeque<MemberRow>,
+    records: Vec<(u64, String)>,
+    buf: Vec<u8>,
+    is_eof: bool,
+}
+
+impl MemberLister {
+    fn new(input: ProgramInput, file: String) -> Self {
+        Self {
+            file,
+            reader: CountingReader::new(input),
+            scanner: RecordScanner::new(),
+            pending: VecDeque::new(),
+            records: Vec::new(),
+            buf: vec![0; crate::io::IO_BUFFER_LENGTH],
+            is_eof: false,
+        }
+    }
+
+    fn next_member(&mut self) -> anyhow::Result<Option<MemberRow>> {
+        loop {
+            if let Some(member) = self.pop_complete_member() {
+                return Ok(Some(member));
+            }
+
+            if self.is_eof {
+                return Ok(None);
+            }
+
+            self.read_member()?;
+        }
+    }
+
+    fn pop_complete_member(&mut self) -> Option<MemberRow> {
+        let member = self.pending.front()?;
+        let is_complete = self.is_eof
+            || self
+                .scanner
+                .header_start()
+                .map_or(true, |start| start >= member.decoded_end());
+
+        if is_complete {
+            let mut member = self.pending.pop_front().unwrap();
+            member.update_status();
+            Some(member)
+        } else {
+            None
+        }
+    }
+
+    fn read_member(&mut self) -> anyhow::Result<()> {
+        if self.reader.fill_buf()?.is_empty() {
+            self.is_eof = true;
+            return Ok(());
+        }
+
+        let position = self.reader.position;
+        let decoded_position = self.scanner.position;
+        let starts_at_boundary = self.scanner.is_boundary();
+        let mut decoder = flate2::bufread::GzDecoder::new(&mut self.reader);
+
+        loop {
+            let read_length = decoder
+                .read(&mut self.buf)
+                .with_context(|| format!("decoding member at position {} failed", position))?;
+
+            if read_length == 0 {
+                break;
+            }
+
+            self.scanner
+                .feed(&self.buf[0..read_length], &mut self.records)?;
+        }
+
+        self.pending.push_back(MemberRow {
+            file: self.file.clone(),
+            position,
+            compressed_size: self.reader.position - position,
+            decoded_position,
+            decoded_size: self.scanner.position - decoded_position,
+            record_count: 0,
+            record_id: String::new(),
+            status: MemberStatus::Empty,
+            starts_at_boundary,
+            ends_at_boundary: self.scanner.is_boundary(),
+        });
+
+        for (start, record_id) in self.records.drain(..) {
+            let member = self
+                .pending
+                .iter_mut()
+                .find(|member| member.decoded_position <= start && start < member.decoded_end());
+
+            if let Some(member) = member {
+                if member.record_count == 0 {
+                    member.record_id = record_id;
+                }
+                member.record_count += 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Tracks the record boundaries in the decompressed data.
+struct RecordScanner {
+    position: u64,
+    state: ScanState,
+}
+
+enum ScanState {
+    Header { buf: Vec<u8>, start: Option<u64> },
+    Block { remaining: u64 },
+}
+
+impl RecordScanner {
+    fn new() -> Self {
+        Self {
+            position: 0,
+            state: ScanState::Header {
+                buf: Vec::new(),
+                start: None,
+            },
+        }
+    }
+
+    /// Returns whether the position is between records.
+    fn is_boundary(&self) -> bool {
+        matches!(&self.state, ScanState::Header { start: None, .. })
+    }
+
+    /// Returns the position of the header that is not yet completely read.
+    fn header_start(&self) -> Option<u64> {
+        match &self.state {
+            ScanState::Header { start, .. } => *start,
+            ScanState::Block { .. } => None,
+        }
+    }
+
+    /// Processes the data and appends the positions and IDs of the records
+    /// that start.
+    fn feed(&mut self, mut data: &[u8], records: &mut Vec<(u64, String)>) -> anyhow::Result<()> {
+        while !data.is_empty() {
+            match &mut self.state {
+                ScanState::Header { buf, start } => {
+                    if start.is_none() {
+                        // Tolerate extra line endings between records.
+                        let skip_len = data
+                            .iter()
+                            .position(|c| !matches!(c, b'\r' | b'\n'))
+                            .unwrap_or(data.len());
+                        self.position += skip_len as u64;
+                        data = &data[skip_len..];
+
+                        if data.is_empty() {
+                            break;
+                        }
+
+                        *start = Some(self.position);
+                    }
+
+                    let previous_len = buf.len();
+                    buf.extend_from_slice(data);
+
+                    let Some(index) = crate::parse::scan_header_deliminator(buf) else {
+                        if buf.len() > super::common::max_header_length() {
+                            anyhow::bail!("header too long at decoded position {}", start.unwrap());
+                        }
+
+                        self.position += data.len() as u64;
+                        break;
+                    };
+
+                    let header = WarcHeader::parse(&buf[0..index]).with_context(|| {
+                        format!("invalid WARC header at decoded position {}", start.unwrap())
+                    })?;
+                    let length = header.content_length()?;
+                    let record_id = header
+                        .fields
+                        .get("WARC-Record-ID")
+                        .cloned()
+                        .unwrap_or_default();
+
+                    records.push((start.unwrap(), record_id));
+
+                    let consumed_len = index - previous_len;
+                    self.position += consumed_len as u64;
+                    data = &data[consumed_len..];
+
+                    // The block is followed by two line endings.
+                    self.state = ScanState::Block {
+                        remaining: length + 4,
+                    };
+                }
+                ScanState::Block { remaining } => {
+                    let length = data
+                        .len()
+                        .min(usize::try_from(*remaining).unwrap_or(usize::MAX));
+                    *remaining -= length as u64;
+                    self.position += length as u64;
+                    data = &data[length..];
+
+                    if *remaining == 0 {
+                        self.state = ScanState::Header {
+                            buf: Vec::new(),
+                            start: None,
+                        };
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Buffered reader that counts the bytes consumed.
+struct CountingReader<R> {
+    inner: BufReader<R>,
+    position: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner: BufReader::new(inner),
+            position: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let length = self.inner.read(buf)?;
+        self.position += length as u64;
+        Ok(length)
+    }
+}
+
+impl<R: Read> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.position += amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_scanner() {
+        let data =
+            b"WARC/1.1\r\nWARC-Record-ID: <urn:example:1>\r\nContent-Length: 3\r\n\r\nabc\r\n\r\n\
+            WARC/1.1\r\nWARC-Record-ID: <urn:example:2>\r\nContent-Length: 0\r\n\r\n\r\n\r\n";
+        let mut scanner = RecordScanner::new();
+        let mut records = Vec::new();
+
+        for (index, chunk) in data.chunks(10).enumerate() {
+            scanner.feed(chunk, &mut records).unwrap();
+
+            if index == 0 {
+                assert!(!scanner.is_boundary());
+                assert_eq!(scanner.header_start(), Some(0));
+            }
+        }
+
+        assert!(scanner.is_boundary());
+        assert_eq!(scanner.position, data.len() as u64);
+        assert_eq!(
+            records,
+            vec![
+                (0, "<urn:example:1>".to_string()),
+                (71, "<urn:example:2>".to_string())
+            ]
+        );
+    }
+}
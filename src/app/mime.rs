@@ -0,0 +1,192 @@
+use std::{path::Path, str::FromStr};
+
+use crate::{
+    extract::ExtractMetadata,
+    header::{fields::MediaType, WarcHeader},
+};
+
+/// Number of bytes at the start of the content used for sniffing.
+pub const SNIFF_LENGTH: usize = 512;
+
+/// Filename extensions and media types.
+///
+/// The first extension for a media type is the preferred extension.
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("js", "application/javascript"),
+    ("json", "application/json"),
+    ("xml", "application/xml"),
+    ("xml", "text/xml"),
+    ("txt", "text/plain"),
+    ("csv", "text/csv"),
+    ("md", "text/markdown"),
+    ("svg", "image/svg+xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("ico", "image/x-icon"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("wasm", "application/wasm"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+];
+
+/// Returns a media type by the filename extension or by the contents.
+pub fn guess_media_type(path: &Path, head: &[u8]) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|value| value.to_str())
+        .map(|value| value.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    for (candidate, media_type) in EXTENSIONS {
+        if *candidate == extension {
+            return media_type;
+        }
+    }
+
+    sniff_media_type(head)
+}
+
+/// Returns the preferred filename extension for a media type.
+pub fn media_type_extension(media_type: &str) -> Option<&'static str> {
+    EXTENSIONS
+        .iter()
+        .find(|(_, candidate)| candidate.eq_ignore_ascii_case(media_type))
+        .map(|(extension, _)| *extension)
+}
+
+/// Returns a media type by the contents.
+pub fn sniff_media_type(head: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\0asm", "application/wasm"),
+    ];
+
+    for (signature, media_type) in SIGNATURES {
+        if head.starts_with(signature) {
+            return media_type;
+        }
+    }
+
+    let text = head.trim_ascii_start();
+
+    if starts_with_ignore_case(text, b"<!doctype html") || starts_with_ignore_case(text, b"<html") {
+        "text/html"
+    } else if is_text(head) {
+        "text/plain"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+/// Returns the media type of the extracted content.
+///
+/// The declared Content-Type is used unless it is missing or generic,
+/// in which case the media type is sniffed from the start of the content.
+pub fn content_media_type(header: &WarcHeader, metadata: &ExtractMetadata, head: &[u8]) -> String {
+    let declared = if metadata.http_status.is_some() {
+        metadata
+            .http_headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("Content-Type"))
+            .map(|(_, value)| value.as_str())
+    } else {
+        header
+            .fields
+            .get("Content-Type")
+            .map(|value| value.as_str())
+    };
+    let declared = declared
+        .and_then(|value| MediaType::from_str(value).ok())
+        .map(|value| format!("{}/{}", value.type_, value.subtype).to_ascii_lowercase())
+        .filter(|value| value != "application/octet-stream" && value != "application/http");
+
+    declared.unwrap_or_else(|| sniff_media_type(head).to_string())
+}
+
+/// Returns whether a media type matches a pattern such as "image/png" or
+/// "image/*".
+pub fn is_media_type_match(pattern: &str, media_type: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(type_) => media_type
+            .split_once('/')
+            .is_some_and(|(candidate, _)| candidate.eq_ignore_ascii_case(type_)),
+        None => pattern == "*" || pattern.eq_ignore_ascii_case(media_type),
+    }
+}
+
+fn starts_with_ignore_case(data: &[u8], prefix: &[u8]) -> bool {
+    data.len() >= prefix.len() && data[0..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+fn is_text(data: &[u8]) -> bool {
+    let valid_up_to = match std::str::from_utf8(data) {
+        Ok(_) => data.len(),
+        Err(error) => error.valid_up_to(),
+    };
+
+    // A multi-byte character may be cut off at the end.
+    data.len() - valid_up_to < 4
+        && !data[0..valid_up_to]
+            .iter()
+            .any(|&c| c < 0x20 && !matches!(c, b'\t' | b'\n' | b'\r' | 0x0c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guess_media_type() {
+        assert_eq!(guess_media_type(Path::new("a/b.HTML"), b""), "text/html");
+        assert_eq!(
+            guess_media_type(Path::new("a"), b"\x89PNG\r\n\x1a\n"),
+            "image/png"
+        );
+        assert_eq!(
+            guess_media_type(Path::new("a"), b"  <!DOCTYPE html><p>"),
+            "text/html"
+        );
+        assert_eq!(guess_media_type(Path::new("a"), b"hello\n"), "text/plain");
+        assert_eq!(
+            guess_media_type(Path::new("a"), b"\x00\x01\x02"),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn test_media_type_extension() {
+        assert_eq!(media_type_extension("text/html"), Some("html"));
+        assert_eq!(media_type_extension("Image/JPEG"), Some("jpg"));
+        assert_eq!(media_type_extension("application/javascript"), Some("js"));
+        assert_eq!(media_type_extension("application/octet-stream"), None);
+    }
+
+    #[test]
+    fn test_is_media_type_match() {
+        assert!(is_media_type_match("image/*", "image/png"));
+        assert!(is_media_type_match("text/html", "TEXT/HTML"));
+        assert!(is_media_type_match("*", "text/html"));
+        assert!(!is_media_type_match("image/*", "text/html"));
+        assert!(!is_media_type_match("text/html", "text/plain"));
+    }
+}
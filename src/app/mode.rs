@@ -0,0 +1,58 @@
+use std::sync::OnceLock;
+
+use crate::header::LineFolding;
+
+static GLOBAL_MODE: OnceLock<Mode> = OnceLock::new();
+
+/// Tolerance of malformed input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Mode {
+    /// Stop at the first problem in the input.
+    #[default]
+    Strict,
+    /// Recover from problems in the input where possible and log them as
+    /// warnings.
+    Permissive,
+}
+
+impl Mode {
+    /// Whether a file that ends within a record is accepted.
+    ///
+    /// The incomplete record is ignored.
+    pub fn accepts_truncation(&self) -> bool {
+        *self == Self::Permissive
+    }
+
+    /// Whether errors processing a record are logged instead of stopping.
+    pub fn continues_on_error(&self) -> bool {
+        *self == Self::Permissive
+    }
+
+    /// Whether records are accepted with the deviations from the
+    /// specification commonly written by other software, such as bare LF
+    /// line endings and sloppy record boundaries.
+    pub fn is_lenient(&self) -> bool {
+        *self == Self::Permissive
+    }
+
+    /// Whether corrupt data is skipped to the next record.
+    pub fn recovers_corrupt_data(&self) -> bool {
+        *self == Self::Permissive
+    }
+
+    /// Handling of folded header lines unless given by `--line-folding`.
+    ///
+    /// Folded lines are allowed by the specification, so both modes
+    /// unfold them.
+    pub fn line_folding(&self) -> LineFolding {
+        LineFolding::Unfold
+    }
+}
+
+pub fn set_global_mode(mode: Mode) {
+    GLOBAL_MODE.set(mode).unwrap();
+}
+
+pub fn global_mode() -> Mode {
+    GLOBAL_MODE.get().copied().unwrap_or_default()
+}
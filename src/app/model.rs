@@ -1,8 +1,10 @@
 use std::path::PathBuf;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Message of the export and import commands.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub enum WarcMessage {
     Metadata(Metadata),
     Header(Header),
@@ -14,33 +16,50 @@ pub enum WarcMessage {
     EndOfFile(EndOfFile),
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Location of a record, provided only by export.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Metadata {
+    /// Path of the input WARC file.
     pub file: PathBuf,
+    /// Position of the record in the file.
     pub position: u64,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde_with::serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Header {
+    /// Version such as "WARC/1.1".
     pub version: String,
+    /// Name-value pairs of the fields.
     pub fields: Vec<(String, String)>,
+    /// Header as it appears in the file, provided only by export with
+    /// `--raw-header`. In JSON, it is encoded in standard base64. When
+    /// present, import writes it verbatim instead of the version and fields.
+    #[serde_as(
+        as = "Option<serde_with::IfIsHumanReadable<serde_with::base64::Base64,serde_with::Bytes>>"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub raw: Option<Vec<u8>>,
 }
 
 #[serde_with::serde_as]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct BlockChunk {
+    /// Segment of the block. In JSON, it is encoded in standard base64.
     #[serde_as(as = "serde_with::IfIsHumanReadable<serde_with::base64::Base64,serde_with::Bytes>")]
+    #[schemars(with = "String")]
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct BlockEnd {
     pub crc32: Option<u32>,
     pub crc32c: Option<u32>,
     pub xxh3: Option<u64>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ExtractMetadata {
     pub has_content: bool,
     pub file_path_components: Vec<String>,
@@ -48,18 +67,21 @@ pub struct ExtractMetadata {
 }
 
 #[serde_with::serde_as]
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ExtractChunk {
+    /// Segment of the extracted content. In JSON, it is encoded in standard
+    /// base64.
     #[serde_as(as = "serde_with::IfIsHumanReadable<serde_with::base64::Base64,serde_with::Bytes>")]
+    #[schemars(with = "String")]
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct ExtractEnd {
     pub crc32: Option<u32>,
     pub crc32c: Option<u32>,
     pub xxh3: Option<u64>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct EndOfFile {}
@@ -0,0 +1,21 @@
+use std::sync::OnceLock;
+
+use crate::header::{record_id::RecordId, WarcHeader};
+
+static GLOBAL_DETERMINISTIC: OnceLock<bool> = OnceLock::new();
+
+pub fn set_global_deterministic_record_ids(value: bool) {
+    GLOBAL_DETERMINISTIC.set(value).unwrap();
+}
+
+/// Returns a new ID for the record.
+///
+/// If the user asked for deterministic IDs, the ID is derived from the
+/// fields of the record, otherwise it is random.
+pub fn generate_record_id(header: &WarcHeader) -> RecordId {
+    if GLOBAL_DETERMINISTIC.get().copied().unwrap_or_default() {
+        RecordId::from_header(header)
+    } else {
+        RecordId::new()
+    }
+}
@@ -0,0 +1,474 @@
+use percent_encoding::{AsciiSet, CONTROLS};
+use regex::bytes::{Captures, Regex, RegexBuilder};
+
+use crate::extract::url_to_path_components;
+
+/// Characters of filenames that are escaped in relative links.
+const PATH_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'\'')
+    .add(b'(')
+    .add(b')')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'\\')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Names of HTML attributes containing a single URL.
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "action", "poster", "background"];
+
+const CSS_URL_PATTERN: &str =
+    r#"(?i)url\(\s*(?:"([^"]*)"|'([^']*)'|([^)\s"']*))\s*\)|@import\s+(?:"([^"]*)"|'([^']*)')"#;
+
+/// Kind of document containing links.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Html,
+    Css,
+}
+
+impl DocumentKind {
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "text/html" | "application/xhtml+xml" => Some(Self::Html),
+            "text/css" => Some(Self::Css),
+            _ => None,
+        }
+    }
+}
+
+/// The URL of a document and the path components of its extracted file.
+pub struct Location {
+    url: url::Url,
+    components: Vec<String>,
+}
+
+impl Location {
+    pub fn new(url: &str, components: &[String]) -> Option<Self> {
+        Some(Self {
+            url: url::Url::parse(url).ok()?,
+            components: components.to_vec(),
+        })
+    }
+
+    /// Returns the relative path to the extracted file of the linked URL.
+    ///
+    /// Links that are not HTTP URLs or that refer to the same document
+    /// are not rewritten.
+    fn rewrite_url(&self, value: &[u8]) -> Option<String> {
+        let value = String::from_utf8_lossy(value);
+        let value = value.trim();
+
+        if value.is_empty() || value.starts_with('#') {
+            return None;
+        }
+
+        let mut url = self.url.join(value).ok()?;
+
+        if !matches!(url.scheme(), "http" | "https") {
+            return None;
+        }
+
+        let fragment = url.fragment().map(|value| value.to_string());
+        url.set_fragment(None);
+
+        let mut path = relative_path(&self.components, &url_to_path_components(url.as_str()));
+
+        if let Some(fragment) = fragment {
+            path.push('#');
+            path.push_str(&fragment);
+        }
+
+        Some(path)
+    }
+}
+
+/// Returns the path from the directory of the file to the target file.
+fn relative_path(from: &[String], to: &[String]) -> String {
+    let from_dir = &from[0..from.len().saturating_sub(1)];
+    let to_dir = &to[0..to.len().saturating_sub(1)];
+    let common_len = from_dir
+        .iter()
+        .zip(to_dir)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut segments = Vec::new();
+
+    for _ in common_len..from_dir.len() {
+        segments.push("..".to_string());
+    }
+
+    for component in &to[common_len..] {
+        segments
+            .push(percent_encoding::utf8_percent_encode(component, PATH_ENCODE_SET).to_string());
+    }
+
+    segments.join("/")
+}
+
+/// Rewrites links in HTML and CSS documents to relative paths of the
+/// extracted files.
+///
+/// Documents are modified only where links occur so that the
+/// rest of the document, including its character encoding, is preserved.
+pub struct LinkRewriter {
+    css_pattern: Regex,
+}
+
+impl LinkRewriter {
+    pub fn new() -> Self {
+        Self {
+            css_pattern: RegexBuilder::new(CSS_URL_PATTERN)
+                .unicode(false)
+                .build()
+                .unwrap(),
+        }
+    }
+
+    pub fn rewrite(&self, kind: DocumentKind, location: &Location, data: &[u8]) -> Vec<u8> {
+        match kind {
+            DocumentKind::Html => self.rewrite_html(location, data),
+            DocumentKind::Css => self.rewrite_css(location, data),
+        }
+    }
+
+    fn rewrite_css(&self, location: &Location, data: &[u8]) -> Vec<u8> {
+        self.css_pattern
+            .replace_all(data, |captures: &Captures| {
+                let whole = captures.get(0).unwrap().as_bytes();
+                let is_import = whole[0] == b'@';
+
+                for (group, quote) in [(1, "\""), (2, "'"), (3, ""), (4, "\""), (5, "'")] {
+                    if let Some(value) = captures.get(group) {
+                        return match location.rewrite_url(value.as_bytes()) {
+                            Some(path) if is_import => {
+                                format!("@import {}{}{}", quote, path, quote).into_bytes()
+                            }
+                            Some(path) => format!("url({}{}{})", quote, path, quote).into_bytes(),
+                            None => whole.to_vec(),
+                        };
+                    }
+                }
+
+                whole.to_vec()
+            })
+            .into_owned()
+    }
+
+    fn rewrite_html(&self, location: &Location, data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(data.len());
+        let mut index = 0;
+
+        while index < data.len() {
+            let Some(offset) = data[index..].iter().position(|&c| c == b'<') else {
+                output.extend_from_slice(&data[index..]);
+                break;
+            };
+
+            let start = index + offset;
+            output.extend_from_slice(&data[index..start]);
+
+            if data[start..].starts_with(b"<!--") {
+                let end = find(&data[start..], b"-->")
+                    .map(|offset| start + offset + 3)
+                    .unwrap_or(data.len());
+                output.extend_from_slice(&data[start..end]);
+                index = end;
+                continue;
+            }
+
+            if !data.get(start + 1).is_some_and(|c| c.is_ascii_alphabetic()) {
+                output.push(b'<');
+                index = start + 1;
+                continue;
+            }
+
+            let end = tag_end(data, start);
+            let tag = &data[start..end];
+            let name = tag_name(tag);
+
+            self.rewrite_tag(location, tag, &mut output);
+            index = end;
+
+            if name == "script" || name == "style" {
+                // Contents of these elements are not HTML.
+                let closing = format!("</{}", name);
+                let close = find_ignore_case(&data[index..], closing.as_bytes())
+                    .map(|offset| index + offset)
+                    .unwrap_or(data.len());
+
+                if name == "style" {
+                    output.extend(self.rewrite_css(location, &data[index..close]));
+                } else {
+                    output.extend_from_slice(&data[index..close]);
+                }
+
+                index = close;
+            }
+        }
+
+        output
+    }
+
+    fn rewrite_tag(&self, location: &Location, tag: &[u8], output: &mut Vec<u8>) {
+        let mut index = 1 + tag_name(tag).len();
+        let mut copied = 0;
+
+        while index < tag.len() {
+            while index < tag.len() && (tag[index].is_ascii_whitespace() || tag[index] == b'/') {
+                index += 1;
+            }
+
+            let name_start = index;
+
+            while index < tag.len()
+                && !tag[index].is_ascii_whitespace()
+                && !matches!(tag[index], b'=' | b'>' | b'/')
+            {
+                index += 1;
+            }
+
+            if index == name_start {
+                break;
+            }
+
+            let name = String::from_utf8_lossy(&tag[name_start..index]).to_ascii_lowercase();
+
+            while index < tag.len() && tag[index].is_ascii_whitespace() {
+                index += 1;
+            }
+
+            if tag.get(index) != Some(&b'=') {
+                continue;
+            }
+
+            index += 1;
+
+            while index < tag.len() && tag[index].is_ascii_whitespace() {
+                index += 1;
+            }
+
+            let (value_start, value_end) = match tag.get(index) {
+                Some(&quote) if quote == b'"' || quote == b'\'' => {
+                    let value_start = index + 1;
+                    let value_end = tag[value_start..]
+                        .iter()
+                        .position(|&c| c == quote)
+                        .map(|offset| value_start + offset)
+                        .unwrap_or(tag.len());
+                    index = (value_end + 1).min(tag.len());
+                    (value_start, value_end)
+                }
+                _ => {
+                    let value_start = index;
+
+                    while index < tag.len()
+                        && !tag[index].is_ascii_whitespace()
+                        && tag[index] != b'>'
+                    {
+                        index += 1;
+                    }
+
+                    (value_start, index)
+                }
+            };
+
+            let value = &tag[value_start..value_end];
+            let new_value = if URL_ATTRIBUTES.contains(&name.as_str()) {
+                location
+                    .rewrite_url(&decode_ampersand(value))
+                    .map(String::into_bytes)
+            } else if name == "srcset" {
+                Some(rewrite_srcset(location, &decode_ampersand(value)))
+            } else if name == "style" {
+                Some(self.rewrite_css(location, value))
+            } else {
+                None
+            };
+
+            if let Some(new_value) = new_value {
+                output.extend_from_slice(&tag[copied..value_start]);
+                output.extend_from_slice(&new_value);
+                copied = value_end;
+            }
+        }
+
+        output.extend_from_slice(&tag[copied..]);
+    }
+}
+
+impl Default for LinkRewriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Rewrites the image candidates in a `srcset` attribute value.
+fn rewrite_srcset(location: &Location, value: &[u8]) -> Vec<u8> {
+    let mut candidates = Vec::new();
+
+    for candidate in value.split(|&c| c == b',') {
+        let candidate = candidate.trim_ascii();
+        let url_end = candidate
+            .iter()
+            .position(|c| c.is_ascii_whitespace())
+            .unwrap_or(candidate.len());
+        let (url, descriptor) = candidate.split_at(url_end);
+
+        let mut output = match location.rewrite_url(url) {
+            Some(path) => path.into_bytes(),
+            None => url.to_vec(),
+        };
+        output.extend_from_slice(descriptor);
+        candidates.push(output);
+    }
+
+    candidates.join(&b", "[..])
+}
+
+/// Returns the index after the end of the tag starting at `start`.
+fn tag_end(data: &[u8], start: usize) -> usize {
+    let mut quote = None;
+
+    for (index, &c) in data.iter().enumerate().skip(start + 1) {
+        match quote {
+            Some(value) if c == value => quote = None,
+            Some(_) => {}
+            None if c == b'"' || c == b'\'' => quote = Some(c),
+            None if c == b'>' => return index + 1,
+            None => {}
+        }
+    }
+
+    data.len()
+}
+
+fn tag_name(tag: &[u8]) -> String {
+    let name_len = tag[1..]
+        .iter()
+        .position(|c| !c.is_ascii_alphanumeric() && *c != b'-')
+        .unwrap_or(tag.len() - 1);
+
+    String::from_utf8_lossy(&tag[1..1 + name_len]).to_ascii_lowercase()
+}
+
+fn decode_ampersand(value: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(value.len());
+    let mut index = 0;
+
+    while index < value.len() {
+        if value[index..].starts_with(b"&amp;") {
+            output.push(b'&');
+            index += 5;
+        } else {
+            output.push(value[index]);
+            index += 1;
+        }
+    }
+
+    output
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn find_ignore_case(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(url: &str) -> Location {
+        Location::new(url, &url_to_path_components(url)).unwrap()
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let components = |value: &str| {
+            value
+                .split('/')
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            relative_path(
+                &components("http/a/b/c.html"),
+                &components("http/a/b/d.html")
+            ),
+            "d.html"
+        );
+        assert_eq!(
+            relative_path(
+                &components("http/a/b/c.html"),
+                &components("http/a/e/f g.png")
+            ),
+            "../e/f%20g.png"
+        );
+        assert_eq!(
+            relative_path(&components("http/a/c.html"), &components("https/x/y.css")),
+            "../../https/x/y.css"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_html() {
+        let rewriter = LinkRewriter::new();
+        let location = location("http://example.com/dir/page.html");
+        let html = b"<!DOCTYPE html><html><head>\
+            <link rel=stylesheet href=\"/style.css\">\
+            <style>body { background: url('/bg.png') }</style>\
+            <script>var a = '<a href=\"/x\">';</script></head>\
+            <body><!-- <a href=\"/comment\"> -->\
+            <a href='http://example.com/dir/other.html?a=1&amp;b=2#top' class=\"link\">a</a>\
+            <a href=\"#here\">b</a><a href=\"mailto:a@example.com\">c</a>\
+            <img src=img.png srcset=\"/a.png 1x, /b.png 2x\" style=\"background: url(/c.png)\">\
+            x < y</body></html>";
+
+        let output = rewriter.rewrite(DocumentKind::Html, &location, html);
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<!DOCTYPE html><html><head>\
+            <link rel=stylesheet href=\"../style.css\">\
+            <style>body { background: url('../bg.png') }</style>\
+            <script>var a = '<a href=\"/x\">';</script></head>\
+            <body><!-- <a href=\"/comment\"> -->\
+            <a href='other.html/a=1&b=2#top' class=\"link\">a</a>\
+            <a href=\"#here\">b</a><a href=\"mailto:a@example.com\">c</a>\
+            <img src=img.png srcset=\"../a.png 1x, ../b.png 2x\" style=\"background: url(../c.png)\">\
+            x < y</body></html>"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_css() {
+        let rewriter = LinkRewriter::new();
+        let location = location("http://example.com/css/main.css");
+        let css = b"@import \"base.css\";\n@IMPORT url(/print.css) print;\n\
+            a { background: URL( \"data:image/png;base64,AA==\" ) }";
+
+        let output = rewriter.rewrite(DocumentKind::Css, &location, css);
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "@import \"base.css\";\n@IMPORT url(../print.css) print;\n\
+            a { background: URL( \"data:image/png;base64,AA==\" ) }"
+        );
+    }
+}
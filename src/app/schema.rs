@@ -0,0 +1,86 @@
+use std::{collections::BTreeMap, io::Write};
+
+use schemars::{schema::RootSchema, JsonSchema};
+
+use crate::verify::Problem;
+
+use super::{model::WarcMessage, verify::SummaryMessage};
+
+/// Value of a field selected for a listing row or a problem.
+// The types are not constructed and only describe the output for the schema.
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(untagged)]
+enum FieldValue {
+    Text(String),
+    Number(f64),
+    /// Object such as the values of `:extensions`.
+    Object(BTreeMap<String, serde_json::Value>),
+}
+
+/// Row of a listing containing a value for each selected field.
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct ListRow(Vec<Option<FieldValue>>);
+
+/// Message of the verify command.
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(untagged)]
+enum VerifyMessage<'a> {
+    Problem(Problem),
+    /// Values of the fields of a problem selected by `--field`.
+    Fields(Vec<Option<FieldValue>>),
+    Summary(SummaryMessage<'a>),
+}
+
+/// Returns the schema of the messages of the export and import commands.
+pub fn export_schema() -> RootSchema {
+    schemars::schema_for!(WarcMessage)
+}
+
+/// Returns the schema of the rows of the list command.
+pub fn list_schema() -> RootSchema {
+    schemars::schema_for!(ListRow)
+}
+
+/// Returns the schema of the messages of the verify command.
+pub fn verify_schema() -> RootSchema {
+    schemars::schema_for!(VerifyMessage)
+}
+
+/// Prints the schema to standard output as JSON.
+pub fn print_schema(schema: &RootSchema) -> anyhow::Result<()> {
+    let mut output = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut output, schema)?;
+    writeln!(output)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_schema_file(schema: RootSchema, file: &str) {
+        let expected: serde_json::Value = serde_json::from_str(file).unwrap();
+
+        assert_eq!(serde_json::to_value(schema).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_schema_files() {
+        assert_schema_file(
+            export_schema(),
+            include_str!("../../doc/schema/export.schema.json"),
+        );
+        assert_schema_file(
+            list_schema(),
+            include_str!("../../doc/schema/list.schema.json"),
+        );
+        assert_schema_file(
+            verify_schema(),
+            include_str!("../../doc/schema/verify.schema.json"),
+        );
+    }
+}
@@ -0,0 +1,445 @@
+use std::{num::NonZeroUsize, path::Path};
+
+use anyhow::Context;
+use tantivy::{
+    collector::TopDocs,
+    query::QueryParser,
+    schema::{Field, Schema, Value, INDEXED, STORED, STRING, TEXT},
+    Index, IndexWriter, TantivyDocument, Term,
+};
+
+use crate::{
+    app::common::{ReaderEvent, ReaderPipeline},
+    extract::WarcExtractor,
+    header::{uri::normalize_uri, WarcHeader},
+};
+
+use super::{
+    arg::{IndexCommand, SearchCommand},
+    list::RowWriter,
+};
+
+/// Maximum length of the content that is indexed per record.
+const MAX_CONTENT_LENGTH: usize = 4 * 1024 * 1024;
+
+const WRITER_MEMORY_BUDGET: usize = 50_000_000;
+
+const SEARCH_COLUMNS: &[&str] = &[
+    ":score",
+    ":file",
+    ":position",
+    "WARC-Record-ID",
+    "WARC-Target-URI",
+    "WARC-Date",
+    ":title",
+];
+
+/// Fields of a document in the index.
+///
+/// Each document is a record keyed by the file and the position of the
+/// record in the file.
+struct Fields {
+    file: Field,
+    position: Field,
+    record_id: Field,
+    target_uri: Field,
+    date: Field,
+    media_type: Field,
+    title: Field,
+    body: Field,
+}
+
+impl Fields {
+    fn build() -> (Schema, Self) {
+        let mut builder = Schema::builder();
+        let fields = Self {
+            file: builder.add_text_field("file", STRING | STORED),
+            position: builder.add_u64_field("position", INDEXED | STORED),
+            record_id: builder.add_text_field("record_id", STRING | STORED),
+            target_uri: builder.add_text_field("target_uri", STRING | STORED),
+            date: builder.add_text_field("date", STRING | STORED),
+            media_type: builder.add_text_field("media_type", STRING | STORED),
+            title: builder.add_text_field("title", TEXT | STORED),
+            body: builder.add_text_field("body", TEXT),
+        };
+
+        (builder.build(), fields)
+    }
+
+    fn from_schema(schema: &Schema) -> anyhow::Result<Self> {
+        Ok(Self {
+            file: schema.get_field("file")?,
+            position: schema.get_field("position")?,
+            record_id: schema.get_field("record_id")?,
+            target_uri: schema.get_field("target_uri")?,
+            date: schema.get_field("date")?,
+            media_type: schema.get_field("media_type")?,
+            title: schema.get_field("title")?,
+            body: schema.get_field("body")?,
+        })
+    }
+}
+
+fn open_index(path: &Path) -> anyhow::Result<(Index, Fields)> {
+    let index =
+        Index::open_in_dir(path).with_context(|| format!("opening index {:?} failed", path))?;
+    let fields = Fields::from_schema(&index.schema())?;
+
+    Ok((index, fields))
+}
+
+fn open_or_create_index(path: &Path) -> anyhow::Result<(Index, Fields)> {
+    if path.join("meta.json").exists() {
+        return open_index(path);
+    }
+
+    std::fs::create_dir_all(path)
+        .with_context(|| format!("creating directory {:?} failed", path))?;
+
+    let (schema, fields) = Fields::build();
+    let index = Index::create_in_dir(path, schema)
+        .with_context(|| format!("creating index {:?} failed", path))?;
+
+    Ok((index, fields))
+}
+
+pub fn index(args: &IndexCommand) -> anyhow::Result<()> {
+    let (index, fields) = open_or_create_index(&args.index)?;
+    let mut writer: IndexWriter = index.writer(WRITER_MEMORY_BUDGET)?;
+
+    for input_path in &args.input {
+        let span = tracing::info_span!("index", path = ?input_path);
+        let _span_guard = span.enter();
+
+        let input = super::common::open_input(input_path)?;
+
+        tracing::info!("opened file");
+
+        let compression_format = args.compression.try_into_native(input_path)?;
+        let file_len = std::fs::metadata(input_path).map(|m| m.len()).ok();
+        let file = input_path.to_string_lossy().to_string();
+
+        // Documents from a previous run on the same file are replaced.
+        writer.delete_term(Term::from_field_text(fields.file, &file));
+
+        let mut indexer = Indexer::new(&fields, &file, args.normalize_uri);
+
+        ReaderPipeline::new(
+            |event| match event {
+                ReaderEvent::Header {
+                    header,
+                    record_boundary_position,
+                    ..
+                } => indexer.process_header(&header, record_boundary_position),
+                ReaderEvent::Block { data } => indexer.process_data(data),
+                ReaderEvent::EndRecord { .. } => {
+                    if let Some(document) = indexer.finish_record() {
+                        writer.add_document(document)?;
+                    }
+
+                    Ok(())
+                }
+            },
+            input,
+            compression_format,
+            file_len,
+        )?
+        .with_threads(args.jobs.map_or(1, NonZeroUsize::get))
+        .run()?;
+
+        writer.commit()?;
+
+        tracing::info!(count = indexer.count, "closed file");
+    }
+
+    writer.wait_merging_threads()?;
+
+    Ok(())
+}
+
+pub fn search(args: &SearchCommand) -> anyhow::Result<()> {
+    let (index, fields) = open_index(&args.index)?;
+    let reader = index.reader()?;
+    let searcher = reader.searcher();
+
+    let query_parser = QueryParser::for_index(&index, vec![fields.title, fields.body]);
+    let query = query_parser
+        .parse_query(&args.query)
+        .context("invalid query")?;
+    let results = searcher.search(&query, &TopDocs::with_limit(args.limit))?;
+
+    let output = super::common::open_output(&args.output)?;
+    let columns = SEARCH_COLUMNS
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    let mut writer = RowWriter::new(output, args.format, &args.query, &columns)?;
+
+    for (score, address) in results {
+        let document: TantivyDocument = searcher.doc(address)?;
+        let text = |field: Field| {
+            serde_json::Value::String(
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            )
+        };
+        let position = document
+            .get_first(fields.position)
+            .and_then(|value| value.as_u64())
+            .unwrap_or_default();
+
+        writer.put(vec![
+            serde_json::json!(score),
+            text(fields.file),
+            serde_json::Value::Number(position.into()),
+            text(fields.record_id),
+            text(fields.target_uri),
+            text(fields.date),
+            text(fields.title),
+        ])?;
+    }
+
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Collects the text content of records into documents.
+struct Indexer<'a> {
+    fields: &'a Fields,
+    file: &'a str,
+    normalize_uri: bool,
+    extractor: WarcExtractor,
+    header: Option<(WarcHeader, u64)>,
+    buf: Vec<u8>,
+    count: u64,
+}
+
+impl<'a> Indexer<'a> {
+    fn new(fields: &'a Fields, file: &'a str, normalize_uri: bool) -> Self {
+        Self {
+            fields,
+            file,
+            normalize_uri,
+            extractor: WarcExtractor::new(),
+            header: None,
+            buf: Vec::new(),
+            count: 0,
+        }
+    }
+
+    fn process_header(&mut self, header: &WarcHeader, position: u64) -> anyhow::Result<()> {
+        self.extractor.reset();
+        self.header = None;
+        self.buf.clear();
+
+        self.extractor.read_header(header)?;
+
+        if self.extractor.has_content() {
+            self.header = Some((header.clone(), position));
+        }
+
+        Ok(())
+    }
+
+    fn process_data(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        if self.header.is_some() && !data.is_empty() && self.buf.len() < MAX_CONTENT_LENGTH {
+            self.extractor.extract_data(data, &mut self.buf)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish_record(&mut self) -> Option<TantivyDocument> {
+        let (header, position) = self.header.take()?;
+        let media_type =
+            super::mime::content_media_type(&header, self.extractor.metadata(), &self.buf);
+
+        self.buf.truncate(MAX_CONTENT_LENGTH);
+        let content = String::from_utf8_lossy(&self.buf);
+
+        let (title, body) = if is_html(&media_type) {
+            html_to_text(&content)
+        } else if is_text(&media_type) {
+            (String::new(), content.to_string())
+        } else {
+            tracing::debug!(media_type, "skipped content by media type");
+            return None;
+        };
+
+        let metadata = self.extractor.metadata();
+        let mut document = TantivyDocument::new();
+        document.add_text(self.fields.file, self.file);
+        document.add_u64(self.fields.position, position);
+        document.add_text(self.fields.record_id, &metadata.record_id);
+        if self.normalize_uri {
+            document.add_text(self.fields.target_uri, normalize_uri(&metadata.target_uri));
+        } else {
+            document.add_text(self.fields.target_uri, &metadata.target_uri);
+        }
+        document.add_text(
+            self.fields.date,
+            metadata.date.as_deref().unwrap_or_default(),
+        );
+        document.add_text(self.fields.media_type, &media_type);
+        document.add_text(self.fields.title, title);
+        document.add_text(self.fields.body, body);
+
+        self.count += 1;
+
+        Some(document)
+    }
+}
+
+fn is_html(media_type: &str) -> bool {
+    matches!(media_type, "text/html" | "application/xhtml+xml")
+}
+
+fn is_text(media_type: &str) -> bool {
+    media_type.starts_with("text/")
+        || matches!(
+            media_type,
+            "application/json" | "application/xml" | "application/javascript"
+        )
+}
+
+/// Returns the title and the visible text of a HTML document.
+///
+/// This is a crude conversion that removes tags, comments, scripts, and
+/// styles and decodes only common character references.
+fn html_to_text(html: &str) -> (String, String) {
+    let mut title = String::new();
+    let mut text = String::new();
+    let mut remain = html;
+    let mut is_title = false;
+
+    while !remain.is_empty() {
+        let Some(index) = remain.find('<') else {
+            push_text(&mut text, remain);
+
+            if is_title {
+                push_text(&mut title, remain);
+            }
+            break;
+        };
+
+        push_text(&mut text, &remain[0..index]);
+
+        if is_title {
+            push_text(&mut title, &remain[0..index]);
+        }
+
+        remain = &remain[index..];
+
+        if let Some(comment) = remain.strip_prefix("<!--") {
+            remain = comment
+                .find("-->")
+                .map(|index| &comment[index + 3..])
+                .unwrap_or_default();
+            continue;
+        }
+
+        let Some(end_index) = remain.find('>') else {
+            break;
+        };
+
+        let tag = &remain[1..end_index];
+        remain = &remain[end_index + 1..];
+
+        let name = tag
+            .split(|c: char| c.is_ascii_whitespace() || c == '/')
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match name.as_str() {
+            "script" | "style" if !tag.starts_with('/') && !tag.ends_with('/') => {
+                let closing = format!("</{}", name);
+                remain = find_ignore_case(remain, &closing)
+                    .map(|index| &remain[index..])
+                    .unwrap_or_default();
+            }
+            "title" => is_title = !tag.starts_with('/'),
+            _ => {}
+        }
+
+        text.push(' ');
+    }
+
+    (
+        title.split_whitespace().collect::<Vec<_>>().join(" "),
+        text.split_whitespace().collect::<Vec<_>>().join(" "),
+    )
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+fn push_text(output: &mut String, text: &str) {
+    let mut remain = text;
+
+    while let Some(index) = remain.find('&') {
+        output.push_str(&remain[0..index]);
+        remain = &remain[index..];
+
+        let decoded = remain.find(';').and_then(|end_index| {
+            let value = match &remain[1..end_index] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => ' ',
+                name => {
+                    let number = name.strip_prefix('#')?;
+                    let number = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => number.parse().ok()?,
+                    };
+                    char::from_u32(number)?
+                }
+            };
+
+            Some((value, end_index))
+        });
+
+        match decoded {
+            Some((value, end_index)) => {
+                output.push(value);
+                remain = &remain[end_index + 1..];
+            }
+            None => {
+                output.push('&');
+                remain = &remain[1..];
+            }
+        }
+    }
+
+    output.push_str(remain);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text() {
+        let (title, text) = html_to_text(
+            "<!DOCTYPE html><html><head><title>Fish &amp; Chips</title>\
+            <style>body { color: red; }</style>\
+            <script type=\"text/javascript\">var a = '<b>';</script></head>\
+            <body><!-- comment --><p>Hello&nbsp;<b>world</b>&#33;</p>\
+            <SCRIPT>x</SCRIPT>a &unknown; b</body></html>",
+        );
+
+        assert_eq!(title, "Fish & Chips");
+        assert_eq!(text, "Fish & Chips Hello world ! a &unknown; b");
+    }
+}
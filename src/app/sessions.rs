@@ -0,0 +1,343 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, FixedOffset, SecondsFormat};
+use serde::Serialize;
+
+use crate::{
+    dataseq::SeqWriter,
+    header::{fields::FieldsExt, WarcHeader},
+    warc::EndState,
+};
+
+use super::{
+    arg::SessionsCommand,
+    common::{ReaderEvent, ReaderPipeline},
+};
+
+const MAX_WARCINFO_LENGTH: usize = 65536;
+
+pub fn sessions(args: &SessionsCommand) -> anyhow::Result<()> {
+    let output = super::common::open_output(&args.output)?;
+    let mut writer = SeqWriter::new(output, args.format.into());
+    let mut sessions = Sessions::new();
+
+    for input_path in &args.input {
+        let span = tracing::info_span!("sessions", path = ?input_path);
+        let _span_guard = span.enter();
+
+        let input = super::common::open_input(input_path)?;
+
+        tracing::info!("opened file");
+
+        let compression_format = args.compression.try_into_native(input_path)?;
+        let file_len = std::fs::metadata(input_path).map(|m| m.len()).ok();
+        let file = input_path.to_string_lossy().to_string();
+        sessions.begin_file(&file);
+
+        let mut reader = ReaderPipeline::new(
+            |event| {
+                match event {
+                    ReaderEvent::Header {
+                        header,
+                        record_boundary_position,
+                        ..
+                    } => sessions.begin_record(&header, record_boundary_position),
+                    ReaderEvent::Block { data } => sessions.block_data(data),
+                    ReaderEvent::EndRecord {
+                        record_boundary_position,
+                    } => sessions.end_record(record_boundary_position),
+                }
+
+                Ok(())
+            },
+            input,
+            compression_format,
+            file_len,
+        )?;
+        let result = reader.run();
+        let end_state = reader.end_state;
+        drop(reader);
+
+        if let Err(error) = result {
+            if end_state != EndState::Truncated {
+                return Err(error);
+            }
+
+            tracing::warn!(?error, "file appears truncated");
+        }
+
+        tracing::info!("closed file");
+    }
+
+    for session in sessions.sessions {
+        writer.put(session.row())?;
+    }
+
+    Ok(())
+}
+
+/// Statistics of the records belonging to a warcinfo record.
+#[derive(Debug, Default)]
+struct Session {
+    warcinfo_id: String,
+    software: Option<String>,
+    is_part_of: Option<String>,
+    files: Vec<String>,
+    record_count: u64,
+    record_types: BTreeMap<String, u64>,
+    first_date: Option<DateTime<FixedOffset>>,
+    last_date: Option<DateTime<FixedOffset>>,
+    content_length: u64,
+    compressed_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionRow<'a> {
+    warcinfo_id: &'a str,
+    software: Option<&'a str>,
+    is_part_of: Option<&'a str>,
+    files: &'a [String],
+    record_count: u64,
+    record_types: &'a BTreeMap<String, u64>,
+    first_date: Option<String>,
+    last_date: Option<String>,
+    content_length: u64,
+    compressed_size: u64,
+}
+
+impl Session {
+    fn row(&self) -> SessionRow<'_> {
+        let format_date =
+            |date: DateTime<FixedOffset>| date.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+
+        SessionRow {
+            warcinfo_id: &self.warcinfo_id,
+            software: self.software.as_deref(),
+            is_part_of: self.is_part_of.as_deref(),
+            files: &self.files,
+            record_count: self.record_count,
+            record_types: &self.record_types,
+            first_date: self.first_date.map(format_date),
+            last_date: self.last_date.map(format_date),
+            content_length: self.content_length,
+            compressed_size: self.compressed_size,
+        }
+    }
+}
+
+/// Groups records into sessions in order of appearance.
+///
+/// A record belongs to the session of its WARC-Warcinfo-ID. Records without
+/// the field belong to the session of the last warcinfo record in the same
+/// file, or to a session with an empty ID if there is none.
+struct Sessions {
+    sessions: Vec<Session>,
+    index: HashMap<String, usize>,
+    file: String,
+    file_warcinfo_id: Option<String>,
+    current: Option<(usize, u64)>,
+    is_warcinfo: bool,
+    warcinfo_buf: Vec<u8>,
+}
+
+impl Sessions {
+    fn new() -> Self {
+        Self {
+            sessions: Vec::new(),
+            index: HashMap::new(),
+            file: String::new(),
+            file_warcinfo_id: None,
+            current: None,
+            is_warcinfo: false,
+            warcinfo_buf: Vec::new(),
+        }
+    }
+
+    fn begin_file(&mut self, file: &str) {
+        self.file = file.to_string();
+        self.file_warcinfo_id = None;
+        self.current = None;
+    }
+
+    fn session_mut(&mut self, warcinfo_id: &str) -> usize {
+        if let Some(index) = self.index.get(warcinfo_id) {
+            return *index;
+        }
+
+        let index = self.sessions.len();
+        self.sessions.push(Session {
+            warcinfo_id: warcinfo_id.to_string(),
+            ..Default::default()
+        });
+        self.index.insert(warcinfo_id.to_string(), index);
+
+        index
+    }
+
+    fn begin_record(&mut self, header: &WarcHeader, position: u64) {
+        let record_type = header.fields.get_or_default("WARC-Type");
+        self.is_warcinfo = record_type == "warcinfo";
+        self.warcinfo_buf.clear();
+
+        let warcinfo_id = if self.is_warcinfo {
+            let id = header.fields.get_or_default("WARC-Record-ID").to_string();
+            self.file_warcinfo_id = Some(id.clone());
+            id
+        } else if let Some(id) = header.fields.get("WARC-Warcinfo-ID") {
+            id.clone()
+        } else {
+            self.file_warcinfo_id.clone().unwrap_or_default()
+        };
+
+        let index = self.session_mut(&warcinfo_id);
+        let file = &self.file;
+        let session = &mut self.sessions[index];
+
+        if !session.files.contains(file) {
+            session.files.push(file.clone());
+        }
+
+        session.record_count += 1;
+        *session
+            .record_types
+            .entry(record_type.to_string())
+            .or_default() += 1;
+
+        if let Some(Ok(date)) = header.fields.get_date("WARC-Date") {
+            session.first_date = Some(session.first_date.map_or(date, |value| value.min(date)));
+            session.last_date = Some(session.last_date.map_or(date, |value| value.max(date)));
+        }
+
+        session.content_length += header.content_length().unwrap_or_default();
+
+        self.current = Some((index, position));
+    }
+
+    fn block_data(&mut self, data: &[u8]) {
+        if !self.is_warcinfo {
+            return;
+        }
+
+        if data.is_empty() {
+            if let Some((index, _)) = self.current {
+                let fields = parse_warcinfo(&self.warcinfo_buf);
+                let session = &mut self.sessions[index];
+                session.software = fields.get("software").cloned();
+                session.is_part_of = fields.get("isPartOf").cloned();
+            }
+        } else {
+            let remain_len = MAX_WARCINFO_LENGTH.saturating_sub(self.warcinfo_buf.len());
+            self.warcinfo_buf
+                .extend_from_slice(&data[0..data.len().min(remain_len)]);
+        }
+    }
+
+    fn end_record(&mut self, position: u64) {
+        if let Some((index, begin_position)) = self.current.take() {
+            self.sessions[index].compressed_size += position.saturating_sub(begin_position);
+        }
+    }
+}
+
+/// Returns the fields of a warcinfo block.
+fn parse_warcinfo(block: &[u8]) -> HashMap<String, String> {
+    let Ok(pairs) = crate::parse::parse_name_value_fields(block) else {
+        return HashMap::new();
+    };
+
+    pairs
+        .iter()
+        .map(|pair| {
+            (
+                String::from_utf8_lossy(pair.name).to_string(),
+                String::from_utf8_lossy(pair.value).trim().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(id: &str, record_type: &str, warcinfo_id: Option<&str>) -> WarcHeader {
+        let mut header = WarcHeader::new(10, record_type);
+        header
+            .fields
+            .insert("WARC-Record-ID".to_string(), id.to_string());
+
+        if let Some(warcinfo_id) = warcinfo_id {
+            header
+                .fields
+                .insert("WARC-Warcinfo-ID".to_string(), warcinfo_id.to_string());
+        }
+
+        header
+    }
+
+    #[test]
+    fn test_sessions() {
+        let mut sessions = Sessions::new();
+
+        sessions.begin_file("a.warc");
+        sessions.begin_record(&header("<urn:example:1>", "warcinfo", None), 0);
+        sessions.block_data(b"software: Crawler/1.0\r\nisPartOf: job1\r\n");
+        sessions.block_data(b"");
+        sessions.end_record(100);
+        sessions.begin_record(&header("<urn:example:2>", "response", None), 100);
+        sessions.end_record(150);
+        sessions.begin_record(
+            &header("<urn:example:3>", "response", Some("<urn:example:9>")),
+            150,
+        );
+        sessions.end_record(200);
+
+        sessions.begin_file("b.warc");
+        sessions.begin_record(&header("<urn:example:4>", "response", None), 0);
+        sessions.end_record(50);
+        sessions.begin_record(
+            &header("<urn:example:5>", "request", Some("<urn:example:1>")),
+            50,
+        );
+        sessions.end_record(80);
+
+        let rows = sessions
+            .sessions
+            .iter()
+            .map(|session| {
+                (
+                    session.warcinfo_id.as_str(),
+                    session.software.as_deref(),
+                    session.files.clone(),
+                    session.record_count,
+                    session.content_length,
+                    session.compressed_size,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "<urn:example:1>",
+                    Some("Crawler/1.0"),
+                    vec!["a.warc".to_string(), "b.warc".to_string()],
+                    3,
+                    30,
+                    180
+                ),
+                (
+                    "<urn:example:9>",
+                    None,
+                    vec!["a.warc".to_string()],
+                    1,
+                    10,
+                    50
+                ),
+                ("", None, vec!["b.warc".to_string()], 1, 10, 50),
+            ]
+        );
+        assert_eq!(sessions.sessions[0].is_part_of.as_deref(), Some("job1"));
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use data_encoding::HEXLOWER;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC};
+use tempfile::NamedTempFile;
+
+use crate::{
+    app::common::{ReaderEvent, ReaderPipeline},
+    compress::{CompressorConfig, Format, Level},
+    digest::{AlgorithmName, Digest, Hasher},
+    header::{record_id::RecordId, WarcHeader},
+    warc::{EncStateBlock, Encoder, EncoderConfig},
+};
+
+use super::{
+    arg::{SplitCommand, SplitName},
+    filter::{EmptyRecordFilter, FieldFilter},
+};
+
+const FILENAME_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_');
+
+pub fn split(args: &SplitCommand) -> anyhow::Result<()> {
+    let output_dir = &args.output;
+
+    if !output_dir.is_dir() {
+        anyhow::bail!("not a directory: {:?}", output_dir)
+    }
+
+    let mut filter = FieldFilter::new();
+
+    for rule in &args.include {
+        filter.add_include(rule);
+    }
+    for rule in &args.include_pattern {
+        filter.add_include_pattern(rule)?;
+    }
+    for rule in &args.exclude {
+        filter.add_exclude(rule);
+    }
+    for rule in &args.exclude_pattern {
+        filter.add_exclude_pattern(rule)?;
+    }
+
+    let mut splitter = Splitter::new(
+        output_dir,
+        filter,
+        args.name_by,
+        args.compression_level.into(),
+    )
+    .with_drop_empty(args.drop_empty);
+
+    for input_path in &args.input {
+        let span = tracing::info_span!("split", path = ?input_path);
+        let _span_guard = span.enter();
+
+        let input = super::common::open_input(input_path)?;
+
+        tracing::info!("opened file");
+
+        let compression_format = args.compression.try_into_native(input_path)?;
+        let file_len = std::fs::metadata(input_path).map(|m| m.len()).ok();
+
+        ReaderPipeline::new(
+            |event| match event {
+                ReaderEvent::Header { header, .. } => splitter.process_header(&header),
+                ReaderEvent::Block { data } => splitter.process_data(data),
+                ReaderEvent::EndRecord { .. } => splitter.finish_record(),
+            },
+            input,
+            compression_format,
+            file_len,
+        )?
+        .run()?;
+
+        tracing::info!("closed file");
+    }
+
+    Ok(())
+}
+
+struct Splitter {
+    output_dir: PathBuf,
+    filter: FieldFilter,
+    name_by: SplitName,
+    compression_level: Level,
+    name: Option<String>,
+    hasher: Option<Hasher>,
+    writer: Option<Encoder<EncStateBlock, NamedTempFile>>,
+    empty_filter: Option<EmptyRecordFilter>,
+}
+
+impl Splitter {
+    fn new(
+        output_dir: &Path,
+        filter: FieldFilter,
+        name_by: SplitName,
+        compression_level: Level,
+    ) -> Self {
+        Self {
+            output_dir: output_dir.to_path_buf(),
+            filter,
+            name_by,
+            compression_level,
+            name: None,
+            hasher: None,
+            writer: None,
+            empty_filter: None,
+        }
+    }
+
+    /// Skip resource and metadata records without content.
+    fn with_drop_empty(mut self, value: bool) -> Self {
+        self.empty_filter = value.then(EmptyRecordFilter::new);
+        self
+    }
+
+    fn process_header(&mut self, header: &WarcHeader) -> anyhow::Result<()> {
+        if !self.filter.is_allow(header) {
+            return Ok(());
+        }
+
+        self.name = None;
+        self.hasher = None;
+
+        if let Some(empty_filter) = &mut self.empty_filter {
+            empty_filter.begin_record(header);
+        }
+
+        match self.name_by {
+            SplitName::RecordId => {
+                let value = header
+                    .fields
+                    .get("WARC-Record-ID")
+                    .ok_or_else(|| anyhow::anyhow!("missing WARC-Record-ID"))?;
+                self.name = Some(record_id_filename(value));
+            }
+            SplitName::Digest => {
+                let digest = header
+                    .fields
+                    .get("WARC-Block-Digest")
+                    .and_then(|value| Digest::from_str(value).ok());
+
+                match digest {
+                    Some(digest) => self.name = Some(digest_filename(&digest)),
+                    // Digest is computed while the block is written.
+                    None => self.hasher = Some(Hasher::new(AlgorithmName::Sha256)),
+                }
+            }
+        }
+
+        let file = super::temp::named_tempfile_for(&self.output_dir, ".warcat-tmp-", "")?;
+        let config = EncoderConfig {
+            compressor: CompressorConfig {
+                format: Format::Gzip,
+                level: self.compression_level,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let writer = Encoder::new(file, config).write_header(header)?;
+
+        self.writer = Some(writer);
+
+        Ok(())
+    }
+
+    fn process_data(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        if let Some(writer) = &mut self.writer {
+            writer.write_all(data)?;
+
+            if let Some(hasher) = &mut self.hasher {
+                hasher.update(data);
+            }
+
+            if let Some(empty_filter) = &mut self.empty_filter {
+                empty_filter.block_data(data);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish_record(&mut self) -> anyhow::Result<()> {
+        let Some(writer) = self.writer.take() else {
+            return Ok(());
+        };
+
+        if self
+            .empty_filter
+            .as_ref()
+            .is_some_and(|filter| filter.is_empty_record())
+        {
+            tracing::debug!("skipped empty record");
+            return Ok(());
+        }
+
+        let file = writer.finish_block()?.finish()?;
+
+        let name = match (self.name.take(), self.hasher.take()) {
+            (Some(name), _) => name,
+            (None, Some(mut hasher)) => {
+                digest_filename(&Digest::new(hasher.algorithm(), hasher.finish()))
+            }
+            (None, None) => unreachable!(),
+        };
+
+        let target_path = self.output_dir.join(format!("{}.warc.gz", name));
+
+        if target_path.exists() {
+            tracing::warn!(path = ?target_path, "file already exists, skipped record");
+        } else {
+            super::temp::persist(file, &target_path)?;
+
+            tracing::info!(path = ?target_path, "wrote record file");
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns a filename for a record ID.
+///
+/// UUIDs are used as is. Other URIs are percent-encoded.
+fn record_id_filename(value: &str) -> String {
+    match RecordId::from_str(value) {
+        Ok(id) => match id.uuid() {
+            Some(uuid) => uuid.hyphenated().to_string(),
+            None => {
+                percent_encoding::utf8_percent_encode(id.as_uri(), FILENAME_ENCODE_SET).to_string()
+            }
+        },
+        Err(_) => {
+            let value = value.trim();
+            let value = value.strip_prefix('<').unwrap_or(value);
+            let value = value.strip_suffix('>').unwrap_or(value);
+
+            percent_encoding::utf8_percent_encode(value, FILENAME_ENCODE_SET).to_string()
+        }
+    }
+}
+
+/// Returns a filename such as "sha1-0123abcd" for a digest.
+fn digest_filename(digest: &Digest) -> String {
+    format!(
+        "{}-{}",
+        digest.algorithm().as_str(),
+        HEXLOWER.encode(digest.value())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filenames() {
+        assert_eq!(
+            record_id_filename("<urn:uuid:F81D4FAE-7DEC-11D0-A765-00A0C91E6BF6>"),
+            "f81d4fae-7dec-11d0-a765-00a0c91e6bf6"
+        );
+        assert_eq!(
+            record_id_filename("<http://example.com/a?b>"),
+            "http%3A%2F%2Fexample.com%2Fa%3Fb"
+        );
+        assert_eq!(record_id_filename("<urn:uuid:1>"), "urn%3Auuid%3A1");
+        assert_eq!(record_id_filename("../a b"), "..%2Fa%20b");
+        assert_eq!(
+            digest_filename(&Digest::from_str("sha1:AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA").unwrap()),
+            "sha1-0000000000000000000000000000000000000000"
+        );
+    }
+}
@@ -0,0 +1,397 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+};
+
+use chrono::{DateTime, FixedOffset, SecondsFormat};
+use indicatif::HumanBytes;
+
+use crate::{
+    compress::Format,
+    header::{fields::FieldsExt, WarcHeader},
+    verify::Verifier,
+    warc::EndState,
+};
+
+use super::{
+    arg::SummaryCommand,
+    common::{ReaderEvent, ReaderPipeline},
+};
+
+const MAX_WARCINFO_LENGTH: usize = 65536;
+/// Record types that are captures of a target URI.
+const CAPTURE_RECORD_TYPES: [&str; 3] = ["response", "resource", "revisit"];
+/// Number of hosts shown in a list of hosts.
+const MAX_LISTED_HOSTS: usize = 5;
+
+pub fn summary(args: &SummaryCommand) -> anyhow::Result<()> {
+    let mut output = super::common::open_output(&args.output)?;
+
+    for (index, input_path) in args.input.iter().enumerate() {
+        let span = tracing::info_span!("summary", path = ?input_path);
+        let _span_guard = span.enter();
+
+        let input = super::common::open_input(input_path)?;
+
+        tracing::info!("opened file");
+
+        let compression_format = args.compression.try_into_native(input_path)?;
+        let file_len = std::fs::metadata(input_path).map(|m| m.len()).ok();
+        let mut summary = FileSummary::new(args.sample);
+
+        let mut reader = ReaderPipeline::new(
+            |event| {
+                match event {
+                    ReaderEvent::Header { header, .. } => summary.begin_record(header)?,
+                    ReaderEvent::Block { data } => summary.block_data(data),
+                    ReaderEvent::EndRecord { .. } => {}
+                }
+
+                Ok(())
+            },
+            input,
+            compression_format,
+            file_len,
+        )?;
+        let result = reader.run();
+        let end_state = reader.end_state;
+        let has_record_at_time_compression_fault = reader.has_record_at_time_compression_fault;
+        drop(reader);
+
+        if let Err(error) = result {
+            if end_state != EndState::Truncated {
+                return Err(error);
+            }
+
+            tracing::warn!(?error, "file appears truncated");
+        }
+
+        if index > 0 {
+            writeln!(output)?;
+        }
+
+        writeln!(output, "File: {}", input_path.to_string_lossy())?;
+        summary.write(
+            &mut output,
+            compression_format,
+            has_record_at_time_compression_fault,
+            file_len,
+        )?;
+
+        if end_state == EndState::Truncated {
+            writeln!(output, "Truncated: file ends within a record")?;
+        }
+
+        tracing::info!("closed file");
+    }
+
+    output.flush()?;
+
+    Ok(())
+}
+
+/// Statistics of a file and problems found in its first records.
+struct FileSummary {
+    sample: u64,
+    verifier: Verifier,
+    problem_count: u64,
+    record_count: u64,
+    record_types: BTreeMap<String, u64>,
+    first_date: Option<DateTime<FixedOffset>>,
+    last_date: Option<DateTime<FixedOffset>>,
+    decoded_size: u64,
+    software: Option<String>,
+    is_warcinfo: bool,
+    warcinfo_buf: Vec<u8>,
+    uri_stats: UriStats,
+}
+
+impl FileSummary {
+    fn new(sample: u64) -> Self {
+        Self {
+            sample,
+            verifier: Verifier::new(),
+            problem_count: 0,
+            record_count: 0,
+            record_types: BTreeMap::new(),
+            first_date: None,
+            last_date: None,
+            decoded_size: 0,
+            software: None,
+            is_warcinfo: false,
+            warcinfo_buf: Vec::new(),
+            uri_stats: UriStats::default(),
+        }
+    }
+
+    fn is_sampled(&self) -> bool {
+        self.record_count <= self.sample
+    }
+
+    fn begin_record(&mut self, header: WarcHeader) -> anyhow::Result<()> {
+        self.record_count += 1;
+
+        let record_type = header.fields.get_or_default("WARC-Type");
+        *self
+            .record_types
+            .entry(record_type.to_string())
+            .or_default() += 1;
+
+        if let Some(Ok(date)) = header.fields.get_date("WARC-Date") {
+            self.first_date = Some(self.first_date.map_or(date, |value| value.min(date)));
+            self.last_date = Some(self.last_date.map_or(date, |value| value.max(date)));
+        }
+
+        self.uri_stats.add_record(&header);
+
+        self.is_warcinfo = record_type == "warcinfo" && self.software.is_none();
+        self.warcinfo_buf.clear();
+
+        if self.is_sampled() {
+            self.verifier.begin_record(&header)?;
+        }
+
+        Ok(())
+    }
+
+    fn block_data(&mut self, data: &[u8]) {
+        self.decoded_size += data.len() as u64;
+
+        if self.is_warcinfo {
+            if data.is_empty() {
+                self.software = parse_software(&self.warcinfo_buf);
+            } else {
+                let remain_len = MAX_WARCINFO_LENGTH.saturating_sub(self.warcinfo_buf.len());
+                self.warcinfo_buf
+                    .extend_from_slice(&data[0..data.len().min(remain_len)]);
+            }
+        }
+
+        if self.is_sampled() {
+            if data.is_empty() {
+                self.verifier.end_record();
+                self.problem_count += self.verifier.problems().len() as u64;
+                self.verifier.problems_mut().clear();
+            } else {
+                self.verifier.block_data(data);
+            }
+        }
+    }
+
+    fn write<W: Write>(
+        &self,
+        output: &mut W,
+        compression_format: Format,
+        has_record_at_time_compression_fault: bool,
+        file_len: Option<u64>,
+    ) -> std::io::Result<()> {
+        if let Some(software) = &self.software {
+            writeln!(output, "Created by: {}", software)?;
+        }
+
+        match compression_format {
+            Format::Identity => writeln!(output, "Compression: none")?,
+            format if has_record_at_time_compression_fault => writeln!(
+                output,
+                "Compression: {} (not record-at-time compression)",
+                format
+            )?,
+            format => writeln!(output, "Compression: {}", format)?,
+        }
+
+        if let (Some(first_date), Some(last_date)) = (self.first_date, self.last_date) {
+            writeln!(
+                output,
+                "Dates: {} to {}",
+                first_date.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                last_date.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+            )?;
+        }
+
+        let types = self
+            .record_types
+            .iter()
+            .map(|(name, count)| format!("{} {}", count, name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(output, "Records: {} ({})", self.record_count, types)?;
+
+        if !self.uri_stats.schemes.is_empty() {
+            let schemes = self
+                .uri_stats
+                .schemes
+                .iter()
+                .map(|(name, count)| format!("{} {}", count, name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(output, "URI schemes: {}", schemes)?;
+        }
+
+        // Files without DNS records are not from a crawler that records them.
+        if !self.uri_stats.dns_hosts.is_empty() {
+            writeln!(
+                output,
+                "Hosts without DNS records: {}",
+                format_hosts(&self.uri_stats.hosts_without_dns())
+            )?;
+            writeln!(
+                output,
+                "DNS records without captures: {}",
+                format_hosts(&self.uri_stats.dns_without_captures())
+            )?;
+        }
+
+        match file_len {
+            Some(file_len) => writeln!(
+                output,
+                "Size: {} file, {} record blocks",
+                HumanBytes(file_len),
+                HumanBytes(self.decoded_size)
+            )?,
+            None => writeln!(
+                output,
+                "Size: {} record blocks",
+                HumanBytes(self.decoded_size)
+            )?,
+        }
+
+        writeln!(
+            output,
+            "Problems: {} in the first {} records",
+            self.problem_count,
+            self.record_count.min(self.sample)
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Counts of the schemes of captured URIs and the hosts of the captures and
+/// of `dns:` records.
+///
+/// Crawlers such as Heritrix write a `dns:` record for the lookup of each
+/// host they capture.
+#[derive(Debug, Default)]
+struct UriStats {
+    schemes: BTreeMap<String, u64>,
+    dns_hosts: BTreeSet<String>,
+    captured_hosts: BTreeSet<String>,
+}
+
+impl UriStats {
+    fn add_record(&mut self, header: &WarcHeader) {
+        if !CAPTURE_RECORD_TYPES.contains(&header.fields.get_or_default("WARC-Type")) {
+            return;
+        }
+
+        let Some(uri) = header.fields.get_url_str("WARC-Target-URI") else {
+            return;
+        };
+        let Some((scheme, remain)) = uri.split_once(':') else {
+            return;
+        };
+        let scheme = scheme.to_ascii_lowercase();
+
+        if scheme == "dns" {
+            self.dns_hosts.insert(remain.trim().to_ascii_lowercase());
+        } else if let Ok(url) = url::Url::parse(uri) {
+            // Addresses are not looked up.
+            if let Some(url::Host::Domain(host)) = url.host() {
+                self.captured_hosts.insert(host.to_string());
+            }
+        }
+
+        *self.schemes.entry(scheme).or_default() += 1;
+    }
+
+    /// Returns the captured hosts without a `dns:` record.
+    fn hosts_without_dns(&self) -> Vec<&str> {
+        self.captured_hosts
+            .difference(&self.dns_hosts)
+            .map(|host| host.as_str())
+            .collect()
+    }
+
+    /// Returns the hosts of `dns:` records that were not captured.
+    fn dns_without_captures(&self) -> Vec<&str> {
+        self.dns_hosts
+            .difference(&self.captured_hosts)
+            .map(|host| host.as_str())
+            .collect()
+    }
+}
+
+/// Formats the number of hosts and the first hosts.
+fn format_hosts(hosts: &[&str]) -> String {
+    if hosts.is_empty() {
+        return "0".to_string();
+    }
+
+    let mut listed = hosts[0..hosts.len().min(MAX_LISTED_HOSTS)].join(", ");
+
+    if hosts.len() > MAX_LISTED_HOSTS {
+        listed.push_str(", ...");
+    }
+
+    format!("{} ({})", hosts.len(), listed)
+}
+
+/// Returns the `software` field of a warcinfo block.
+fn parse_software(block: &[u8]) -> Option<String> {
+    let pairs = crate::parse::parse_name_value_fields(block).ok()?;
+
+    pairs
+        .iter()
+        .find(|pair| pair.name.eq_ignore_ascii_case(b"software"))
+        .map(|pair| String::from_utf8_lossy(pair.value).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_software() {
+        assert_eq!(
+            parse_software(b"software: Example/1.0\r\nformat: WARC File Format 1.1\r\n").as_deref(),
+            Some("Example/1.0")
+        );
+        assert_eq!(parse_software(b"format: WARC File Format 1.1\r\n"), None);
+    }
+
+    #[test]
+    fn test_uri_stats() {
+        let header = |record_type: &str, uri: &str| {
+            let mut header = WarcHeader::new(0, record_type);
+            header
+                .fields
+                .insert("WARC-Target-URI".to_string(), uri.to_string());
+            header
+        };
+        let mut stats = UriStats::default();
+
+        stats.add_record(&header("response", "dns:example.com"));
+        stats.add_record(&header("response", "dns:unused.example"));
+        stats.add_record(&header("request", "http://example.com/"));
+        stats.add_record(&header("response", "http://Example.com/"));
+        stats.add_record(&header("revisit", "https://example.com/a"));
+        stats.add_record(&header("response", "<http://other.example/>"));
+        stats.add_record(&header("response", "http://192.0.2.1/"));
+
+        assert_eq!(
+            stats.schemes,
+            BTreeMap::from([
+                ("dns".to_string(), 2),
+                ("http".to_string(), 3),
+                ("https".to_string(), 1)
+            ])
+        );
+        assert_eq!(stats.hosts_without_dns(), vec!["other.example"]);
+        assert_eq!(stats.dns_without_captures(), vec!["unused.example"]);
+        assert_eq!(format_hosts(&[]), "0");
+        assert_eq!(
+            format_hosts(&["a", "b", "c", "d", "e", "f"]),
+            "6 (a, b, c, d, e, ...)"
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use tempfile::NamedTempFile;
+
+static GLOBAL_TEMP_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_global_temp_dir(path: PathBuf) {
+    GLOBAL_TEMP_DIR.set(path).unwrap();
+}
+
+/// Returns the directory given by the user for temporary files.
+pub fn global_temp_dir() -> Option<&'static Path> {
+    GLOBAL_TEMP_DIR.get().map(PathBuf::as_path)
+}
+
+/// Creates an unnamed temporary file.
+///
+/// Without a global directory, the directory in the TMPDIR environment
+/// variable or the system default is used.
+pub fn tempfile() -> std::io::Result<File> {
+    match global_temp_dir() {
+        Some(dir) => tempfile::tempfile_in(dir),
+        None => tempfile::tempfile(),
+    }
+}
+
+/// Creates a temporary file that will be moved into the given directory
+/// with [`persist()`].
+///
+/// The file is created in the global directory if there is one, otherwise
+/// it is created in the given directory so that it can be renamed.
+pub fn named_tempfile_for(
+    dir: &Path,
+    prefix: &str,
+    suffix: &str,
+) -> std::io::Result<NamedTempFile> {
+    tempfile::Builder::new()
+        .prefix(prefix)
+        .suffix(suffix)
+        .tempfile_in(global_temp_dir().unwrap_or(dir))
+}
+
+/// Moves the temporary file to the path and returns the file opened for
+/// writing.
+///
+/// If the file cannot be renamed, such as when the temporary directory is
+/// on another filesystem, the file is copied instead.
+pub fn persist(file: NamedTempFile, path: &Path) -> std::io::Result<File> {
+    match file.persist(path) {
+        Ok(file) => Ok(file),
+        Err(error) => {
+            tracing::debug!(error = ?error.error, "rename failed, copying temporary file");
+
+            std::fs::copy(error.file.path(), path)?;
+
+            File::options().write(true).open(path)
+        }
+    }
+}
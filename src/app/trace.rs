@@ -0,0 +1,283 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::{
+    dataseq::SeqWriter,
+    header::fields::FieldsExt,
+    verify::{Check, RecordReference, Verifier},
+};
+
+use super::{
+    arg::TraceCommand,
+    common::{ReaderEvent, ReaderPipeline},
+};
+
+/// Checks that record the references between records.
+const REFERENCE_CHECKS: [Check; 4] = [
+    Check::ConcurrentTo,
+    Check::RefersTo,
+    Check::WarcinfoId,
+    Check::Segment,
+];
+
+pub fn trace(args: &TraceCommand) -> anyhow::Result<()> {
+    let mut verifier = match &args.database {
+        Some(path) => Verifier::open(path)?,
+        None => Verifier::new(),
+    };
+    verifier.checks_mut().clear();
+    verifier.checks_mut().extend(REFERENCE_CHECKS);
+
+    for input_path in &args.input {
+        let span = tracing::info_span!("trace", path = ?input_path);
+        let _span_guard = span.enter();
+
+        read_file(args, input_path, |header, _position| {
+            verifier.begin_record(header)?;
+            verifier.end_record();
+            verifier.problems_mut().clear();
+
+            Ok(())
+        })?;
+    }
+
+    let graph = Graph::build(&verifier, &args.id)?;
+
+    if graph.references.is_empty() && !verifier.has_record(&args.id)? {
+        anyhow::bail!("record {:?} not found", args.id);
+    }
+
+    let mut details = HashMap::new();
+
+    for input_path in &args.input {
+        let file = input_path.to_string_lossy().to_string();
+
+        read_file(args, input_path, |header, position| {
+            let id = header.fields.get_or_default("WARC-Record-ID");
+
+            if graph.distances.contains_key(id) && !details.contains_key(id) {
+                details.insert(
+                    id.to_string(),
+                    (
+                        header.fields.get_or_default("WARC-Type").to_string(),
+                        file.clone(),
+                        position,
+                    ),
+                );
+            }
+
+            Ok(())
+        })?;
+    }
+
+    let output = super::common::open_output(&args.output)?;
+    let mut writer = SeqWriter::new(output, args.format.into());
+
+    for id in &graph.records {
+        let detail = details.get(id);
+
+        writer.put(TraceMessage::Record(RecordRow {
+            id,
+            distance: graph.distances[id],
+            record_type: detail.map(|detail| detail.0.as_str()),
+            file: detail.map(|detail| detail.1.as_str()),
+            position: detail.map(|detail| detail.2),
+        }))?;
+    }
+
+    for reference in &graph.references {
+        writer.put(TraceMessage::Reference(reference))?;
+    }
+
+    Ok(())
+}
+
+fn read_file<F>(args: &TraceCommand, input_path: &std::path::Path, mut f: F) -> anyhow::Result<()>
+where
+    F: FnMut(&crate::header::WarcHeader, u64) -> anyhow::Result<()>,
+{
+    let input = super::common::open_input(input_path)?;
+    let compression_format = args.compression.try_into_native(input_path)?;
+    let file_len = std::fs::metadata(input_path).map(|m| m.len()).ok();
+
+    ReaderPipeline::new(
+        |event| match event {
+            ReaderEvent::Header {
+                header,
+                record_boundary_position,
+                ..
+            } => f(&header, record_boundary_position),
+            ReaderEvent::Block { .. } | ReaderEvent::EndRecord { .. } => Ok(()),
+        },
+        input,
+        compression_format,
+        file_len,
+    )?
+    .with_no_block(true)
+    .run()
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum TraceMessage<'a> {
+    Record(RecordRow<'a>),
+    Reference(&'a RecordReference),
+}
+
+#[derive(Serialize)]
+struct RecordRow<'a> {
+    id: &'a str,
+    distance: u64,
+    #[serde(rename = "type")]
+    record_type: Option<&'a str>,
+    file: Option<&'a str>,
+    position: Option<u64>,
+}
+
+/// Records connected to a starting record.
+struct Graph {
+    /// Record IDs in order of distance.
+    records: Vec<String>,
+    /// Number of references between a record and the starting record.
+    distances: HashMap<String, u64>,
+    references: Vec<RecordReference>,
+}
+
+impl Graph {
+    /// Follows the references from and to the records, starting from the
+    /// record with the ID.
+    ///
+    /// References to a warcinfo record are followed only from the referring
+    /// record, otherwise every record of a crawl would be connected.
+    fn build(verifier: &Verifier, id: &str) -> anyhow::Result<Self> {
+        let mut graph = Self {
+            records: vec![id.to_string()],
+            distances: HashMap::from([(id.to_string(), 0)]),
+            references: Vec::new(),
+        };
+        let mut frontier = HashSet::from([id.to_string()]);
+        let mut distance = 0;
+
+        while !frontier.is_empty() {
+            distance += 1;
+            let mut next_frontier = HashSet::new();
+
+            for reference in verifier.find_references(&frontier)? {
+                let next_id = if frontier.contains(&reference.source) {
+                    &reference.target
+                } else if reference.kind != "Warcinfo-ID" {
+                    &reference.source
+                } else {
+                    continue;
+                };
+
+                if !graph.distances.contains_key(next_id) {
+                    graph.distances.insert(next_id.clone(), distance);
+                    graph.records.push(next_id.clone());
+                    next_frontier.insert(next_id.clone());
+                }
+
+                if !graph.references.contains(&reference) {
+                    graph.references.push(reference);
+                }
+            }
+
+            frontier = next_frontier;
+        }
+
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::header::WarcHeader;
+
+    use super::*;
+
+    fn header(id: &str, record_type: &str, fields: &[(&str, &str)]) -> WarcHeader {
+        let mut header = WarcHeader::new(0, record_type);
+        header
+            .fields
+            .insert("WARC-Record-ID".to_string(), id.to_string());
+
+        for (name, value) in fields {
+            header.fields.insert(name.to_string(), value.to_string());
+        }
+
+        header
+    }
+
+    #[test]
+    fn test_graph() {
+        let headers = [
+            header("<urn:example:info>", "warcinfo", &[]),
+            header(
+                "<urn:example:request>",
+                "request",
+                &[
+                    ("WARC-Warcinfo-ID", "<urn:example:info>"),
+                    ("WARC-Concurrent-To", "<urn:example:response>"),
+                ],
+            ),
+            header(
+                "<urn:example:response>",
+                "response",
+                &[
+                    ("WARC-Warcinfo-ID", "<urn:example:info>"),
+                    ("WARC-Segment-Number", "1"),
+                ],
+            ),
+            header(
+                "<urn:example:continuation>",
+                "continuation",
+                &[
+                    ("WARC-Segment-Origin-ID", "<urn:example:response>"),
+                    ("WARC-Segment-Number", "2"),
+                    ("WARC-Segment-Total-Length", "0"),
+                ],
+            ),
+            header(
+                "<urn:example:revisit>",
+                "revisit",
+                &[("WARC-Refers-To", "<urn:example:response>")],
+            ),
+            header(
+                "<urn:example:other>",
+                "response",
+                &[("WARC-Warcinfo-ID", "<urn:example:info>")],
+            ),
+        ];
+
+        let mut verifier = Verifier::new();
+        verifier.checks_mut().clear();
+        verifier.checks_mut().extend(REFERENCE_CHECKS);
+
+        for header in &headers {
+            verifier.begin_record(header).unwrap();
+            verifier.end_record();
+        }
+
+        let graph = Graph::build(&verifier, "<urn:example:revisit>").unwrap();
+        let mut distances = graph
+            .distances
+            .iter()
+            .map(|(id, distance)| (id.as_str(), *distance))
+            .collect::<Vec<_>>();
+        distances.sort();
+
+        assert_eq!(
+            distances,
+            vec![
+                ("<urn:example:continuation>", 2),
+                ("<urn:example:info>", 2),
+                ("<urn:example:request>", 2),
+                ("<urn:example:response>", 1),
+                ("<urn:example:revisit>", 0),
+            ]
+        );
+        assert_eq!(graph.references.len(), 5);
+        assert_eq!(graph.records[0], "<urn:example:revisit>");
+    }
+}
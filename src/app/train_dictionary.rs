@@ -0,0 +1,63 @@
+use std::io::Write;
+
+use anyhow::Context;
+
+use super::{
+    arg::TrainDictionaryCommand,
+    common::{ReaderEvent, ReaderPipeline},
+};
+
+pub fn train_dictionary(args: &TrainDictionaryCommand) -> anyhow::Result<()> {
+    let mut samples = Vec::new();
+
+    for input_path in &args.input {
+        let span = tracing::info_span!("train_dictionary", path = ?input_path);
+        let _span_guard = span.enter();
+
+        let input = super::common::open_input(input_path)?;
+        let compression_format = args.compression.try_into_native(input_path)?;
+        let file_len = std::fs::metadata(input_path).map(|m| m.len()).ok();
+        let mut sample = Vec::new();
+
+        ReaderPipeline::new(
+            |event| {
+                match event {
+                    ReaderEvent::Header { header, .. } => {
+                        sample.clear();
+                        header.serialize(&mut sample)?;
+                        sample.truncate(args.sample_size);
+                    }
+                    ReaderEvent::Block { data } => {
+                        let len = data.len().min(args.sample_size - sample.len());
+                        sample.extend_from_slice(&data[0..len]);
+                    }
+                    ReaderEvent::EndRecord { .. } => samples.push(std::mem::take(&mut sample)),
+                }
+
+                Ok(())
+            },
+            input,
+            compression_format,
+            file_len,
+        )?
+        .with_limit(args.limit)
+        .run()?;
+    }
+
+    if samples.is_empty() {
+        anyhow::bail!("no records to sample");
+    }
+
+    tracing::info!(sample_count = samples.len(), "training dictionary");
+
+    let dictionary = crate::compress::zstd::train_dictionary(&samples, args.max_size)
+        .context("failed to train dictionary")?;
+
+    tracing::info!(dictionary_len = dictionary.len(), "trained dictionary");
+
+    let mut output = super::common::open_output(&args.output)?;
+    output.write_all(&dictionary)?;
+    output.flush()?;
+
+    Ok(())
+}
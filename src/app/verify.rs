@@ -1,22 +1,67 @@
-use std::{cell::RefCell, process::ExitCode, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, VecDeque},
+    io::Write,
+    num::NonZeroUsize,
+    process::ExitCode,
+    rc::Rc,
+    sync::mpsc::{Receiver, Sender, SyncSender},
+    thread::JoinHandle,
+    time::Instant,
+};
+
+use anyhow::Context;
+use chrono::TimeDelta;
+use schemars::JsonSchema;
+use serde::Serialize;
 
 use crate::{
     app::common::{ReaderEvent, ReaderPipeline},
     dataseq::SeqWriter,
-    verify::{Check, Verifier, VerifyStatus},
+    header::fields::FieldsExt,
+    verify::{Check, DigestTask, Problem, ProblemKind, Severity, Verifier, VerifyStatus},
+    warc::EndState,
 };
 
-use super::arg::VerifyCommand;
+use super::{
+    arg::{SampleSize, VerifyCommand, VerifyField, VerifyProblemKind},
+    cdx::CdxChecker,
+    estimate::{print_estimate, Estimator},
+    fix::RecordFixer,
+    io::ProgramOutput,
+};
 
 const VERIFY_FAILED_EXIT_CODE: u8 = 8;
+const DIGEST_JOB_QUEUE_LEN: usize = 64;
+/// Number of input bytes between saving checkpoints.
+const CHECKPOINT_INTERVAL: u64 = 64 * 1024 * 1024;
 
 pub fn verify(args: &VerifyCommand) -> anyhow::Result<ExitCode> {
+    if args.emit_schema {
+        super::schema::print_schema(&super::schema::verify_schema())?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.resume && args.database.is_none() {
+        anyhow::bail!("resuming is supported only with a database");
+    }
+    if args.resume && args.fix.is_some() {
+        anyhow::bail!("resuming is not supported when fixing");
+    }
+    if args.estimate {
+        return estimate(args);
+    }
+
     let output_path = &args.output;
-    let output = super::common::open_output(output_path)?;
+    let output = if args.resume {
+        ProgramOutput::open_append(output_path).context("opening output file failed")?
+    } else {
+        super::common::open_output(output_path)?
+    };
     let seq_format = args.format.into();
 
-    let mut writer = SeqWriter::new(output, seq_format);
-    let mut problem_count = 0u64;
+    let mut writer = ProblemWriter::new(SeqWriter::new(output, seq_format), args);
+    let mut failure_count = 0u64;
     let mut verifier = if let Some(path) = &args.database {
         Verifier::open(path)?
     } else {
@@ -27,34 +72,155 @@ pub fn verify(args: &VerifyCommand) -> anyhow::Result<ExitCode> {
         verifier.checks_mut().remove(&Check::from(*exclude));
     }
 
+    if let Some(range) = args.date_range {
+        verifier.set_date_range(range.start, range.end);
+    }
+
+    verifier.set_date_order_tolerance(
+        args.date_order_tolerance
+            .map(|hours| TimeDelta::hours(hours.into())),
+    );
+    verifier.set_header_length_limits(args.max_field_length, args.max_header_length);
+
+    let mut fixer = match &args.fix {
+        Some(path) => Some(
+            RecordFixer::new(
+                super::common::open_output(path)?,
+                args.fix_compression.try_into_native_output(path)?,
+            )?
+            .with_drop_empty(args.fix_drop_empty),
+        ),
+        None => None,
+    };
+
+    let mut cdx_checker = match &args.cdx {
+        Some(path) => Some(CdxChecker::open(path)?),
+        None => None,
+    };
+
     let verifier = Rc::new(RefCell::new(verifier));
+    // The fixer needs the problems of each record before the next record.
+    let jobs = if fixer.is_some() {
+        1
+    } else {
+        args.jobs
+            .or_else(|| std::thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get)
+    };
+    let mut pool = if jobs > 1 {
+        Some(DigestPool::new(jobs))
+    } else {
+        None
+    };
+
+    let mut sampler = DigestSampler::new(args.sample, args.sample_seed);
+
+    // Decompressing in parallel reads each record into memory, so it is
+    // only done when asked for.
+    let decode_jobs = args.jobs.map_or(1, NonZeroUsize::get);
+
+    tracing::debug!(jobs, decode_jobs, "digest threads");
 
     for input_path in &args.input {
         let span = tracing::info_span!("verify", path = ?input_path);
         let _span_guard = span.enter();
 
+        let file = input_path.to_string_lossy().to_string();
+        writer.begin_file(&file);
+        verifier.borrow_mut().begin_file();
+
+        let mut start_position = 0;
+
+        if args.resume {
+            let mut verifier = verifier.borrow_mut();
+
+            if let Some(checkpoint) = verifier.checkpoint(&file)? {
+                verifier.resume(&checkpoint);
+
+                if checkpoint.is_done {
+                    tracing::info!("skipping verified file");
+                    continue;
+                }
+
+                tracing::info!(position = checkpoint.position, "resuming from checkpoint");
+                start_position = checkpoint.position;
+            }
+        }
+
+        if args.container_checksums && start_position == 0 {
+            if input_path.to_str() == Some("-") {
+                tracing::warn!("container checksums cannot be checked for standard input");
+            } else {
+                let (problems, is_intact) = super::checksum::check_container(input_path)?;
+
+                for problem in problems {
+                    if writer.put(&problem)? {
+                        failure_count += 1;
+                    }
+                }
+
+                if !is_intact {
+                    tracing::warn!("container checksum mismatch; skipping record checks");
+                    writer.end_file()?;
+                    continue;
+                }
+            }
+        }
+
         let input = super::common::open_input(input_path)?;
 
         tracing::info!("opened file");
 
         let compression_format = args.compression.try_into_native(input_path)?;
         let file_len = std::fs::metadata(input_path).map(|m| m.len()).ok();
+        let mut position = start_position;
+        let mut checkpoint_position = start_position;
+        sampler.begin_file(file_len);
+
+        if let Some(cdx_checker) = &mut cdx_checker {
+            cdx_checker.begin_file(input_path, start_position);
+        }
 
         let mut reader = ReaderPipeline::new(
             |event| match event {
                 ReaderEvent::Header {
                     header,
-                    record_boundary_position: _,
+                    record_boundary_position,
+                    ..
                 } => {
                     let mut verifier = verifier.borrow_mut();
+                    writer.summary.record_count += 1;
+
+                    let record_id = header.fields.get_or_default("WARC-Record-ID");
+                    let is_sampled = sampler.is_selected(record_id, record_boundary_position);
 
-                    for problem in verifier.problems() {
-                        problem_count += 1;
-                        writer.put(problem)?;
+                    if let Some(cdx_checker) = &mut cdx_checker {
+                        cdx_checker.begin_record(&header, record_boundary_position);
                     }
-                    verifier.problems_mut().clear();
+
+                    if let Some(pool) = &mut pool {
+                        verifier.begin_record(&header)?;
+
+                        if !is_sampled {
+                            verifier.skip_digests();
+                        }
+
+                        let problems = std::mem::take(verifier.problems_mut());
+                        pool.begin_record(problems, verifier.take_digest_task())?;
+
+                        return Ok(());
+                    }
+
                     verifier.begin_record(&header)?;
 
+                    if !is_sampled {
+                        verifier.skip_digests();
+                    }
+
+                    if let Some(fixer) = &mut fixer {
+                        fixer.begin_record(&header)?;
+                    }
+
                     Ok(())
                 }
                 ReaderEvent::Block { data } => {
@@ -62,8 +228,76 @@ pub fn verify(args: &VerifyCommand) -> anyhow::Result<ExitCode> {
 
                     if data.is_empty() {
                         verifier.end_record();
+
+                        if let Some(fixer) = &mut fixer {
+                            fixer.end_record(verifier.problems())?;
+                        }
+
+                        if let Some(pool) = &mut pool {
+                            pool.end_record(std::mem::take(verifier.problems_mut()))?;
+                        }
                     } else {
+                        writer.summary.decoded_size += data.len() as u64;
+
+                        // Only one of them holds the digest checks of the record.
                         verifier.block_data(data);
+
+                        if let Some(fixer) = &mut fixer {
+                            fixer.block_data(data)?;
+                        }
+
+                        if let Some(pool) = &mut pool {
+                            pool.block_data(data)?;
+                        }
+                    }
+
+                    Ok(())
+                }
+                ReaderEvent::EndRecord {
+                    record_boundary_position,
+                } => {
+                    let mut verifier = verifier.borrow_mut();
+
+                    // Position after the records whose problems are written.
+                    let done_position = if let Some(pool) = &mut pool {
+                        pool.set_end_position(record_boundary_position);
+
+                        for problem in pool.take_ready(false)? {
+                            if writer.put(&problem)? {
+                                failure_count += 1;
+                            }
+                        }
+
+                        pool.ready_position
+                    } else {
+                        for problem in verifier.problems() {
+                            if writer.put(problem)? {
+                                failure_count += 1;
+                            }
+                        }
+                        verifier.problems_mut().clear();
+
+                        Some(record_boundary_position)
+                    };
+
+                    if let Some(cdx_checker) = &mut cdx_checker {
+                        for problem in cdx_checker.end_record(record_boundary_position) {
+                            if writer.put(&problem)? {
+                                failure_count += 1;
+                            }
+                        }
+                    }
+
+                    position = record_boundary_position;
+
+                    if let Some(done_position) = done_position {
+                        if args.database.is_some()
+                            && done_position >= checkpoint_position + CHECKPOINT_INTERVAL
+                        {
+                            writer.flush()?;
+                            verifier.save_checkpoint(&file, done_position, false)?;
+                            checkpoint_position = done_position;
+                        }
                     }
 
                     Ok(())
@@ -72,12 +306,43 @@ pub fn verify(args: &VerifyCommand) -> anyhow::Result<ExitCode> {
             input,
             compression_format,
             file_len,
-        )?;
-        reader.run()?;
+        )?
+        .with_start_position(start_position)
+        .with_threads(decode_jobs);
+        let result = reader.run();
+        let end_state = reader.end_state;
+        let has_record_at_time_compression_fault = reader.has_record_at_time_compression_fault;
+        drop(reader);
 
         let mut verifier = verifier.borrow_mut();
 
-        if reader.has_record_at_time_compression_fault {
+        if let Some(pool) = &mut pool {
+            pool.abort_record()?;
+
+            for problem in pool.take_ready(true)? {
+                if writer.put(&problem)? {
+                    failure_count += 1;
+                }
+            }
+        }
+
+        if let Err(error) = result {
+            if end_state != EndState::Truncated {
+                return Err(error);
+            }
+
+            tracing::warn!(?error, "file appears truncated");
+        }
+
+        if end_state == EndState::Truncated {
+            verifier.add_truncated_file();
+
+            if let Some(fixer) = &mut fixer {
+                fixer.end_truncated_record(verifier.problems())?;
+            }
+        }
+
+        if has_record_at_time_compression_fault {
             verifier.add_not_record_at_time_compression();
         }
 
@@ -85,8 +350,9 @@ pub fn verify(args: &VerifyCommand) -> anyhow::Result<ExitCode> {
             let action = verifier.verify_end()?;
 
             for problem in verifier.problems() {
-                problem_count += 1;
-                writer.put(problem)?;
+                if writer.put(problem)? {
+                    failure_count += 1;
+                }
             }
             verifier.problems_mut().clear();
 
@@ -96,10 +362,30 @@ pub fn verify(args: &VerifyCommand) -> anyhow::Result<ExitCode> {
             }
         }
 
+        if let Some(cdx_checker) = &mut cdx_checker {
+            for problem in cdx_checker.end_file() {
+                if writer.put(&problem)? {
+                    failure_count += 1;
+                }
+            }
+        }
+
+        writer.end_file()?;
+
+        if args.database.is_some() {
+            writer.flush()?;
+            verifier.save_checkpoint(&file, position, true)?;
+        }
+
         tracing::info!("closed file");
     }
 
-    let exit_code = if problem_count == 0 {
+    if let Some(fixer) = fixer {
+        tracing::info!(fixed_count = fixer.fixed_count(), "wrote fixed file");
+        fixer.finish()?;
+    }
+
+    let exit_code = if failure_count == 0 {
         ExitCode::SUCCESS
     } else {
         ExitCode::from(VERIFY_FAILED_EXIT_CODE)
@@ -107,3 +393,669 @@ pub fn verify(args: &VerifyCommand) -> anyhow::Result<ExitCode> {
 
     Ok(exit_code)
 }
+
+/// Predicts the size of the data the verifier stores for the records.
+fn estimate(args: &VerifyCommand) -> anyhow::Result<ExitCode> {
+    let mut estimator = Estimator::new(args.database.is_some());
+
+    for input_path in &args.input {
+        estimator.sample_file(input_path, args.compression, |header| {
+            let field_len = |name: &str| header.fields.get_or_default(name).len() as u64;
+            let record_id_len = field_len("WARC-Record-ID");
+            // Record ID and sequence number.
+            let mut len = record_id_len + 8;
+
+            for name in ["WARC-Concurrent-To", "WARC-Refers-To", "WARC-Warcinfo-ID"] {
+                for value in header.fields.get_all(name) {
+                    len += record_id_len + value.len() as u64 + name.len() as u64;
+                }
+            }
+
+            if header.fields.contains_name("WARC-Payload-Digest") {
+                // Payload digest and capture, or revisit reference.
+                len += record_id_len * 2
+                    + field_len("WARC-Payload-Digest")
+                    + field_len("WARC-Target-URI")
+                    + field_len("WARC-Date");
+            }
+
+            if header.fields.contains_name("WARC-Segment-Number") {
+                len += record_id_len + 16;
+            }
+
+            len
+        })?;
+    }
+
+    print_estimate(&estimator.finish())?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+enum DigestJob {
+    Begin(u64, Box<DigestTask>),
+    Data(Vec<u8>),
+    End,
+    Abort,
+}
+
+struct PendingRecord {
+    seq: u64,
+    problems: Vec<Problem>,
+    is_waiting: bool,
+    end_position: Option<u64>,
+}
+
+/// Checks the digests of records on worker threads.
+///
+/// Records are assigned to the workers in turn. Problems are returned
+/// in the order of the records.
+struct DigestPool {
+    senders: Vec<SyncSender<DigestJob>>,
+    workers: Vec<JoinHandle<()>>,
+    results: Receiver<(u64, Vec<Problem>)>,
+    pending: VecDeque<PendingRecord>,
+    next_seq: u64,
+    current_worker: Option<usize>,
+    /// End position of the last record returned by `take_ready()`.
+    ready_position: Option<u64>,
+}
+
+impl DigestPool {
+    fn new(jobs: usize) -> Self {
+        let (result_sender, results) = std::sync::mpsc::channel();
+        let mut senders = Vec::with_capacity(jobs);
+        let mut workers = Vec::with_capacity(jobs);
+
+        for _ in 0..jobs {
+            let (sender, receiver) = std::sync::mpsc::sync_channel(DIGEST_JOB_QUEUE_LEN);
+            let result_sender = result_sender.clone();
+
+            senders.push(sender);
+            workers.push(std::thread::spawn(move || {
+                digest_worker(receiver, result_sender)
+            }));
+        }
+
+        Self {
+            senders,
+            workers,
+            results,
+            pending: VecDeque::new(),
+            next_seq: 0,
+            current_worker: None,
+            ready_position: None,
+        }
+    }
+
+    fn begin_record(
+        &mut self,
+        problems: Vec<Problem>,
+        task: Option<DigestTask>,
+    ) -> anyhow::Result<()> {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        self.current_worker = match task {
+            Some(task) => {
+                let index = (seq % self.senders.len() as u64) as usize;
+                self.send(index, DigestJob::Begin(seq, Box::new(task)))?;
+                Some(index)
+            }
+            None => None,
+        };
+        self.pending.push_back(PendingRecord {
+            seq,
+            problems,
+            is_waiting: self.current_worker.is_some(),
+            end_position: None,
+        });
+
+        Ok(())
+    }
+
+    fn block_data(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        if let Some(index) = self.current_worker {
+            self.send(index, DigestJob::Data(data.to_vec()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Ends the current record with additional problems from the verifier.
+    fn end_record(&mut self, mut problems: Vec<Problem>) -> anyhow::Result<()> {
+        if let Some(record) = self.pending.back_mut() {
+            record.problems.append(&mut problems);
+        }
+
+        if let Some(index) = self.current_worker.take() {
+            self.send(index, DigestJob::End)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the position of the record boundary after the current record.
+    fn set_end_position(&mut self, position: u64) {
+        if let Some(record) = self.pending.back_mut() {
+            record.end_position = Some(position);
+        }
+    }
+
+    /// Discards the digest checks of the current record if it did not end.
+    fn abort_record(&mut self) -> anyhow::Result<()> {
+        if let Some(index) = self.current_worker.take() {
+            self.send(index, DigestJob::Abort)?;
+
+            if let Some(record) = self.pending.back_mut() {
+                record.is_waiting = false;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the problems of the records that are done in order.
+    ///
+    /// If `wait` is true, waits for all the records to be done.
+    fn take_ready(&mut self, wait: bool) -> anyhow::Result<Vec<Problem>> {
+        while let Ok((seq, problems)) = self.results.try_recv() {
+            self.put_result(seq, problems);
+        }
+
+        if wait {
+            while self.pending.iter().any(|record| record.is_waiting) {
+                let (seq, problems) = self.results.recv().map_err(|_| worker_exited_error())?;
+                self.put_result(seq, problems);
+            }
+        }
+
+        let mut problems = Vec::new();
+
+        while self
+            .pending
+            .front()
+            .is_some_and(|record| !record.is_waiting)
+        {
+            let mut record = self.pending.pop_front().unwrap();
+            problems.append(&mut record.problems);
+
+            if record.end_position.is_some() {
+                self.ready_position = record.end_position;
+            }
+        }
+
+        Ok(problems)
+    }
+
+    fn put_result(&mut self, seq: u64, mut problems: Vec<Problem>) {
+        let index = (seq - self.pending.front().unwrap().seq) as usize;
+        let record = &mut self.pending[index];
+
+        record.problems.append(&mut problems);
+        record.is_waiting = false;
+    }
+
+    fn send(&self, index: usize, job: DigestJob) -> anyhow::Result<()> {
+        self.senders[index]
+            .send(job)
+            .map_err(|_| worker_exited_error())
+    }
+}
+
+impl Drop for DigestPool {
+    fn drop(&mut self) {
+        self.senders.clear();
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn worker_exited_error() -> anyhow::Error {
+    anyhow::anyhow!("digest thread exited")
+}
+
+fn digest_worker(receiver: Receiver<DigestJob>, results: Sender<(u64, Vec<Problem>)>) {
+    let mut current = None;
+
+    for job in receiver {
+        match job {
+            DigestJob::Begin(seq, task) => current = Some((seq, task)),
+            DigestJob::Data(data) => {
+                if let Some((_, task)) = &mut current {
+                    task.block_data(&data);
+                }
+            }
+            DigestJob::End => {
+                if let Some((seq, task)) = current.take() {
+                    if results.send((seq, task.finish())).is_err() {
+                        break;
+                    }
+                }
+            }
+            DigestJob::Abort => current = None,
+        }
+    }
+}
+
+/// Selects the records whose digests are checked by `--sample`.
+struct DigestSampler {
+    size: Option<SampleSize>,
+    seed: u64,
+    /// Distance between selected positions when the file size is known.
+    interval: Option<u64>,
+    next_position: u64,
+    selected_count: u64,
+}
+
+impl DigestSampler {
+    fn new(size: Option<SampleSize>, seed: u64) -> Self {
+        Self {
+            size,
+            seed,
+            interval: None,
+            next_position: 0,
+            selected_count: 0,
+        }
+    }
+
+    fn begin_file(&mut self, file_len: Option<u64>) {
+        self.interval = match (self.size, file_len) {
+            (Some(SampleSize::Count(count)), Some(file_len)) if count > 0 => {
+                Some(file_len.div_ceil(count).max(1))
+            }
+            _ => None,
+        };
+        self.next_position = 0;
+        self.selected_count = 0;
+    }
+
+    /// Returns whether the digests of the record at the position should be
+    /// checked.
+    fn is_selected(&mut self, record_id: &str, position: u64) -> bool {
+        match self.size {
+            None => true,
+            Some(SampleSize::Percent(percent)) => {
+                let hash = xxhash_rust::xxh3::xxh3_64_with_seed(record_id.as_bytes(), self.seed);
+
+                hash as f64 / u64::MAX as f64 * 100.0 < percent
+            }
+            Some(SampleSize::Count(count)) => {
+                let is_selected = match self.interval {
+                    Some(_) => position >= self.next_position,
+                    None => self.selected_count < count,
+                };
+
+                if is_selected {
+                    self.selected_count += 1;
+
+                    if let Some(interval) = self.interval {
+                        self.next_position = (position / interval + 1) * interval;
+                    }
+                }
+
+                is_selected
+            }
+        }
+    }
+}
+
+/// Statistics of verifying a file.
+///
+/// When resuming from a checkpoint, only the records after the checkpoint
+/// are counted.
+#[derive(Debug, Default, Serialize, JsonSchema)]
+struct FileSummary {
+    file: String,
+    record_count: u64,
+    decoded_size: u64,
+    /// Number of output problems by kind.
+    problem_counts: BTreeMap<String, u64>,
+    /// Number of output problems that fail the verification.
+    failure_count: u64,
+    /// Seconds taken.
+    duration: f64,
+}
+
+/// Message containing the summary of a file when `--summary` is given.
+#[derive(Serialize, JsonSchema)]
+pub(super) struct SummaryMessage<'a> {
+    summary: &'a FileSummary,
+}
+
+/// Writes problems that are selected by kind with the selected fields.
+struct ProblemWriter {
+    writer: SeqWriter<ProgramOutput>,
+    only_kinds: Vec<VerifyProblemKind>,
+    ignore_kinds: Vec<VerifyProblemKind>,
+    fields: Vec<VerifyField>,
+    fail_on: Severity,
+    file: String,
+    is_summary_enabled: bool,
+    summary: FileSummary,
+    start_time: Instant,
+}
+
+impl ProblemWriter {
+    fn new(writer: SeqWriter<ProgramOutput>, args: &VerifyCommand) -> Self {
+        Self {
+            writer,
+            only_kinds: args.only_kind.clone(),
+            ignore_kinds: args.ignore_kind.clone(),
+            fields: args.field.clone(),
+            fail_on: args.fail_on.into(),
+            file: String::new(),
+            is_summary_enabled: args.summary,
+            summary: FileSummary::default(),
+            start_time: Instant::now(),
+        }
+    }
+
+    fn begin_file(&mut self, file: &str) {
+        self.file = file.to_string();
+        self.summary = FileSummary {
+            file: file.to_string(),
+            ..Default::default()
+        };
+        self.start_time = Instant::now();
+    }
+
+    /// Writes the summary of the file if enabled.
+    fn end_file(&mut self) -> anyhow::Result<()> {
+        if self.is_summary_enabled {
+            self.summary.duration = self.start_time.elapsed().as_secs_f64();
+            self.writer.put(SummaryMessage {
+                summary: &self.summary,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn is_selected(&self, problem: &Problem) -> bool {
+        let kind = VerifyProblemKind::from(problem.kind());
+
+        (self.only_kinds.is_empty() || self.only_kinds.contains(&kind))
+            && !self.ignore_kinds.contains(&kind)
+    }
+
+    /// Writes the problem and returns whether it was selected and is severe
+    /// enough to fail the verification.
+    fn put(&mut self, problem: &Problem) -> anyhow::Result<bool> {
+        if !self.is_selected(problem) {
+            return Ok(false);
+        }
+
+        if self.fields.is_empty() {
+            self.writer.put(problem)?;
+        } else {
+            let values = problem_values(problem, &self.fields, &self.file)?;
+            self.writer.put(values)?;
+        }
+
+        let is_failure = problem.severity() >= self.fail_on;
+        let (kind_name, _) = kind_name_detail(problem.kind())?;
+        *self.summary.problem_counts.entry(kind_name).or_default() += 1;
+
+        if is_failure {
+            self.summary.failure_count += 1;
+        }
+
+        Ok(is_failure)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.get_mut().flush()
+    }
+}
+
+/// Returns the variant name and the details of the problem kind.
+fn kind_name_detail(kind: &ProblemKind) -> anyhow::Result<(String, serde_json::Value)> {
+    // The kind is serialized as the variant name or as a map of the variant
+    // name to the details.
+    Ok(match serde_json::to_value(kind)? {
+        serde_json::Value::Object(map) => map.into_iter().next().unwrap_or_default(),
+        serde_json::Value::String(name) => (name, serde_json::Value::Null),
+        value => (String::new(), value),
+    })
+}
+
+fn problem_values(
+    problem: &Problem,
+    fields: &[VerifyField],
+    file: &str,
+) -> anyhow::Result<Vec<serde_json::Value>> {
+    let (kind_name, detail) = kind_name_detail(problem.kind())?;
+
+    let values = fields
+        .iter()
+        .map(|field| match field {
+            VerifyField::File => serde_json::Value::String(file.to_string()),
+            VerifyField::RecordId => serde_json::Value::String(problem.record_id().to_string()),
+            VerifyField::Kind => serde_json::Value::String(kind_name.clone()),
+            VerifyField::Detail => detail.clone(),
+            VerifyField::Severity => serde_json::to_value(problem.severity()).unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        digest::{AlgorithmName, Digest, Hasher},
+        header::WarcHeader,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_problem_values() {
+        let fields = [
+            VerifyField::Kind,
+            VerifyField::RecordId,
+            VerifyField::File,
+            VerifyField::Detail,
+            VerifyField::Severity,
+        ];
+
+        let problem = Problem::new("<urn:example:1>", ProblemKind::InvalidSegment);
+        assert_eq!(
+            problem_values(&problem, &fields, "a.warc").unwrap(),
+            vec![
+                serde_json::json!("InvalidSegment"),
+                serde_json::json!("<urn:example:1>"),
+                serde_json::json!("a.warc"),
+                serde_json::Value::Null,
+                serde_json::json!("error"),
+            ]
+        );
+
+        let problem = Problem::new("<urn:example:2>", ProblemKind::MissingSegment(3));
+        assert_eq!(
+            problem_values(&problem, &fields[0..1], "a.warc").unwrap(),
+            vec![serde_json::json!("MissingSegment")]
+        );
+        assert_eq!(
+            problem_values(&problem, &fields[3..4], "a.warc").unwrap(),
+            vec![serde_json::json!(3)]
+        );
+    }
+
+    #[test]
+    fn test_digest_sampler() {
+        let mut sampler = DigestSampler::new(Some(SampleSize::Count(3)), 0);
+        sampler.begin_file(Some(1000));
+        let selected = [0, 100, 200, 340, 500, 600, 700, 990]
+            .into_iter()
+            .filter(|position| sampler.is_selected("", *position))
+            .collect::<Vec<_>>();
+        assert_eq!(selected, vec![0, 340, 700]);
+
+        sampler.begin_file(None);
+        let selected_count = (0..10)
+            .filter(|position| sampler.is_selected("", *position))
+            .count();
+        assert_eq!(selected_count, 3);
+
+        let ids = (0..1000)
+            .map(|index| format!("<urn:example:{}>", index))
+            .collect::<Vec<_>>();
+        let mut sampler = DigestSampler::new(Some(SampleSize::Percent(10.0)), 0);
+        sampler.begin_file(None);
+        let selected = ids
+            .iter()
+            .filter(|id| sampler.is_selected(id, 0))
+            .cloned()
+            .collect::<Vec<_>>();
+        assert!((50..150).contains(&selected.len()));
+        assert!(selected.iter().all(|id| sampler.is_selected(id, 0)));
+
+        let mut sampler = DigestSampler::new(Some(SampleSize::Percent(10.0)), 1);
+        assert!(ids
+            .iter()
+            .filter(|id| sampler.is_selected(id, 0))
+            .ne(&selected));
+    }
+
+    fn digest_records() -> Vec<(WarcHeader, Vec<u8>)> {
+        (0..20)
+            .map(|index| {
+                let block = format!("record {}", index).repeat(index * 100).into_bytes();
+                let mut header = WarcHeader::new(block.len() as u64, "resource");
+                header.fields.insert(
+                    "WARC-Record-ID".to_string(),
+                    format!("<urn:example:{}>", index),
+                );
+
+                let digest = match index % 3 {
+                    0 => None,
+                    1 => {
+                        let mut hasher = Hasher::new(AlgorithmName::Sha1);
+                        hasher.update(&block);
+                        Some(Digest::new(AlgorithmName::Sha1, hasher.finish()).to_string())
+                    }
+                    _ => Some("sha1:AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA".to_string()),
+                };
+
+                if let Some(digest) = digest {
+                    header
+                        .fields
+                        .insert("WARC-Block-Digest".to_string(), digest);
+                }
+
+                (header, block)
+            })
+            .collect()
+    }
+
+    /// Verifies the records on the current thread. If `truncated`, the last
+    /// record does not end.
+    fn verify_serial(records: &[(WarcHeader, Vec<u8>)], truncated: bool) -> serde_json::Value {
+        let mut verifier = Verifier::new();
+        let mut problems = Vec::new();
+        verifier.begin_file();
+
+        for (index, (header, block)) in records.iter().enumerate() {
+            verifier.begin_record(header).unwrap();
+
+            if truncated && index == records.len() - 1 {
+                verifier.block_data(&block[..block.len() / 2]);
+                break;
+            }
+
+            for chunk in block.chunks(100) {
+                verifier.block_data(chunk);
+            }
+
+            verifier.end_record();
+            problems.append(verifier.problems_mut());
+        }
+
+        problems.append(verifier.problems_mut());
+
+        serde_json::to_value(problems).unwrap()
+    }
+
+    /// Verifies the records with a digest pool like [`verify_serial`].
+    fn verify_pooled(records: &[(WarcHeader, Vec<u8>)], truncated: bool) -> serde_json::Value {
+        let mut verifier = Verifier::new();
+        let mut pool = DigestPool::new(3);
+        let mut problems = Vec::new();
+        verifier.begin_file();
+
+        for (index, (header, block)) in records.iter().enumerate() {
+            verifier.begin_record(header).unwrap();
+            let header_problems = std::mem::take(verifier.problems_mut());
+            pool.begin_record(header_problems, verifier.take_digest_task())
+                .unwrap();
+
+            if truncated && index == records.len() - 1 {
+                verifier.block_data(&block[..block.len() / 2]);
+                pool.block_data(&block[..block.len() / 2]).unwrap();
+                break;
+            }
+
+            for chunk in block.chunks(100) {
+                verifier.block_data(chunk);
+                pool.block_data(chunk).unwrap();
+            }
+
+            verifier.end_record();
+            pool.end_record(std::mem::take(verifier.problems_mut()))
+                .unwrap();
+            pool.set_end_position(index as u64);
+            problems.append(&mut pool.take_ready(false).unwrap());
+        }
+
+        pool.abort_record().unwrap();
+        problems.append(&mut pool.take_ready(true).unwrap());
+
+        serde_json::to_value(problems).unwrap()
+    }
+
+    #[test]
+    fn test_digest_pool() {
+        let records = digest_records();
+        let problems = verify_serial(&records, false);
+
+        assert!(problems
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|problem| problem["kind"]["DigestMismatch"].is_object()));
+        assert_eq!(verify_pooled(&records, false), problems);
+    }
+
+    #[test]
+    fn test_digest_pool_truncated() {
+        let records = digest_records();
+
+        assert_eq!(verify_pooled(&records, true), verify_serial(&records, true));
+
+        // The record with the bad digest is aborted.
+        assert_eq!(
+            verify_pooled(&records[..3], true),
+            verify_serial(&records[..3], true)
+        );
+    }
+
+    #[test]
+    fn test_digest_pool_worker_exited() {
+        let records = digest_records();
+        let mut verifier = Verifier::new();
+        verifier.begin_record(&records[1].0).unwrap();
+        let task = verifier.take_digest_task();
+        assert!(task.is_some());
+
+        let mut pool = DigestPool::new(2);
+        let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+        drop(receiver);
+        pool.senders = vec![sender.clone(), sender];
+
+        assert!(pool.begin_record(Vec::new(), task).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! Helpers for measuring performance of the library.
+//!
+//! These functions generate synthetic data and measure the throughput of
+//! the hot paths of decoding, hashing, and compression. They are intended for
+//! integrators to evaluate the library in their deployment environment
+//! (such as whether Zstandard is worth using) rather than for
+//! precise benchmarking.
+//!
+//! This module is only available with the `bench` feature.
+use std::{
+    io::{Read, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    compress::{Compressor, CompressorConfig, Decompressor, Format, Level},
+    digest::{AlgorithmName, Hasher},
+    error::GeneralError,
+    header::WarcHeader,
+    warc::{Decoder, DecoderConfig, Encoder, EncoderConfig},
+};
+
+const BUFFER_LENGTH: usize = crate::io::IO_BUFFER_LENGTH;
+
+/// Result of a measurement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    /// Number of bytes processed.
+    pub input_length: u64,
+    /// Number of bytes produced.
+    pub output_length: u64,
+    /// Time elapsed.
+    pub duration: Duration,
+}
+
+impl Measurement {
+    /// Returns the number of input bytes processed per second.
+    pub fn throughput(&self) -> f64 {
+        let seconds = self.duration.as_secs_f64();
+
+        if seconds > 0.0 {
+            self.input_length as f64 / seconds
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    /// Returns the output length divided by the input length.
+    pub fn ratio(&self) -> f64 {
+        if self.input_length > 0 {
+            self.output_length as f64 / self.input_length as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Returns synthetic data of the given length.
+///
+/// The data is a mix of repetitive text and pseudorandom bytes so that it is
+/// somewhat compressible. The output is deterministic.
+pub fn generate_data(length: usize) -> Vec<u8> {
+    const TEXT: &[u8] = b"<p>The quick brown fox jumps over the lazy dog.</p>\n";
+
+    let mut data = Vec::with_capacity(length);
+    let mut state = 0x9e3779b97f4a7c15u64;
+
+    while data.len() < length {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+
+        if state % 4 == 0 {
+            data.extend_from_slice(&state.to_le_bytes());
+        } else {
+            data.extend_from_slice(TEXT);
+        }
+    }
+
+    data.truncate(length);
+    data
+}
+
+/// Returns a WARC file containing synthetic resource records.
+pub fn generate_warc(
+    record_count: usize,
+    block_length: usize,
+    config: EncoderConfig,
+) -> Result<Vec<u8>, GeneralError> {
+    let data = generate_data(block_length);
+    let mut encoder = Encoder::new(Vec::new(), config);
+
+    for index in 0..record_count {
+        let mut header = WarcHeader::new(block_length as u64, "resource");
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            format!("urn:example:bench:{}", index),
+        );
+
+        let mut block_encoder = encoder.write_header(&header)?;
+        block_encoder.write_all(&data)?;
+        encoder = block_encoder.finish_block()?;
+    }
+
+    Ok(encoder.finish()?)
+}
+
+/// Measures decoding records and reading their blocks.
+///
+/// The input length is the length of the file and the output length is the
+/// total length of the blocks.
+pub fn decode(data: &[u8], config: DecoderConfig) -> Result<Measurement, GeneralError> {
+    let start = Instant::now();
+    let mut decoder = Decoder::new(data, config)?;
+    let mut buf = vec![0u8; BUFFER_LENGTH];
+    let mut output_length = 0;
+
+    while decoder.has_next_record()? {
+        let (_header, mut block_decoder) = decoder.read_header()?;
+
+        loop {
+            let read_length = block_decoder.read(&mut buf)?;
+
+            if read_length == 0 {
+                break;
+            }
+
+            output_length += read_length as u64;
+        }
+
+        decoder = block_decoder.finish_block()?;
+    }
+
+    Ok(Measurement {
+        input_length: data.len() as u64,
+        output_length,
+        duration: start.elapsed(),
+    })
+}
+
+/// Measures decoding a number of synthetic records.
+pub fn decode_synthetic(
+    record_count: usize,
+    block_length: usize,
+    format: Format,
+) -> Result<Measurement, GeneralError> {
+    let mut encoder_config = EncoderConfig::default();
+    encoder_config.compressor.format = format;
+    let data = generate_warc(record_count, block_length, encoder_config)?;
+
+    let mut decoder_config = DecoderConfig::default();
+    decoder_config.decompressor.format = format;
+
+    decode(&data, decoder_config)
+}
+
+/// Measures hashing synthetic data of the given length.
+pub fn hash(algorithm: AlgorithmName, length: usize) -> Measurement {
+    let data = generate_data(length);
+
+    let start = Instant::now();
+    let mut hasher = Hasher::new(algorithm);
+
+    for chunk in data.chunks(BUFFER_LENGTH) {
+        hasher.update(chunk);
+    }
+
+    let output = hasher.finish();
+
+    Measurement {
+        input_length: length as u64,
+        output_length: output.len() as u64,
+        duration: start.elapsed(),
+    }
+}
+
+/// Measures compressing synthetic data of the given length.
+///
+/// The output length is the compressed length.
+pub fn compress(format: Format, level: Level, length: usize) -> std::io::Result<Measurement> {
+    let data = generate_data(length);
+
+    let start = Instant::now();
+    let output = compress_impl(&data, format, level)?;
+
+    Ok(Measurement {
+        input_length: length as u64,
+        output_length: output.len() as u64,
+        duration: start.elapsed(),
+    })
+}
+
+/// Measures decompressing synthetic data of the given (decompressed) length.
+///
+/// The input length is the compressed length and the output length is the
+/// decompressed length.
+pub fn decompress(format: Format, level: Level, length: usize) -> std::io::Result<Measurement> {
+    let data = generate_data(length);
+    let compressed = compress_impl(&data, format, level)?;
+
+    let start = Instant::now();
+    let mut decompressor = Decompressor::new(compressed.as_slice(), format)?;
+    let mut buf = vec![0u8; BUFFER_LENGTH];
+    let mut output_length = 0;
+
+    loop {
+        let read_length = decompressor.read(&mut buf)?;
+
+        if read_length == 0 {
+            break;
+        }
+
+        output_length += read_length as u64;
+    }
+
+    Ok(Measurement {
+        input_length: compressed.len() as u64,
+        output_length,
+        duration: start.elapsed(),
+    })
+}
+
+fn compress_impl(data: &[u8], format: Format, level: Level) -> std::io::Result<Vec<u8>> {
+    let mut compressor = Compressor::with_config(
+        Vec::new(),
+        CompressorConfig {
+            format,
+            level,
+            ..Default::default()
+        },
+    );
+
+    for chunk in data.chunks(BUFFER_LENGTH) {
+        compressor.write_all(chunk)?;
+    }
+
+    compressor.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_data() {
+        let data = generate_data(1000);
+
+        assert_eq!(data.len(), 1000);
+        assert_eq!(data, generate_data(1000));
+    }
+
+    #[test]
+    fn test_decode_synthetic() {
+        let measurement = decode_synthetic(10, 1000, Format::Gzip).unwrap();
+
+        assert_eq!(measurement.output_length, 10 * 1000);
+    }
+
+    #[test]
+    fn test_hash() {
+        let measurement = hash(AlgorithmName::Sha256, 1000);
+
+        assert_eq!(measurement.input_length, 1000);
+        assert_eq!(measurement.output_length, 32);
+    }
+
+    #[test]
+    fn test_compress_decompress() {
+        let measurement = compress(Format::Gzip, Level::Low, 10000).unwrap();
+
+        assert!(measurement.ratio() < 1.0);
+
+        let measurement = decompress(Format::Gzip, Level::Low, 10000).unwrap();
+
+        assert_eq!(measurement.output_length, 10000);
+    }
+}
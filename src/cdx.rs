@@ -0,0 +1,223 @@
+//! CDX index files.
+//!
+//! A CDX file lists the records of WARC files, one record per line, usually
+//! sorted by URL key. The fields of a line are separated by spaces in the
+//! order given by the legend in the first line, such as
+//! ` CDX N b a m s k r M S V g`. Files without a legend are read as the
+//! common 11-field format. CDXJ lines, which have a JSON object after the
+//! URL key and timestamp, are also supported.
+use std::io::BufRead;
+
+use crate::error::{GeneralError, ParseError, ParseErrorKind};
+
+const DEFAULT_LEGEND: &str = "NbamskrMSVg";
+
+/// A line of a CDX file.
+///
+/// Fields with the value `-` are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CdxEntry {
+    /// Canonicalized URL used for sorting, such as SURT.
+    pub url_key: String,
+    /// Date of the capture in the 14 digit format.
+    pub timestamp: String,
+    /// Original URL.
+    pub url: Option<String>,
+    pub mime_type: Option<String>,
+    pub status_code: Option<String>,
+    /// Digest of the payload, usually SHA-1 encoded in Base32 without the
+    /// algorithm label.
+    pub digest: Option<String>,
+    /// Length of the record in the file, compressed if the file is
+    /// compressed.
+    pub length: Option<u64>,
+    /// Position of the record in the file.
+    pub offset: Option<u64>,
+    /// Name of the WARC file.
+    pub filename: Option<String>,
+}
+
+/// Reads the entries of a CDX or CDXJ file.
+pub struct CdxReader<R: BufRead> {
+    input: R,
+    legend: Vec<char>,
+    line: String,
+}
+
+impl<R: BufRead> CdxReader<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            input,
+            legend: DEFAULT_LEGEND.chars().collect(),
+            line: String::new(),
+        }
+    }
+
+    /// Returns the field letters of the legend.
+    pub fn legend(&self) -> &[char] {
+        &self.legend
+    }
+
+    /// Reads the next entry.
+    ///
+    /// Returns `None` at the end of the file. Legend and empty lines are
+    /// skipped.
+    pub fn read_entry(&mut self) -> Result<Option<CdxEntry>, GeneralError> {
+        loop {
+            self.line.clear();
+
+            if self.input.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+
+            let line = self.line.trim_end_matches(['\r', '\n']);
+            let mut parts = line.split_ascii_whitespace();
+
+            match parts.next() {
+                None => continue,
+                Some("CDX") => {
+                    self.legend = parts.flat_map(|part| part.chars()).collect();
+                    continue;
+                }
+                Some(_) => {}
+            }
+
+            return Ok(Some(parse_line(line, &self.legend)?));
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for CdxReader<R> {
+    type Item = Result<CdxEntry, GeneralError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+/// Parses a CDX line with fields in the order of the legend letters, or a
+/// CDXJ line.
+pub fn parse_line(line: &str, legend: &[char]) -> Result<CdxEntry, ParseError> {
+    let syntax_error = || ParseError::new(ParseErrorKind::Syntax).with_snippet(snippet(line));
+    let mut parts = line.trim().splitn(3, ' ');
+    let url_key = parts.next().unwrap_or_default();
+    let timestamp = parts.next().ok_or_else(syntax_error)?;
+    let remain = parts.next().ok_or_else(syntax_error)?.trim_start();
+
+    if remain.starts_with('{') {
+        return parse_cdxj(url_key, timestamp, remain).ok_or_else(syntax_error);
+    }
+
+    let mut entry = CdxEntry::default();
+
+    for (letter, value) in legend.iter().zip(line.split_ascii_whitespace()) {
+        let optional = || Some(value.to_string()).filter(|value| value != "-");
+        let number = || match value {
+            "-" => Ok(None),
+            _ => value.parse::<u64>().map(Some).map_err(|_| syntax_error()),
+        };
+
+        match letter {
+            'N' => entry.url_key = value.to_string(),
+            'b' => entry.timestamp = value.to_string(),
+            'a' => entry.url = optional(),
+            'm' => entry.mime_type = optional(),
+            's' => entry.status_code = optional(),
+            'k' => entry.digest = optional(),
+            'S' => entry.length = number()?,
+            'V' => entry.offset = number()?,
+            'g' => entry.filename = optional(),
+            _ => {}
+        }
+    }
+
+    Ok(entry)
+}
+
+fn parse_cdxj(url_key: &str, timestamp: &str, json: &str) -> Option<CdxEntry> {
+    let object = serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(json).ok()?;
+    let text = |name: &str| match object.get(name)? {
+        serde_json::Value::String(value) => Some(value.clone()),
+        serde_json::Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    };
+    let number = |name: &str| text(name).and_then(|value| value.parse::<u64>().ok());
+
+    Some(CdxEntry {
+        url_key: url_key.to_string(),
+        timestamp: timestamp.to_string(),
+        url: text("url"),
+        mime_type: text("mime"),
+        status_code: text("status"),
+        digest: text("digest"),
+        length: number("length"),
+        offset: number("offset"),
+        filename: text("filename"),
+    })
+}
+
+fn snippet(line: &str) -> String {
+    line.chars().take(64).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reader() {
+        let input = b" CDX N b a m s k r M S V g\n\
+            com,example)/ 20240102030405 http://example.com/ text/html 200 \
+            3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ - - 1043 0 example.warc.gz\n\
+            \n\
+            com,example)/a 20240102030406 http://example.com/a - - - - - 500 1043 example.warc.gz\n\
+            com,example)/b 20240102030407 {\"url\": \"http://example.com/b\", \"digest\": \"sha1:AAAA\", \"length\": \"12\", \"offset\": 1543, \"filename\": \"example.warc.gz\"}\n";
+        let entries = CdxReader::new(input.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0],
+            CdxEntry {
+                url_key: "com,example)/".to_string(),
+                timestamp: "20240102030405".to_string(),
+                url: Some("http://example.com/".to_string()),
+                mime_type: Some("text/html".to_string()),
+                status_code: Some("200".to_string()),
+                digest: Some("3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ".to_string()),
+                length: Some(1043),
+                offset: Some(0),
+                filename: Some("example.warc.gz".to_string()),
+            }
+        );
+        assert_eq!(entries[1].digest, None);
+        assert_eq!(entries[1].offset, Some(1043));
+        assert_eq!(entries[2].url.as_deref(), Some("http://example.com/b"));
+        assert_eq!(entries[2].digest.as_deref(), Some("sha1:AAAA"));
+        assert_eq!(entries[2].length, Some(12));
+        assert_eq!(entries[2].offset, Some(1543));
+    }
+
+    #[test]
+    fn test_parse_line_legend() {
+        let legend = ['N', 'b', 'a', 'm', 's', 'k', 'r', 'V', 'g'];
+        let entry = parse_line(
+            "com,example)/ 20240102030405 http://example.com/ - 200 - - 77 a.warc",
+            &legend,
+        )
+        .unwrap();
+
+        assert_eq!(entry.offset, Some(77));
+        assert_eq!(entry.length, None);
+        assert_eq!(entry.filename.as_deref(), Some("a.warc"));
+
+        assert!(parse_line("com,example)/ 20240102030405 x - - - - - abc 0 a", &[]).is_ok());
+        assert!(parse_line(
+            "com,example)/ 20240102030405 x - - - - - abc 0 a",
+            &DEFAULT_LEGEND.chars().collect::<Vec<_>>()
+        )
+        .is_err());
+        assert!(parse_line("com,example)/", &legend).is_err());
+    }
+}
@@ -0,0 +1,391 @@
+//! TLS certificate records
+//!
+//! Some crawlers store the certificates of the TLS connections as
+//! `resource` or `metadata` records in PEM or DER encoding. This module
+//! recognizes such records and reads the subject, issuer, and validity of
+//! the certificates for provenance audits. Signatures and extensions are not
+//! processed.
+use chrono::{DateTime, NaiveDateTime, Utc};
+use data_encoding::{BASE64, HEXLOWER};
+
+use crate::{
+    error::{ParseError, ParseErrorKind},
+    header::{fields::FieldsExt, WarcHeader, WarcType},
+};
+
+const CERTIFICATE_MEDIA_TYPES: &[&str] = &[
+    "application/pkix-cert",
+    "application/x-x509-ca-cert",
+    "application/x-x509-user-cert",
+    "application/x-pem-file",
+    "application/pem-certificate-chain",
+];
+
+const CERTIFICATE_EXTENSIONS: &[&str] = &[".pem", ".crt", ".cer", ".der"];
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OID: u8 = 0x06;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_VERSION: u8 = 0xa0;
+
+/// Summary of a X.509 certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateInfo {
+    /// Distinguished name of the subject such as `CN=example.com, O=Example`.
+    pub subject: String,
+    /// Distinguished name of the issuer.
+    pub issuer: String,
+    /// Hex-encoded serial number.
+    pub serial_number: String,
+    /// Start of the validity period.
+    pub not_before: DateTime<Utc>,
+    /// End of the validity period.
+    pub not_after: DateTime<Utc>,
+}
+
+impl CertificateInfo {
+    /// Returns whether the certificate is valid at the given time.
+    ///
+    /// Only the validity period is checked.
+    pub fn is_valid_at(&self, time: DateTime<Utc>) -> bool {
+        self.not_before <= time && time <= self.not_after
+    }
+}
+
+/// Returns whether the record appears to contain certificates.
+///
+/// The record is recognized by the Content-Type or by the filename extension
+/// of the WARC-Target-URI.
+pub fn is_certificate_record(header: &WarcHeader) -> bool {
+    if !matches!(
+        header.warc_type(),
+        Some(WarcType::Resource | WarcType::Metadata)
+    ) {
+        return false;
+    }
+
+    if let Some(Ok(media_type)) = header.fields.get_media_type("Content-Type") {
+        let media_type = format!("{}/{}", media_type.type_, media_type.subtype);
+
+        if CERTIFICATE_MEDIA_TYPES
+            .iter()
+            .any(|item| item.eq_ignore_ascii_case(&media_type))
+        {
+            return true;
+        }
+    }
+
+    let url = header
+        .fields
+        .get_url_str("WARC-Target-URI")
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    CERTIFICATE_EXTENSIONS
+        .iter()
+        .any(|extension| url.ends_with(extension))
+}
+
+/// Parses the certificates in PEM or DER encoding.
+///
+/// PEM data may contain several certificates. Other PEM blocks, such as
+/// keys, are ignored.
+pub fn parse_certificates(data: &[u8]) -> Result<Vec<CertificateInfo>, ParseError> {
+    if data.trim_ascii_start().starts_with(b"-----BEGIN") {
+        let text = std::str::from_utf8(data)?;
+        let mut certificates = Vec::new();
+
+        for block in text.split(PEM_BEGIN).skip(1) {
+            let Some((block, _)) = block.split_once(PEM_END) else {
+                return Err(ParseError::new(ParseErrorKind::IncompleteInput));
+            };
+            let encoded = block
+                .chars()
+                .filter(|c| !c.is_ascii_whitespace())
+                .collect::<String>();
+            let der = BASE64
+                .decode(encoded.as_bytes())
+                .map_err(|error| ParseError::new(ParseErrorKind::Syntax).with_source(error))?;
+
+            certificates.push(parse_der(&der)?);
+        }
+
+        Ok(certificates)
+    } else {
+        Ok(vec![parse_der(data)?])
+    }
+}
+
+/// Parses a DER encoded certificate.
+pub fn parse_der(data: &[u8]) -> Result<CertificateInfo, ParseError> {
+    let (certificate, _) = read_element(data, TAG_SEQUENCE)?;
+    let (mut tbs, _) = read_element(certificate, TAG_SEQUENCE)?;
+
+    if tbs.first() == Some(&TAG_VERSION) {
+        (_, tbs) = read_element(tbs, TAG_VERSION)?;
+    }
+
+    let (serial_number, tbs) = read_element(tbs, TAG_INTEGER)?;
+    let (_signature, tbs) = read_element(tbs, TAG_SEQUENCE)?;
+    let (issuer, tbs) = read_element(tbs, TAG_SEQUENCE)?;
+    let (validity, tbs) = read_element(tbs, TAG_SEQUENCE)?;
+    let (subject, _) = read_element(tbs, TAG_SEQUENCE)?;
+
+    let (not_before, validity) = read_time(validity)?;
+    let (not_after, _) = read_time(validity)?;
+
+    Ok(CertificateInfo {
+        subject: format_name(subject)?,
+        issuer: format_name(issuer)?,
+        serial_number: HEXLOWER.encode(serial_number),
+        not_before,
+        not_after,
+    })
+}
+
+/// Reads a DER element and returns its contents and the remaining data.
+fn read_any(data: &[u8]) -> Result<(u8, &[u8], &[u8]), ParseError> {
+    let incomplete = || ParseError::new(ParseErrorKind::IncompleteInput);
+
+    let (&tag, data) = data.split_first().ok_or_else(incomplete)?;
+    let (&first, mut data) = data.split_first().ok_or_else(incomplete)?;
+
+    let length = if first < 0x80 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+
+        if count == 0 || count > 4 || data.len() < count {
+            return Err(ParseError::new(ParseErrorKind::Syntax));
+        }
+
+        let length = data[0..count]
+            .iter()
+            .fold(0usize, |length, &byte| (length << 8) | byte as usize);
+        data = &data[count..];
+        length
+    };
+
+    if data.len() < length {
+        return Err(incomplete());
+    }
+
+    Ok((tag, &data[0..length], &data[length..]))
+}
+
+fn read_element(data: &[u8], expected_tag: u8) -> Result<(&[u8], &[u8]), ParseError> {
+    let (tag, contents, remain) = read_any(data)?;
+
+    if tag != expected_tag {
+        return Err(ParseError::new(ParseErrorKind::Syntax));
+    }
+
+    Ok((contents, remain))
+}
+
+fn read_time(data: &[u8]) -> Result<(DateTime<Utc>, &[u8]), ParseError> {
+    let (tag, contents, remain) = read_any(data)?;
+    let value = std::str::from_utf8(contents)?;
+
+    let value = match tag {
+        TAG_UTC_TIME if value.len() >= 2 => {
+            let year = value
+                .get(0..2)
+                .ok_or_else(|| ParseError::new(ParseErrorKind::Syntax))?
+                .parse::<u32>()
+                .map_err(|error| ParseError::new(ParseErrorKind::Syntax).with_source(error))?;
+            let century = if year >= 50 { "19" } else { "20" };
+            format!("{}{}", century, value)
+        }
+        TAG_GENERALIZED_TIME => value.to_string(),
+        _ => return Err(ParseError::new(ParseErrorKind::Syntax)),
+    };
+
+    let time = NaiveDateTime::parse_from_str(&value, "%Y%m%d%H%M%SZ")
+        .map_err(|error| ParseError::new(ParseErrorKind::Syntax).with_source(error))?;
+
+    Ok((time.and_utc(), remain))
+}
+
+/// Formats a distinguished name in the encoded order.
+fn format_name(mut data: &[u8]) -> Result<String, ParseError> {
+    let mut parts = Vec::new();
+
+    while !data.is_empty() {
+        let (mut set, remain) = read_element(data, TAG_SET)?;
+        data = remain;
+
+        while !set.is_empty() {
+            let (attribute, remain) = read_element(set, TAG_SEQUENCE)?;
+            set = remain;
+
+            let (oid, attribute) = read_element(attribute, TAG_OID)?;
+            let (tag, value, _) = read_any(attribute)?;
+
+            parts.push(format!(
+                "{}={}",
+                attribute_name(oid),
+                decode_string(tag, value)
+            ));
+        }
+    }
+
+    Ok(parts.join(", "))
+}
+
+fn attribute_name(oid: &[u8]) -> String {
+    match oid {
+        [0x55, 0x04, 0x03] => "CN".to_string(),
+        [0x55, 0x04, 0x06] => "C".to_string(),
+        [0x55, 0x04, 0x07] => "L".to_string(),
+        [0x55, 0x04, 0x08] => "ST".to_string(),
+        [0x55, 0x04, 0x0a] => "O".to_string(),
+        [0x55, 0x04, 0x0b] => "OU".to_string(),
+        [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01] => "emailAddress".to_string(),
+        _ => format_oid(oid),
+    }
+}
+
+fn format_oid(oid: &[u8]) -> String {
+    let mut components = Vec::new();
+    let mut value = 0u64;
+
+    for &byte in oid {
+        value = (value << 7) | (byte & 0x7f) as u64;
+
+        if byte & 0x80 == 0 {
+            if components.is_empty() {
+                let first = (value / 40).min(2);
+                components.push(first);
+                components.push(value - first * 40);
+            } else {
+                components.push(value);
+            }
+            value = 0;
+        }
+    }
+
+    components
+        .iter()
+        .map(|component| component.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn decode_string(tag: u8, value: &[u8]) -> String {
+    const TAG_BMP_STRING: u8 = 0x1e;
+
+    if tag == TAG_BMP_STRING {
+        let units = value
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+        char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    } else {
+        String::from_utf8_lossy(value).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBtDCCAVugAwIBAgICEjQwCgYIKoZIzj0EAwIwOTELMAkGA1UEBhMCVVMxFDAS
+BgNVBAoMC0V4YW1wbGUgT3JnMRQwEgYDVQQDDAtleGFtcGxlLmNvbTAeFw0yNjEw
+MTYxMTAzMTJaFw0zNjEwMTMxMTAzMTJaMDkxCzAJBgNVBAYTAlVTMRQwEgYDVQQK
+DAtFeGFtcGxlIE9yZzEUMBIGA1UEAwwLZXhhbXBsZS5jb20wWTATBgcqhkjOPQIB
+BggqhkjOPQMBBwNCAARYr2LZDxR2Dv2SIq2zvKPKWaoFEFYe0JDbwUQST375j4t8
+j+eidTl2A2Sgn4FxYeIX7wdCva2r5OZh83Nb6gLBo1MwUTAdBgNVHQ4EFgQUSR4B
+/rzfI2XJPhldCRo01y/amgMwHwYDVR0jBBgwFoAUSR4B/rzfI2XJPhldCRo01y/a
+mgMwDwYDVR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNHADBEAiAJboXumcKWbEub
+mDQp6PX5sNb/P7tyyWGnGH+teAZcFgIgSRJMaAgmLqaoGLZ7+9u6dyAjcgX+cqwc
+ABNO/MMAA0A=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_parse_certificates() {
+        let certificates = parse_certificates(CERTIFICATE.repeat(2).as_bytes()).unwrap();
+
+        assert_eq!(certificates.len(), 2);
+
+        let certificate = &certificates[0];
+
+        assert_eq!(certificate.subject, "C=US, O=Example Org, CN=example.com");
+        assert_eq!(certificate.issuer, certificate.subject);
+        assert_eq!(certificate.serial_number, "1234");
+        assert_eq!(
+            certificate.not_before.to_rfc3339(),
+            "2026-10-16T11:03:12+00:00"
+        );
+        assert_eq!(
+            certificate.not_after.to_rfc3339(),
+            "2036-10-13T11:03:12+00:00"
+        );
+        assert!(certificate.is_valid_at(certificate.not_before));
+        assert!(!certificate.is_valid_at(DateTime::UNIX_EPOCH));
+
+        let encoded = CERTIFICATE
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect::<String>();
+        let der = BASE64.decode(encoded.as_bytes()).unwrap();
+
+        assert_eq!(parse_der(&der).unwrap(), certificates[0]);
+        assert!(parse_der(&der[0..100]).is_err());
+        assert!(parse_certificates(b"-----BEGIN CERTIFICATE-----\nMIIB").is_err());
+    }
+
+    #[test]
+    fn test_read_time() {
+        let (time, remain) = read_time(b"\x17\x0d990102030405Z!").unwrap();
+
+        assert_eq!(time.to_rfc3339(), "1999-01-02T03:04:05+00:00");
+        assert_eq!(remain, b"!");
+
+        assert!(read_time("\x17\x0e€0101000000Z".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_format_oid() {
+        assert_eq!(
+            format_oid(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d]),
+            "1.2.840.113549"
+        );
+        assert_eq!(attribute_name(&[0x55, 0x04, 0x03]), "CN");
+    }
+
+    #[test]
+    fn test_is_certificate_record() {
+        let mut header = WarcHeader::new(0, "resource");
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "http://example.com/".to_string(),
+        );
+
+        assert!(!is_certificate_record(&header));
+
+        header.fields.insert(
+            "Content-Type".to_string(),
+            "application/x-pem-file".to_string(),
+        );
+
+        assert!(is_certificate_record(&header));
+
+        header.fields.remove("Content-Type");
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "urn:example:cert.DER".to_string(),
+        );
+
+        assert!(is_certificate_record(&header));
+    }
+}
@@ -12,9 +12,21 @@ use std::{
 
 use decode::{Decoder, PushDecoder};
 use encode::Encoder;
+#[cfg(feature = "parallel-decode")]
+pub use parallel_decode::{DecompressedSegment, ParallelGzipDecoder};
 
+use crate::error::{ProtocolError, ProtocolErrorKind};
+
+#[cfg(feature = "bzip2")]
+mod bzip2;
 mod decode;
 mod encode;
+mod gzip;
+mod parallel;
+#[cfg(feature = "parallel-decode")]
+mod parallel_decode;
+#[cfg(feature = "codec-registry")]
+pub mod registry;
 pub mod zstd;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -26,6 +38,11 @@ pub enum Level {
     High,
     /// Faster compression speed at expense of worse compression ratio.
     Low,
+    /// Level specific to the codec, such as 0 to 9 for gzip or 1 to 22 for
+    /// Zstandard.
+    ///
+    /// The value is clamped to the range supported by the codec.
+    Precise(i32),
 }
 
 impl Default for Level {
@@ -56,18 +73,45 @@ pub enum Format {
     /// Supports concatenation.
     #[cfg(feature = "zstd")]
     Zstandard,
+
+    /// Codec added to the [`registry`].
+    #[cfg(feature = "codec-registry")]
+    Custom(registry::CodecId),
 }
 
 impl Format {
+    /// Bzip2 file format and codec.
+    ///
+    /// Supports concatenation. The codec is provided through the
+    /// [`registry`].
+    #[cfg(feature = "bzip2")]
+    pub const BZIP2: Self = Self::Custom(registry::BZIP2_ID);
+
     /// Returns whether the codec supports concatenated members.
     pub fn supports_concatenation(&self) -> bool {
         match self {
             Self::Gzip => true,
             #[cfg(feature = "zstd")]
             Self::Zstandard => true,
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(id) => id.codec().supports_concatenation(),
             _ => false,
         }
     }
+
+    /// Returns the bytes at the start of each member or frame.
+    ///
+    /// The value can be used to find the next segment in corrupt data.
+    pub fn segment_magic(&self) -> Option<&'static [u8]> {
+        match self {
+            Self::Gzip => Some(&[0x1f, 0x8b, 0x08]),
+            #[cfg(feature = "zstd")]
+            Self::Zstandard => Some(&[0x28, 0xb5, 0x2f, 0xfd]),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(id) => id.codec().segment_magic(),
+            _ => None,
+        }
+    }
 }
 
 impl Default for Format {
@@ -87,6 +131,9 @@ impl FromStr for Format {
             "br" | "brotli" => Ok(Self::Brotli),
             #[cfg(feature = "zstd")]
             "zstd" | "zstandard" | "zst" => Ok(Self::Zstandard),
+            #[cfg(feature = "codec-registry")]
+            _ => registry::find(s).ok_or(FormatParseError),
+            #[cfg(not(feature = "codec-registry"))]
             _ => Err(FormatParseError),
         }
     }
@@ -101,6 +148,8 @@ impl Display for Format {
             Self::Brotli => write!(f, "br"),
             #[cfg(feature = "zstd")]
             Self::Zstandard => write!(f, "zstd"),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(id) => write!(f, "{}", id.codec().name()),
         }
     }
 }
@@ -121,6 +170,33 @@ pub struct CompressorConfig {
     pub format: Format,
     pub level: Level,
     pub dictionary: Dictionary,
+    /// Write a seek table of the Zstandard seekable format at the end of
+    /// the stream.
+    ///
+    /// The table lists the sizes of the frames, so a frame can be found by
+    /// its position in the decompressed data. It is read with
+    /// [`zstd::SeekTable`]. If a frame is too large for the 32-bit lengths
+    /// of the table, the table is omitted and a warning is logged. Other
+    /// formats are not affected.
+    pub seek_table: bool,
+    /// Number of threads for compressing segments.
+    ///
+    /// If greater than 1, the data of each segment is buffered and the
+    /// segments are compressed in parallel and written in order. Only gzip
+    /// is affected.
+    pub threads: usize,
+    /// Advanced parameters for Zstandard, such as a larger window.
+    ///
+    /// The default is compatible with the decompressors of replay tools.
+    pub zstd_parameters: zstd::ZstdParameters,
+    /// Write an `sl` extra field in the header of each gzip member.
+    ///
+    /// The field contains the compressed length of the member and the
+    /// length of its decompressed data as 32-bit little-endian integers, so
+    /// indexing tools can skip members without decompressing them. Each
+    /// member is compressed in memory before it is written. Other formats
+    /// are not affected.
+    pub gzip_member_length: bool,
 }
 
 /// Encoder for compressing streams.
@@ -142,7 +218,25 @@ impl<W: Write> Compressor<W> {
 
     /// [Create](Self::new()) a compressor with the given configuration.
     pub fn with_config(dest: W, config: CompressorConfig) -> Self {
-        let encoder = Encoder::new(dest, config.format, config.level, &config.dictionary);
+        let encoder = if config.format == Format::Gzip && config.threads > 1 {
+            Encoder::new_parallel_gzip(
+                dest,
+                config.level,
+                config.threads,
+                config.gzip_member_length,
+            )
+        } else if config.format == Format::Gzip && config.gzip_member_length {
+            Encoder::new_member_length_gzip(dest, config.level)
+        } else {
+            Encoder::new(
+                dest,
+                config.format,
+                config.level,
+                &config.dictionary,
+                &config.zstd_parameters,
+                config.seek_table,
+            )
+        };
 
         Self { encoder, config }
     }
@@ -167,8 +261,20 @@ impl<W: Write> Compressor<W> {
     /// This function has effect for only codecs that support concatenation.
     /// If configured with a dictionary, it will be reused.
     pub fn start_new_segment(&mut self) -> std::io::Result<()> {
+        match &mut self.encoder {
+            Encoder::ParallelGzip(encoder) => return encoder.start_new_member(),
+            Encoder::MemberLengthGzip(encoder) => return encoder.start_new_member(),
+            _ => {}
+        }
+
         match self.config.format {
-            Format::Gzip => {
+            #[cfg(feature = "zstd")]
+            Format::Zstandard => {
+                if let Encoder::Zstandard(encoder) = &mut self.encoder {
+                    encoder.start_new_frame()?;
+                }
+            }
+            format if format.supports_concatenation() => {
                 let encoder = std::mem::replace(&mut self.encoder, Encoder::None);
                 let dest = encoder.finish()?;
                 self.encoder = Encoder::new(
@@ -176,23 +282,43 @@ impl<W: Write> Compressor<W> {
                     self.config.format,
                     self.config.level,
                     &self.config.dictionary,
+                    &self.config.zstd_parameters,
+                    self.config.seek_table,
                 );
             }
-            #[cfg(feature = "zstd")]
-            Format::Zstandard => {
-                if let Encoder::Zstandard(encoder) = &mut self.encoder {
-                    encoder.start_new_frame()?;
-                }
-            }
             _ => {}
         }
 
         Ok(())
     }
+
+    /// Writes data that is already compressed as a complete segment.
+    ///
+    /// The data must be a member or frame of the same format that is not
+    /// compressed with a dictionary. This function must be called at the
+    /// start of a segment, such as after
+    /// [`start_new_segment()`](Self::start_new_segment). The decompressed
+    /// length is used for the Zstandard seek table.
+    pub fn write_raw_segment(&mut self, data: &[u8], decompressed_len: u64) -> std::io::Result<()> {
+        if !self.config.format.supports_concatenation() {
+            return Err(std::io::Error::other(ProtocolError::new(
+                ProtocolErrorKind::UnsupportedCompressionFormat,
+            )));
+        }
+
+        match &mut self.encoder {
+            Encoder::ParallelGzip(encoder) => encoder.write_raw_member(data),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstandard(encoder) => encoder.write_raw_frame(data, decompressed_len),
+            encoder => encoder.get_mut().write_all(data),
+        }
+    }
 }
 
 impl<W: Write> Write for Compressor<W> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let _span = tracing::trace_span!("compress", input_len = buf.len()).entered();
+
         self.encoder.write(buf)
     }
 
@@ -201,17 +327,69 @@ impl<W: Write> Write for Compressor<W> {
     }
 }
 
+/// Number of decompressed bytes of a segment before
+/// [`DecompressorConfig::max_expansion_ratio`] is checked.
+///
+/// Small segments, such as records of repetitive text, can have high ratios
+/// without being a concern.
+pub const MIN_EXPANSION_CHECK_LENGTH: u64 = 1024 * 1024;
+
 #[derive(Debug, Clone, Default)]
 pub struct DecompressorConfig {
     pub format: Format,
     pub dictionary: Dictionary,
+    /// Maximum number of decompressed bytes of a segment.
+    ///
+    /// For files with record-at-time compression, a segment is a record.
+    /// Files without record-at-time compression are a single segment, so
+    /// the WARC [`Decoder`](crate::warc::Decoder) restarts counting at each
+    /// record instead. Exceeding the limit causes an error containing a
+    /// [`ProtocolError`] with
+    /// [`ProtocolErrorKind::DecompressionLimitExceeded`].
+    ///
+    /// The limits do not apply to [`Format::Identity`].
+    pub max_segment_length: Option<u64>,
+    /// Maximum ratio of decompressed bytes to compressed bytes of a segment.
+    ///
+    /// The ratio is checked after [`MIN_EXPANSION_CHECK_LENGTH`] bytes of the
+    /// segment are decompressed. Exceeding the limit causes the same error
+    /// as [`max_segment_length`](Self::max_segment_length).
+    pub max_expansion_ratio: Option<u64>,
+}
+
+impl DecompressorConfig {
+    fn check_limits(&self, input_len: u64, output_len: u64) -> std::io::Result<()> {
+        if self.format == Format::Identity {
+            return Ok(());
+        }
+
+        let is_too_long = self
+            .max_segment_length
+            .is_some_and(|max_length| output_len > max_length);
+        let is_too_expanded = self.max_expansion_ratio.is_some_and(|max_ratio| {
+            output_len > MIN_EXPANSION_CHECK_LENGTH
+                && output_len > input_len.saturating_mul(max_ratio)
+        });
+
+        if is_too_long || is_too_expanded {
+            tracing::debug!(input_len, output_len, "decompression limit exceeded");
+
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                ProtocolError::new(ProtocolErrorKind::DecompressionLimitExceeded),
+            ))
+        } else {
+            Ok(())
+        }
+    }
 }
 
 /// Decoder for decompressing streams.
 #[derive(Debug)]
 pub struct Decompressor<R: BufRead> {
-    decoder: Decoder<R>,
+    decoder: Decoder<LengthReader<R>>,
     config: DecompressorConfig,
+    output_len: u64,
 }
 
 impl<R: BufRead> Decompressor<R> {
@@ -227,24 +405,25 @@ impl<R: BufRead> Decompressor<R> {
     /// [Create](Self::new()) a decompressor with a configuration.
     pub fn with_config(source: R, config: DecompressorConfig) -> std::io::Result<Self> {
         Ok(Self {
-            decoder: Decoder::new(source, config.format, &config.dictionary)?,
+            decoder: Decoder::new(LengthReader::new(source), config.format, &config.dictionary)?,
             config,
+            output_len: 0,
         })
     }
 
     /// Return a reference of the underlying reader.
     pub fn get_ref(&self) -> &R {
-        self.decoder.get_ref()
+        &self.decoder.get_ref().inner
     }
 
     /// Return a mutable reference of the underlying reader.
     pub fn get_mut(&mut self) -> &mut R {
-        self.decoder.get_mut()
+        &mut self.decoder.get_mut().inner
     }
 
     /// Return the underlying reader.
     pub fn into_inner(self) -> R {
-        self.decoder.into_inner()
+        self.decoder.into_inner().inner
     }
 
     /// Prepares the codec for reading a new stream.
@@ -254,18 +433,21 @@ impl<R: BufRead> Decompressor<R> {
     /// be called at the end of decompressing a segment. The end of a segment
     /// is indicated by 0 bytes returned when reading from this struct.
     pub fn start_next_segment(&mut self) -> std::io::Result<()> {
+        self.decoder.get_mut().length = 0;
+        self.output_len = 0;
+
         match self.config.format {
-            Format::Gzip => {
-                let decoder = std::mem::replace(&mut self.decoder, Decoder::None);
-                let source = decoder.into_inner();
-                self.decoder = Decoder::new(source, self.config.format, &self.config.dictionary)?;
-            }
             #[cfg(feature = "zstd")]
             Format::Zstandard => {
                 if let Decoder::Zstandard(decoder) = &mut self.decoder {
                     decoder.start_next_frame()?;
                 }
             }
+            format if format.supports_concatenation() => {
+                let decoder = std::mem::replace(&mut self.decoder, Decoder::None);
+                let source = decoder.into_inner();
+                self.decoder = Decoder::new(source, self.config.format, &self.config.dictionary)?;
+            }
             _ => {}
         }
 
@@ -285,7 +467,24 @@ impl<R: BufRead> Decompressor<R> {
 
 impl<R: BufRead> Read for Decompressor<R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.decoder.read(buf)
+        let span = tracing::trace_span!(
+            "decompress",
+            input_len = tracing::field::Empty,
+            output_len = tracing::field::Empty
+        )
+        .entered();
+        let input_len = self.decoder.get_ref().length;
+
+        let read_len = self.decoder.read(buf)?;
+        self.output_len += read_len as u64;
+
+        span.record("input_len", self.decoder.get_ref().length - input_len);
+        span.record("output_len", read_len);
+
+        self.config
+            .check_limits(self.decoder.get_ref().length, self.output_len)?;
+
+        Ok(read_len)
     }
 }
 
@@ -295,8 +494,9 @@ impl<R: BufRead> Read for Decompressor<R> {
 /// moving data.
 #[derive(Debug)]
 pub struct PushDecompressor<W: Write> {
-    decoder: PushDecoder<W>,
+    decoder: PushDecoder<LengthWriter<W>>,
     config: DecompressorConfig,
+    input_len: u64,
 }
 
 impl<W: Write> PushDecompressor<W> {
@@ -312,24 +512,29 @@ impl<W: Write> PushDecompressor<W> {
     /// [Create](Self::new()) a decompressor with the given configuration.
     pub fn with_config(output: W, config: DecompressorConfig) -> std::io::Result<Self> {
         Ok(Self {
-            decoder: PushDecoder::new(output, config.format, &config.dictionary)?,
+            decoder: PushDecoder::new(
+                LengthWriter::new(output),
+                config.format,
+                &config.dictionary,
+            )?,
             config,
+            input_len: 0,
         })
     }
 
     /// Return a reference of the underlying writer.
     pub fn get_ref(&self) -> &W {
-        self.decoder.get_ref()
+        &self.decoder.get_ref().inner
     }
 
     /// Return a mutable reference of the underlying writer.
     pub fn get_mut(&mut self) -> &mut W {
-        self.decoder.get_mut()
+        &mut self.decoder.get_mut().inner
     }
 
     /// Return the underlying writer.
     pub fn into_inner(self) -> std::io::Result<W> {
-        self.decoder.into_inner()
+        Ok(self.decoder.into_inner()?.inner)
     }
 
     /// Prepares the codec for reading a new stream.
@@ -339,18 +544,71 @@ impl<W: Write> PushDecompressor<W> {
     /// be called at the end of decompressing a segment. The end of a segment
     /// is indicated by 0 bytes written when writing to this struct.
     pub fn start_next_segment(&mut self) -> std::io::Result<()> {
+        self.decoder.get_mut().length = 0;
+        self.input_len = 0;
+
         match self.config.format {
-            Format::Gzip => {
+            #[cfg(feature = "zstd")]
+            Format::Zstandard => {
+                if let PushDecoder::Zstandard(decoder) = &mut self.decoder {
+                    decoder.start_next_frame()?;
+                }
+            }
+            format if format.supports_concatenation() => {
                 let decoder = std::mem::replace(&mut self.decoder, PushDecoder::None);
                 let dest = decoder.into_inner()?;
                 self.decoder = PushDecoder::new(dest, self.config.format, &self.config.dictionary)?;
             }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> PushDecompressor<W> {
+    /// Restarts counting the lengths checked against the limits without
+    /// starting a new segment.
+    ///
+    /// This makes the limits apply to parts of a segment, such as records in
+    /// an uncompressed file. `output_len` is the length of the decompressed
+    /// data already written that belongs to the next part.
+    pub fn reset_limits(&mut self, output_len: u64) {
+        self.decoder.get_mut().length = output_len;
+        self.input_len = 0;
+    }
+}
+
+impl<W: Write + Default> PushDecompressor<W> {
+    /// Discards the current segment and prepares the codec for a new stream.
+    ///
+    /// Unlike [`start_next_segment()`](Self::start_next_segment), this
+    /// function may be called after the codec returned an error. Any data
+    /// held by the codec is lost.
+    pub fn discard_segment(&mut self) -> std::io::Result<()> {
+        self.input_len = 0;
+
+        match self.config.format {
             #[cfg(feature = "zstd")]
             Format::Zstandard => {
+                self.decoder.get_mut().length = 0;
+
                 if let PushDecoder::Zstandard(decoder) = &mut self.decoder {
                     decoder.start_next_frame()?;
                 }
             }
+            format if format.supports_concatenation() => {
+                // The codec is missing if finishing the segment failed.
+                let output = match self.decoder {
+                    PushDecoder::None => W::default(),
+                    _ => std::mem::take(self.get_mut()),
+                };
+                self.decoder = PushDecoder::new(
+                    LengthWriter::new(output),
+                    self.config.format,
+                    &self.config.dictionary,
+                )?;
+            }
             _ => {}
         }
 
@@ -360,7 +618,24 @@ impl<W: Write> PushDecompressor<W> {
 
 impl<W: Write> Write for PushDecompressor<W> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.decoder.write(buf)
+        let span = tracing::trace_span!(
+            "decompress",
+            input_len = tracing::field::Empty,
+            output_len = tracing::field::Empty
+        )
+        .entered();
+        let output_len = self.decoder.get_ref().length;
+
+        let write_len = self.decoder.write(buf)?;
+        self.input_len += write_len as u64;
+
+        span.record("input_len", write_len);
+        span.record("output_len", self.decoder.get_ref().length - output_len);
+
+        self.config
+            .check_limits(self.input_len, self.decoder.get_ref().length)?;
+
+        Ok(write_len)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
@@ -368,6 +643,63 @@ impl<W: Write> Write for PushDecompressor<W> {
     }
 }
 
+/// Reader that counts the bytes read from the source.
+#[derive(Debug)]
+struct LengthReader<R> {
+    inner: R,
+    length: u64,
+}
+
+impl<R> LengthReader<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, length: 0 }
+    }
+}
+
+impl<R: Read> Read for LengthReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read_len = self.inner.read(buf)?;
+        self.length += read_len as u64;
+        Ok(read_len)
+    }
+}
+
+impl<R: BufRead> BufRead for LengthReader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.length += amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+/// Writer that counts the bytes written to the destination.
+#[derive(Debug)]
+struct LengthWriter<W> {
+    inner: W,
+    length: u64,
+}
+
+impl<W> LengthWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, length: 0 }
+    }
+}
+
+impl<W: Write> Write for LengthWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let write_len = self.inner.write(buf)?;
+        self.length += write_len as u64;
+        Ok(write_len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 pub enum Dictionary {
@@ -432,6 +764,8 @@ impl Default for Dictionary {
 mod tests {
     use std::io::{BufReader, Cursor};
 
+    use crate::error::GeneralError;
+
     use super::*;
 
     #[test]
@@ -473,6 +807,70 @@ mod tests {
         assert_eq!(&buf, b"Hello world");
     }
 
+    #[test]
+    fn test_compress_precise_level() {
+        for (format, value) in [
+            (Format::Gzip, 3),
+            (Format::Gzip, 100),
+            (Format::Brotli, -1),
+            #[cfg(feature = "zstd")]
+            (Format::Zstandard, 19),
+            #[cfg(feature = "zstd")]
+            (Format::Zstandard, 1000),
+        ] {
+            let mut c = Compressor::with_config(
+                Vec::new(),
+                CompressorConfig {
+                    format,
+                    level: Level::Precise(value),
+                    ..Default::default()
+                },
+            );
+
+            c.write_all(b"Hello world").unwrap();
+
+            let buf = c.finish().unwrap();
+            let mut d = Decompressor::new(BufReader::new(Cursor::new(buf)), format).unwrap();
+
+            let mut buf = Vec::new();
+            d.read_to_end(&mut buf).unwrap();
+
+            assert_eq!(&buf, b"Hello world");
+        }
+    }
+
+    #[cfg(feature = "bzip2")]
+    #[test]
+    fn test_compress_decompress_bzip2() {
+        let mut c = Compressor::new(Vec::new(), Format::BZIP2);
+
+        c.write_all(b"Hello").unwrap();
+        c.start_new_segment().unwrap();
+        c.write_all(b"world").unwrap();
+
+        let buf = c.finish().unwrap();
+
+        let mut d =
+            Decompressor::new(BufReader::new(Cursor::new(buf.clone())), Format::BZIP2).unwrap();
+
+        let mut output = Vec::new();
+
+        d.read_to_end(&mut output).unwrap();
+        assert_eq!(&output, b"Hello");
+
+        output.clear();
+        d.start_next_segment().unwrap();
+        d.read_to_end(&mut output).unwrap();
+        assert_eq!(&output, b"world");
+        assert!(!d.has_data_left().unwrap());
+
+        let mut d = PushDecompressor::new(Vec::new(), Format::BZIP2).unwrap();
+        let write_len = d.write(&buf).unwrap();
+        assert!(write_len < buf.len());
+        assert_eq!(d.get_ref(), b"Hello");
+        assert_eq!(d.write(&buf[write_len..]).unwrap(), 0);
+    }
+
     #[test]
     fn test_compress_decompress_multistream() {
         let buf = Vec::new();
@@ -500,4 +898,293 @@ mod tests {
 
         d.into_inner();
     }
+
+    #[test]
+    fn test_decompress_limits() {
+        let mut c = Compressor::new(Vec::new(), Format::Gzip);
+        c.write_all(&vec![0u8; 4 * 1024 * 1024]).unwrap();
+        let buf = c.finish().unwrap();
+
+        let decompress = |config: DecompressorConfig| {
+            let mut d = Decompressor::with_config(buf.as_slice(), config.clone()).unwrap();
+            let result = d.read_to_end(&mut Vec::new()).map(|_| ());
+
+            let mut d = PushDecompressor::with_config(std::io::sink(), config).unwrap();
+            let push_result = d.write_all(&buf).and_then(|_| d.flush());
+
+            assert_eq!(result.is_ok(), push_result.is_ok());
+            result
+        };
+        let config = DecompressorConfig {
+            format: Format::Gzip,
+            ..Default::default()
+        };
+
+        assert!(decompress(config.clone()).is_ok());
+        assert!(decompress(DecompressorConfig {
+            max_segment_length: Some(4 * 1024 * 1024),
+            max_expansion_ratio: Some(2000),
+            ..config.clone()
+        })
+        .is_ok());
+
+        let error = decompress(DecompressorConfig {
+            max_expansion_ratio: Some(100),
+            ..config.clone()
+        })
+        .unwrap_err();
+
+        assert!(matches!(
+            GeneralError::from(error),
+            GeneralError::Protocol(error)
+                if matches!(error.kind(), ProtocolErrorKind::DecompressionLimitExceeded)
+        ));
+        assert!(decompress(DecompressorConfig {
+            max_segment_length: Some(1024),
+            ..config
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_compress_parallel_gzip() {
+        let mut segments = (0..50)
+            .map(|index| format!("segment {}", index).repeat(index * 100))
+            .collect::<Vec<_>>();
+        segments.insert(10, "a".repeat(17 * 1024 * 1024));
+
+        let mut c = Compressor::with_config(
+            Vec::new(),
+            CompressorConfig {
+                format: Format::Gzip,
+                threads: 4,
+                ..Default::default()
+            },
+        );
+
+        for (index, segment) in segments.iter().enumerate() {
+            if index > 0 {
+                c.start_new_segment().unwrap();
+            }
+
+            c.write_all(segment.as_bytes()).unwrap();
+        }
+
+        let buf = c.finish().unwrap();
+
+        let mut d = Decompressor::new(BufReader::new(Cursor::new(buf)), Format::Gzip).unwrap();
+
+        for (index, segment) in segments.iter().enumerate() {
+            if index > 0 {
+                d.start_next_segment().unwrap();
+            }
+
+            let mut buf = Vec::new();
+            d.read_to_end(&mut buf).unwrap();
+            assert_eq!(buf, segment.as_bytes());
+        }
+
+        assert!(!d.has_data_left().unwrap());
+    }
+
+    #[cfg(feature = "parallel-decode")]
+    #[test]
+    fn test_parallel_gzip_decoder() {
+        // Stored blocks contain the data unchanged, so the compressed data
+        // contains the start of a gzip header.
+        let segments = (0..50)
+            .map(|index| {
+                let mut segment = format!("segment {}", index)
+                    .repeat(index * 100)
+                    .into_bytes();
+                segment.extend_from_slice(&[0x1f, 0x8b, 0x08, 0x00]);
+                segment
+            })
+            .collect::<Vec<_>>();
+
+        for gzip_member_length in [false, true] {
+            let mut c = Compressor::with_config(
+                Vec::new(),
+                CompressorConfig {
+                    format: Format::Gzip,
+                    level: Level::Precise(0),
+                    gzip_member_length,
+                    ..Default::default()
+                },
+            );
+            let mut positions = vec![0];
+
+            for (index, segment) in segments.iter().enumerate() {
+                if index > 0 {
+                    c.start_new_segment().unwrap();
+                    positions.push(c.get_ref().len() as u64);
+                }
+
+                c.write_all(segment).unwrap();
+            }
+
+            let buf = c.finish().unwrap();
+            let mut d = ParallelGzipDecoder::new(buf.as_slice(), DecompressorConfig::default(), 4);
+
+            for (segment, position) in segments.iter().zip(&positions) {
+                let decompressed = d.next_segment().unwrap().unwrap();
+                assert_eq!(&decompressed.data, segment);
+                assert_eq!(decompressed.position, *position);
+            }
+
+            assert!(d.next_segment().unwrap().is_none());
+
+            let mut d =
+                ParallelGzipDecoder::new(&buf[..buf.len() - 1], DecompressorConfig::default(), 4);
+            let result =
+                std::iter::from_fn(|| d.next_segment().transpose()).collect::<Result<Vec<_>, _>>();
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn test_compress_gzip_member_length() {
+        let mut segments = (0..20)
+            .map(|index| format!("segment {}", index).repeat(index * 100))
+            .collect::<Vec<_>>();
+        segments.insert(10, "a".repeat(17 * 1024 * 1024));
+
+        for threads in [1, 4] {
+            let mut c = Compressor::with_config(
+                Vec::new(),
+                CompressorConfig {
+                    format: Format::Gzip,
+                    threads,
+                    gzip_member_length: true,
+                    ..Default::default()
+                },
+            );
+
+            for (index, segment) in segments.iter().enumerate() {
+                if index > 0 {
+                    c.start_new_segment().unwrap();
+                }
+
+                c.write_all(segment.as_bytes()).unwrap();
+            }
+
+            let buf = c.finish().unwrap();
+            let mut position = 0;
+
+            for segment in &segments {
+                let member = &buf[position..];
+                assert_eq!(&member[12..14], b"sl");
+
+                let compressed_len = u32::from_le_bytes(member[16..20].try_into().unwrap());
+                let decompressed_len = u32::from_le_bytes(member[20..24].try_into().unwrap());
+                let member = &member[0..compressed_len as usize];
+
+                let mut data = Vec::new();
+                flate2::read::GzDecoder::new(member)
+                    .read_to_end(&mut data)
+                    .unwrap();
+
+                assert_eq!(data, segment.as_bytes());
+                assert_eq!(decompressed_len as usize, segment.len());
+
+                position += compressed_len as usize;
+            }
+
+            assert_eq!(position, buf.len());
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_compress_warc_zstd_dictionary() {
+        let samples = (0..1000)
+            .map(|index| {
+                format!(
+                    "WARC/1.1\r\nWARC-Type: resource\r\nWARC-Record-ID: <urn:example:{}>\r\n\
+                    Content-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}\r\n\r\n",
+                    index,
+                    index % 97,
+                    "a".repeat(index % 97)
+                )
+            })
+            .collect::<Vec<_>>();
+        let dictionary = zstd::train_dictionary(&samples, 4096).unwrap();
+        assert!(!dictionary.is_empty() && dictionary.len() <= 4096);
+
+        let mut c = Compressor::with_config(
+            Vec::new(),
+            CompressorConfig {
+                format: Format::Zstandard,
+                dictionary: Dictionary::WarcZstd(dictionary.clone()),
+                ..Default::default()
+            },
+        );
+
+        c.write_all(samples[0].as_bytes()).unwrap();
+        c.start_new_segment().unwrap();
+        c.write_all(samples[1].as_bytes()).unwrap();
+
+        let buf = c.finish().unwrap();
+
+        assert_eq!(
+            zstd::extract_warc_zst_dictionary(buf.as_slice()).unwrap(),
+            dictionary
+        );
+
+        let mut d = Decompressor::with_config(
+            BufReader::new(Cursor::new(buf)),
+            DecompressorConfig {
+                format: Format::Zstandard,
+                dictionary: Dictionary::WarcZstd(Vec::new()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let mut buf = Vec::new();
+
+        d.read_to_end(&mut buf).unwrap();
+        d.start_next_segment().unwrap();
+        d.read_to_end(&mut buf).unwrap();
+
+        assert_eq!(buf, format!("{}{}", samples[0], samples[1]).as_bytes());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_compress_zstd_parameters() {
+        let data = b"hello world!".repeat(1000);
+        let zstd_parameters = zstd::ZstdParameters {
+            window_log: Some(30),
+            long_distance_matching: true,
+            chain_log: Some(24),
+        };
+
+        let mut c = Compressor::with_config(
+            Vec::new(),
+            CompressorConfig {
+                format: Format::Zstandard,
+                zstd_parameters,
+                ..Default::default()
+            },
+        );
+
+        c.write_all(&data).unwrap();
+        c.start_new_segment().unwrap();
+        c.write_all(&data).unwrap();
+
+        let buf = c.finish().unwrap();
+
+        // The window is larger than the default limit of decompressors.
+        assert!(::zstd::stream::decode_all(buf.as_slice()).is_err());
+
+        let mut d = Decompressor::new(BufReader::new(Cursor::new(buf)), Format::Zstandard).unwrap();
+        let mut buf = Vec::new();
+
+        d.read_to_end(&mut buf).unwrap();
+        d.start_next_segment().unwrap();
+        d.read_to_end(&mut buf).unwrap();
+
+        assert_eq!(buf, data.repeat(2));
+    }
 }
@@ -0,0 +1,134 @@
+//! Bzip2 codec provided through the codec registry.
+use bzip2::{Action, Compress, Compression, Decompress, Status};
+
+use super::{
+    registry::{Codec, CodecDecoder, CodecEncoder},
+    Level,
+};
+
+/// Number of bytes reserved in the output buffer for each call to the codec.
+const BUFFER_SIZE: usize = 16384;
+
+pub(super) struct Bzip2Codec;
+
+impl Codec for Bzip2Codec {
+    fn name(&self) -> &str {
+        "bzip2"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["x-bzip2", "bz2"]
+    }
+
+    fn supports_concatenation(&self) -> bool {
+        true
+    }
+
+    fn segment_magic(&self) -> Option<&'static [u8]> {
+        Some(b"BZh")
+    }
+
+    fn file_extension(&self) -> &str {
+        ".bz2"
+    }
+
+    fn new_encoder(&self, level: Level) -> Box<dyn CodecEncoder> {
+        let level = match level {
+            Level::Balanced => 6,
+            Level::High => 9,
+            Level::Low => 1,
+            Level::Precise(value) => value.clamp(1, 9),
+        };
+
+        Box::new(Bzip2Encoder {
+            compress: Compress::new(Compression::new(level as u32), 30),
+        })
+    }
+
+    fn new_decoder(&self) -> std::io::Result<Box<dyn CodecDecoder>> {
+        Ok(Box::new(Bzip2Decoder {
+            decompress: Decompress::new(false),
+            is_done: false,
+        }))
+    }
+}
+
+struct Bzip2Encoder {
+    compress: Compress,
+}
+
+impl Bzip2Encoder {
+    /// Runs the action until the codec returns the status.
+    fn run_until(
+        &mut self,
+        action: Action,
+        status: Status,
+        output: &mut Vec<u8>,
+    ) -> std::io::Result<()> {
+        loop {
+            output.reserve(BUFFER_SIZE);
+
+            if self.compress.compress_vec(&[], output, action)? == status {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl CodecEncoder for Bzip2Encoder {
+    fn encode(&mut self, data: &[u8], output: &mut Vec<u8>) -> std::io::Result<()> {
+        let start_in = self.compress.total_in();
+
+        loop {
+            let consumed_len = (self.compress.total_in() - start_in) as usize;
+
+            if consumed_len == data.len() {
+                return Ok(());
+            }
+
+            output.reserve(BUFFER_SIZE);
+            self.compress
+                .compress_vec(&data[consumed_len..], output, Action::Run)?;
+        }
+    }
+
+    fn flush(&mut self, output: &mut Vec<u8>) -> std::io::Result<()> {
+        self.run_until(Action::Flush, Status::RunOk, output)
+    }
+
+    fn finish(&mut self, output: &mut Vec<u8>) -> std::io::Result<()> {
+        self.run_until(Action::Finish, Status::StreamEnd, output)
+    }
+}
+
+struct Bzip2Decoder {
+    decompress: Decompress,
+    is_done: bool,
+}
+
+impl CodecDecoder for Bzip2Decoder {
+    fn decode(&mut self, data: &[u8], output: &mut Vec<u8>) -> std::io::Result<usize> {
+        let start_in = self.decompress.total_in();
+
+        while !self.is_done {
+            let consumed_len = (self.decompress.total_in() - start_in) as usize;
+
+            output.reserve(BUFFER_SIZE);
+            let status = self
+                .decompress
+                .decompress_vec(&data[consumed_len..], output)?;
+
+            if status == Status::StreamEnd {
+                self.is_done = true;
+            } else if self.decompress.total_in() - start_in == data.len() as u64
+                && output.len() < output.capacity()
+            {
+                // All the data was used and the output was not limited by
+                // the buffer.
+                break;
+            }
+        }
+
+        Ok((self.decompress.total_in() - start_in) as usize)
+    }
+}
@@ -9,6 +9,8 @@ use flate2::{
     write::{GzDecoder as GzPushDecoder, ZlibDecoder as ZlibPushDecoder},
 };
 
+#[cfg(feature = "codec-registry")]
+use super::registry::{CustomDecoder, CustomPushDecoder};
 #[cfg(feature = "zstd")]
 use super::zstd::{ZstdDecoder, ZstdPushDecoder};
 use super::{Dictionary, Format};
@@ -20,6 +22,8 @@ pub enum Decoder<R: BufRead> {
     Brotli(Box<BrDecoder<R>>),
     #[cfg(feature = "zstd")]
     Zstandard(ZstdDecoder<R>),
+    #[cfg(feature = "codec-registry")]
+    Custom(CustomDecoder<R>),
     None,
 }
 
@@ -35,6 +39,8 @@ impl<R: BufRead> Decoder<R> {
                 source,
                 dictionary.clone(),
             )?)),
+            #[cfg(feature = "codec-registry")]
+            Format::Custom(id) => Ok(Decoder::Custom(CustomDecoder::new(source, id)?)),
         }
     }
 }
@@ -48,6 +54,8 @@ impl<R: BufRead> Debug for Decoder<R> {
             Self::Brotli(_arg0) => f.debug_tuple("Brotli").finish(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(_arg0) => f.debug_tuple("Zstandard").finish(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(_arg0) => f.debug_tuple("Custom").finish(),
             Self::None => write!(f, "None"),
         }
     }
@@ -62,6 +70,8 @@ impl<R: BufRead> Decoder<R> {
             Self::Brotli(codec) => codec.get_ref(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(codec) => codec.get_ref(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(codec) => codec.get_ref(),
             Self::None => unreachable!(),
         }
     }
@@ -74,6 +84,8 @@ impl<R: BufRead> Decoder<R> {
             Self::Brotli(codec) => codec.get_mut(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(codec) => codec.get_mut(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(codec) => codec.get_mut(),
             Self::None => unreachable!(),
         }
     }
@@ -86,6 +98,8 @@ impl<R: BufRead> Decoder<R> {
             Self::Brotli(codec) => codec.into_inner(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(codec) => codec.into_inner(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(codec) => codec.into_inner(),
             Self::None => unreachable!(),
         }
     }
@@ -100,6 +114,8 @@ impl<R: BufRead> Read for Decoder<R> {
             Decoder::Brotli(codec) => codec.read(buf),
             #[cfg(feature = "zstd")]
             Decoder::Zstandard(codec) => codec.read(buf),
+            #[cfg(feature = "codec-registry")]
+            Decoder::Custom(codec) => codec.read(buf),
             Decoder::None => unreachable!(),
         }
     }
@@ -112,6 +128,8 @@ pub enum PushDecoder<W: Write> {
     Brotli(Box<BrPushDecoder<W>>),
     #[cfg(feature = "zstd")]
     Zstandard(ZstdPushDecoder<W>),
+    #[cfg(feature = "codec-registry")]
+    Custom(CustomPushDecoder<W>),
     None,
 }
 
@@ -133,6 +151,8 @@ impl<W: Write> PushDecoder<W> {
                 dest,
                 dictionary.clone(),
             )?)),
+            #[cfg(feature = "codec-registry")]
+            Format::Custom(id) => Ok(PushDecoder::Custom(CustomPushDecoder::new(dest, id)?)),
         }
     }
 }
@@ -146,6 +166,8 @@ impl<W: Write> Debug for PushDecoder<W> {
             Self::Brotli(_arg0) => f.debug_tuple("Brotli").finish(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(_arg0) => f.debug_tuple("Zstandard").finish(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(_arg0) => f.debug_tuple("Custom").finish(),
             Self::None => write!(f, "None"),
         }
     }
@@ -160,6 +182,8 @@ impl<W: Write> PushDecoder<W> {
             Self::Brotli(codec) => codec.get_ref(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(codec) => codec.get_ref(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(codec) => codec.get_ref(),
             Self::None => unreachable!(),
         }
     }
@@ -172,6 +196,8 @@ impl<W: Write> PushDecoder<W> {
             Self::Brotli(codec) => codec.get_mut(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(codec) => codec.get_mut(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(codec) => codec.get_mut(),
             Self::None => unreachable!(),
         }
     }
@@ -190,6 +216,8 @@ impl<W: Write> PushDecoder<W> {
             }
             #[cfg(feature = "zstd")]
             Self::Zstandard(codec) => Ok(codec.into_inner()),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(codec) => Ok(codec.into_inner()),
             Self::None => unreachable!(),
         }
     }
@@ -200,10 +228,18 @@ impl<W: Write> Write for PushDecoder<W> {
         match self {
             Self::Identity(w) => w.write(buf),
             Self::Deflate(w) => w.write(buf),
-            Self::Gzip(w) => w.write(buf),
+            Self::Gzip(w) => {
+                // The decoder holds decompressed data until the next write,
+                // which is never done after the end of the last member.
+                let write_len = w.write(buf)?;
+                w.flush()?;
+                Ok(write_len)
+            }
             Self::Brotli(w) => w.write(buf),
             #[cfg(feature = "zstd")]
             Self::Zstandard(w) => w.write(buf),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(w) => w.write(buf),
             Self::None => unreachable!(),
         }
     }
@@ -216,6 +252,8 @@ impl<W: Write> Write for PushDecoder<W> {
             Self::Brotli(w) => w.flush(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(w) => w.flush(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(w) => w.flush(),
             Self::None => unreachable!(),
         }
     }
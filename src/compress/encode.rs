@@ -5,49 +5,108 @@ use super::zstd::ZstdEncoder;
 use brotli::CompressorWriter as BrEncoder;
 use flate2::write::{GzEncoder, ZlibEncoder};
 
-use super::{Dictionary, Format, Level};
+#[cfg(feature = "codec-registry")]
+use super::registry::CustomEncoder;
+use super::{
+    gzip::MemberLengthGzipEncoder, parallel::ParallelGzipEncoder, zstd::ZstdParameters, Dictionary,
+    Format, Level,
+};
 
 pub enum Encoder<W: Write> {
     Identity(W),
     Deflate(ZlibEncoder<W>),
     Gzip(GzEncoder<W>),
+    ParallelGzip(ParallelGzipEncoder<W>),
+    MemberLengthGzip(MemberLengthGzipEncoder<W>),
     Brotli(Box<BrEncoder<W>>),
     #[cfg(feature = "zstd")]
     Zstandard(ZstdEncoder<W>),
+    #[cfg(feature = "codec-registry")]
+    Custom(CustomEncoder<W>),
     None,
 }
 
 impl<W: Write> Encoder<W> {
-    pub fn new(dest: W, format: Format, level: Level, dictionary: &Dictionary) -> Encoder<W> {
-        let level = get_encoder_level(format, level);
+    pub fn new(
+        dest: W,
+        format: Format,
+        level: Level,
+        dictionary: &Dictionary,
+        zstd_parameters: &ZstdParameters,
+        seek_table: bool,
+    ) -> Encoder<W> {
+        let codec_level = get_encoder_level(format, level);
 
         match format {
             Format::Identity => Encoder::Identity(dest),
             Format::Deflate => Encoder::Deflate(ZlibEncoder::new(
                 dest,
-                flate2::Compression::new(level as u32),
+                flate2::Compression::new(codec_level as u32),
+            )),
+            Format::Gzip => Encoder::Gzip(GzEncoder::new(
+                dest,
+                flate2::Compression::new(codec_level as u32),
             )),
-            Format::Gzip => {
-                Encoder::Gzip(GzEncoder::new(dest, flate2::Compression::new(level as u32)))
-            }
             Format::Brotli => {
-                Encoder::Brotli(Box::new(BrEncoder::new(dest, 4096, level as u32, 22)))
+                Encoder::Brotli(Box::new(BrEncoder::new(dest, 4096, codec_level as u32, 22)))
             }
             #[cfg(feature = "zstd")]
-            Format::Zstandard => {
-                Encoder::Zstandard(ZstdEncoder::new(dest, level, dictionary.clone()).unwrap())
-            }
+            Format::Zstandard => Encoder::Zstandard(
+                ZstdEncoder::new(
+                    dest,
+                    codec_level,
+                    dictionary.clone(),
+                    *zstd_parameters,
+                    seek_table,
+                )
+                .unwrap(),
+            ),
+            #[cfg(feature = "codec-registry")]
+            Format::Custom(id) => Encoder::Custom(CustomEncoder::new(dest, id, level)),
         }
     }
 
+    /// Creates a gzip encoder that compresses each member on a pool of
+    /// threads.
+    pub fn new_parallel_gzip(
+        dest: W,
+        level: Level,
+        threads: usize,
+        member_length: bool,
+    ) -> Encoder<W> {
+        let codec_level = get_encoder_level(Format::Gzip, level);
+
+        Encoder::ParallelGzip(ParallelGzipEncoder::new(
+            dest,
+            flate2::Compression::new(codec_level as u32),
+            threads,
+            member_length,
+        ))
+    }
+
+    /// Creates a gzip encoder that writes the lengths of each member in an
+    /// `sl` extra field.
+    pub fn new_member_length_gzip(dest: W, level: Level) -> Encoder<W> {
+        let codec_level = get_encoder_level(Format::Gzip, level);
+
+        Encoder::MemberLengthGzip(MemberLengthGzipEncoder::new(
+            dest,
+            flate2::Compression::new(codec_level as u32),
+        ))
+    }
+
     pub fn get_ref(&self) -> &W {
         match self {
             Self::Identity(w) => w,
             Self::Deflate(codec) => codec.get_ref(),
             Self::Gzip(codec) => codec.get_ref(),
+            Self::ParallelGzip(codec) => codec.get_ref(),
+            Self::MemberLengthGzip(codec) => codec.get_ref(),
             Self::Brotli(codec) => codec.get_ref(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(codec) => codec.get_ref(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(codec) => codec.get_ref(),
             Self::None => unreachable!(),
         }
     }
@@ -57,9 +116,13 @@ impl<W: Write> Encoder<W> {
             Self::Identity(w) => w,
             Self::Deflate(codec) => codec.get_mut(),
             Self::Gzip(codec) => codec.get_mut(),
+            Self::ParallelGzip(codec) => codec.get_mut(),
+            Self::MemberLengthGzip(codec) => codec.get_mut(),
             Self::Brotli(codec) => codec.get_mut(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(codec) => codec.get_mut(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(codec) => codec.get_mut(),
             Self::None => unreachable!(),
         }
     }
@@ -69,9 +132,13 @@ impl<W: Write> Encoder<W> {
             Self::Identity(w) => Ok(w),
             Self::Deflate(codec) => codec.finish(),
             Self::Gzip(codec) => codec.finish(),
+            Self::ParallelGzip(codec) => codec.finish(),
+            Self::MemberLengthGzip(codec) => codec.finish(),
             Self::Brotli(codec) => Ok(codec.into_inner()),
             #[cfg(feature = "zstd")]
             Self::Zstandard(codec) => codec.finish(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(codec) => codec.finish(),
             Self::None => unreachable!(),
         }
     }
@@ -83,9 +150,13 @@ impl<W: Write> Write for Encoder<W> {
             Self::Identity(w) => w.write(buf),
             Self::Deflate(w) => w.write(buf),
             Self::Gzip(w) => w.write(buf),
+            Self::ParallelGzip(w) => w.write(buf),
+            Self::MemberLengthGzip(w) => w.write(buf),
             Self::Brotli(w) => w.write(buf),
             #[cfg(feature = "zstd")]
             Self::Zstandard(w) => w.write(buf),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(w) => w.write(buf),
             Self::None => unreachable!(),
         }
     }
@@ -95,9 +166,13 @@ impl<W: Write> Write for Encoder<W> {
             Self::Identity(w) => w.flush(),
             Self::Deflate(w) => w.flush(),
             Self::Gzip(w) => w.flush(),
+            Self::ParallelGzip(w) => w.flush(),
+            Self::MemberLengthGzip(w) => w.flush(),
             Self::Brotli(w) => w.flush(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(w) => w.flush(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(w) => w.flush(),
             Self::None => unreachable!(),
         }
     }
@@ -109,9 +184,13 @@ impl<W: Write> Debug for Encoder<W> {
             Self::Identity(_arg0) => f.debug_tuple("Identity").finish(),
             Self::Deflate(_arg0) => f.debug_tuple("Deflate").finish(),
             Self::Gzip(_arg0) => f.debug_tuple("Gzip").finish(),
+            Self::ParallelGzip(_arg0) => f.debug_tuple("ParallelGzip").finish(),
+            Self::MemberLengthGzip(_arg0) => f.debug_tuple("MemberLengthGzip").finish(),
             Self::Brotli(_arg0) => f.debug_tuple("Brotli").finish(),
             #[cfg(feature = "zstd")]
             Self::Zstandard(_arg0) => f.debug_tuple("Zstandard").finish(),
+            #[cfg(feature = "codec-registry")]
+            Self::Custom(_arg0) => f.debug_tuple("Custom").finish(),
             Self::None => write!(f, "None"),
         }
     }
@@ -123,23 +202,32 @@ fn get_encoder_level(format: Format, level: Level) -> i32 {
             Level::Balanced => 0,
             Level::High => 0,
             Level::Low => 0,
+            Level::Precise(_) => 0,
         },
         Format::Deflate | Format::Gzip => match level {
             Level::Balanced => 6,
             Level::High => 9,
             Level::Low => 1,
+            Level::Precise(value) => value.clamp(0, 9),
         },
 
         Format::Brotli => match level {
             Level::Balanced => 4,
             Level::High => 7,
             Level::Low => 0,
+            Level::Precise(value) => value.clamp(0, 11),
         },
         #[cfg(feature = "zstd")]
         Format::Zstandard => match level {
             Level::Balanced => 3,
             Level::High => 9,
             Level::Low => 1,
+            Level::Precise(value) => {
+                let range = ::zstd::compression_level_range();
+                value.clamp(*range.start(), *range.end())
+            }
         },
+        #[cfg(feature = "codec-registry")]
+        Format::Custom(_) => 0,
     }
 }
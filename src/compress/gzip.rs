@@ -0,0 +1,135 @@
+use std::io::Write;
+
+use flate2::{write::GzEncoder, Compression, GzBuilder};
+
+/// Subfield ID of the extra field with the lengths of a member.
+const MEMBER_LENGTH_SUBFIELD_ID: [u8; 2] = *b"sl";
+/// Position of the subfield data following the fixed header, the extra
+/// field length, and the subfield ID and length.
+const MEMBER_LENGTH_POSITION: usize = 16;
+
+/// Creates an encoder that compresses a member into a buffer.
+///
+/// If `member_length` is true, the header contains an `sl` extra field
+/// that is filled in by [`set_member_length()`].
+pub fn new_member_encoder(compression: Compression, member_length: bool) -> GzEncoder<Vec<u8>> {
+    if member_length {
+        let mut extra = MEMBER_LENGTH_SUBFIELD_ID.to_vec();
+        extra.extend_from_slice(&8u16.to_le_bytes());
+        extra.extend_from_slice(&[0; 8]);
+
+        GzBuilder::new().extra(extra).write(Vec::new(), compression)
+    } else {
+        GzEncoder::new(Vec::new(), compression)
+    }
+}
+
+/// Writes the compressed length of the member and the length of the
+/// decompressed data to the `sl` extra field of the member.
+pub fn set_member_length(member: &mut [u8], decompressed_len: u64) -> std::io::Result<()> {
+    let (Ok(compressed_len), Ok(decompressed_len)) =
+        (u32::try_from(member.len()), u32::try_from(decompressed_len))
+    else {
+        return Err(std::io::Error::other("member too large for a length field"));
+    };
+
+    member[MEMBER_LENGTH_POSITION..MEMBER_LENGTH_POSITION + 4]
+        .copy_from_slice(&compressed_len.to_le_bytes());
+    member[MEMBER_LENGTH_POSITION + 4..MEMBER_LENGTH_POSITION + 8]
+        .copy_from_slice(&decompressed_len.to_le_bytes());
+
+    Ok(())
+}
+
+/// Returns the compressed length of the member from the `sl` extra field
+/// at the start of the data.
+///
+/// Returns `None` if the header does not start with the field.
+#[cfg(feature = "parallel-decode")]
+pub fn member_length(member: &[u8]) -> Option<usize> {
+    const FLAG_EXTRA: u8 = 0x04;
+
+    let header = member.get(0..MEMBER_LENGTH_POSITION + 8)?;
+
+    if header[0..3] != [0x1f, 0x8b, 0x08]
+        || header[3] & FLAG_EXTRA == 0
+        || header[12..14] != MEMBER_LENGTH_SUBFIELD_ID
+        || header[14..16] != 8u16.to_le_bytes()
+    {
+        return None;
+    }
+
+    let compressed_len = u32::from_le_bytes(
+        header[MEMBER_LENGTH_POSITION..MEMBER_LENGTH_POSITION + 4]
+            .try_into()
+            .unwrap(),
+    );
+
+    match usize::try_from(compressed_len) {
+        Ok(0) | Err(_) => None,
+        Ok(len) => Some(len),
+    }
+}
+
+/// Gzip encoder that writes an `sl` extra field containing the compressed
+/// and decompressed lengths of each member.
+///
+/// The lengths are in the header, so each member is compressed in memory
+/// before it is written.
+pub struct MemberLengthGzipEncoder<W: Write> {
+    dest: W,
+    compression: Compression,
+    encoder: GzEncoder<Vec<u8>>,
+    decompressed_len: u64,
+}
+
+impl<W: Write> MemberLengthGzipEncoder<W> {
+    pub fn new(dest: W, compression: Compression) -> Self {
+        Self {
+            dest,
+            compression,
+            encoder: new_member_encoder(compression, true),
+            decompressed_len: 0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.dest
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.dest
+    }
+
+    /// Ends the current member.
+    pub fn start_new_member(&mut self) -> std::io::Result<()> {
+        let encoder = std::mem::replace(
+            &mut self.encoder,
+            new_member_encoder(self.compression, true),
+        );
+        let mut data = encoder.finish()?;
+        set_member_length(&mut data, self.decompressed_len)?;
+        self.decompressed_len = 0;
+
+        self.dest.write_all(&data)
+    }
+
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.start_new_member()?;
+
+        Ok(self.dest)
+    }
+}
+
+impl<W: Write> Write for MemberLengthGzipEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let write_len = self.encoder.write(buf)?;
+        self.decompressed_len += write_len as u64;
+
+        Ok(write_len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.dest.flush()
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::VecDeque,
+    io::Write,
+    sync::{
+        mpsc::{Receiver, Sender, SyncSender},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+};
+
+use flate2::{write::GzEncoder, Compression};
+
+use super::gzip::{new_member_encoder, set_member_length};
+
+/// Maximum length of a member buffered for compressing on a worker thread.
+///
+/// The rest of a longer member is compressed on the calling thread.
+const MAX_BUFFER_LENGTH: usize = 16 * 1024 * 1024;
+
+struct Job {
+    data: Vec<u8>,
+    result: SyncSender<std::io::Result<Vec<u8>>>,
+}
+
+/// Gzip encoder that compresses each member on a pool of threads.
+///
+/// The data of a member is buffered until the next member is started and
+/// is then compressed by a worker thread. The compressed members are
+/// written in order.
+pub struct ParallelGzipEncoder<W: Write> {
+    dest: W,
+    compression: Compression,
+    member_length: bool,
+    buf: Vec<u8>,
+    /// Encoder of a member that is too long to be buffered.
+    direct_encoder: Option<GzEncoder<Vec<u8>>>,
+    direct_decompressed_len: u64,
+    jobs: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+    pending: VecDeque<Receiver<std::io::Result<Vec<u8>>>>,
+    max_pending: usize,
+}
+
+impl<W: Write> ParallelGzipEncoder<W> {
+    /// Creates an encoder with the given number of worker threads.
+    ///
+    /// If `member_length` is true, each member has an `sl` extra field with
+    /// its lengths. A member that is too long to be buffered is then kept in
+    /// memory in compressed form until it ends.
+    pub fn new(dest: W, compression: Compression, threads: usize, member_length: bool) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..threads)
+            .map(|_| {
+                let receiver = receiver.clone();
+                std::thread::spawn(move || gzip_worker(receiver, compression, member_length))
+            })
+            .collect();
+
+        Self {
+            dest,
+            compression,
+            member_length,
+            buf: Vec::new(),
+            direct_encoder: None,
+            direct_decompressed_len: 0,
+            jobs: Some(sender),
+            workers,
+            pending: VecDeque::new(),
+            max_pending: threads * 2,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.dest
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.dest
+    }
+
+    /// Ends the current member.
+    pub fn start_new_member(&mut self) -> std::io::Result<()> {
+        if let Some(encoder) = self.direct_encoder.take() {
+            let mut data = encoder.finish()?;
+
+            if self.member_length {
+                set_member_length(&mut data, self.direct_decompressed_len)?;
+            }
+
+            self.dest.write_all(&data)?;
+
+            return Ok(());
+        }
+
+        let (result, receiver) = std::sync::mpsc::sync_channel(1);
+        let job = Job {
+            data: std::mem::take(&mut self.buf),
+            result,
+        };
+
+        self.jobs
+            .as_ref()
+            .unwrap()
+            .send(job)
+            .map_err(|_| worker_stopped_error())?;
+        self.pending.push_back(receiver);
+
+        while self.pending.len() > self.max_pending {
+            self.write_next_member()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a compressed member after the pending members.
+    ///
+    /// The current member must be empty.
+    pub fn write_raw_member(&mut self, data: &[u8]) -> std::io::Result<()> {
+        debug_assert!(self.buf.is_empty() && self.direct_encoder.is_none());
+
+        self.write_pending_members()?;
+        self.dest.write_all(data)
+    }
+
+    fn write_next_member(&mut self) -> std::io::Result<()> {
+        if let Some(receiver) = self.pending.pop_front() {
+            let data = receiver.recv().map_err(|_| worker_stopped_error())??;
+            self.dest.write_all(&data)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_pending_members(&mut self) -> std::io::Result<()> {
+        while !self.pending.is_empty() {
+            self.write_next_member()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.start_new_member()?;
+        self.write_pending_members()?;
+        self.jobs = None;
+
+        for worker in std::mem::take(&mut self.workers) {
+            worker.join().map_err(|_| worker_stopped_error())?;
+        }
+
+        Ok(self.dest)
+    }
+}
+
+impl<W: Write> Write for ParallelGzipEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Some(encoder) = &mut self.direct_encoder {
+            encoder.write_all(buf)?;
+            self.direct_decompressed_len += buf.len() as u64;
+
+            // The header cannot be filled in after it is written.
+            if !self.member_length {
+                let data = encoder.get_mut();
+                self.dest.write_all(data)?;
+                data.clear();
+            }
+
+            return Ok(buf.len());
+        }
+
+        self.buf.extend_from_slice(buf);
+
+        if self.buf.len() > MAX_BUFFER_LENGTH {
+            self.write_pending_members()?;
+
+            let mut encoder = new_member_encoder(self.compression, self.member_length);
+            encoder.write_all(&self.buf)?;
+            self.direct_decompressed_len = self.buf.len() as u64;
+            self.buf = Vec::new();
+            self.direct_encoder = Some(encoder);
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.write_pending_members()?;
+        self.dest.flush()
+    }
+}
+
+fn gzip_worker(jobs: Arc<Mutex<Receiver<Job>>>, compression: Compression, member_length: bool) {
+    loop {
+        let job = jobs.lock().unwrap().recv();
+        let Ok(job) = job else {
+            break;
+        };
+
+        let mut encoder = new_member_encoder(compression, member_length);
+        let result = encoder
+            .write_all(&job.data)
+            .and_then(|_| encoder.finish())
+            .and_then(|mut data| {
+                if member_length {
+                    set_member_length(&mut data, job.data.len() as u64)?;
+                }
+                Ok(data)
+            });
+
+        let _ = job.result.send(result);
+    }
+}
+
+fn worker_stopped_error() -> std::io::Error {
+    std::io::Error::other("compression thread stopped")
+}
@@ -0,0 +1,460 @@
+use std::{
+    collections::VecDeque,
+    io::{BufRead, Read},
+    sync::{
+        mpsc::{Receiver, Sender, SyncSender},
+        Arc, Mutex,
+    },
+};
+
+use flate2::bufread::GzDecoder;
+
+use crate::error::ProtocolError;
+
+use super::{gzip::member_length, DecompressorConfig};
+
+/// Length of the data read from the input or decompressed at a time.
+const READ_LENGTH: usize = 64 * 1024;
+/// Length of a gzip header with an `sl` extra field.
+const MEMBER_LENGTH_HEADER_LENGTH: usize = 24;
+/// Length of the data searched for the header of the next member before the
+/// data is split without one.
+const MAX_SCAN_LENGTH: usize = 16 * 1024 * 1024;
+/// Reserved bits of the flags of a gzip header.
+const RESERVED_FLAGS: u8 = 0xe0;
+
+type JobResult = std::io::Result<Vec<u8>>;
+
+struct Job {
+    data: Arc<Vec<u8>>,
+    result: SyncSender<JobResult>,
+}
+
+#[derive(Debug)]
+struct PendingSegment {
+    position: u64,
+    data: Arc<Vec<u8>>,
+    result: Receiver<JobResult>,
+}
+
+/// Gzip members decompressed by a [`ParallelGzipDecoder`].
+#[derive(Debug, Clone)]
+pub struct DecompressedSegment {
+    /// Position of the first member in the input.
+    pub position: u64,
+    /// Length of the compressed members.
+    pub compressed_len: u64,
+    /// Decompressed data.
+    pub data: Vec<u8>,
+}
+
+/// Gzip decoder that decompresses members on a pool of threads.
+///
+/// The end of a member is found with the `sl` extra field written by
+/// [`CompressorConfig::gzip_member_length`](super::CompressorConfig::gzip_member_length)
+/// or by searching for the header of the next member. A header found
+/// inside compressed data causes the member to fail decompressing, and the
+/// member is then decompressed sequentially from the input instead. The
+/// decompressed segments are returned in order.
+///
+/// Each segment is held in memory, so this is intended for files with
+/// record-at-time compression. If no header is found within 16 MiB, the data
+/// is decompressed sequentially, but a member is still held in memory
+/// entirely. Use [`DecompressorConfig::max_segment_length`] to limit the
+/// memory used by large members.
+#[derive(Debug)]
+pub struct ParallelGzipDecoder<R: Read> {
+    input: R,
+    is_input_eof: bool,
+    buf: Vec<u8>,
+    /// Position in the input of the start of the buffer.
+    buf_position: u64,
+    /// Index in the buffer where the search for a header continues.
+    scan_index: usize,
+    config: Arc<DecompressorConfig>,
+    jobs: Sender<Job>,
+    pending: VecDeque<PendingSegment>,
+    max_pending: usize,
+}
+
+impl<R: Read> ParallelGzipDecoder<R> {
+    /// Creates a decoder with the given number of worker threads.
+    ///
+    /// The decompression limits of the configuration apply to each segment.
+    /// The format is not used.
+    pub fn new(input: R, config: DecompressorConfig, threads: usize) -> Self {
+        let config = Arc::new(config);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        // The workers stop when the sender is dropped.
+        for _ in 0..threads.max(1) {
+            let receiver = receiver.clone();
+            let config = config.clone();
+            std::thread::spawn(move || gzip_worker(receiver, config));
+        }
+
+        Self {
+            input,
+            is_input_eof: false,
+            buf: Vec::new(),
+            buf_position: 0,
+            scan_index: 0,
+            config,
+            jobs: sender,
+            pending: VecDeque::new(),
+            max_pending: threads.max(1) * 2,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.input
+    }
+
+    /// Returns the underlying reader.
+    ///
+    /// Seeking the reader is only valid before the first segment is read.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.input
+    }
+
+    /// Returns the next decompressed segment.
+    ///
+    /// Returns `None` at the end of the input.
+    pub fn next_segment(&mut self) -> std::io::Result<Option<DecompressedSegment>> {
+        self.fill_pending()?;
+
+        let Some(segment) = self.pending.pop_front() else {
+            return Ok(None);
+        };
+
+        let result = segment.result.recv().map_err(|_| worker_stopped_error())?;
+
+        match result {
+            Ok(data) => Ok(Some(DecompressedSegment {
+                position: segment.position,
+                compressed_len: segment.data.len() as u64,
+                data,
+            })),
+            Err(error) => self.join_segment(segment, error).map(Some),
+        }
+    }
+
+    /// Decompresses a segment that failed by reading its members
+    /// sequentially from the input.
+    ///
+    /// The following segments are discarded and the data after the members
+    /// is searched for headers again.
+    fn join_segment(
+        &mut self,
+        segment: PendingSegment,
+        error: std::io::Error,
+    ) -> std::io::Result<DecompressedSegment> {
+        if is_limit_error(&error) {
+            return Err(error);
+        }
+
+        tracing::debug!(
+            position = segment.position,
+            len = segment.data.len(),
+            "decompressing gzip segment sequentially"
+        );
+
+        let segment_len = segment.data.len();
+        let mut data = Arc::unwrap_or_clone(segment.data);
+
+        for next_segment in self.pending.drain(..) {
+            data.extend_from_slice(&next_segment.data);
+        }
+
+        data.append(&mut self.buf);
+        self.buf = data;
+        self.buf_position = segment.position;
+        self.scan_index = 0;
+
+        let config = self.config.clone();
+        let mut reader = BufferReader {
+            decoder: self,
+            offset: 0,
+        };
+        let mut output = Vec::new();
+
+        while reader.offset < segment_len {
+            let mut decoder = GzDecoder::new(&mut reader);
+
+            loop {
+                let read_len = (&mut decoder)
+                    .take(READ_LENGTH as u64)
+                    .read_to_end(&mut output)?;
+                config.check_limits(decoder.get_ref().offset as u64, output.len() as u64)?;
+
+                if read_len == 0 {
+                    break;
+                }
+            }
+        }
+
+        let compressed_len = reader.offset;
+        self.buf.drain(..compressed_len);
+        self.buf_position += compressed_len as u64;
+
+        Ok(DecompressedSegment {
+            position: segment.position,
+            compressed_len: compressed_len as u64,
+            data: output,
+        })
+    }
+
+    fn fill_pending(&mut self) -> std::io::Result<()> {
+        while self.pending.len() < self.max_pending {
+            let Some((position, data)) = self.take_next_segment()? else {
+                break;
+            };
+
+            let (result, receiver) = std::sync::mpsc::sync_channel(1);
+            let data = Arc::new(data);
+            let job = Job {
+                data: data.clone(),
+                result,
+            };
+
+            self.jobs.send(job).map_err(|_| worker_stopped_error())?;
+            self.pending.push_back(PendingSegment {
+                position,
+                data,
+                result: receiver,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Removes the compressed data of the next segment from the buffer.
+    fn take_next_segment(&mut self) -> std::io::Result<Option<(u64, Vec<u8>)>> {
+        loop {
+            let segment_len = match self.find_segment_end() {
+                Some(len) => len,
+                None if self.is_input_eof && !self.buf.is_empty() => self.buf.len(),
+                None if self.is_input_eof => return Ok(None),
+                None => {
+                    self.read_input()?;
+                    continue;
+                }
+            };
+
+            let remain_data = self.buf.split_off(segment_len);
+            let data = std::mem::replace(&mut self.buf, remain_data);
+            let position = self.buf_position;
+            self.buf_position += segment_len as u64;
+            self.scan_index = 0;
+
+            return Ok(Some((position, data)));
+        }
+    }
+
+    /// Returns the length of the segment at the start of the buffer if its
+    /// end is in the buffer.
+    fn find_segment_end(&mut self) -> Option<usize> {
+        if self.buf.len() < MEMBER_LENGTH_HEADER_LENGTH && !self.is_input_eof {
+            return None;
+        }
+
+        if let Some(len) = member_length(&self.buf) {
+            return (len <= self.buf.len()).then_some(len);
+        }
+
+        // The header at the start is the current segment.
+        let start = self.scan_index.max(1);
+
+        if let Some(index) = self
+            .buf
+            .get(start..)
+            .unwrap_or_default()
+            .windows(4)
+            .position(is_member_start)
+        {
+            return Some(start + index);
+        }
+
+        if self.buf.len() >= MAX_SCAN_LENGTH {
+            // Likely a large member without record-at-time compression.
+            return Some(self.buf.len());
+        }
+
+        self.scan_index = start.max(self.buf.len().saturating_sub(3));
+
+        None
+    }
+
+    fn read_input(&mut self) -> std::io::Result<()> {
+        let buf_len = self.buf.len();
+        self.buf.resize(buf_len + READ_LENGTH, 0);
+
+        let result = self.input.read(&mut self.buf[buf_len..]);
+        self.buf
+            .truncate(buf_len + result.as_ref().map_or(0, |len| *len));
+
+        match result {
+            Ok(0) => self.is_input_eof = true,
+            Ok(_) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the buffer of a decoder from the start, filling it from the input.
+struct BufferReader<'a, R: Read> {
+    decoder: &'a mut ParallelGzipDecoder<R>,
+    offset: usize,
+}
+
+impl<R: Read> Read for BufferReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.fill_buf()?.read(buf)?;
+        self.consume(len);
+
+        Ok(len)
+    }
+}
+
+impl<R: Read> BufRead for BufferReader<'_, R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        while self.offset == self.decoder.buf.len() && !self.decoder.is_input_eof {
+            self.decoder.read_input()?;
+        }
+
+        Ok(&self.decoder.buf[self.offset..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.offset += amt;
+    }
+}
+
+fn is_member_start(header: &[u8]) -> bool {
+    header[0..3] == [0x1f, 0x8b, 0x08] && header[3] & RESERVED_FLAGS == 0
+}
+
+fn is_limit_error(error: &std::io::Error) -> bool {
+    error
+        .get_ref()
+        .is_some_and(|error| error.is::<ProtocolError>())
+}
+
+fn gzip_worker(jobs: Arc<Mutex<Receiver<Job>>>, config: Arc<DecompressorConfig>) {
+    loop {
+        let job = jobs.lock().unwrap().recv();
+        let Ok(job) = job else {
+            break;
+        };
+
+        let _ = job.result.send(decompress(&job.data, &config));
+    }
+}
+
+/// Decompresses all the members in the data.
+fn decompress(mut data: &[u8], config: &DecompressorConfig) -> std::io::Result<Vec<u8>> {
+    let input_len = data.len() as u64;
+    let mut output = Vec::new();
+
+    while !data.is_empty() {
+        let mut decoder = GzDecoder::new(data);
+
+        loop {
+            let read_len = (&mut decoder)
+                .take(READ_LENGTH as u64)
+                .read_to_end(&mut output)?;
+            config.check_limits(input_len, output.len() as u64)?;
+
+            if read_len == 0 {
+                break;
+            }
+        }
+
+        data = decoder.into_inner();
+    }
+
+    Ok(output)
+}
+
+fn worker_stopped_error() -> std::io::Error {
+    std::io::Error::other("decompression thread stopped")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        // Stored blocks keep the data as is in the member.
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::none());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_false_member_headers() {
+        let data1 = b"\x1f\x8b\x08\x00".repeat(100);
+        let data2 = b"hello world".to_vec();
+        let member1 = gzip(&data1);
+        let member2 = gzip(&data2);
+        let input = [member1.as_slice(), member2.as_slice()].concat();
+
+        let mut decoder =
+            ParallelGzipDecoder::new(input.as_slice(), DecompressorConfig::default(), 4);
+
+        let segment = decoder.next_segment().unwrap().unwrap();
+        assert_eq!(segment.position, 0);
+        assert_eq!(segment.compressed_len, member1.len() as u64);
+        assert_eq!(segment.data, data1);
+
+        let segment = decoder.next_segment().unwrap().unwrap();
+        assert_eq!(segment.position, member1.len() as u64);
+        assert_eq!(segment.compressed_len, member2.len() as u64);
+        assert_eq!(segment.data, data2);
+
+        assert!(decoder.next_segment().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_large_member() {
+        let data1 = vec![0; MAX_SCAN_LENGTH + 1000];
+        let data2 = b"hello world".to_vec();
+        let member1 = gzip(&data1);
+        let member2 = gzip(&data2);
+        let input = [member1.as_slice(), member2.as_slice()].concat();
+
+        let mut decoder =
+            ParallelGzipDecoder::new(input.as_slice(), DecompressorConfig::default(), 2);
+
+        let segment = decoder.next_segment().unwrap().unwrap();
+        assert_eq!(segment.compressed_len, member1.len() as u64);
+        assert!(segment.data == data1);
+
+        let segment = decoder.next_segment().unwrap().unwrap();
+        assert_eq!(segment.position, member1.len() as u64);
+        assert_eq!(segment.data, data2);
+
+        assert!(decoder.next_segment().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_truncated_member() {
+        let member = gzip(&b"\x1f\x8b\x08\x00".repeat(100));
+
+        let mut decoder = ParallelGzipDecoder::new(
+            &member[..member.len() - 10],
+            DecompressorConfig::default(),
+            2,
+        );
+
+        assert!(decoder.next_segment().is_err());
+    }
+}
@@ -0,0 +1,457 @@
+//! Runtime registry of additional compression codecs.
+//!
+//! Codecs that are not built into this crate can be implemented with the
+//! [`Codec`] trait and added with [`register()`]. The returned [`Format`]
+//! can be used like a built-in format with [`Compressor`](super::Compressor),
+//! [`Decompressor`](super::Decompressor),
+//! [`PushDecompressor`](super::PushDecompressor), and the WARC encoder and
+//! decoder. Registered codecs are also found by name with
+//! `Format::from_str()`.
+//!
+//! Some codecs provided by this crate, such as bzip2, are also registered
+//! here and are available as constants on [`Format`].
+//!
+//! Codecs work on buffers: encoders and decoders are given input data and
+//! append their output to a `Vec`. The adapters in this module move the data
+//! between the buffers and the underlying readers and writers.
+use std::{
+    io::{BufRead, Read, Write},
+    sync::{Arc, LazyLock, RwLock},
+};
+
+use super::{Format, Level};
+
+static CODECS: LazyLock<RwLock<Vec<Arc<dyn Codec>>>> = LazyLock::new(|| {
+    RwLock::new(vec![
+        #[cfg(feature = "bzip2")]
+        Arc::new(super::bzip2::Bzip2Codec),
+    ])
+});
+
+/// ID of the built-in bzip2 codec.
+#[cfg(feature = "bzip2")]
+pub(super) const BZIP2_ID: CodecId = CodecId(0);
+
+/// Identifies a registered codec in [`Format::Custom`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CodecId(usize);
+
+impl CodecId {
+    /// Returns the registered codec.
+    pub fn codec(&self) -> Arc<dyn Codec> {
+        CODECS.read().unwrap()[self.0].clone()
+    }
+}
+
+/// A compression codec that can be registered at runtime.
+pub trait Codec: Send + Sync + 'static {
+    /// Returns the name used by `Display` and `FromStr` of [`Format`].
+    fn name(&self) -> &str;
+
+    /// Returns additional names accepted by `FromStr` of [`Format`].
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Returns whether the codec supports concatenated members.
+    ///
+    /// Each segment is encoded and decoded with a new encoder or decoder.
+    fn supports_concatenation(&self) -> bool {
+        false
+    }
+
+    /// Returns the bytes at the start of each segment, if any.
+    ///
+    /// The value can be used to find the next segment in corrupt data.
+    fn segment_magic(&self) -> Option<&'static [u8]> {
+        None
+    }
+
+    /// Returns the filename extension, such as `.gz`, added after `.warc`.
+    fn file_extension(&self) -> &str {
+        ""
+    }
+
+    fn new_encoder(&self, level: Level) -> Box<dyn CodecEncoder>;
+
+    fn new_decoder(&self) -> std::io::Result<Box<dyn CodecDecoder>>;
+}
+
+/// Compressor of a single stream.
+pub trait CodecEncoder: Send {
+    /// Compresses the data and appends any output to the buffer.
+    fn encode(&mut self, data: &[u8], output: &mut Vec<u8>) -> std::io::Result<()>;
+
+    /// Appends the output for all the data given so far.
+    fn flush(&mut self, _output: &mut Vec<u8>) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// Appends the end of the stream.
+    fn finish(&mut self, output: &mut Vec<u8>) -> std::io::Result<()>;
+}
+
+/// Decompressor of a single stream.
+pub trait CodecDecoder: Send {
+    /// Decompresses the data and appends any output to the buffer.
+    ///
+    /// Returns the number of bytes of the data that were used. Returning 0
+    /// for non-empty data indicates the end of the stream.
+    fn decode(&mut self, data: &[u8], output: &mut Vec<u8>) -> std::io::Result<usize>;
+}
+
+/// Adds a codec and returns its format.
+///
+/// If codecs share a name, the last registered codec is found by
+/// `Format::from_str()`.
+pub fn register<C: Codec>(codec: C) -> Format {
+    let mut codecs = CODECS.write().unwrap();
+    codecs.push(Arc::new(codec));
+
+    Format::Custom(CodecId(codecs.len() - 1))
+}
+
+/// Returns the format of the registered codec with the name or alias.
+pub fn find(name: &str) -> Option<Format> {
+    let codecs = CODECS.read().unwrap();
+
+    codecs
+        .iter()
+        .rposition(|codec| codec.name() == name || codec.aliases().contains(&name))
+        .map(|index| Format::Custom(CodecId(index)))
+}
+
+pub(super) struct CustomEncoder<W: Write> {
+    encoder: Box<dyn CodecEncoder>,
+    dest: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> CustomEncoder<W> {
+    pub fn new(dest: W, id: CodecId, level: Level) -> Self {
+        Self {
+            encoder: id.codec().new_encoder(level),
+            dest,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.dest
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.dest
+    }
+
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.encoder.finish(&mut self.buf)?;
+        self.dest.write_all(&self.buf)?;
+
+        Ok(self.dest)
+    }
+
+    fn write_buf(&mut self) -> std::io::Result<()> {
+        self.dest.write_all(&self.buf)?;
+        self.buf.clear();
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for CustomEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.encoder.encode(buf, &mut self.buf)?;
+        self.write_buf()?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.encoder.flush(&mut self.buf)?;
+        self.write_buf()?;
+        self.dest.flush()
+    }
+}
+
+pub(super) struct CustomDecoder<R: BufRead> {
+    decoder: Box<dyn CodecDecoder>,
+    source: R,
+    buf: Vec<u8>,
+    buf_position: usize,
+}
+
+impl<R: BufRead> CustomDecoder<R> {
+    pub fn new(source: R, id: CodecId) -> std::io::Result<Self> {
+        Ok(Self {
+            decoder: id.codec().new_decoder()?,
+            source,
+            buf: Vec::new(),
+            buf_position: 0,
+        })
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.source
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.source
+    }
+
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+}
+
+impl<R: BufRead> Read for CustomDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.buf_position >= self.buf.len() {
+            self.buf.clear();
+            self.buf_position = 0;
+
+            let input = self.source.fill_buf()?;
+
+            if input.is_empty() {
+                return Ok(0);
+            }
+
+            let consumed_len = self.decoder.decode(input, &mut self.buf)?;
+            self.source.consume(consumed_len);
+
+            if consumed_len == 0 && self.buf.is_empty() {
+                return Ok(0);
+            }
+        }
+
+        let remain = &self.buf[self.buf_position..];
+        let len = remain.len().min(buf.len());
+        buf[0..len].copy_from_slice(&remain[0..len]);
+        self.buf_position += len;
+
+        Ok(len)
+    }
+}
+
+pub(super) struct CustomPushDecoder<W: Write> {
+    decoder: Box<dyn CodecDecoder>,
+    dest: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> CustomPushDecoder<W> {
+    pub fn new(dest: W, id: CodecId) -> std::io::Result<Self> {
+        Ok(Self {
+            decoder: id.codec().new_decoder()?,
+            dest,
+            buf: Vec::new(),
+        })
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.dest
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.dest
+    }
+
+    pub fn into_inner(self) -> W {
+        self.dest
+    }
+}
+
+impl<W: Write> Write for CustomPushDecoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let consumed_len = self.decoder.decode(buf, &mut self.buf)?;
+        self.dest.write_all(&self.buf)?;
+        self.buf.clear();
+
+        Ok(consumed_len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.dest.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufReader, Cursor},
+        str::FromStr,
+    };
+
+    use crate::{
+        compress::{
+            Compressor, CompressorConfig, Decompressor, DecompressorConfig, PushDecompressor,
+        },
+        header::WarcHeader,
+        warc::{Decoder, DecoderConfig, Encoder, EncoderConfig},
+    };
+
+    use super::*;
+
+    /// Encodes each byte as two hex digits and ends a stream with `.`.
+    struct HexCodec;
+
+    struct HexEncoder;
+
+    struct HexDecoder {
+        is_done: bool,
+    }
+
+    impl Codec for HexCodec {
+        fn name(&self) -> &str {
+            "test-hex"
+        }
+
+        fn aliases(&self) -> &[&str] {
+            &["test-hx"]
+        }
+
+        fn supports_concatenation(&self) -> bool {
+            true
+        }
+
+        fn new_encoder(&self, _level: Level) -> Box<dyn CodecEncoder> {
+            Box::new(HexEncoder)
+        }
+
+        fn new_decoder(&self) -> std::io::Result<Box<dyn CodecDecoder>> {
+            Ok(Box::new(HexDecoder { is_done: false }))
+        }
+    }
+
+    impl CodecEncoder for HexEncoder {
+        fn encode(&mut self, data: &[u8], output: &mut Vec<u8>) -> std::io::Result<()> {
+            for byte in data {
+                output.extend_from_slice(format!("{:02x}", byte).as_bytes());
+            }
+
+            Ok(())
+        }
+
+        fn finish(&mut self, output: &mut Vec<u8>) -> std::io::Result<()> {
+            output.push(b'.');
+            Ok(())
+        }
+    }
+
+    impl CodecDecoder for HexDecoder {
+        fn decode(&mut self, data: &[u8], output: &mut Vec<u8>) -> std::io::Result<usize> {
+            let mut index = 0;
+
+            while !self.is_done && index < data.len() {
+                if data[index] == b'.' {
+                    self.is_done = true;
+                    index += 1;
+                    break;
+                }
+
+                let Some(pair) = data.get(index..index + 2) else {
+                    break;
+                };
+                let byte = std::str::from_utf8(pair)
+                    .ok()
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or(std::io::ErrorKind::InvalidData)?;
+                output.push(byte);
+                index += 2;
+            }
+
+            Ok(index)
+        }
+    }
+
+    #[test]
+    fn test_custom_codec() {
+        let format = register(HexCodec);
+
+        assert_eq!(Format::from_str("test-hex").unwrap(), format);
+        assert_eq!(Format::from_str("test-hx").unwrap(), format);
+        assert_eq!(format.to_string(), "test-hex");
+        assert!(format.supports_concatenation());
+
+        let mut c = Compressor::new(Vec::new(), format);
+        c.write_all(b"hello").unwrap();
+        c.start_new_segment().unwrap();
+        c.write_all(b"world").unwrap();
+        let compressed = c.finish().unwrap();
+
+        assert_eq!(compressed, b"68656c6c6f.776f726c64.");
+
+        let mut d = Decompressor::new(BufReader::new(Cursor::new(&compressed)), format).unwrap();
+        let mut output = Vec::new();
+        d.read_to_end(&mut output).unwrap();
+        assert_eq!(output, b"hello");
+        assert!(d.has_data_left().unwrap());
+        d.start_next_segment().unwrap();
+        d.read_to_end(&mut output).unwrap();
+        assert_eq!(output, b"helloworld");
+
+        let mut d = PushDecompressor::new(Vec::new(), format).unwrap();
+        let mut input = compressed.as_slice();
+
+        while !input.is_empty() {
+            let write_len = d.write(input).unwrap();
+
+            if write_len == 0 {
+                d.start_next_segment().unwrap();
+            }
+
+            input = &input[write_len..];
+        }
+
+        assert_eq!(d.into_inner().unwrap(), b"helloworld");
+    }
+
+    #[test]
+    fn test_custom_codec_warc() {
+        let format = register(HexCodec);
+
+        let mut encoder = Encoder::new(
+            Vec::new(),
+            EncoderConfig {
+                compressor: CompressorConfig {
+                    format,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        for body in [b"hello", b"world"] {
+            let mut block_encoder = encoder
+                .write_header(&WarcHeader::new(5, "resource"))
+                .unwrap();
+            block_encoder.write_all(body).unwrap();
+            encoder = block_encoder.finish_block().unwrap();
+        }
+
+        let compressed = encoder.finish().unwrap();
+
+        let mut decoder = Decoder::new(
+            compressed.as_slice(),
+            DecoderConfig {
+                decompressor: DecompressorConfig {
+                    format,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut bodies = Vec::new();
+
+        while decoder.has_next_record().unwrap() {
+            let (_header, mut block_decoder) = decoder.read_header().unwrap();
+            let mut body = Vec::new();
+            block_decoder.read_to_end(&mut body).unwrap();
+            bodies.push(body);
+            decoder = block_decoder.finish_block().unwrap();
+        }
+
+        assert_eq!(bodies, vec![b"hello".to_vec(), b"world".to_vec()]);
+    }
+}
@@ -4,20 +4,64 @@ use std::io::Read;
 pub(crate) use decode::{ZstdDecoder, ZstdPushDecoder};
 #[cfg(feature = "zstd")]
 pub(crate) use encode::ZstdEncoder;
+pub use seek::*;
 
 #[cfg(feature = "zstd")]
 mod decode;
 #[cfg(feature = "zstd")]
 mod encode;
+mod seek;
 
 const WARC_DICT_FRAME: u32 = 0x184D2A5D;
 const ZSTD_FRAME: u32 = 0xFD2FB528;
 const BULK_BUFFER_LENGTH: usize = 16 * 1024 * 1024;
 
+/// Advanced parameters of the Zstandard encoder.
+///
+/// The default uses the parameters of the compression level, which keep
+/// the window at most 8 MiB so the output can be decompressed by other
+/// tools without extra options. Larger windows and long distance matching
+/// improve the ratio of archives with repeated content at the cost of
+/// memory, and decompressors may need to be configured to accept them (for
+/// example, `zstd --long=31 -d`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ZstdParameters {
+    /// Base 2 logarithm of the maximum distance of a match.
+    ///
+    /// Values greater than 27 require decompressors to raise their memory
+    /// limit.
+    pub window_log: Option<u32>,
+    /// Enables long distance matching for finding matches far back in the
+    /// window.
+    ///
+    /// If `window_log` is not set, the window is raised to 128 MiB.
+    pub long_distance_matching: bool,
+    /// Base 2 logarithm of the size of the table used for searching matches.
+    ///
+    /// Larger values find better matches at the cost of speed.
+    pub chain_log: Option<u32>,
+}
+
 pub fn is_skippable_frame(magic_number: u32) -> bool {
     (0x184D2A50..=0x184D2A5F).contains(&magic_number)
 }
 
+/// Trains a Zstandard dictionary from the samples.
+///
+/// Each sample should be data that is compressed on its own, such as a
+/// record of a file with record-at-time compression. The dictionary is at
+/// most `max_size` bytes. It can be embedded in the leading skippable frame
+/// of a `.warc.zst` file with
+/// [`Dictionary::WarcZstd`](crate::compress::Dictionary::WarcZstd).
+#[cfg(feature = "zstd")]
+pub fn train_dictionary<S: AsRef<[u8]>>(
+    samples: &[S],
+    max_size: usize,
+) -> std::io::Result<Vec<u8>> {
+    zstd::dict::from_samples(samples, max_size)
+}
+
 pub fn extract_warc_zst_dictionary<R: Read>(
     mut input: R,
 ) -> Result<Vec<u8>, WarcZstDictExtractError> {
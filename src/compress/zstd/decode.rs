@@ -4,7 +4,7 @@ use std::{
 };
 
 use zstd::{
-    stream::raw::{Decoder as ZstdFrameDecoder, Operation},
+    stream::raw::{DParameter, Decoder as ZstdFrameDecoder, Operation},
     zstd_safe::{InBuffer, OutBuffer},
 };
 
@@ -13,6 +13,12 @@ use crate::compress::Dictionary;
 use super::{BULK_BUFFER_LENGTH, WARC_DICT_FRAME, ZSTD_FRAME};
 
 const BUFFER_LENGTH: usize = crate::io::IO_BUFFER_LENGTH;
+/// Largest window accepted, so files compressed with a larger window log
+/// (see [`ZstdParameters`](super::ZstdParameters)) can be read.
+#[cfg(target_pointer_width = "64")]
+const WINDOW_LOG_MAX: u32 = 31;
+#[cfg(not(target_pointer_width = "64"))]
+const WINDOW_LOG_MAX: u32 = 30;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum PushDecoderState {
@@ -39,8 +45,8 @@ pub struct ZstdPushDecoder<W: Write> {
 impl<W: Write> ZstdPushDecoder<W> {
     pub fn new(output: W, dictionary: Dictionary) -> std::io::Result<Self> {
         let decoder_impl = match &dictionary {
-            Dictionary::Zstd(vec) => ZstdFrameDecoder::with_dictionary(vec)?,
-            _ => ZstdFrameDecoder::new()?,
+            Dictionary::Zstd(vec) => new_frame_decoder(vec)?,
+            _ => new_frame_decoder(&[])?,
         };
 
         Ok(Self {
@@ -159,14 +165,14 @@ impl<W: Write> ZstdPushDecoder<W> {
                     "read dictionary frame (compressed)"
                 );
 
-                self.frame_decoder = ZstdFrameDecoder::with_dictionary(&decomp_dict)?;
+                self.frame_decoder = new_frame_decoder(&decomp_dict)?;
                 self.dictionary = Dictionary::WarcZstd(decomp_dict);
             } else {
                 let dict = self.dictionary.as_warc_zstd().unwrap();
 
                 tracing::trace!(dict_len = dict.len(), "read dictionary frame");
 
-                self.frame_decoder = ZstdFrameDecoder::with_dictionary(dict)?;
+                self.frame_decoder = new_frame_decoder(dict)?;
             }
 
             self.start_next_frame()?;
@@ -258,6 +264,13 @@ impl<W: Write> Write for ZstdPushDecoder<W> {
     }
 }
 
+fn new_frame_decoder(dictionary: &[u8]) -> std::io::Result<ZstdFrameDecoder<'static>> {
+    let mut decoder = ZstdFrameDecoder::with_dictionary(dictionary)?;
+    decoder.set_parameter(DParameter::WindowLogMax(WINDOW_LOG_MAX))?;
+
+    Ok(decoder)
+}
+
 pub struct ZstdDecoder<R: Read> {
     input: R,
     push_decoder: ZstdPushDecoder<VecDeque<u8>>,
@@ -277,15 +290,19 @@ impl<R: Read> ZstdDecoder<R> {
         tracing::trace!("fill decoder");
 
         while self.push_decoder.get_ref().is_empty() {
-            self.buf.resize(BUFFER_LENGTH, 0);
-            let source_read_len = self.input.read(&mut self.buf)?;
-            self.buf.truncate(source_read_len);
-
-            tracing::trace!(source_read_len, "fill decoder");
-
-            if source_read_len == 0 {
-                // End of input file
-                break;
+            // Input not consumed by the push decoder, such as the data
+            // following a skippable frame, is kept for the next write.
+            if self.buf.is_empty() {
+                self.buf.resize(BUFFER_LENGTH, 0);
+                let source_read_len = self.input.read(&mut self.buf)?;
+                self.buf.truncate(source_read_len);
+
+                tracing::trace!(source_read_len, "fill decoder");
+
+                if source_read_len == 0 {
+                    // End of input file
+                    break;
+                }
             }
 
             let decode_write_len = self.push_decoder.write(&self.buf)?;
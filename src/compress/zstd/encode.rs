@@ -1,10 +1,10 @@
 use std::io::Write;
 
-use zstd::stream::write::Encoder as ZstdEncoderImpl;
+use zstd::stream::{raw::CParameter, write::Encoder as ZstdEncoderImpl};
 
 use crate::compress::Dictionary;
 
-use super::WARC_DICT_FRAME;
+use super::{SeekTable, ZstdParameters, WARC_DICT_FRAME};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum WarcDictionaryState {
@@ -13,45 +13,98 @@ enum WarcDictionaryState {
     Ok,
 }
 
+/// Writer that counts the bytes written for the seek table.
+struct CountWriter<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> Write for CountWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let write_len = self.inner.write(buf)?;
+        self.count += write_len as u64;
+        Ok(write_len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 pub struct ZstdEncoder<W: Write> {
     level: i32,
     dictionary: Dictionary,
+    parameters: ZstdParameters,
     warc_dict_state: WarcDictionaryState,
-    encoder_impl: Option<ZstdEncoderImpl<'static, W>>,
+    encoder_impl: Option<ZstdEncoderImpl<'static, CountWriter<W>>>,
+    seek_table: Option<SeekTable>,
+    frame_position: u64,
+    frame_decompressed_len: u64,
 }
 
 impl<W: Write> ZstdEncoder<W> {
-    pub fn new(dest: W, level: i32, dictionary: Dictionary) -> std::io::Result<Self> {
+    pub fn new(
+        dest: W,
+        level: i32,
+        dictionary: Dictionary,
+        parameters: ZstdParameters,
+        seek_table: bool,
+    ) -> std::io::Result<Self> {
         let warc_dict_state = match &dictionary {
             Dictionary::None => WarcDictionaryState::None,
             Dictionary::Zstd(_vec) => WarcDictionaryState::None,
             Dictionary::WarcZstd(_vec) => WarcDictionaryState::PendingFrameWrite,
         };
-        let mut encoder_impl = match &dictionary {
-            Dictionary::None => ZstdEncoderImpl::new(dest, level)?,
-            Dictionary::Zstd(vec) => ZstdEncoderImpl::with_dictionary(dest, level, vec)?,
-            Dictionary::WarcZstd(vec) => ZstdEncoderImpl::with_dictionary(dest, level, vec)?,
+        let dest = CountWriter {
+            inner: dest,
+            count: 0,
         };
-        Self::config_encoder(&mut encoder_impl)?;
+        let encoder_impl = Self::new_encoder_impl(dest, level, &dictionary, &parameters)?;
+
         Ok(Self {
             level,
             dictionary,
+            parameters,
             warc_dict_state,
             encoder_impl: Some(encoder_impl),
+            seek_table: seek_table.then(SeekTable::new),
+            frame_position: 0,
+            frame_decompressed_len: 0,
         })
     }
 
-    fn config_encoder(encoder: &mut ZstdEncoderImpl<'static, W>) -> std::io::Result<()> {
-        encoder.include_checksum(true)?;
-        Ok(())
+    fn new_encoder_impl(
+        dest: CountWriter<W>,
+        level: i32,
+        dictionary: &Dictionary,
+        parameters: &ZstdParameters,
+    ) -> std::io::Result<ZstdEncoderImpl<'static, CountWriter<W>>> {
+        let mut encoder_impl = match dictionary {
+            Dictionary::None => ZstdEncoderImpl::new(dest, level)?,
+            Dictionary::Zstd(vec) => ZstdEncoderImpl::with_dictionary(dest, level, vec)?,
+            Dictionary::WarcZstd(vec) => ZstdEncoderImpl::with_dictionary(dest, level, vec)?,
+        };
+        encoder_impl.include_checksum(true)?;
+
+        if parameters.long_distance_matching {
+            encoder_impl.long_distance_matching(true)?;
+        }
+        if let Some(value) = parameters.window_log {
+            encoder_impl.window_log(value)?;
+        }
+        if let Some(value) = parameters.chain_log {
+            encoder_impl.set_parameter(CParameter::ChainLog(value))?;
+        }
+
+        Ok(encoder_impl)
     }
 
     pub fn get_ref(&self) -> &W {
-        self.encoder_impl.as_ref().unwrap().get_ref()
+        &self.encoder_impl.as_ref().unwrap().get_ref().inner
     }
 
     pub fn get_mut(&mut self) -> &mut W {
-        self.encoder_impl.as_mut().unwrap().get_mut()
+        &mut self.encoder_impl.as_mut().unwrap().get_mut().inner
     }
 
     fn write_warc_dictionary(&mut self) -> std::io::Result<()> {
@@ -60,28 +113,91 @@ impl<W: Write> ZstdEncoder<W> {
             dest.write_all(&WARC_DICT_FRAME.to_le_bytes())?;
             dest.write_all(&(data.len() as u32).to_le_bytes())?;
             dest.write_all(data)?;
+
+            let position = dest.count;
+            self.add_seek_table_entry(position)?;
         }
 
         Ok(())
     }
 
-    pub fn finish(self) -> std::io::Result<W> {
-        self.encoder_impl.unwrap().finish()
+    /// Adds the frame that ended at the position to the seek table.
+    ///
+    /// The seek table stores 32-bit lengths. If the frame is too large, the
+    /// seek table is dropped and the stream is written without it.
+    fn add_seek_table_entry(&mut self, position: u64) -> std::io::Result<()> {
+        let compressed_len = position - self.frame_position;
+        let decompressed_len = self.frame_decompressed_len;
+        self.frame_position = position;
+        self.frame_decompressed_len = 0;
+
+        if let Some(seek_table) = &mut self.seek_table {
+            if compressed_len == 0 {
+                return Ok(());
+            }
+
+            let (Ok(compressed_len_32), Ok(decompressed_len_32)) = (
+                u32::try_from(compressed_len),
+                u32::try_from(decompressed_len),
+            ) else {
+                tracing::warn!(
+                    compressed_len,
+                    decompressed_len,
+                    "frame too large for a seek table, omitting seek table"
+                );
+                self.seek_table = None;
+                return Ok(());
+            };
+
+            seek_table.push(compressed_len_32, decompressed_len_32);
+        }
+
+        Ok(())
+    }
+
+    /// Writes a compressed frame before the current frame.
+    ///
+    /// The current frame must be empty.
+    pub fn write_raw_frame(&mut self, data: &[u8], decompressed_len: u64) -> std::io::Result<()> {
+        debug_assert_eq!(self.frame_decompressed_len, 0);
+
+        if self.warc_dict_state == WarcDictionaryState::PendingFrameWrite {
+            self.warc_dict_state = WarcDictionaryState::Ok;
+
+            self.write_warc_dictionary()?;
+        }
+
+        let dest = self.encoder_impl.as_mut().unwrap().get_mut();
+        dest.write_all(data)?;
+
+        let position = dest.count;
+        self.frame_decompressed_len = decompressed_len;
+        self.add_seek_table_entry(position)
+    }
+
+    pub fn finish(mut self) -> std::io::Result<W> {
+        let mut dest = self.encoder_impl.take().unwrap().finish()?;
+        self.add_seek_table_entry(dest.count)?;
+
+        if let Some(seek_table) = &self.seek_table {
+            seek_table.write(&mut dest)?;
+        }
+
+        Ok(dest.inner)
     }
 
     pub fn start_new_frame(&mut self) -> std::io::Result<()> {
         // FIXME: We should be reusing the zstd context but the API is a bit difficult.
 
         let dest = self.encoder_impl.take().unwrap().finish()?;
+        self.add_seek_table_entry(dest.count)?;
 
-        let mut encoder_impl = match &self.dictionary {
-            Dictionary::None => ZstdEncoderImpl::new(dest, self.level)?,
-            Dictionary::Zstd(vec) => ZstdEncoderImpl::with_dictionary(dest, self.level, vec)?,
-            Dictionary::WarcZstd(vec) => ZstdEncoderImpl::with_dictionary(dest, self.level, vec)?,
-        };
-        Self::config_encoder(&mut encoder_impl)?;
-
-        self.encoder_impl = Some(encoder_impl);
+        self.encoder_impl = Some(Self::new_encoder_impl(
+            dest,
+            self.level,
+            &self.dictionary,
+            &self.parameters,
+        )?);
 
         Ok(())
     }
@@ -95,10 +211,51 @@ impl<W: Write> Write for ZstdEncoder<W> {
             self.write_warc_dictionary()?;
         }
 
-        self.encoder_impl.as_mut().unwrap().write(buf)
+        let write_len = self.encoder_impl.as_mut().unwrap().write(buf)?;
+        self.frame_decompressed_len += write_len as u64;
+
+        Ok(write_len)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
         self.encoder_impl.as_mut().unwrap().flush()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_too_large_for_seek_table() {
+        let mut encoder = ZstdEncoder::new(
+            Vec::new(),
+            3,
+            Dictionary::None,
+            ZstdParameters::default(),
+            true,
+        )
+        .unwrap();
+
+        encoder.write_all(b"hello world").unwrap();
+        encoder.start_new_frame().unwrap();
+        assert!(encoder.seek_table.is_some());
+
+        encoder.write_all(b"hello world").unwrap();
+        encoder.frame_decompressed_len = u32::MAX as u64 + 1;
+        encoder.start_new_frame().unwrap();
+        assert!(encoder.seek_table.is_none());
+
+        encoder.write_all(b"hello world").unwrap();
+        let output = encoder.finish().unwrap();
+
+        assert!(SeekTable::read(std::io::Cursor::new(&output))
+            .unwrap()
+            .is_none());
+
+        let mut decoder = zstd::stream::read::Decoder::new(output.as_slice()).unwrap();
+        let mut data = Vec::new();
+        std::io::Read::read_to_end(&mut decoder, &mut data).unwrap();
+        assert_eq!(data, b"hello world".repeat(3));
+    }
+}
@@ -0,0 +1,196 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+const SEEK_TABLE_FRAME: u32 = 0x184D2A5E;
+const SEEKABLE_MAGIC_NUMBER: u32 = 0x8F92EAB1;
+const FOOTER_LENGTH: u64 = 9;
+const CHECKSUM_FLAG: u8 = 0x80;
+/// Maximum number of frames accepted when reading a seek table.
+const MAX_FRAME_COUNT: u32 = 0x8000000;
+
+/// Location of a frame in a Zstandard file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeekTableFrame {
+    /// Position of the frame in the file.
+    pub compressed_position: u64,
+    /// Position of the frame's data in the decompressed stream.
+    pub decompressed_position: u64,
+    pub compressed_len: u32,
+    pub decompressed_len: u32,
+}
+
+/// Seek table of the Zstandard seekable format.
+///
+/// The table is written in a skippable frame at the end of the file and
+/// lists the compressed and decompressed sizes of the frames, so the frame
+/// containing a position in the decompressed data can be found without
+/// decompressing the file. Skippable frames, such as the `.warc.zst`
+/// dictionary frame, are listed with a decompressed size of 0.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeekTable {
+    entries: Vec<(u32, u32)>,
+}
+
+impl SeekTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a frame with the given sizes.
+    pub fn push(&mut self, compressed_len: u32, decompressed_len: u32) {
+        self.entries.push((compressed_len, decompressed_len));
+    }
+
+    /// Returns the number of frames.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the frames in the order of the file.
+    pub fn frames(&self) -> impl Iterator<Item = SeekTableFrame> + '_ {
+        let mut compressed_position = 0;
+        let mut decompressed_position = 0;
+
+        self.entries
+            .iter()
+            .map(move |&(compressed_len, decompressed_len)| {
+                let frame = SeekTableFrame {
+                    compressed_position,
+                    decompressed_position,
+                    compressed_len,
+                    decompressed_len,
+                };
+                compressed_position += compressed_len as u64;
+                decompressed_position += decompressed_len as u64;
+                frame
+            })
+    }
+
+    /// Returns the frame containing the position in the decompressed data.
+    pub fn find_decompressed(&self, position: u64) -> Option<SeekTableFrame> {
+        self.frames().find(|frame| {
+            position >= frame.decompressed_position
+                && position - frame.decompressed_position < frame.decompressed_len as u64
+        })
+    }
+
+    /// Reads the seek table at the end of the file.
+    ///
+    /// Returns `None` if the file does not end with a seek table. The
+    /// position of the stream is left unspecified.
+    pub fn read<R: Read + Seek>(mut input: R) -> std::io::Result<Option<Self>> {
+        let file_len = input.seek(SeekFrom::End(0))?;
+
+        if file_len < FOOTER_LENGTH + 8 {
+            return Ok(None);
+        }
+
+        let mut footer = [0u8; FOOTER_LENGTH as usize];
+        input.seek(SeekFrom::Start(file_len - FOOTER_LENGTH))?;
+        input.read_exact(&mut footer)?;
+
+        let frame_count = u32::from_le_bytes(footer[0..4].try_into().unwrap());
+        let descriptor = footer[4];
+        let magic_number = u32::from_le_bytes(footer[5..9].try_into().unwrap());
+
+        if magic_number != SEEKABLE_MAGIC_NUMBER || frame_count > MAX_FRAME_COUNT {
+            return Ok(None);
+        }
+
+        let entry_len = if descriptor & CHECKSUM_FLAG != 0 {
+            12
+        } else {
+            8
+        };
+        let data_len = frame_count as u64 * entry_len + FOOTER_LENGTH;
+
+        if file_len < data_len + 8 {
+            return Ok(None);
+        }
+
+        let mut frame_header = [0u8; 8];
+        input.seek(SeekFrom::Start(file_len - data_len - 8))?;
+        input.read_exact(&mut frame_header)?;
+
+        if u32::from_le_bytes(frame_header[0..4].try_into().unwrap()) != SEEK_TABLE_FRAME
+            || u32::from_le_bytes(frame_header[4..8].try_into().unwrap()) as u64 != data_len
+        {
+            return Ok(None);
+        }
+
+        let mut data = vec![0u8; (data_len - FOOTER_LENGTH) as usize];
+        input.read_exact(&mut data)?;
+
+        let entries = data
+            .chunks_exact(entry_len as usize)
+            .map(|entry| {
+                (
+                    u32::from_le_bytes(entry[0..4].try_into().unwrap()),
+                    u32::from_le_bytes(entry[4..8].try_into().unwrap()),
+                )
+            })
+            .collect();
+
+        Ok(Some(Self { entries }))
+    }
+
+    /// Writes the table as a skippable frame without checksums.
+    pub fn write<W: Write>(&self, mut output: W) -> std::io::Result<()> {
+        let data_len = self.entries.len() * 8 + FOOTER_LENGTH as usize;
+        let data_len = u32::try_from(data_len)
+            .map_err(|_| std::io::Error::other("too many frames for a seek table"))?;
+
+        output.write_all(&SEEK_TABLE_FRAME.to_le_bytes())?;
+        output.write_all(&data_len.to_le_bytes())?;
+
+        for (compressed_len, decompressed_len) in &self.entries {
+            output.write_all(&compressed_len.to_le_bytes())?;
+            output.write_all(&decompressed_len.to_le_bytes())?;
+        }
+
+        output.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        output.write_all(&[0])?;
+        output.write_all(&SEEKABLE_MAGIC_NUMBER.to_le_bytes())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_seek_table() {
+        let mut table = SeekTable::new();
+        table.push(20, 0);
+        table.push(100, 500);
+        table.push(50, 200);
+
+        let mut data = vec![0u8; 170];
+        table.write(&mut data).unwrap();
+
+        let table2 = SeekTable::read(Cursor::new(&data)).unwrap().unwrap();
+
+        assert_eq!(table2, table);
+        assert_eq!(table.find_decompressed(0).unwrap().compressed_position, 20);
+        assert_eq!(
+            table.find_decompressed(500),
+            Some(SeekTableFrame {
+                compressed_position: 120,
+                decompressed_position: 500,
+                compressed_len: 50,
+                decompressed_len: 200,
+            })
+        );
+        assert_eq!(table.find_decompressed(700), None);
+
+        assert_eq!(SeekTable::read(Cursor::new(&data[0..170])).unwrap(), None);
+        assert_eq!(SeekTable::read(Cursor::new(b"")).unwrap(), None);
+    }
+}
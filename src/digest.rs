@@ -1,12 +1,22 @@
 //! WARC related hashing functions.
 
-use std::{collections::HashMap, fmt::Display, str::FromStr};
-
-use data_encoding::{BASE32, BASE32_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{Read, Write},
+    str::FromStr,
+};
+
+use data_encoding::{
+    BASE32, BASE32_NOPAD, BASE64, BASE64URL_NOPAD, BASE64_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE,
+};
 use digest::Digest as _;
 
 use crate::error::{ProtocolError, ProtocolErrorKind};
 
+#[cfg(feature = "digest-registry")]
+pub mod registry;
+
 /// Name of a standardized hashing algorithm.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[non_exhaustive]
@@ -16,13 +26,18 @@ pub enum AlgorithmName {
     Xxh3,
     Md5,
     Sha1,
+    Sha224,
     Sha256,
+    Sha384,
     Sha512,
     Sha3_256,
     Sha3_512,
     Blake2s,
     Blake2b,
     Blake3,
+    /// Algorithm added with [`registry::register()`].
+    #[cfg(feature = "digest-registry")]
+    Custom(registry::AlgorithmId),
 }
 
 impl AlgorithmName {
@@ -33,13 +48,17 @@ impl AlgorithmName {
             Self::Xxh3 => "xxh3",
             Self::Md5 => "md5",
             Self::Sha1 => "sha1",
+            Self::Sha224 => "sha224",
             Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
             Self::Sha512 => "sha512",
             Self::Sha3_256 => "sha3-256",
             Self::Sha3_512 => "sha3-512",
             Self::Blake2s => "blake2s",
             Self::Blake2b => "blake2b",
             Self::Blake3 => "blake3",
+            #[cfg(feature = "digest-registry")]
+            Self::Custom(id) => id.name(),
         }
     }
 
@@ -50,13 +69,17 @@ impl AlgorithmName {
             Self::Xxh3 => 8,
             Self::Md5 => 16,
             Self::Sha1 => 20,
+            Self::Sha224 => 28,
             Self::Sha256 => 32,
+            Self::Sha384 => 48,
             Self::Sha512 => 64,
             Self::Sha3_256 => 32,
             Self::Sha3_512 => 64,
             Self::Blake2s => 32,
             Self::Blake2b => 64,
             Self::Blake3 => 32,
+            #[cfg(feature = "digest-registry")]
+            Self::Custom(id) => id.output_len(),
         }
     }
 }
@@ -79,7 +102,9 @@ impl FromStr for AlgorithmName {
             "xxh3" => Ok(Self::Xxh3),
             "md5" => Ok(Self::Md5),
             "sha1" => Ok(Self::Sha1),
+            "sha224" => Ok(Self::Sha224),
             "sha256" => Ok(Self::Sha256),
+            "sha384" => Ok(Self::Sha384),
             "sha512" => Ok(Self::Sha512),
             "sha3-256" => Ok(Self::Sha3_256),
             "sha3-512" => Ok(Self::Sha3_512),
@@ -87,11 +112,31 @@ impl FromStr for AlgorithmName {
             "blake2b" => Ok(Self::Blake2b),
             "blake3" => Ok(Self::Blake3),
 
+            #[cfg(feature = "digest-registry")]
+            _ => registry::find(s)
+                .ok_or_else(|| ProtocolError::new(ProtocolErrorKind::UnsupportedDigest)),
+            #[cfg(not(feature = "digest-registry"))]
             _ => Err(ProtocolError::new(ProtocolErrorKind::UnsupportedDigest)),
         }
     }
 }
 
+/// Text encoding of digest values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum DigestEncoding {
+    /// Base32 for SHA-1 and hexadecimal for other algorithms as commonly
+    /// written by other tools.
+    #[default]
+    Conventional,
+    /// Base32 with padding.
+    Base32,
+    /// Lowercase hexadecimal.
+    Hex,
+    /// Standard Base64 with padding.
+    Base64,
+}
+
 /// Data structure for a hash digest value and the algorithm that produced it.
 ///
 /// Corresponds to the format in the WARC-Block-Digest field.
@@ -113,6 +158,21 @@ impl Digest {
     pub fn value(&self) -> &[u8] {
         &self.value
     }
+
+    /// Returns the digest in the format of the WARC-Block-Digest field with
+    /// the value in the given encoding.
+    pub fn to_string_with_encoding(&self, encoding: DigestEncoding) -> String {
+        let value = match encoding {
+            DigestEncoding::Conventional if self.algorithm == AlgorithmName::Sha1 => {
+                BASE32.encode(&self.value)
+            }
+            DigestEncoding::Conventional | DigestEncoding::Hex => HEXLOWER.encode(&self.value),
+            DigestEncoding::Base32 => BASE32.encode(&self.value),
+            DigestEncoding::Base64 => BASE64.encode(&self.value),
+        };
+
+        format!("{}:{}", self.algorithm.as_str(), value)
+    }
 }
 
 impl FromStr for Digest {
@@ -129,20 +189,7 @@ impl FromStr for Digest {
 
 impl Display for Digest {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.algorithm {
-            AlgorithmName::Sha1 => write!(
-                f,
-                "{}:{}",
-                self.algorithm.as_str(),
-                BASE32.encode(&self.value)
-            ),
-            _ => write!(
-                f,
-                "{}:{}",
-                self.algorithm.as_str(),
-                HEXLOWER.encode(&self.value)
-            ),
-        }
+        f.write_str(&self.to_string_with_encoding(DigestEncoding::Conventional))
     }
 }
 
@@ -166,6 +213,9 @@ fn decode_value(expected_len: usize, value: &str) -> Result<Vec<u8>, ProtocolErr
     let hex_len = HEXLOWER_PERMISSIVE
         .decode_len(nopad_value.len())
         .unwrap_or_default();
+    let b64_len = BASE64_NOPAD
+        .decode_len(nopad_value.len())
+        .unwrap_or_default();
 
     let result = {
         if expected_len == b32_len && expected_len == hex_len {
@@ -178,6 +228,10 @@ fn decode_value(expected_len: usize, value: &str) -> Result<Vec<u8>, ProtocolErr
         } else if expected_len == b32_len {
             let input = crate::util::to_ascii_uppercase_cow(nopad_value);
             BASE32_NOPAD.decode(input.as_bytes())
+        } else if expected_len == b64_len {
+            BASE64_NOPAD
+                .decode(nopad_value.as_bytes())
+                .or_else(|_| BASE64URL_NOPAD.decode(nopad_value.as_bytes()))
         } else {
             HEXLOWER_PERMISSIVE.decode(value.as_bytes())
         }
@@ -195,13 +249,17 @@ enum HasherImpl {
     Xxh3(xxhash_rust::xxh3::Xxh3),
     Md5(md5::Md5),
     Sha1(sha1::Sha1),
+    Sha224(sha2::Sha224),
     Sha256(sha2::Sha256),
+    Sha384(sha2::Sha384),
     Sha512(sha2::Sha512),
     Sha3_256(sha3::Sha3_256),
     Sha3_512(sha3::Sha3_512),
     Blake2s(blake2::Blake2s256),
     Blake2b(blake2::Blake2b512),
     Blake3(blake3::Hasher),
+    #[cfg(feature = "digest-registry")]
+    Custom(Box<dyn registry::DigestAlgorithm>),
 }
 
 impl HasherImpl {
@@ -212,13 +270,17 @@ impl HasherImpl {
             Self::Xxh3(hasher) => hasher.update(data),
             Self::Md5(digest) => digest.update(data),
             Self::Sha1(digest) => digest.update(data),
+            Self::Sha224(digest) => digest.update(data),
             Self::Sha256(digest) => digest.update(data),
+            Self::Sha384(digest) => digest.update(data),
             Self::Sha512(digest) => digest.update(data),
             Self::Sha3_256(digest) => digest.update(data),
             Self::Sha3_512(digest) => digest.update(data),
             Self::Blake2s(digest) => digest.update(data),
             Self::Blake2b(digest) => digest.update(data),
             Self::Blake3(digest) => digest::Digest::update(digest, data),
+            #[cfg(feature = "digest-registry")]
+            Self::Custom(algorithm) => algorithm.update(data),
         }
     }
 
@@ -229,13 +291,17 @@ impl HasherImpl {
             Self::Xxh3(hasher) => hasher.digest().to_be_bytes().to_vec(),
             Self::Md5(digest) => digest.finalize().to_vec(),
             Self::Sha1(digest) => digest.finalize().to_vec(),
+            Self::Sha224(digest) => digest.finalize().to_vec(),
             Self::Sha256(digest) => digest.finalize().to_vec(),
+            Self::Sha384(digest) => digest.finalize().to_vec(),
             Self::Sha512(digest) => digest.finalize().to_vec(),
             Self::Sha3_256(digest) => digest.finalize().to_vec(),
             Self::Sha3_512(digest) => digest.finalize().to_vec(),
             Self::Blake2s(digest) => digest.finalize().to_vec(),
             Self::Blake2b(digest) => digest.finalize().to_vec(),
             Self::Blake3(digest) => digest.finalize().to_vec(),
+            #[cfg(feature = "digest-registry")]
+            Self::Custom(mut algorithm) => algorithm.finish(),
         }
     }
 
@@ -269,13 +335,17 @@ impl Hasher {
             AlgorithmName::Xxh3 => HasherImpl::Xxh3(xxhash_rust::xxh3::Xxh3::new()),
             AlgorithmName::Md5 => HasherImpl::Md5(md5::Md5::new()),
             AlgorithmName::Sha1 => HasherImpl::Sha1(sha1::Sha1::new()),
+            AlgorithmName::Sha224 => HasherImpl::Sha224(sha2::Sha224::new()),
             AlgorithmName::Sha256 => HasherImpl::Sha256(sha2::Sha256::new()),
+            AlgorithmName::Sha384 => HasherImpl::Sha384(sha2::Sha384::new()),
             AlgorithmName::Sha512 => HasherImpl::Sha512(sha2::Sha512::new()),
             AlgorithmName::Sha3_256 => HasherImpl::Sha3_256(sha3::Sha3_256::new()),
             AlgorithmName::Sha3_512 => HasherImpl::Sha3_512(sha3::Sha3_512::new()),
             AlgorithmName::Blake2s => HasherImpl::Blake2s(blake2::Blake2s256::new()),
             AlgorithmName::Blake2b => HasherImpl::Blake2b(blake2::Blake2b512::new()),
             AlgorithmName::Blake3 => HasherImpl::Blake3(blake3::Hasher::new()),
+            #[cfg(feature = "digest-registry")]
+            AlgorithmName::Custom(id) => HasherImpl::Custom(id.new_algorithm()),
         }
     }
     pub fn algorithm(&self) -> AlgorithmName {
@@ -283,6 +353,10 @@ impl Hasher {
     }
 
     pub fn update(&mut self, data: &[u8]) {
+        let _span =
+            tracing::trace_span!("hash", algorithm = %self.algorithm, input_len = data.len())
+                .entered();
+
         self.inner.update(data);
     }
 
@@ -344,6 +418,117 @@ impl MultiHasher {
     }
 }
 
+/// Hashing state that can be updated with data.
+pub trait Update {
+    fn update(&mut self, data: &[u8]);
+}
+
+impl Update for Hasher {
+    fn update(&mut self, data: &[u8]) {
+        Hasher::update(self, data);
+    }
+}
+
+impl Update for MultiHasher {
+    fn update(&mut self, data: &[u8]) {
+        MultiHasher::update(self, data);
+    }
+}
+
+impl<H: Update + ?Sized> Update for &mut H {
+    fn update(&mut self, data: &[u8]) {
+        (**self).update(data);
+    }
+}
+
+/// Writer that updates a hasher with the data written to the underlying
+/// writer.
+pub struct DigestWriter<W: Write, H: Update> {
+    inner: W,
+    hasher: H,
+}
+
+impl<W: Write, H: Update> DigestWriter<W, H> {
+    pub fn new(inner: W, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn hasher_mut(&mut self) -> &mut H {
+        &mut self.hasher
+    }
+
+    pub fn into_inner(self) -> (W, H) {
+        (self.inner, self.hasher)
+    }
+}
+
+impl<W: Write, H: Update> Write for DigestWriter<W, H> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let write_length = self.inner.write(buf)?;
+        self.hasher.update(&buf[0..write_length]);
+
+        Ok(write_length)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader that updates a hasher with the data read from the underlying
+/// reader.
+pub struct DigestReader<R: Read, H: Update> {
+    inner: R,
+    hasher: H,
+}
+
+impl<R: Read, H: Update> DigestReader<R, H> {
+    pub fn new(inner: R, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn hasher_mut(&mut self) -> &mut H {
+        &mut self.hasher
+    }
+
+    pub fn into_inner(self) -> (R, H) {
+        (self.inner, self.hasher)
+    }
+}
+
+impl<R: Read, H: Update> Read for DigestReader<R, H> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read_length = self.inner.read(buf)?;
+        self.hasher.update(&buf[0..read_length]);
+
+        Ok(read_length)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -389,6 +574,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_hash_sha224_sha384() {
+        let digest =
+            Digest::from_str("sha-224:23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7")
+                .unwrap();
+        let mut hasher = Hasher::new(digest.algorithm());
+        hasher.update(b"abc");
+        assert_eq!(digest.algorithm(), AlgorithmName::Sha224);
+        assert_eq!(hasher.finish(), digest.value());
+
+        let digest = Digest::from_str(
+            "SHA-384:cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed\
+            8086072ba1e7cc2358baeca134c825a7",
+        )
+        .unwrap();
+        let mut hasher = Hasher::new(digest.algorithm());
+        hasher.update(b"abc");
+        assert_eq!(digest.algorithm(), AlgorithmName::Sha384);
+        assert_eq!(hasher.finish(), digest.value());
+        assert_eq!(
+            digest.to_string(),
+            "sha384:cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed\
+            8086072ba1e7cc2358baeca134c825a7"
+        );
+    }
+
+    #[test]
+    fn test_parse_base64() {
+        let expected = Digest::from_str(
+            "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        )
+        .unwrap();
+
+        for value in [
+            "sha256:ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=",
+            "sha256:ungWv48Bz-pBQUDeXa4iI7ADYaOWF3qctBD_YfIAFa0",
+        ] {
+            let digest = Digest::from_str(value).unwrap();
+            assert_eq!(digest.value(), expected.value());
+        }
+
+        assert_eq!(
+            expected.to_string_with_encoding(DigestEncoding::Base64),
+            "sha256:ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="
+        );
+
+        let digest = Digest::from_str("crc32:NMGiXw==").unwrap();
+        assert_eq!(digest.value(), b"\x34\xc1\xa2\x5f");
+        let digest = Digest::from_str("crc32:GTA2EXY=").unwrap();
+        assert_eq!(digest.value(), b"\x34\xc1\xa2\x5f");
+    }
+
     #[test]
     fn test_to_string() {
         let digest = Digest::new(
@@ -399,6 +636,30 @@ mod tests {
         assert_eq!(digest.to_string(), "sha1:VL2MMHO4YXUKFWV63YHTWSBM3GXKSQ2N");
     }
 
+    #[test]
+    fn test_digest_writer_reader() {
+        let data = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        let expected = b"\x84\x98>D\x1c;\xd2n\xba\xaeJ\xa1\xf9Q)\xe5\xe5Fp\xf1";
+
+        let mut writer = DigestWriter::new(Vec::new(), Hasher::new(AlgorithmName::Sha1));
+        writer.write_all(&data[0..10]).unwrap();
+        writer.write_all(&data[10..]).unwrap();
+        let (output, mut hasher) = writer.into_inner();
+
+        assert_eq!(output, data);
+        assert_eq!(&hasher.finish(), expected);
+
+        let mut hasher = MultiHasher::new(&[AlgorithmName::Sha1, AlgorithmName::Crc32]);
+        let mut reader = DigestReader::new(data.as_slice(), &mut hasher);
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        let values = hasher.finish();
+
+        assert_eq!(output, data);
+        assert_eq!(&values[&AlgorithmName::Sha1], expected);
+        assert_eq!(values.len(), 2);
+    }
+
     #[test]
     fn test_hash_sha1() {
         let mut hasher = Hasher::new(AlgorithmName::Sha1);
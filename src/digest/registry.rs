@@ -0,0 +1,154 @@
+//! Runtime registry of additional hashing algorithms.
+//!
+//! Algorithms that are not built into this crate can be implemented with the
+//! [`DigestAlgorithm`] trait and added with [`register()`]. The returned
+//! [`AlgorithmName`] can be used like a built-in algorithm with
+//! [`Hasher`](super::Hasher), [`Digest`](super::Digest), and the verifier.
+//! Registered algorithms are also found by name with
+//! `AlgorithmName::from_str()`, so digest fields using them can be read and
+//! checked.
+use std::sync::{Arc, RwLock};
+
+use super::AlgorithmName;
+
+type NewAlgorithmFn = dyn Fn() -> Box<dyn DigestAlgorithm> + Send + Sync;
+
+static ALGORITHMS: RwLock<Vec<Registration>> = RwLock::new(Vec::new());
+
+struct Registration {
+    name: &'static str,
+    output_len: usize,
+    new_algorithm: Arc<NewAlgorithmFn>,
+}
+
+/// Identifies a registered algorithm in [`AlgorithmName::Custom`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AlgorithmId(usize);
+
+impl AlgorithmId {
+    /// Returns the lowercase name of the registered algorithm.
+    pub fn name(&self) -> &'static str {
+        ALGORITHMS.read().unwrap()[self.0].name
+    }
+
+    /// Returns the number of bytes of a digest value.
+    pub fn output_len(&self) -> usize {
+        ALGORITHMS.read().unwrap()[self.0].output_len
+    }
+
+    /// Returns a new hashing state of the registered algorithm.
+    pub fn new_algorithm(&self) -> Box<dyn DigestAlgorithm> {
+        let new_algorithm = ALGORITHMS.read().unwrap()[self.0].new_algorithm.clone();
+
+        new_algorithm()
+    }
+}
+
+/// Hashing state of an algorithm that can be registered at runtime.
+pub trait DigestAlgorithm: Send + 'static {
+    /// Returns the name used by `Display` and `FromStr` of [`AlgorithmName`].
+    ///
+    /// The name is matched case-insensitively.
+    fn name(&self) -> &str;
+
+    /// Returns the number of bytes of a digest value.
+    fn output_len(&self) -> usize;
+
+    fn update(&mut self, data: &[u8]);
+
+    /// Returns the digest value of the data given so far.
+    ///
+    /// The state is not used after this function is called.
+    fn finish(&mut self) -> Vec<u8>;
+}
+
+/// Adds an algorithm and returns its name.
+///
+/// The function is called to create a hashing state whenever data is hashed
+/// with the algorithm. If algorithms share a name, the last registered
+/// algorithm is found by `AlgorithmName::from_str()`.
+pub fn register<A, F>(new_algorithm: F) -> AlgorithmName
+where
+    A: DigestAlgorithm,
+    F: Fn() -> A + Send + Sync + 'static,
+{
+    let example = new_algorithm();
+    // Names are kept until the program exits so that
+    // `AlgorithmName::as_str()` can return them.
+    let name: &'static str = example.name().to_ascii_lowercase().leak();
+    let output_len = example.output_len();
+
+    let mut algorithms = ALGORITHMS.write().unwrap();
+    algorithms.push(Registration {
+        name,
+        output_len,
+        new_algorithm: Arc::new(move || Box::new(new_algorithm())),
+    });
+
+    AlgorithmName::Custom(AlgorithmId(algorithms.len() - 1))
+}
+
+/// Returns the name of the registered algorithm with the lowercase name.
+pub fn find(name: &str) -> Option<AlgorithmName> {
+    let algorithms = ALGORITHMS.read().unwrap();
+
+    algorithms
+        .iter()
+        .rposition(|algorithm| algorithm.name == name)
+        .map(|index| AlgorithmName::Custom(AlgorithmId(index)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::digest::{Digest, Hasher};
+
+    use super::*;
+
+    /// Sums the bytes.
+    struct SumAlgorithm {
+        sum: u16,
+    }
+
+    impl DigestAlgorithm for SumAlgorithm {
+        fn name(&self) -> &str {
+            "Test-Sum"
+        }
+
+        fn output_len(&self) -> usize {
+            2
+        }
+
+        fn update(&mut self, data: &[u8]) {
+            for byte in data {
+                self.sum = self.sum.wrapping_add(*byte as u16);
+            }
+        }
+
+        fn finish(&mut self) -> Vec<u8> {
+            self.sum.to_be_bytes().to_vec()
+        }
+    }
+
+    #[test]
+    fn test_custom_algorithm() {
+        let algorithm = register(|| SumAlgorithm { sum: 0 });
+
+        assert_eq!(AlgorithmName::from_str("test-sum").unwrap(), algorithm);
+        assert_eq!(AlgorithmName::from_str("TEST-SUM").unwrap(), algorithm);
+        assert_eq!(algorithm.to_string(), "test-sum");
+        assert_eq!(algorithm.output_len(), 2);
+
+        let mut hasher = Hasher::new(algorithm);
+        hasher.update(b"abc");
+        assert_eq!(hasher.finish(), b"\x01\x26");
+        hasher.update(b"a");
+        assert_eq!(hasher.finish(), b"\x00\x61");
+
+        let digest = Digest::from_str("test-sum:0126").unwrap();
+        assert_eq!(digest.algorithm(), algorithm);
+        assert_eq!(digest.value(), b"\x01\x26");
+        assert_eq!(digest.to_string(), "test-sum:0126");
+    }
+}
@@ -20,7 +20,18 @@ pub enum GeneralError {
     Storage(#[from] StorageError),
 
     #[error(transparent)]
-    Io(#[from] std::io::Error),
+    Io(std::io::Error),
+}
+
+impl From<std::io::Error> for GeneralError {
+    fn from(value: std::io::Error) -> Self {
+        // Protocol errors raised within readers and writers are passed
+        // through as I/O errors.
+        match value.downcast::<ProtocolError>() {
+            Ok(error) => Self::Protocol(error),
+            Err(value) => Self::Io(value),
+        }
+    }
 }
 
 impl GeneralError {
@@ -310,6 +321,10 @@ impl ProtocolError {
         Self::new(ProtocolErrorKind::Other).with_source(error)
     }
 
+    pub fn kind(&self) -> &ProtocolErrorKind {
+        &self.kind
+    }
+
     pub fn with_source<T: Into<Box<dyn std::error::Error + Send + Sync>>>(
         mut self,
         source: T,
@@ -342,9 +357,14 @@ pub enum ProtocolErrorKind {
     UnsupportedDigest,
     InvalidBaseEncodedValue,
     UnsupportedSegmentedRecord,
+    InvalidSegment,
     NotFound,
     NoContent,
     AmbiguousSpecification,
+    DecompressionLimitExceeded,
+    DuplicateField,
+    InvalidHeader,
+    ContentTooBig,
     Other,
 }
 
@@ -365,9 +385,14 @@ impl Display for ProtocolErrorKind {
             Self::UnsupportedDigest => "unsupported digest",
             Self::InvalidBaseEncodedValue => "invalid base encoded value",
             Self::UnsupportedSegmentedRecord => "unsupported segmented record",
+            Self::InvalidSegment => "invalid segment",
             Self::NotFound => "not found",
             Self::NoContent => "no content",
             Self::AmbiguousSpecification => "ambiguous specification",
+            Self::DecompressionLimitExceeded => "decompression limit exceeded",
+            Self::DuplicateField => "duplicate field",
+            Self::InvalidHeader => "invalid header",
+            Self::ContentTooBig => "content too big",
             Self::Other => "other",
         };
 
@@ -454,3 +479,28 @@ impl From<redb::CommitError> for StorageError {
         Self::new().with_source(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_general_error_from_io_error() {
+        let error = std::io::Error::other(ProtocolError::new(ProtocolErrorKind::InvalidHeader));
+        let error = GeneralError::from(error);
+
+        assert!(matches!(
+            error,
+            GeneralError::Protocol(ref error) if matches!(error.kind(), ProtocolErrorKind::InvalidHeader)
+        ));
+
+        let error = std::io::Error::other(ParseError::new(ParseErrorKind::Syntax));
+        assert!(matches!(GeneralError::from(error), GeneralError::Io(_)));
+
+        let error = std::io::Error::from(std::io::ErrorKind::UnexpectedEof);
+        assert!(matches!(
+            GeneralError::from(error),
+            GeneralError::Io(ref error) if error.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+    }
+}
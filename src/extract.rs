@@ -5,9 +5,15 @@
 
 use std::{borrow::Cow, io::Write};
 
-use crate::error::{GeneralError, ProtocolError, ProtocolErrorKind};
-use crate::header::{fields::FieldsExt, WarcHeader};
+use data_encoding::HEXLOWER;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::GeneralError;
+use crate::header::{fields::FieldsExt, WarcHeader, WarcType};
+use crate::http::h1::header::MessageHeader;
 use crate::http::h1::recv::{Receiver as HttpDecoder, ReceiverEvent};
+use crate::warning::{Warning, WarningSink};
 
 pub const FILENAME_CONFLICT_MARKER: char = '⬧';
 
@@ -15,8 +21,26 @@ pub const FILENAME_CONFLICT_MARKER: char = '⬧';
 enum State {
     None,
     HttpResponse,
+    HttpRequest,
     Resource,
     Conversion,
+    Metadata,
+}
+
+/// Directory structure of the file paths of extracted content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PathLayout {
+    /// Directories named by the scheme, host, and path of the URL.
+    #[default]
+    Url,
+    /// A single directory with files named by the last path segment and
+    /// query of the URL.
+    Flat,
+    /// Files named by the SHA-256 digest of the content in two levels of
+    /// directories named by the first two bytes of the digest.
+    ///
+    /// The filename extension of the URL is kept.
+    Sha256Prefix,
 }
 
 #[derive(Debug)]
@@ -26,6 +50,21 @@ enum Decoder {
     Http(HttpDecoder),
 }
 
+/// Information about the record of extracted content.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractMetadata {
+    /// Value of the WARC-Record-ID field.
+    pub record_id: String,
+    /// Value of the WARC-Target-URI field without deliminators.
+    pub target_uri: String,
+    /// Value of the WARC-Date field.
+    pub date: Option<String>,
+    /// Status code of the HTTP response.
+    pub http_status: Option<u16>,
+    /// Name-value pairs of the HTTP response header fields.
+    pub http_headers: Vec<(String, String)>,
+}
+
 /// Extracts content from a WARC record.
 ///
 /// Supported content:
@@ -34,16 +73,35 @@ enum Decoder {
 /// * Resources
 /// * Conversions
 /// * Records marked as truncated
+/// * Segmented records
 ///
-/// Unsupported content:
+/// Opt-in content:
 ///
-/// * Content stored in segmented records
+/// * HTTP request bodies ([`Self::with_requests()`])
+/// * Metadata records ([`Self::with_metadata_records()`])
+///
+/// Conversions, requests, and metadata records have a suffix appended to
+/// the filename to distinguish them from the original content.
+///
+/// For segmented records, read the header of the first segment and then
+/// provide the blocks of all the segments in order to
+/// [`Self::extract_data()`]. The headers of `continuation` records should not
+/// be read. See [`crate::segment`].
 #[derive(Debug)]
 pub struct WarcExtractor {
     state: State,
     decoder: Decoder,
     is_truncated: bool,
     output_path: Vec<String>,
+    metadata: ExtractMetadata,
+    http_header: Option<MessageHeader>,
+    warnings: WarningSink,
+    layout: PathLayout,
+    content_hasher: Option<Sha256>,
+    extension: Option<String>,
+    is_requests_enabled: bool,
+    is_metadata_records_enabled: bool,
+    is_content_decoding: bool,
 }
 
 impl WarcExtractor {
@@ -53,58 +111,138 @@ impl WarcExtractor {
             decoder: Decoder::None,
             is_truncated: false,
             output_path: Vec::new(),
+            metadata: ExtractMetadata::default(),
+            http_header: None,
+            warnings: WarningSink::default(),
+            layout: PathLayout::default(),
+            content_hasher: None,
+            extension: None,
+            is_requests_enabled: false,
+            is_metadata_records_enabled: false,
+            is_content_decoding: true,
         }
     }
 
+    /// Sets the receiver of non-fatal observations about the records.
+    pub fn with_warning_sink(mut self, warnings: WarningSink) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Sets the directory structure of [`Self::file_path_components()`].
+    pub fn with_layout(mut self, layout: PathLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Sets whether the bodies of HTTP requests are extracted.
+    pub fn with_requests(mut self, value: bool) -> Self {
+        self.is_requests_enabled = value;
+        self
+    }
+
+    /// Sets whether the blocks of `metadata` records are extracted.
+    pub fn with_metadata_records(mut self, value: bool) -> Self {
+        self.is_metadata_records_enabled = value;
+        self
+    }
+
+    /// Sets whether the Content-Encoding of HTTP messages, such as gzip, is
+    /// decoded.
+    ///
+    /// When false, the content is extracted as sent by the server. The
+    /// default is true.
+    pub fn with_content_decoding(mut self, value: bool) -> Self {
+        self.is_content_decoding = value;
+        self
+    }
+
     pub fn reset(&mut self) {
         self.state = State::None;
         self.decoder = Decoder::None;
         self.is_truncated = false;
         self.output_path.clear();
+        self.metadata = ExtractMetadata::default();
+        self.http_header = None;
+        self.content_hasher = None;
+        self.extension = None;
     }
 
     pub fn read_header(&mut self, header: &WarcHeader) -> Result<(), GeneralError> {
-        if header.fields.contains_name("WARC-Segment-Number") {
-            return Err(ProtocolError::new(ProtocolErrorKind::UnsupportedSegmentedRecord).into());
-        }
-
-        let warc_type = header.fields.get_or_default("WARC-Type");
+        let warc_type = header
+            .warc_type()
+            .unwrap_or_else(|| WarcType::Unknown(String::new()));
         let media_type = header.fields.get_media_type("Content-Type");
         self.is_truncated = header.fields.contains_name("WARC-Truncated");
-        let mut is_http_response = false;
+        let mut http_msgtype = None;
 
         if let Some(media_type) = media_type {
             let media_type = media_type?;
-            is_http_response = media_type.type_ == "application"
-                && media_type.subtype == "http"
-                && media_type
-                    .parameters
-                    .get("msgtype")
-                    .map(String::as_str)
-                    .unwrap_or_default()
-                    == "response";
+
+            if media_type.type_ == "application" && media_type.subtype == "http" {
+                http_msgtype = media_type.parameters.get("msgtype").cloned();
+            }
         }
+        let is_http_response = http_msgtype.as_deref() == Some("response");
+        let is_http_request = http_msgtype.as_deref() == Some("request");
+        let is_empty = header.content_length().is_ok_and(|length| length == 0);
         let url = header
             .fields
             .get_url_str("WARC-Target-URI")
             .unwrap_or_default();
 
-        if warc_type == "response" && is_http_response && !url.is_empty() {
+        self.metadata = ExtractMetadata {
+            record_id: header.fields.get_or_default("WARC-Record-ID").to_string(),
+            target_uri: url.to_string(),
+            date: header.fields.get("WARC-Date").cloned(),
+            ..Default::default()
+        };
+        self.http_header = None;
+
+        if matches!(
+            warc_type,
+            WarcType::Response | WarcType::Resource | WarcType::Conversion
+        ) || (warc_type == WarcType::Request && self.is_requests_enabled)
+            || (warc_type == WarcType::Metadata && self.is_metadata_records_enabled)
+        {
+            self.check_target_uri(url);
+        }
+
+        if is_empty {
+            // Some generators write records without a block.
+            self.state = State::None;
+        } else if warc_type == WarcType::Response && is_http_response && !url.is_empty() {
             self.state = State::HttpResponse;
-            self.decoder = Decoder::Http(HttpDecoder::new());
-            self.output_path = url_to_path_components(url);
-        } else if warc_type == "resource" && !url.is_empty() {
+            self.decoder =
+                Decoder::Http(HttpDecoder::new().with_content_decoding(self.is_content_decoding));
+            self.set_output_path(url);
+        } else if warc_type == WarcType::Resource && !url.is_empty() {
             self.state = State::Resource;
             self.decoder = Decoder::Identity;
-            self.output_path = url_to_path_components(url);
-        } else if warc_type == "conversion" && !url.is_empty() {
+            self.set_output_path(url);
+        } else if warc_type == WarcType::Conversion && !url.is_empty() {
             self.state = State::Conversion;
             self.decoder = Decoder::Identity;
-            self.output_path = url_to_path_components(url);
-            if let Some(last) = self.output_path.last_mut() {
-                last.push(FILENAME_CONFLICT_MARKER);
-                last.push_str("conversion");
-            }
+            self.set_output_path(url);
+            self.push_filename_suffix("conversion");
+        } else if warc_type == WarcType::Request
+            && self.is_requests_enabled
+            && is_http_request
+            && !url.is_empty()
+        {
+            self.state = State::HttpRequest;
+            self.decoder =
+                Decoder::Http(HttpDecoder::new().with_content_decoding(self.is_content_decoding));
+            self.set_output_path(url);
+            self.push_filename_suffix("request");
+        } else if warc_type == WarcType::Metadata
+            && self.is_metadata_records_enabled
+            && !url.is_empty()
+        {
+            self.state = State::Metadata;
+            self.decoder = Decoder::Identity;
+            self.set_output_path(url);
+            self.push_filename_suffix("metadata");
         } else {
             self.state = State::None;
         }
@@ -112,15 +250,78 @@ impl WarcExtractor {
         Ok(())
     }
 
+    fn push_filename_suffix(&mut self, suffix: &str) {
+        if let Some(last) = self.output_path.last_mut() {
+            last.push(FILENAME_CONFLICT_MARKER);
+            last.push_str(suffix);
+        }
+    }
+
+    fn set_output_path(&mut self, url: &str) {
+        match self.layout {
+            PathLayout::Url => {
+                self.output_path = url_to_path_components(url);
+            }
+            PathLayout::Flat => {
+                self.output_path = vec![url_to_flat_filename(url)];
+            }
+            PathLayout::Sha256Prefix => {
+                // The path is known once the content is extracted.
+                self.output_path.clear();
+                self.content_hasher = Some(Sha256::new());
+                self.extension = url_extension(url);
+            }
+        }
+    }
+
+    fn check_target_uri(&self, url: &str) {
+        let record_id = &self.metadata.record_id;
+
+        if url.is_empty() {
+            self.warnings.emit(Warning::MissingField {
+                record_id: record_id.clone(),
+                name: "WARC-Target-URI".to_string(),
+            });
+        } else if url::Url::parse(url).is_err() {
+            // The file path is made from the value as is.
+            self.warnings.emit(Warning::InvalidTargetUri {
+                record_id: record_id.clone(),
+            });
+        }
+    }
+
     /// Returns whether the record has supported extractable contents.
+    ///
+    /// Records with an empty block have no content.
     pub fn has_content(&self) -> bool {
         self.state != State::None
     }
 
     /// Returns file components suitable safe for saving to a filesystem for
     /// most operating systems (Windows and Unix).
+    ///
+    /// For [`PathLayout::Sha256Prefix`], the components are complete once
+    /// all the data has been given to [`Self::extract_data()`].
     pub fn file_path_components(&self) -> Vec<String> {
-        self.output_path.clone()
+        match &self.content_hasher {
+            Some(hasher) => {
+                let digest = HEXLOWER.encode(&hasher.clone().finalize());
+                let mut filename = digest.clone();
+
+                if let Some(extension) = &self.extension {
+                    filename.push('.');
+                    filename.push_str(extension);
+                }
+
+                vec![digest[0..2].to_string(), digest[2..4].to_string(), filename]
+            }
+            None => self.output_path.clone(),
+        }
+    }
+
+    /// Returns whether the content is the body of a HTTP request.
+    pub fn is_request(&self) -> bool {
+        self.state == State::HttpRequest
     }
 
     /// Returns whether the record is marked as not containing a complete file.
@@ -128,6 +329,22 @@ impl WarcExtractor {
         self.is_truncated
     }
 
+    /// Returns information about the record.
+    ///
+    /// The HTTP fields are populated once the HTTP header has been read
+    /// by [`Self::extract_data()`].
+    pub fn metadata(&self) -> &ExtractMetadata {
+        &self.metadata
+    }
+
+    /// Returns the parsed HTTP header of a HTTP response record.
+    ///
+    /// The header is available once it has been read by
+    /// [`Self::extract_data()`].
+    pub fn http_header(&self) -> Option<&MessageHeader> {
+        self.http_header.as_ref()
+    }
+
     pub fn extract_data<W: Write>(
         &mut self,
         block_data: &[u8],
@@ -135,15 +352,34 @@ impl WarcExtractor {
     ) -> Result<(), GeneralError> {
         match &mut self.decoder {
             Decoder::None => Ok(()),
-            Decoder::Identity => Ok(output.write_all(block_data)?),
+            Decoder::Identity => {
+                if let Some(hasher) = &mut self.content_hasher {
+                    hasher.update(block_data);
+                }
+                Ok(output.write_all(block_data)?)
+            }
             Decoder::Http(decoder) => {
                 decoder.recv_data(block_data);
 
                 loop {
                     match decoder.get_event()? {
                         ReceiverEvent::WantData => break,
-                        ReceiverEvent::Header(_header) => {}
+                        ReceiverEvent::Header(header) => {
+                            self.metadata.http_status =
+                                header.start_line.as_status().map(|line| line.status_code);
+                            self.metadata.http_headers = header
+                                .fields
+                                .iter()
+                                .map(|(name, value)| {
+                                    (name.to_string(), value.to_string_lossy().to_string())
+                                })
+                                .collect();
+                            self.http_header = Some(header);
+                        }
                         ReceiverEvent::Body(data) => {
+                            if let Some(hasher) = &mut self.content_hasher {
+                                hasher.update(data);
+                            }
                             output.write_all(data)?;
                         }
                         ReceiverEvent::Trailer(_trailer) => {}
@@ -196,6 +432,46 @@ pub fn url_to_path_components(url: &str) -> Vec<String> {
     components
 }
 
+/// Returns a filename from the last path segment and the query of the URL.
+///
+/// The host is used if the URL does not have a path.
+pub fn url_to_flat_filename(url: &str) -> String {
+    let Ok(url) = url::Url::parse(url) else {
+        return escape_component(url).to_string();
+    };
+
+    let mut filename = url
+        .path_segments()
+        .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+        .or_else(|| url.host_str())
+        .unwrap_or(url.path())
+        .to_string();
+
+    if let Some(query) = url.query() {
+        filename.push('?');
+        filename.push_str(query);
+    }
+
+    escape_component(&filename).to_string()
+}
+
+/// Returns the filename extension of the last path segment of the URL if it
+/// is short and alphanumeric.
+fn url_extension(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    let segment = url.path_segments()?.next_back()?;
+    let (_, extension) = segment.rsplit_once('.')?;
+
+    if !extension.is_empty()
+        && extension.len() <= 10
+        && extension.bytes().all(|c| c.is_ascii_alphanumeric())
+    {
+        Some(extension.to_string())
+    } else {
+        None
+    }
+}
+
 const ESCAPE_SET: percent_encoding::AsciiSet = percent_encoding::CONTROLS
     .add(b'/')
     .add(b'\\')
@@ -305,4 +581,150 @@ mod tests {
         assert_eq!(escape_component("\u{00ff}"), "%C3%BF");
         assert_eq!(escape_component(&"a".repeat(300)), "a".repeat(200));
     }
+
+    #[test]
+    fn test_url_to_flat_filename() {
+        assert_eq!(
+            url_to_flat_filename("http://example.com/a/b.html"),
+            "b.html"
+        );
+        assert_eq!(url_to_flat_filename("http://example.com/a/"), "a");
+        assert_eq!(url_to_flat_filename("http://example.com/"), "example.com");
+        assert_eq!(url_to_flat_filename("http://example.com/a?b=c"), "a%3Fb=c");
+        assert_eq!(url_to_flat_filename("not a url"), "not a url");
+    }
+
+    #[test]
+    fn test_extract_layout() {
+        let mut header = WarcHeader::new(5, "resource");
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "http://example.com/a/b.txt".to_string(),
+        );
+
+        let mut extractor = WarcExtractor::new().with_layout(PathLayout::Flat);
+        extractor.read_header(&header).unwrap();
+
+        assert_eq!(extractor.file_path_components(), vec!["b.txt"]);
+
+        let mut extractor = WarcExtractor::new().with_layout(PathLayout::Sha256Prefix);
+        extractor.read_header(&header).unwrap();
+        extractor.extract_data(b"hello", std::io::sink()).unwrap();
+
+        assert_eq!(
+            extractor.file_path_components(),
+            vec![
+                "2c",
+                "f2",
+                "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824.txt"
+            ]
+        );
+
+        extractor.reset();
+        assert!(extractor.file_path_components().is_empty());
+    }
+
+    #[test]
+    fn test_extract_request_metadata_records() {
+        let block = b"POST /a HTTP/1.1\r\nHost: example.com\r\nContent-Length: 3\r\n\r\na=b";
+        let mut header = WarcHeader::new(block.len() as u64, "request");
+        header.fields.insert(
+            "Content-Type".to_string(),
+            "application/http;msgtype=request".to_string(),
+        );
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "http://example.com/a".to_string(),
+        );
+
+        let mut extractor = WarcExtractor::new();
+        extractor.read_header(&header).unwrap();
+
+        assert!(!extractor.has_content());
+
+        let mut extractor = WarcExtractor::new()
+            .with_requests(true)
+            .with_metadata_records(true);
+        extractor.read_header(&header).unwrap();
+
+        assert!(extractor.is_request());
+        assert_eq!(
+            extractor.file_path_components(),
+            vec!["http", "example.com", "a⬧request"]
+        );
+
+        let mut output = Vec::new();
+        extractor.extract_data(block, &mut output).unwrap();
+
+        assert_eq!(output, b"a=b");
+
+        header
+            .fields
+            .insert("WARC-Type".to_string(), "metadata".to_string());
+        header.fields.insert(
+            "Content-Type".to_string(),
+            "application/warc-fields".to_string(),
+        );
+        extractor.read_header(&header).unwrap();
+
+        assert!(extractor.has_content());
+        assert!(!extractor.is_request());
+        assert_eq!(
+            extractor.file_path_components(),
+            vec!["http", "example.com", "a⬧metadata"]
+        );
+
+        header.set_content_length(0);
+        extractor.read_header(&header).unwrap();
+
+        assert!(!extractor.has_content());
+    }
+
+    #[test]
+    fn test_extract_metadata() {
+        let block =
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello";
+        let mut header = WarcHeader::new(block.len() as u64, "response");
+        header.fields.insert(
+            "Content-Type".to_string(),
+            "application/http;msgtype=response".to_string(),
+        );
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "http://example.com/a".to_string(),
+        );
+
+        let mut extractor = WarcExtractor::new();
+        extractor.read_header(&header).unwrap();
+
+        assert_eq!(extractor.metadata().target_uri, "http://example.com/a");
+        assert_eq!(
+            extractor.metadata().record_id,
+            header.fields.get_or_default("WARC-Record-ID")
+        );
+        assert!(extractor.metadata().date.is_some());
+        assert_eq!(extractor.metadata().http_status, None);
+
+        let mut output = Vec::new();
+        extractor.extract_data(block, &mut output).unwrap();
+
+        assert_eq!(output, b"hello");
+        assert_eq!(extractor.metadata().http_status, Some(200));
+        assert_eq!(
+            extractor.metadata().http_headers,
+            vec![
+                ("Content-Type".to_string(), "text/plain".to_string()),
+                ("Content-Length".to_string(), "5".to_string())
+            ]
+        );
+
+        let http_header = extractor.http_header().unwrap();
+        assert_eq!(http_header.start_line.as_status().unwrap().status_code, 200);
+        assert!(http_header.fields.contains_name("Content-Type"));
+
+        extractor.reset();
+
+        assert_eq!(extractor.metadata(), &ExtractMetadata::default());
+        assert!(extractor.http_header().is_none());
+    }
 }
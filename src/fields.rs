@@ -98,6 +98,14 @@ impl<N: EqIcase, V> FieldMap<N, V> {
             .retain(|(n, _v)| !n.borrow().eq_ignore_ascii_case(name));
     }
 
+    /// Keeps only the fields for which the function returns true.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&N, &V) -> bool,
+    {
+        self.fields.retain(|(n, v)| f(n, v))
+    }
+
     pub fn contains_name<Q>(&self, name: &Q) -> bool
     where
         Q: EqIcase + ?Sized,
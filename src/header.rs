@@ -1,24 +1,181 @@
 //! WARC headers
-use std::io::Write;
+use std::{convert::Infallible, fmt::Display, io::Write, str::FromStr};
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use url::Url;
 
 use crate::{
-    error::{ParseError, ProtocolError, ProtocolErrorKind},
+    error::{ParseError, ParseErrorKind, ProtocolError, ProtocolErrorKind},
     fields::FieldMap,
+    http::h1::header::Hstring,
 };
 
+use self::{fields::FieldsExt, record_id::RecordId};
+
+pub use builder::{WarcHeaderBuilder, WARC_1_0, WARC_1_1};
+
+mod builder;
+pub mod extension;
 pub mod fields;
+pub mod protocol;
+pub mod record_id;
+pub mod uri;
 
 pub type WarcFields = FieldMap<String, String>;
 
+/// Fields that the specification does not allow to be repeated.
+pub const UNIQUE_FIELDS: &[&str] = &[
+    "WARC-Record-ID",
+    "Content-Length",
+    "WARC-Date",
+    "WARC-Type",
+    "Content-Type",
+    "WARC-Refers-To",
+    "WARC-Refers-To-Target-URI",
+    "WARC-Refers-To-Date",
+    "WARC-Target-URI",
+    "WARC-Truncated",
+    "WARC-Warcinfo-ID",
+    "WARC-Filename",
+    "WARC-Profile",
+    "WARC-Identified-Payload-Type",
+    "WARC-Segment-Number",
+    "WARC-Segment-Origin-ID",
+    "WARC-Segment-Total-Length",
+    "WARC-IP-Address",
+];
+
+/// How repeated [`UNIQUE_FIELDS`] are handled.
+///
+/// Producers disagree on which value of a repeated field is the intended
+/// one. Functions such as [`FieldMap::get()`] return the first value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateFieldPolicy {
+    /// Keep all the values unchanged.
+    #[default]
+    Keep,
+    /// Remove all but the first value.
+    FirstWins,
+    /// Remove all but the last value.
+    LastWins,
+    /// Return an error.
+    Error,
+}
+
+/// How obsolete line folding in field values is handled.
+///
+/// A field value may continue on the following lines if they start with
+/// spaces or tabs. The WARC 1.0 grammar allows this folding from HTTP/1.0,
+/// while HTTP/1.1 made it obsolete, and archives differ in whether they
+/// write it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineFolding {
+    /// Replace each line break and the whitespace following it with a
+    /// single space.
+    #[default]
+    Unfold,
+    /// Return an error.
+    Reject,
+    /// Keep the line breaks in the values so that the fields are serialized
+    /// unchanged.
+    Preserve,
+}
+
+/// Value of the `WARC-Type` field.
+///
+/// Values are case-sensitive. Values not defined by the specification are
+/// kept as [`WarcType::Unknown`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WarcType {
+    Warcinfo,
+    Response,
+    Resource,
+    Request,
+    Metadata,
+    Revisit,
+    Conversion,
+    Continuation,
+    /// A type not defined by the specification.
+    Unknown(String),
+}
+
+impl WarcType {
+    /// Returns the field value.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Warcinfo => "warcinfo",
+            Self::Response => "response",
+            Self::Resource => "resource",
+            Self::Request => "request",
+            Self::Metadata => "metadata",
+            Self::Revisit => "revisit",
+            Self::Conversion => "conversion",
+            Self::Continuation => "continuation",
+            Self::Unknown(value) => value,
+        }
+    }
+
+    /// Returns whether the type is defined by the specification.
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Self::Unknown(_))
+    }
+}
+
+impl From<&str> for WarcType {
+    fn from(value: &str) -> Self {
+        match value {
+            "warcinfo" => Self::Warcinfo,
+            "response" => Self::Response,
+            "resource" => Self::Resource,
+            "request" => Self::Request,
+            "metadata" => Self::Metadata,
+            "revisit" => Self::Revisit,
+            "conversion" => Self::Conversion,
+            "continuation" => Self::Continuation,
+            _ => Self::Unknown(value.to_string()),
+        }
+    }
+}
+
+impl FromStr for WarcType {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s))
+    }
+}
+
+impl Display for WarcType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<WarcType> for String {
+    fn from(value: WarcType) -> Self {
+        match value {
+            WarcType::Unknown(value) => value,
+            value => value.as_str().to_string(),
+        }
+    }
+}
+
 /// Data structure for representing a WARC header.
+///
+/// The struct has private fields, so it is created with
+/// [`empty()`](Self::empty), [`new()`](Self::new), or
+/// [`parse()`](Self::parse) instead of a struct expression.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct WarcHeader {
     /// The version string such as "WARC/1.1".
     pub version: String,
     /// The name-value fields of the header.
     pub fields: WarcFields,
+    /// Original values of the fields that are not valid UTF-8.
+    opaque_fields: FieldMap<String, Vec<u8>>,
+    /// Handling of folded lines the header was parsed with.
+    line_folding: LineFolding,
 }
 
 impl WarcHeader {
@@ -29,6 +186,8 @@ impl WarcHeader {
         Self {
             version: String::new(),
             fields: FieldMap::new(),
+            opaque_fields: FieldMap::new(),
+            line_folding: LineFolding::default(),
         }
     }
 
@@ -42,12 +201,12 @@ impl WarcHeader {
     {
         let mut header = WarcHeader::empty();
         header.version = "WARC/1.1".to_string();
-        let uuid = uuid::Uuid::now_v7();
         let date_now = Utc::now();
 
-        header
-            .fields
-            .insert("WARC-Record-ID".to_string(), format!("<{}>", uuid.urn()));
+        header.fields.insert(
+            "WARC-Record-ID".to_string(),
+            record_id::RecordId::new().to_string(),
+        );
         header
             .fields
             .insert("WARC-Type".to_string(), warc_type.into());
@@ -59,25 +218,71 @@ impl WarcHeader {
         header
     }
 
+    /// Returns a builder of a header of the given record type with the
+    /// mandatory fields filled in.
+    ///
+    /// See [`WarcHeaderBuilder`].
+    pub fn builder(warc_type: WarcType) -> WarcHeaderBuilder {
+        WarcHeaderBuilder::new(warc_type)
+    }
+
     /// Parses a WARC header from the given bytes.
+    ///
+    /// Field values that are not valid UTF-8, such as Latin-1 text, are
+    /// accepted as with [`append_hstring()`](Self::append_hstring). Folded
+    /// lines are unfolded.
     pub fn parse(input: &[u8]) -> Result<Self, ParseError> {
+        Self::parse_with_line_folding(input, LineFolding::Unfold)
+    }
+
+    /// Parses a WARC header from the given bytes with the given handling of
+    /// folded lines.
+    pub fn parse_with_line_folding(
+        input: &[u8],
+        line_folding: LineFolding,
+    ) -> Result<Self, ParseError> {
         let (remain, version) = crate::parse::warc::version_line(input)?;
 
         let mut header = Self::empty();
         header.version = String::from_utf8(version.to_vec())?;
+        header.line_folding = line_folding;
 
         let (_remain, pairs) = crate::parse::fields::field_pairs(remain)?;
 
         for pair in pairs {
             let name = String::from_utf8(pair.name.to_vec())?;
-            let value = String::from_utf8(crate::parse::remove_line_folding(pair.value).to_vec())?;
+            let value = match line_folding {
+                LineFolding::Unfold => crate::parse::remove_line_folding(pair.value).into_owned(),
+                LineFolding::Reject if is_folded(pair.value) => {
+                    return Err(ParseError::new(ParseErrorKind::Syntax)
+                        .with_source(format!("folded line in field {}", name)))
+                }
+                LineFolding::Reject | LineFolding::Preserve => pair.value.to_vec(),
+            };
 
-            header.fields.insert(name, value);
+            header.append_hstring(name, Hstring::from(value));
         }
 
         Ok(header)
     }
 
+    /// Appends a field with a value that may not be valid UTF-8.
+    ///
+    /// A value that is not valid UTF-8 is stored in `fields` with the invalid
+    /// bytes replaced by U+FFFD. The original bytes are kept and written by
+    /// [`serialize()`](Self::serialize) as long as the value in `fields` is
+    /// unchanged.
+    pub fn append_hstring(&mut self, name: String, value: Hstring) {
+        match value {
+            Hstring::Text(value) => self.fields.append(name, value),
+            Hstring::Opaque(value) => {
+                let text = String::from_utf8_lossy(&value).into_owned();
+                self.opaque_fields.append(name.clone(), value);
+                self.fields.append(name, text);
+            }
+        }
+    }
+
     /// Returns the value of `Content-Length` as an integer.
     pub fn content_length(&self) -> Result<u64, ProtocolError> {
         if let Some(value) = self.fields.get_u64_strict("Content-Length") {
@@ -89,27 +294,170 @@ impl WarcHeader {
         }
     }
 
+    /// Returns the first value of the field as text, or as the original
+    /// bytes if it is not valid UTF-8.
+    pub fn get_hstring(&self, name: &str) -> Option<Hstring> {
+        let value = self.fields.get(name)?;
+
+        Some(match self.opaque_value(name, value) {
+            Some(bytes) => Hstring::Opaque(bytes.to_vec()),
+            None => Hstring::Text(value.clone()),
+        })
+    }
+
+    /// Returns the names of the fields with values that are not valid UTF-8.
+    pub fn opaque_field_names(&self) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter(|(name, value)| self.opaque_value(name, value).is_some())
+            .map(|(name, _value)| name.as_str())
+            .collect()
+    }
+
+    /// Returns the names of the fields with values containing folded lines.
+    ///
+    /// Folded lines are only kept by [`LineFolding::Preserve`].
+    pub fn folded_field_names(&self) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter(|(_name, value)| is_folded(value.as_bytes()))
+            .map(|(name, _value)| name.as_str())
+            .collect()
+    }
+
+    /// Returns the original bytes of a field value that was not valid UTF-8.
+    fn opaque_value<'a>(&'a self, name: &'a str, value: &str) -> Option<&'a [u8]> {
+        self.opaque_fields
+            .get_all(name)
+            .find(|bytes| is_lossy_text_of(bytes, value))
+            .map(Vec::as_slice)
+    }
+
+    /// Returns the bytes of the field value to be serialized.
+    fn value_bytes<'a>(&'a self, name: &'a str, value: &'a str) -> &'a [u8] {
+        self.opaque_value(name, value).unwrap_or(value.as_bytes())
+    }
+
+    /// Returns the value of `WARC-Type`.
+    pub fn warc_type(&self) -> Option<WarcType> {
+        self.fields
+            .get("WARC-Type")
+            .map(|value| WarcType::from(value.as_str()))
+    }
+
+    /// Returns the value of `WARC-Record-ID`.
+    ///
+    /// Returns `None` if the field is missing or is not a valid URI.
+    pub fn record_id(&self) -> Option<RecordId> {
+        self.fields
+            .get("WARC-Record-ID")
+            .and_then(|value| value.parse().ok())
+    }
+
+    /// Returns the value of `WARC-Target-URI`.
+    ///
+    /// Returns `None` if the field is missing or is not a valid URL.
+    pub fn target_uri(&self) -> Option<Url> {
+        self.fields.get_url("WARC-Target-URI").and_then(Result::ok)
+    }
+
+    /// Returns the value of `WARC-Date`.
+    ///
+    /// Returns `None` if the field is missing or is not a valid date.
+    pub fn date(&self) -> Option<DateTime<Utc>> {
+        self.fields
+            .get_date("WARC-Date")
+            .and_then(Result::ok)
+            .map(|date| date.to_utc())
+    }
+
     /// Sets the value of `Content-Length` as an integer.
     pub fn set_content_length(&mut self, value: u64) {
         self.fields
             .insert("Content-Length".to_string(), value.to_string());
     }
 
+    /// Returns the names of [`UNIQUE_FIELDS`] that occur more than once.
+    pub fn duplicate_fields(&self) -> Vec<&'static str> {
+        UNIQUE_FIELDS
+            .iter()
+            .filter(|name| self.fields.get_all(**name).nth(1).is_some())
+            .copied()
+            .collect()
+    }
+
+    /// Applies the policy to repeated [`UNIQUE_FIELDS`].
+    ///
+    /// With [`DuplicateFieldPolicy::Error`], the header is not modified and
+    /// an error is returned if there are repeated fields.
+    pub fn apply_duplicate_field_policy(
+        &mut self,
+        policy: DuplicateFieldPolicy,
+    ) -> Result<(), ProtocolError> {
+        let names = self.duplicate_fields();
+
+        match (policy, names.first()) {
+            (DuplicateFieldPolicy::Keep, _) | (_, None) => return Ok(()),
+            (DuplicateFieldPolicy::Error, Some(name)) => {
+                return Err(ProtocolError::new(ProtocolErrorKind::DuplicateField)
+                    .with_source(format!("repeated field {}", name)))
+            }
+            _ => {}
+        }
+
+        for name in names {
+            let count = self.fields.get_all(name).count();
+            let keep_index = match policy {
+                DuplicateFieldPolicy::LastWins => count - 1,
+                _ => 0,
+            };
+            let mut index = 0;
+
+            self.fields.retain(|field_name, _value| {
+                if !field_name.eq_ignore_ascii_case(name) {
+                    return true;
+                }
+
+                index += 1;
+                index - 1 == keep_index
+            });
+        }
+
+        Ok(())
+    }
+
     /// Returns whether the header is a valid WARC formatted header.
     ///
+    /// Folded lines in field values are only accepted if the header was
+    /// parsed with [`LineFolding::Preserve`].
+    ///
     /// **Important:** This function does not validate whether the *contents* of
     /// the header conforms to the WARC specification!
     pub fn validate(&self) -> Result<(), ParseError> {
         crate::parse::warc::version(self.version.as_bytes())?;
 
+        let multiline = self.line_folding == LineFolding::Preserve;
+
         for (name, value) in &self.fields {
             crate::parse::validate_field_name(name.as_bytes())?;
-            crate::parse::validate_field_value(value.as_bytes(), false)?;
+            crate::parse::validate_field_value(self.value_bytes(name, value), multiline)?;
         }
 
         Ok(())
     }
 
+    /// Returns the number of bytes written by [`serialize()`](Self::serialize).
+    pub fn serialized_len(&self) -> u64 {
+        let fields_len = self
+            .fields
+            .iter()
+            .map(|(name, value)| (name.len() + self.value_bytes(name, value).len()) as u64 + 4)
+            .sum::<u64>();
+
+        // Version line and the blank line at the end of the header
+        self.version.len() as u64 + 4 + fields_len
+    }
+
     /// Write the WARC header as serialized bytes.
     pub fn serialize<W: Write>(&self, mut buf: W) -> std::io::Result<()> {
         buf.write_all(self.version.as_bytes())?;
@@ -118,7 +466,7 @@ impl WarcHeader {
         for (name, value) in &self.fields {
             buf.write_all(name.as_bytes())?;
             buf.write_all(b": ")?;
-            buf.write_all(value.as_bytes())?;
+            buf.write_all(self.value_bytes(name, value))?;
             buf.write_all(b"\r\n")?;
         }
 
@@ -128,6 +476,32 @@ impl WarcHeader {
     }
 }
 
+fn is_folded(value: &[u8]) -> bool {
+    value.contains(&b'\n')
+}
+
+/// Returns whether the text is the bytes with invalid UTF-8 sequences
+/// replaced by U+FFFD, as by [`String::from_utf8_lossy()`].
+fn is_lossy_text_of(bytes: &[u8], text: &str) -> bool {
+    let mut remain = text.as_bytes();
+
+    for chunk in bytes.utf8_chunks() {
+        let Some(rest) = remain.strip_prefix(chunk.valid().as_bytes()) else {
+            return false;
+        };
+        remain = rest;
+
+        if !chunk.invalid().is_empty() {
+            let Some(rest) = remain.strip_prefix("\u{FFFD}".as_bytes()) else {
+                return false;
+            };
+            remain = rest;
+        }
+    }
+
+    remain.is_empty()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,5 +522,211 @@ mod tests {
         header.serialize(&mut buf).unwrap();
 
         assert_eq!(&buf, data.as_bytes());
+        assert_eq!(header.serialized_len(), data.len() as u64);
+    }
+
+    #[test]
+    fn test_is_lossy_text_of() {
+        assert!(is_lossy_text_of(b"", ""));
+        assert!(is_lossy_text_of(b"abc", "abc"));
+        assert!(is_lossy_text_of(b"caf\xe9", "caf\u{fffd}"));
+        assert!(is_lossy_text_of(b"\xff\xfe!", "\u{fffd}\u{fffd}!"));
+        assert!(!is_lossy_text_of(b"caf\xe9", "caf"));
+        assert!(!is_lossy_text_of(b"caf\xe9", "caf\u{fffd}\u{fffd}"));
+        assert!(!is_lossy_text_of(b"caf\xe9", "cafe"));
+        assert!(!is_lossy_text_of(b"abc", "abcd"));
+    }
+
+    #[test]
+    fn test_header_parse_non_utf8() {
+        let data = b"WARC/1.1\r\n\
+            WARC-Target-URI: http://example.com/caf\xe9\r\n\
+            X-Note: ok\r\n\
+            Content-Length: 0\r\n\
+            \r\n";
+        let mut header = WarcHeader::parse(data).unwrap();
+
+        assert_eq!(
+            header.fields.get("WARC-Target-URI").unwrap(),
+            "http://example.com/caf\u{fffd}"
+        );
+        assert_eq!(
+            header.get_hstring("WARC-Target-URI"),
+            Some(Hstring::Opaque(b"http://example.com/caf\xe9".to_vec()))
+        );
+        assert_eq!(header.get_hstring("X-Note"), Some(Hstring::from("ok")));
+        assert_eq!(header.opaque_field_names(), vec!["WARC-Target-URI"]);
+        assert!(header.validate().is_ok());
+
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+
+        assert_eq!(buf, data);
+        assert_eq!(header.serialized_len(), data.len() as u64);
+
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "http://example.com/caf%E9".to_string(),
+        );
+        assert!(header.opaque_field_names().is_empty());
+
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+
+        assert!(buf.is_ascii());
+
+        let mut header = WarcHeader::empty();
+        header.version = "WARC/1.1".to_string();
+        header.append_hstring("X-Note".to_string(), Hstring::Opaque(b"\xff".to_vec()));
+        assert_eq!(header.fields.get("X-Note").unwrap(), "\u{fffd}");
+
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+
+        assert_eq!(buf, b"WARC/1.1\r\nX-Note: \xff\r\n\r\n");
+    }
+
+    #[test]
+    fn test_header_parse_line_folding() {
+        let data = "WARC/1.1\r\n\
+            X-Note: a\r\n \
+            b\r\n\
+            Content-Length: 0\r\n\
+            \r\n";
+
+        let mut header = WarcHeader::parse(data.as_bytes()).unwrap();
+        assert_eq!(header.fields.get("X-Note").unwrap(), "a b");
+        assert!(header.folded_field_names().is_empty());
+        assert!(header.validate().is_ok());
+
+        header
+            .fields
+            .insert("X-Note".to_string(), "a\r\n b".to_string());
+        assert!(header.validate().is_err());
+
+        assert!(WarcHeader::parse_with_line_folding(data.as_bytes(), LineFolding::Reject).is_err());
+
+        let header =
+            WarcHeader::parse_with_line_folding(data.as_bytes(), LineFolding::Preserve).unwrap();
+        assert_eq!(header.fields.get("X-Note").unwrap(), "a\r\n b");
+        assert_eq!(header.folded_field_names(), vec!["X-Note"]);
+        assert!(header.validate().is_ok());
+
+        let mut buf = Vec::new();
+        header.serialize(&mut buf).unwrap();
+        assert_eq!(buf, data.as_bytes());
+
+        let data = data.replace("a\r\n ", "a ");
+        assert!(WarcHeader::parse_with_line_folding(data.as_bytes(), LineFolding::Reject).is_ok());
+    }
+
+    #[test]
+    fn test_header_parse_repeated_field() {
+        let data = "WARC/1.1\r\n\
+            WARC-Protocol: h2\r\n\
+            WARC-Protocol: tls/1.3\r\n\
+            \r\n";
+        let header = WarcHeader::parse(data.as_bytes()).unwrap();
+
+        assert_eq!(
+            header.fields.get_all("WARC-Protocol").collect::<Vec<_>>(),
+            vec!["h2", "tls/1.3"]
+        );
+
+        let protocols = header
+            .fields
+            .get_protocols()
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert!(!protocols[0].is_tls());
+        assert!(protocols[1].is_tls());
+    }
+
+    #[test]
+    fn test_duplicate_field_policy() {
+        let data = "WARC/1.1\r\n\
+            WARC-Date: 2024-01-01T00:00:00Z\r\n\
+            WARC-Protocol: h2\r\n\
+            WARC-Date: 2024-01-02T00:00:00Z\r\n\
+            WARC-Protocol: tls/1.3\r\n\
+            \r\n";
+        let header = WarcHeader::parse(data.as_bytes()).unwrap();
+
+        assert_eq!(header.duplicate_fields(), vec!["WARC-Date"]);
+
+        let mut keep_header = header.clone();
+        keep_header
+            .apply_duplicate_field_policy(DuplicateFieldPolicy::Keep)
+            .unwrap();
+        assert_eq!(keep_header.fields.len(), 4);
+
+        let mut first_header = header.clone();
+        first_header
+            .apply_duplicate_field_policy(DuplicateFieldPolicy::FirstWins)
+            .unwrap();
+        assert_eq!(
+            first_header.fields.get_all("WARC-Date").collect::<Vec<_>>(),
+            vec!["2024-01-01T00:00:00Z"]
+        );
+        assert_eq!(first_header.fields.get_all("WARC-Protocol").count(), 2);
+
+        let mut last_header = header.clone();
+        last_header
+            .apply_duplicate_field_policy(DuplicateFieldPolicy::LastWins)
+            .unwrap();
+        assert_eq!(
+            last_header.fields.get_all("WARC-Date").collect::<Vec<_>>(),
+            vec!["2024-01-02T00:00:00Z"]
+        );
+
+        let mut error_header = header.clone();
+        assert!(error_header
+            .apply_duplicate_field_policy(DuplicateFieldPolicy::Error)
+            .is_err());
+        assert_eq!(error_header.fields.len(), 4);
+    }
+
+    #[test]
+    fn test_header_typed_fields() {
+        let data = "WARC/1.1\r\n\
+            WARC-Type: response\r\n\
+            WARC-Record-ID: <urn:uuid:0190B7F5-0000-7000-8000-000000000000>\r\n\
+            WARC-Target-URI: http://example.com/a\r\n\
+            WARC-Date: 2024-01-02T03:04:05+01:00\r\n\
+            \r\n";
+        let header = WarcHeader::parse(data.as_bytes()).unwrap();
+
+        assert_eq!(header.warc_type(), Some(WarcType::Response));
+        assert_eq!(
+            header.record_id().unwrap().as_uri(),
+            "urn:uuid:0190b7f5-0000-7000-8000-000000000000"
+        );
+        assert_eq!(
+            header.target_uri().unwrap().as_str(),
+            "http://example.com/a"
+        );
+        assert_eq!(
+            header.date().unwrap().to_rfc3339(),
+            "2024-01-02T02:04:05+00:00"
+        );
+
+        let mut header = WarcHeader::new(0, WarcType::Unknown("x-custom".to_string()));
+        header
+            .fields
+            .insert("WARC-Date".to_string(), "yesterday".to_string());
+        header.fields.remove("WARC-Record-ID");
+
+        assert_eq!(
+            header.warc_type(),
+            Some(WarcType::Unknown("x-custom".to_string()))
+        );
+        assert!(!header.warc_type().unwrap().is_known());
+        assert_eq!(header.record_id(), None);
+        assert_eq!(header.target_uri(), None);
+        assert_eq!(header.date(), None);
+        assert_eq!("revisit".parse(), Ok(WarcType::Revisit));
+        assert_eq!(WarcType::Continuation.to_string(), "continuation");
     }
 }
@@ -0,0 +1,208 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::fields::FieldMap;
+
+use super::{record_id::RecordId, WarcFields, WarcHeader, WarcType};
+
+/// Version string of WARC 1.0.
+pub const WARC_1_0: &str = "WARC/1.0";
+/// Version string of WARC 1.1.
+pub const WARC_1_1: &str = "WARC/1.1";
+
+/// Builder of a [`WarcHeader`] with the mandatory fields filled in.
+///
+/// Created with [`WarcHeader::builder()`]. Fields that are not set are
+/// filled in when the header is built:
+///
+/// * `WARC-Record-ID` with a new `urn:uuid` ID.
+/// * `WARC-Date` with the current time. WARC 1.0 dates are truncated to
+///   seconds.
+/// * `Content-Type` for a block that is not empty, from the record type. It
+///   is omitted for types without a usual media type, such as `revisit`.
+///
+/// ```
+/// use warcat::header::{WarcHeader, WarcType};
+///
+/// let header = WarcHeader::builder(WarcType::Resource)
+///     .with_content_type("text/plain")
+///     .with_field("WARC-Target-URI", "http://example.com/a.txt")
+///     .build(12);
+///
+/// assert_eq!(header.content_length().unwrap(), 12);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WarcHeaderBuilder {
+    version: String,
+    warc_type: WarcType,
+    record_id: Option<String>,
+    date: Option<DateTime<Utc>>,
+    content_type: Option<String>,
+    fields: WarcFields,
+}
+
+impl WarcHeaderBuilder {
+    pub(super) fn new(warc_type: WarcType) -> Self {
+        Self {
+            version: WARC_1_1.to_string(),
+            warc_type,
+            record_id: None,
+            date: None,
+            content_type: None,
+            fields: FieldMap::new(),
+        }
+    }
+
+    /// Sets the version string such as [`WARC_1_0`].
+    ///
+    /// The default is [`WARC_1_1`].
+    pub fn with_version<V: Into<String>>(mut self, version: V) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Sets the WARC-Record-ID field.
+    pub fn with_record_id(mut self, record_id: &RecordId) -> Self {
+        self.record_id = Some(record_id.to_string());
+        self
+    }
+
+    /// Sets the WARC-Date field.
+    pub fn with_date(mut self, date: DateTime<Utc>) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    /// Sets the Content-Type field.
+    pub fn with_content_type<C: Into<String>>(mut self, content_type: C) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Adds a field after the mandatory fields.
+    pub fn with_field<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.fields.append(name.into(), value.into());
+        self
+    }
+
+    /// Returns the header of a record with a block of the given length.
+    pub fn build(self, content_length: u64) -> WarcHeader {
+        let date_format = if self.version == WARC_1_0 {
+            SecondsFormat::Secs
+        } else {
+            SecondsFormat::AutoSi
+        };
+        let content_type = self.content_type.or_else(|| {
+            default_content_type(&self.warc_type)
+                .filter(|_| content_length > 0)
+                .map(str::to_string)
+        });
+
+        let mut header = WarcHeader::empty();
+        header.version = self.version;
+
+        header.fields.insert(
+            "WARC-Record-ID".to_string(),
+            self.record_id
+                .unwrap_or_else(|| RecordId::new().to_string()),
+        );
+        header
+            .fields
+            .insert("WARC-Type".to_string(), self.warc_type.into());
+        header.fields.insert(
+            "WARC-Date".to_string(),
+            self.date
+                .unwrap_or_else(Utc::now)
+                .to_rfc3339_opts(date_format, true),
+        );
+
+        if let Some(content_type) = content_type {
+            header
+                .fields
+                .insert("Content-Type".to_string(), content_type);
+        }
+
+        header.set_content_length(content_length);
+
+        for (name, value) in self.fields {
+            header.fields.append(name, value);
+        }
+
+        header
+    }
+}
+
+fn default_content_type(warc_type: &WarcType) -> Option<&'static str> {
+    match warc_type {
+        WarcType::Warcinfo | WarcType::Metadata => Some("application/warc-fields"),
+        WarcType::Response => Some("application/http;msgtype=response"),
+        WarcType::Request => Some("application/http;msgtype=request"),
+        WarcType::Resource | WarcType::Conversion => Some("application/octet-stream"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        header::fields::FieldsExt,
+        verify::{Check, Verifier},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_warc_header_builder() {
+        let header = WarcHeader::builder(WarcType::Response)
+            .with_field("WARC-Target-URI", "http://example.com/")
+            .build(100);
+
+        assert_eq!(header.version, WARC_1_1);
+        assert_eq!(header.warc_type(), Some(WarcType::Response));
+        assert!(header.record_id().unwrap().uuid().is_some());
+        assert!(header.date().is_some());
+        assert_eq!(
+            header.fields.get_or_default("Content-Type"),
+            "application/http;msgtype=response"
+        );
+        assert_eq!(header.content_length().unwrap(), 100);
+        assert_eq!(
+            header.fields.iter().last().unwrap().0.as_str(),
+            "WARC-Target-URI"
+        );
+
+        let mut verifier = Verifier::new();
+        *verifier.checks_mut() = [Check::MandatoryFields].into_iter().collect();
+        verifier.begin_record(&header).unwrap();
+        verifier.end_record();
+        assert!(verifier.problems().is_empty(), "{:?}", verifier.problems());
+    }
+
+    #[test]
+    fn test_warc_header_builder_version_date() {
+        let record_id = RecordId::new();
+        let date = "2024-01-02T03:04:05.678Z".parse().unwrap();
+        let header = WarcHeader::builder(WarcType::Revisit)
+            .with_version(WARC_1_0)
+            .with_record_id(&record_id)
+            .with_date(date)
+            .build(10);
+
+        assert_eq!(header.version, WARC_1_0);
+        assert_eq!(header.record_id(), Some(record_id));
+        assert_eq!(
+            header.fields.get_or_default("WARC-Date"),
+            "2024-01-02T03:04:05Z"
+        );
+        assert!(!header.fields.contains_name("Content-Type"));
+
+        let header = WarcHeader::builder(WarcType::Resource)
+            .with_date(date)
+            .build(0);
+
+        assert_eq!(
+            header.fields.get_or_default("WARC-Date"),
+            "2024-01-02T03:04:05.678Z"
+        );
+        assert!(!header.fields.contains_name("Content-Type"));
+    }
+}
@@ -0,0 +1,242 @@
+//! Extension fields
+//!
+//! Crawlers often write fields that are not defined by the WARC
+//! specification, such as the time taken to fetch a resource. An
+//! [`ExtensionFields`] is a list of such fields that are of interest and how
+//! their values are interpreted. The list starts with commonly used timing
+//! fields and can be extended with a configuration text.
+//!
+//! The configuration text contains one field per line. Each line is a field
+//! name optionally followed by whitespace and a kind (`text`, `integer`, or
+//! `duration`). Empty lines and lines starting with `#` are ignored:
+//!
+//! ```text
+//! # Fields written by our crawler
+//! X-Crawler-Fetch-Time duration
+//! X-Crawler-Hops integer
+//! X-Crawler-Job
+//! ```
+use std::{str::FromStr, time::Duration};
+
+use crate::error::{ParseError, ParseErrorKind};
+
+use super::WarcHeader;
+
+/// Timing fields written by various crawlers.
+///
+/// Durations without a unit are in milliseconds.
+const TIMING_FIELDS: &[&str] = &[
+    "WARC-Fetch-Time-Ms",
+    "WARC-Fetch-Duration",
+    "WARC-Response-Time-Ms",
+    "WARC-Time-To-First-Byte-Ms",
+];
+
+/// How the value of an extension field is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtensionKind {
+    Text,
+    Integer,
+    /// A number of milliseconds or a number with a `ms` or `s` unit.
+    Duration,
+}
+
+impl FromStr for ExtensionKind {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "integer" => Ok(Self::Integer),
+            "duration" => Ok(Self::Duration),
+            _ => Err(ParseError::new(ParseErrorKind::Syntax).with_snippet(s)),
+        }
+    }
+}
+
+/// A declared extension field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionField {
+    pub name: String,
+    pub kind: ExtensionKind,
+}
+
+/// Interpreted value of an extension field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtensionValue {
+    Text(String),
+    Integer(u64),
+    Duration(Duration),
+}
+
+/// List of extension fields of interest.
+///
+/// The default list contains the known timing fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionFields {
+    fields: Vec<ExtensionField>,
+}
+
+impl ExtensionFields {
+    /// Creates an empty list.
+    pub fn new() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    pub fn fields(&self) -> &[ExtensionField] {
+        &self.fields
+    }
+
+    /// Adds a field or replaces the kind of a field with the same name.
+    pub fn insert(&mut self, field: ExtensionField) {
+        match self
+            .fields
+            .iter_mut()
+            .find(|item| item.name.eq_ignore_ascii_case(&field.name))
+        {
+            Some(item) => item.kind = field.kind,
+            None => self.fields.push(field),
+        }
+    }
+
+    /// Adds the fields from a configuration text.
+    ///
+    /// See the [module](self) documentation for the format.
+    pub fn add_config(&mut self, text: &str) -> Result<(), ParseError> {
+        for line in text.lines() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let name = parts.next().unwrap_or_default();
+            let kind = match parts.next() {
+                Some(kind) => kind
+                    .parse()
+                    .map_err(|_| ParseError::new(ParseErrorKind::Syntax).with_snippet(line))?,
+                None => ExtensionKind::Text,
+            };
+
+            if parts.next().is_some() || crate::parse::validate_field_name(name.as_bytes()).is_err()
+            {
+                return Err(ParseError::new(ParseErrorKind::Syntax).with_snippet(line));
+            }
+
+            self.insert(ExtensionField {
+                name: name.to_string(),
+                kind,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the declared fields present in the header and their values.
+    ///
+    /// Values that cannot be interpreted as their kind are returned as text.
+    pub fn values<'a>(
+        &'a self,
+        header: &'a WarcHeader,
+    ) -> impl Iterator<Item = (&'a ExtensionField, ExtensionValue)> + 'a {
+        self.fields.iter().filter_map(|field| {
+            let value = header.fields.get(&field.name)?;
+            let parsed = match field.kind {
+                ExtensionKind::Text => None,
+                ExtensionKind::Integer => value.trim().parse().ok().map(ExtensionValue::Integer),
+                ExtensionKind::Duration => parse_duration(value).map(ExtensionValue::Duration),
+            };
+
+            Some((
+                field,
+                parsed.unwrap_or_else(|| ExtensionValue::Text(value.clone())),
+            ))
+        })
+    }
+}
+
+impl Default for ExtensionFields {
+    fn default() -> Self {
+        let mut fields = Self::new();
+
+        for name in TIMING_FIELDS {
+            fields.insert(ExtensionField {
+                name: name.to_string(),
+                kind: ExtensionKind::Duration,
+            });
+        }
+
+        fields
+    }
+}
+
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, scale) = if let Some(number) = value.strip_suffix("ms") {
+        (number, 0.001)
+    } else if let Some(number) = value.strip_suffix('s') {
+        (number, 1.0)
+    } else {
+        (value, 0.001)
+    };
+    let number = number.trim_end().parse::<f64>().ok()?;
+
+    Duration::try_from_secs_f64(number * scale).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension_fields() {
+        let mut fields = ExtensionFields::default();
+        fields
+            .add_config("# comment\n\nX-Hops integer\nX-Job\nwarc-fetch-duration text\n")
+            .unwrap();
+
+        assert!(fields.add_config("X-Bad kind").is_err());
+        assert!(fields.add_config("X-Bad text extra").is_err());
+        assert!(fields.add_config("X:Bad").is_err());
+
+        let mut header = WarcHeader::empty();
+        header
+            .fields
+            .insert("WARC-Fetch-Time-Ms".into(), "12.5".into());
+        header
+            .fields
+            .insert("WARC-Fetch-Duration".into(), "3s".into());
+        header
+            .fields
+            .insert("WARC-Response-Time-Ms".into(), "abc".into());
+        header.fields.insert("X-Hops".into(), "2".into());
+
+        let values = fields
+            .values(&header)
+            .map(|(field, value)| (field.name.as_str(), value))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            values,
+            vec![
+                (
+                    "WARC-Fetch-Time-Ms",
+                    ExtensionValue::Duration(Duration::from_micros(12500))
+                ),
+                ("WARC-Fetch-Duration", ExtensionValue::Text("3s".into())),
+                ("WARC-Response-Time-Ms", ExtensionValue::Text("abc".into())),
+                ("X-Hops", ExtensionValue::Integer(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("250"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("250 ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("-1"), None);
+        assert_eq!(parse_duration("fast"), None);
+    }
+}
@@ -5,7 +5,10 @@ use url::Url;
 
 use crate::error::ParseError;
 
-use super::WarcFields;
+use super::{
+    protocol::{CipherSuite, Protocol},
+    WarcFields,
+};
 
 pub trait FieldsExt {
     /// Returns the value if the name is present, otherwise empty string.
@@ -29,6 +32,12 @@ pub trait FieldsExt {
 
     /// Parse an IP address.
     fn get_ip_addr<N: AsRef<str>>(&self, name: N) -> Option<Result<IpAddr, ParseError>>;
+
+    /// Parse all the `WARC-Protocol` fields.
+    fn get_protocols(&self) -> Vec<Result<Protocol, ParseError>>;
+
+    /// Parse the `WARC-Cipher-Suite` field.
+    fn get_cipher_suite(&self) -> Option<Result<CipherSuite, ParseError>>;
 }
 
 #[derive(Debug, Clone, Default)]
@@ -123,4 +132,15 @@ impl FieldsExt for WarcFields {
         self.get(name.as_ref())
             .map(|value| IpAddr::from_str(value).map_err(|error| error.into()))
     }
+
+    fn get_protocols(&self) -> Vec<Result<Protocol, ParseError>> {
+        self.get_all("WARC-Protocol")
+            .map(|value| Protocol::from_str(value))
+            .collect()
+    }
+
+    fn get_cipher_suite(&self) -> Option<Result<CipherSuite, ParseError>> {
+        self.get("WARC-Cipher-Suite")
+            .map(|value| CipherSuite::from_str(value))
+    }
 }
@@ -0,0 +1,161 @@
+//! WARC-Protocol and WARC-Cipher-Suite values.
+//!
+//! These fields are extensions to WARC 1.1 that record the network protocols
+//! used to transfer the content of a record. `WARC-Protocol` may be repeated
+//! with one protocol per field, such as `h2` and `tls/1.3`.
+//! `WARC-Cipher-Suite` contains the IANA name of the TLS cipher suite.
+use std::{fmt::Display, str::FromStr};
+
+use crate::error::{ParseError, ParseErrorKind};
+
+/// Application protocols from the TLS ALPN registry and other protocols
+/// commonly used by crawlers.
+const KNOWN_APPLICATION_PROTOCOLS: &[&str] = &[
+    "http/0.9", "http/1.0", "http/1.1", "h2", "h2c", "h3", "spdy/1", "spdy/2", "spdy/3", "ftp",
+    "gopher", "dns",
+];
+
+const KNOWN_TLS_PROTOCOLS: &[&str] = &[
+    "ssl/2", "ssl/3", "tls/1.0", "tls/1.1", "tls/1.2", "tls/1.3", "dtls/1.0", "dtls/1.2",
+    "dtls/1.3",
+];
+
+/// A `WARC-Protocol` value.
+///
+/// The value is an identifier such as `h2` or a name and version separated
+/// by a slash such as `tls/1.3`. Names are case-insensitive and stored
+/// lowercase.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Protocol {
+    /// Name of the protocol such as `http` or `tls`.
+    pub name: String,
+    /// Version of the protocol such as `1.1`.
+    pub version: Option<String>,
+}
+
+impl Protocol {
+    /// Returns whether the protocol is a TLS or SSL version.
+    pub fn is_tls(&self) -> bool {
+        matches!(self.name.as_str(), "tls" | "ssl" | "dtls")
+    }
+
+    /// Returns whether the protocol is a registered or commonly used
+    /// protocol.
+    pub fn is_known(&self) -> bool {
+        let value = self.to_string();
+
+        KNOWN_APPLICATION_PROTOCOLS.contains(&value.as_str())
+            || KNOWN_TLS_PROTOCOLS.contains(&value.as_str())
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_valid = |value: &str| {
+            !value.is_empty()
+                && value
+                    .bytes()
+                    .all(|c| c.is_ascii_alphanumeric() || b".-_".contains(&c))
+        };
+        let (name, version) = match s.split_once('/') {
+            Some((name, version)) => (name, Some(version)),
+            None => (s, None),
+        };
+
+        if !is_valid(name) || !version.map_or(true, is_valid) {
+            return Err(ParseError::new(ParseErrorKind::Syntax).with_snippet(s));
+        }
+
+        Ok(Self {
+            name: name.to_ascii_lowercase(),
+            version: version.map(|version| version.to_ascii_lowercase()),
+        })
+    }
+}
+
+impl Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}/{}", self.name, version),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// A `WARC-Cipher-Suite` value.
+///
+/// The value must be an IANA cipher suite name such as
+/// `TLS_AES_128_GCM_SHA256`. OpenSSL style names are not accepted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CipherSuite {
+    name: String,
+}
+
+impl CipherSuite {
+    /// Returns the IANA name.
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+}
+
+impl FromStr for CipherSuite {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_valid = (s.starts_with("TLS_") || s.starts_with("SSL_"))
+            && s.len() > 4
+            && s.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_');
+
+        if is_valid {
+            Ok(Self {
+                name: s.to_string(),
+            })
+        } else {
+            Err(ParseError::new(ParseErrorKind::Syntax).with_snippet(s))
+        }
+    }
+}
+
+impl Display for CipherSuite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_parse() {
+        let protocol = Protocol::from_str("TLS/1.3").unwrap();
+
+        assert_eq!(protocol.name, "tls");
+        assert_eq!(protocol.version.as_deref(), Some("1.3"));
+        assert_eq!(protocol.to_string(), "tls/1.3");
+        assert!(protocol.is_tls());
+        assert!(protocol.is_known());
+
+        let protocol = Protocol::from_str("h2").unwrap();
+
+        assert_eq!(protocol.version, None);
+        assert!(!protocol.is_tls());
+        assert!(protocol.is_known());
+
+        assert!(!Protocol::from_str("example/2").unwrap().is_known());
+        assert!(Protocol::from_str("").is_err());
+        assert!(Protocol::from_str("tls/").is_err());
+        assert!(Protocol::from_str("http 1.1").is_err());
+    }
+
+    #[test]
+    fn test_cipher_suite_parse() {
+        let suite = CipherSuite::from_str("TLS_AES_128_GCM_SHA256").unwrap();
+
+        assert_eq!(suite.as_str(), "TLS_AES_128_GCM_SHA256");
+        assert!(CipherSuite::from_str("ECDHE-RSA-AES128-GCM-SHA256").is_err());
+        assert!(CipherSuite::from_str("TLS_").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! WARC-Record-ID values.
+use std::{fmt::Display, str::FromStr};
+
+use sha1::Digest;
+
+use crate::error::{ParseError, ParseErrorKind};
+
+use super::{fields::FieldsExt, WarcHeader};
+
+/// Fields of a record that [`RecordId::from_header()`] derives the ID from.
+const IDENTIFYING_FIELDS: [&str; 4] = [
+    "WARC-Type",
+    "WARC-Target-URI",
+    "WARC-Date",
+    "WARC-Block-Digest",
+];
+
+/// A normalized `WARC-Record-ID` value.
+///
+/// The value is stored without the `<` and `>` deliminators. The URI scheme
+/// is lowercased and `urn:uuid` values are converted to the canonical
+/// lowercase hyphenated form, so comparing two IDs ignores those variations.
+///
+/// Parsing with [`FromStr`] is lenient and accepts values with or
+/// without the deliminators. Use [`RecordId::parse_strict`] to require the
+/// form in the specification.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RecordId {
+    uri: String,
+}
+
+impl RecordId {
+    /// Generates a new `urn:uuid` ID.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            uri: uuid::Uuid::now_v7().urn().to_string(),
+        }
+    }
+
+    /// Generates a `urn:uuid` ID that is a version 5 (name-based) UUID in
+    /// the URL namespace.
+    ///
+    /// The same name always generates the same ID.
+    pub fn new_v5(name: &[u8]) -> Self {
+        let mut hasher = sha1::Sha1::new();
+        hasher.update(uuid::Uuid::NAMESPACE_URL.as_bytes());
+        hasher.update(name);
+        let hash = hasher.finalize();
+        let uuid = uuid::Builder::from_sha1_bytes(hash[0..16].try_into().unwrap()).into_uuid();
+
+        Self {
+            uri: uuid.urn().to_string(),
+        }
+    }
+
+    /// Generates an ID with [`new_v5()`](Self::new_v5) from the WARC-Type,
+    /// WARC-Target-URI, WARC-Date, and WARC-Block-Digest fields.
+    ///
+    /// Records with the same values for these fields, such as a record
+    /// converted again from the same source, get the same ID.
+    pub fn from_header(header: &WarcHeader) -> Self {
+        let name = IDENTIFYING_FIELDS
+            .map(|name| header.fields.get_or_default(name))
+            .join("\n");
+
+        Self::new_v5(name.as_bytes())
+    }
+
+    /// Parses a value that must be a URI delimitated by `<` and `>`.
+    pub fn parse_strict(value: &str) -> Result<Self, ParseError> {
+        if value.len() >= 2 && value.starts_with('<') && value.ends_with('>') {
+            Self::parse_uri(&value[1..value.len() - 1])
+        } else {
+            Err(ParseError::new(ParseErrorKind::Syntax).with_snippet(value))
+        }
+    }
+
+    fn parse_uri(value: &str) -> Result<Self, ParseError> {
+        // The URL parser strips these characters, so the scheme length would
+        // not match the value.
+        if value.contains(|c: char| c.is_whitespace() || c.is_control()) {
+            return Err(ParseError::new(ParseErrorKind::Syntax).with_snippet(value));
+        }
+
+        let url = url::Url::parse(value)?;
+        let scheme_len = url.scheme().len();
+
+        let rest = &value[scheme_len + 1..];
+
+        let uuid_str = rest
+            .get(..5)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("uuid:"))
+            .and_then(|_| rest.get(5..))
+            .filter(|uuid_str| url.scheme() == "urn" && !uuid_str.is_empty());
+
+        if let Some(uuid_str) = uuid_str {
+            let uuid = uuid::Uuid::parse_str(uuid_str).map_err(|error| {
+                ParseError::new(ParseErrorKind::Syntax)
+                    .with_snippet(value)
+                    .with_source(error)
+            })?;
+
+            Ok(Self {
+                uri: uuid.urn().to_string(),
+            })
+        } else {
+            Ok(Self {
+                uri: format!("{}:{}", url.scheme(), rest),
+            })
+        }
+    }
+
+    /// Returns the URI without the deliminators.
+    pub fn as_uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Returns the UUID if the ID is a `urn:uuid` value.
+    pub fn uuid(&self) -> Option<uuid::Uuid> {
+        self.uri
+            .strip_prefix("urn:uuid:")
+            .and_then(|value| uuid::Uuid::parse_str(value).ok())
+    }
+
+    /// Returns whether the given value refers to this ID.
+    ///
+    /// The comparison ignores deliminator and case variations.
+    pub fn matches_str(&self, value: &str) -> bool {
+        value.parse::<Self>().is_ok_and(|other| &other == self)
+    }
+}
+
+impl FromStr for RecordId {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix('<').unwrap_or(s);
+        let s = s.strip_suffix('>').unwrap_or(s);
+
+        Self::parse_uri(s)
+    }
+}
+
+impl Display for RecordId {
+    /// Formats the ID with deliminators suitable for a header field value.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{}>", self.uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_id_parse() {
+        let id = RecordId::parse_strict("<urn:uuid:F81D4FAE-7DEC-11D0-A765-00A0C91E6BF6>").unwrap();
+
+        assert_eq!(id.as_uri(), "urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6");
+        assert_eq!(
+            id.to_string(),
+            "<urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6>"
+        );
+        assert!(id.uuid().is_some());
+
+        assert!(RecordId::parse_strict("urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6").is_err());
+        assert!(RecordId::parse_strict("<urn:uuid:not-a-uuid>").is_err());
+        assert!(RecordId::parse_strict("<no scheme>").is_err());
+        assert!(RecordId::parse_strict("<>").is_err());
+
+        let id = RecordId::parse_strict("<EXAMPLE:Path/A>").unwrap();
+        assert_eq!(id.as_uri(), "example:Path/A");
+        assert!(id.uuid().is_none());
+
+        let id = RecordId::parse_strict("<urn:ééé>").unwrap();
+        assert_eq!(id.as_uri(), "urn:ééé");
+        assert!(RecordId::parse_strict("<urn:éé>").is_ok());
+        assert!(RecordId::parse_strict("<urn:uuid:é>").is_err());
+        assert!(
+            RecordId::parse_strict("<\x01urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6>").is_err()
+        );
+        assert!(
+            RecordId::parse_strict("<urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6\x7f>").is_err()
+        );
+        assert!(RecordId::parse_strict("< urn:example>").is_err());
+    }
+
+    #[test]
+    fn test_record_id_matches() {
+        let id = RecordId::from_str("<urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6>").unwrap();
+
+        assert!(id.matches_str("urn:uuid:F81D4FAE-7DEC-11D0-A765-00A0C91E6BF6"));
+        assert!(id.matches_str(" <URN:UUID:f81d4fae-7dec-11d0-a765-00a0c91e6bf6> "));
+        assert!(!id.matches_str("<urn:uuid:00000000-7dec-11d0-a765-00a0c91e6bf6>"));
+
+        let id = RecordId::from_str("<example:Path/A>").unwrap();
+
+        assert!(id.matches_str("example:Path/A"));
+        assert!(!id.matches_str("example:path/a"));
+    }
+
+    #[test]
+    fn test_record_id_new() {
+        let id = RecordId::new();
+
+        assert!(id.uuid().is_some());
+        assert_eq!(RecordId::parse_strict(&id.to_string()).unwrap(), id);
+    }
+
+    #[test]
+    fn test_record_id_new_v5() {
+        let id = RecordId::new_v5(b"http://example.com/");
+
+        assert_eq!(id.as_uri(), "urn:uuid:0a300ee9-f9e4-5697-a51a-efc7fafaba67");
+
+        let mut header = WarcHeader::new(0, "resource");
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "http://example.com/".to_string(),
+        );
+        let id = RecordId::from_header(&header);
+
+        assert_eq!(RecordId::from_header(&header.clone()), id);
+
+        header
+            .fields
+            .insert("WARC-Type".to_string(), "metadata".to_string());
+
+        assert_ne!(RecordId::from_header(&header), id);
+    }
+}
@@ -0,0 +1,87 @@
+//! Target URI normalization
+//!
+//! The same URI may be written in different forms by different tools:
+//! percent-encoding with lowercase hex digits, unreserved characters that
+//! are escaped unnecessarily, or internationalized host names in Unicode
+//! instead of Punycode. [`normalize_uri()`] rewrites a URI into a single
+//! form so that values can be compared or used as keys.
+use url::Url;
+
+/// Returns the URI in normalized form.
+///
+/// The deliminator `<` and `>` is removed. URIs that can be parsed have
+/// their scheme and host lowercased, internationalized hosts converted to
+/// Punycode, default ports removed, and non-ASCII characters
+/// percent-encoded. In all URIs, percent-encoded unreserved characters
+/// (letters, digits, `-`, `.`, `_`, `~`) are decoded and the hex digits of
+/// the remaining escapes are uppercased.
+pub fn normalize_uri(value: &str) -> String {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('<')
+        .and_then(|value| value.strip_suffix('>'))
+        .unwrap_or(value);
+
+    match Url::parse(value) {
+        Ok(url) => normalize_percent_encoding(url.as_str()),
+        Err(_) => normalize_percent_encoding(value),
+    }
+}
+
+fn normalize_percent_encoding(value: &str) -> String {
+    let mut parts = value.split('%');
+    let mut output = String::with_capacity(value.len());
+    output.push_str(parts.next().unwrap_or_default());
+
+    for part in parts {
+        let byte = part
+            .get(0..2)
+            .filter(|hex| hex.bytes().all(|c| c.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match byte {
+            Some(byte) if is_unreserved(byte) => {
+                output.push(byte as char);
+                output.push_str(&part[2..]);
+            }
+            Some(_) => {
+                output.push('%');
+                output.push_str(&part[0..2].to_ascii_uppercase());
+                output.push_str(&part[2..]);
+            }
+            None => {
+                output.push('%');
+                output.push_str(part);
+            }
+        }
+    }
+
+    output
+}
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_uri() {
+        assert_eq!(
+            normalize_uri("<HTTP://B\u{fc}cher.Example:80/%7euser/%c3%a9%2f?q=%41%3d>"),
+            "http://xn--bcher-kva.example/~user/%C3%A9%2F?q=A%3D"
+        );
+        assert_eq!(
+            normalize_uri("http://example.com/caf\u{e9}"),
+            "http://example.com/caf%C3%A9"
+        );
+        assert_eq!(
+            normalize_uri("http://example.com/%E2%82%ac"),
+            normalize_uri("http://example.com/\u{20ac}")
+        );
+        assert_eq!(normalize_uri("not a uri %7e%zz%4"), "not a uri ~%zz%4");
+        assert_eq!(normalize_uri("%"), "%");
+    }
+}
@@ -10,7 +10,7 @@ use super::header::{fields::FieldsExt, MessageHeader};
 pub mod chunked;
 pub mod compress;
 
-pub type BoxedCodec = Box<dyn Codec>;
+pub type BoxedCodec = Box<dyn Codec + Send>;
 
 pub trait Codec: Debug {
     fn transform(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), GeneralError>;
@@ -51,20 +51,30 @@ pub fn build_decoders(
     header: &MessageHeader,
     codecs: &mut Vec<BoxedCodec>,
 ) -> Result<(), ProtocolError> {
-    build_codecs(header, codecs, false)
+    build_codecs(header, codecs, false, true)
+}
+
+/// Builds decoders for only the Transfer-Encoding so that the content is
+/// left as is.
+pub fn build_transfer_decoders(
+    header: &MessageHeader,
+    codecs: &mut Vec<BoxedCodec>,
+) -> Result<(), ProtocolError> {
+    build_codecs(header, codecs, false, false)
 }
 
 pub fn build_encoders(
     header: &MessageHeader,
     codecs: &mut Vec<BoxedCodec>,
 ) -> Result<(), ProtocolError> {
-    build_codecs(header, codecs, true)
+    build_codecs(header, codecs, true, true)
 }
 
 fn build_codecs(
     header: &MessageHeader,
     codecs: &mut Vec<BoxedCodec>,
     encode: bool,
+    content_encoding: bool,
 ) -> Result<(), ProtocolError> {
     let mut te_names = header
         .fields
@@ -88,6 +98,10 @@ fn build_codecs(
         ));
     }
 
+    if !content_encoding {
+        return Ok(());
+    }
+
     for name in ce_names {
         if name == "identity" {
             continue;
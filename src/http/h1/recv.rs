@@ -42,6 +42,7 @@ pub struct Receiver {
     has_trailer: bool,
     trailer_buf: Vec<u8>,
     codec_pipeline: CodecPipeline,
+    is_content_decoding: bool,
 }
 
 impl Receiver {
@@ -55,9 +56,18 @@ impl Receiver {
             has_trailer: false,
             trailer_buf: Vec::new(),
             codec_pipeline: CodecPipeline::default(),
+            is_content_decoding: true,
         }
     }
 
+    /// Sets whether the Content-Encoding, such as gzip, is decoded.
+    ///
+    /// The Transfer-Encoding is always decoded. The default is true.
+    pub fn with_content_decoding(mut self, value: bool) -> Self {
+        self.is_content_decoding = value;
+        self
+    }
+
     /// Put input data.
     pub fn recv_data(&mut self, data: &[u8]) {
         self.input_buf.extend_from_slice(data);
@@ -167,7 +177,11 @@ impl Receiver {
     fn config_codecs(&mut self, header: &MessageHeader) -> Result<(), GeneralError> {
         let mut codecs = Vec::new();
 
-        super::codec::build_decoders(header, &mut codecs)?;
+        if self.is_content_decoding {
+            super::codec::build_decoders(header, &mut codecs)?;
+        } else {
+            super::codec::build_transfer_decoders(header, &mut codecs)?;
+        }
 
         self.codec_pipeline = CodecPipeline::new(codecs);
 
@@ -441,6 +455,7 @@ mod tests {
         let mut compressor = Compressor::new(&mut content, Format::Gzip);
         compressor.write_all(b"Hello world!").unwrap();
         compressor.finish().unwrap();
+        let compressed_content = Vec::from(content.clone());
 
         let mut input = Vec::new();
         input.extend_from_slice(
@@ -477,6 +492,20 @@ mod tests {
 
         dbg!(String::from_utf8_lossy(&input));
 
+        let mut receiver = Receiver::new().with_content_decoding(false);
+        receiver.recv_data(&input);
+
+        let mut output = Vec::new();
+
+        loop {
+            match receiver.get_event().unwrap() {
+                ReceiverEvent::Body(data) => output.extend_from_slice(data),
+                ReceiverEvent::End => break,
+                _ => {}
+            }
+        }
+        assert_eq!(output, compressed_content);
+
         let mut receiver = Receiver::new();
         receiver.recv_data(&input);
 
@@ -1,5 +1,8 @@
 //! IO utilities
-use std::io::{BufRead, Read, Write};
+use std::{
+    fs::File,
+    io::{BufRead, Read, Seek, SeekFrom, Write},
+};
 
 pub(crate) const IO_BUFFER_LENGTH: usize = 4096;
 
@@ -149,6 +152,211 @@ impl<R: Read> LogicalPosition for BufferReader<R> {
     }
 }
 
+/// A [`Write`] implementation that writes the same data to two writers
+///
+/// A writer that fails is not written to anymore and its error is kept.
+/// Writing fails only when both writers have failed so that one output can
+/// be completed even if the other is interrupted. After writing, check
+/// [`first_error()`](Self::first_error) and
+/// [`second_error()`](Self::second_error).
+#[derive(Debug)]
+pub struct TeeWriter<A: Write, B: Write> {
+    first: A,
+    second: B,
+    first_error: Option<std::io::Error>,
+    second_error: Option<std::io::Error>,
+}
+
+impl<A: Write, B: Write> TeeWriter<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            first_error: None,
+            second_error: None,
+        }
+    }
+
+    pub fn get_ref(&self) -> (&A, &B) {
+        (&self.first, &self.second)
+    }
+
+    pub fn get_mut(&mut self) -> (&mut A, &mut B) {
+        (&mut self.first, &mut self.second)
+    }
+
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+
+    /// Returns the error of the first writer if it failed.
+    pub fn first_error(&self) -> Option<&std::io::Error> {
+        self.first_error.as_ref()
+    }
+
+    /// Returns the error of the second writer if it failed.
+    pub fn second_error(&self) -> Option<&std::io::Error> {
+        self.second_error.as_ref()
+    }
+
+    fn apply<F, G>(&mut self, mut first_op: F, mut second_op: G) -> std::io::Result<()>
+    where
+        F: FnMut(&mut A) -> std::io::Result<()>,
+        G: FnMut(&mut B) -> std::io::Result<()>,
+    {
+        if self.first_error.is_none() {
+            self.first_error = first_op(&mut self.first).err();
+        }
+
+        if self.second_error.is_none() {
+            self.second_error = second_op(&mut self.second).err();
+        }
+
+        match (&self.first_error, &self.second_error) {
+            (Some(error), Some(_)) => Err(std::io::Error::new(
+                error.kind(),
+                format!("both outputs failed: {}", error),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<A: Write, B: Write> Write for TeeWriter<A, B> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // Writers are always given the whole buffer to keep them in sync.
+        self.apply(|w| w.write_all(buf), |w| w.write_all(buf))?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.apply(|w| w.flush(), |w| w.flush())
+    }
+}
+
+/// A [`Write`] implementation that keeps the data in memory up to a limit
+/// and in a temporary file beyond it.
+#[derive(Debug)]
+pub struct SpooledBuffer {
+    memory: Vec<u8>,
+    file: Option<File>,
+    memory_limit: usize,
+    len: u64,
+}
+
+impl SpooledBuffer {
+    /// Creates a buffer that moves the data to a temporary file when it
+    /// exceeds the given number of bytes.
+    pub fn new(memory_limit: usize) -> Self {
+        Self {
+            memory: Vec::new(),
+            file: None,
+            memory_limit,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of bytes written.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns whether the data is in a temporary file.
+    pub fn is_spooled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Writes the buffered data to the writer.
+    pub fn copy_to<W: Write>(&mut self, mut dest: W) -> std::io::Result<u64> {
+        match &mut self.file {
+            Some(file) => {
+                file.seek(SeekFrom::Start(0))?;
+                std::io::copy(file, &mut dest)
+            }
+            None => {
+                dest.write_all(&self.memory)?;
+                Ok(self.len)
+            }
+        }
+    }
+}
+
+impl Write for SpooledBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.file.is_none() && self.memory.len() + buf.len() > self.memory_limit {
+            let mut file = tempfile::tempfile()?;
+            file.write_all(&self.memory)?;
+
+            self.memory = Vec::new();
+            self.file = Some(file);
+        }
+
+        let write_len = match &mut self.file {
+            Some(file) => file.write(buf)?,
+            None => {
+                self.memory.extend_from_slice(buf);
+                buf.len()
+            }
+        };
+        self.len += write_len as u64;
+
+        Ok(write_len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Read-only memory map of a file.
+///
+/// The contents can be read with [`SliceDecoder`](crate::warc::SliceDecoder)
+/// without copying.
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MappedFile {
+    map: memmap2::Mmap,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedFile {
+    /// Maps the file into memory.
+    ///
+    /// The file must not be modified while it is mapped. Otherwise, the
+    /// contents of the map are undefined and reading it may crash the
+    /// program.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        Self::from_file(&std::fs::File::open(path)?)
+    }
+
+    /// Maps the opened file into memory.
+    ///
+    /// See [`open()`](Self::open) for the requirements.
+    pub fn from_file(file: &std::fs::File) -> std::io::Result<Self> {
+        // Safety: the caller is responsible for not modifying the file.
+        let map = unsafe { memmap2::Mmap::map(file)? };
+
+        Ok(Self { map })
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl std::ops::Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
@@ -205,4 +413,84 @@ mod tests {
 
         assert_eq!(buf.len(), 5001);
     }
+
+    struct FailingWriter {
+        remain_len: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.remain_len < buf.len() {
+                return Err(std::io::ErrorKind::BrokenPipe.into());
+            }
+
+            self.remain_len -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_tee_writer() {
+        let mut w = TeeWriter::new(Vec::new(), FailingWriter { remain_len: 5 });
+        w.write_all(b"abc").unwrap();
+        w.write_all(b"def").unwrap();
+        w.write_all(b"ghi").unwrap();
+        w.flush().unwrap();
+
+        assert!(w.first_error().is_none());
+        assert_eq!(
+            w.second_error().unwrap().kind(),
+            std::io::ErrorKind::BrokenPipe
+        );
+
+        let (first, second) = w.into_inner();
+        assert_eq!(first, b"abcdefghi");
+        assert_eq!(second.remain_len, 2);
+
+        let mut w = TeeWriter::new(
+            FailingWriter { remain_len: 0 },
+            FailingWriter { remain_len: 3 },
+        );
+        w.write_all(b"abc").unwrap();
+        assert!(w.write_all(b"def").is_err());
+    }
+
+    #[test]
+    fn test_spooled_buffer() {
+        let mut buffer = SpooledBuffer::new(10);
+        buffer.write_all(b"Hello").unwrap();
+        assert!(!buffer.is_spooled());
+
+        let mut output = Vec::new();
+        buffer.copy_to(&mut output).unwrap();
+        assert_eq!(output, b"Hello");
+
+        buffer.write_all(b" world!").unwrap();
+        assert!(buffer.is_spooled());
+        assert_eq!(buffer.len(), 12);
+
+        let mut output = Vec::new();
+        buffer.copy_to(&mut output).unwrap();
+        assert_eq!(output, b"Hello world!");
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mapped_file() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(b"WARC/1.1\r\nContent-Length: 5\r\n\r\nhello\r\n\r\n")
+            .unwrap();
+
+        let map = MappedFile::from_file(&file).unwrap();
+        let mut decoder =
+            crate::warc::SliceDecoder::new(&map, crate::warc::DecoderConfig::default());
+        let (_header, block) = decoder.read_record().unwrap();
+
+        assert_eq!(block, b"hello");
+        assert!(!decoder.has_next_record());
+    }
 }
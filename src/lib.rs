@@ -18,6 +18,10 @@
 
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod cdx;
+pub mod cert;
 pub mod compress;
 pub mod dataseq;
 pub mod digest;
@@ -28,9 +32,12 @@ pub mod header;
 pub mod http;
 pub mod io;
 pub mod parse;
+pub mod segment;
+pub mod testing;
 pub(crate) mod util;
 pub mod verify;
 pub mod warc;
+pub mod warning;
 
 #[cfg(feature = "bin")]
 #[doc(hidden)]
@@ -17,6 +17,13 @@ pub fn scan_header_deliminator(data: &[u8]) -> Option<usize> {
     }
 }
 
+/// Returns whether a line in the data ends with LF not preceded by CR.
+pub fn has_bare_line_feed(data: &[u8]) -> bool {
+    data.iter()
+        .enumerate()
+        .any(|(index, &b)| b == b'\n' && (index == 0 || data[index - 1] != b'\r'))
+}
+
 /// Parse a HTTP-like fields of name-value pairs.
 pub fn parse_name_value_fields(value: &[u8]) -> Result<Vec<fields::FieldPairRef>, ParseError> {
     match fields::field_pairs(value) {
@@ -84,6 +91,13 @@ mod tests {
         assert_eq!(scan_header_deliminator(b"a\nb\n\nz"), Some(5));
     }
 
+    #[test]
+    fn test_has_bare_line_feed() {
+        assert!(!has_bare_line_feed(b"a\r\nb\r\n\r\n"));
+        assert!(has_bare_line_feed(b"\na"));
+        assert!(has_bare_line_feed(b"a\r\nb\n\r\n"));
+    }
+
     #[test]
     fn test_remove_line_folding() {
         assert_eq!(*remove_line_folding(b"abc"), *b"abc");
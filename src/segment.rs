@@ -0,0 +1,217 @@
+//! Segmented records
+//!
+//! A record may be split into segments across several records and WARC
+//! files. The first segment is a record of the original type with
+//! `WARC-Segment-Number: 1`. The remaining segments are `continuation`
+//! records that refer to the first segment with `WARC-Segment-Origin-ID`.
+//! The last segment contains `WARC-Segment-Total-Length`.
+//!
+//! The logical block of a segmented record is the concatenation of the
+//! blocks of its segments. This module provides helpers for processing the
+//! segments as a single block. For example, a [`WarcExtractor`](crate::extract::WarcExtractor)
+//! that read the header of the first segment can be given the blocks of the
+//! following continuation records.
+
+use std::collections::HashMap;
+
+use crate::{
+    error::{ProtocolError, ProtocolErrorKind},
+    header::WarcHeader,
+};
+
+/// Position of a record within a segmented record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentInfo {
+    /// Record ID of the first segment.
+    pub origin_id: String,
+    /// Segment number starting from 1.
+    pub number: u64,
+    /// Length of the logical block when the record is the last segment.
+    pub total_length: Option<u64>,
+}
+
+impl SegmentInfo {
+    /// Returns the segment information of a record or `None` if the record is
+    /// not segmented.
+    pub fn from_header(header: &WarcHeader) -> Result<Option<Self>, ProtocolError> {
+        let number = match header.fields.get_u64_strict("WARC-Segment-Number") {
+            Some(number) => number.map_err(|error| {
+                ProtocolError::new(ProtocolErrorKind::InvalidSegment).with_source(error)
+            })?,
+            None => return Ok(None),
+        };
+
+        if number == 0 {
+            return Err(ProtocolError::new(ProtocolErrorKind::InvalidSegment));
+        }
+
+        let origin_id = if number == 1 {
+            header.fields.get("WARC-Record-ID")
+        } else {
+            header.fields.get("WARC-Segment-Origin-ID")
+        };
+        let origin_id = origin_id
+            .ok_or_else(|| ProtocolError::new(ProtocolErrorKind::InvalidSegment))?
+            .to_string();
+
+        let total_length = match header.fields.get_u64_strict("WARC-Segment-Total-Length") {
+            Some(value) => Some(value.map_err(|error| {
+                ProtocolError::new(ProtocolErrorKind::InvalidSegment).with_source(error)
+            })?),
+            None => None,
+        };
+
+        Ok(Some(Self {
+            origin_id,
+            number,
+            total_length,
+        }))
+    }
+
+    /// Returns whether the record is the first segment.
+    pub fn is_first(&self) -> bool {
+        self.number == 1
+    }
+
+    /// Returns whether the record is the last segment.
+    pub fn is_last(&self) -> bool {
+        self.total_length.is_some()
+    }
+
+    /// Returns whether the given segment is the segment following this
+    /// segment.
+    pub fn is_continued_by(&self, other: &SegmentInfo) -> bool {
+        !self.is_last() && self.origin_id == other.origin_id && self.number + 1 == other.number
+    }
+}
+
+/// Holds state, such as an extractor or hasher, of segmented records
+/// between their segments.
+///
+/// Segments of a record must be processed in order, but segments of
+/// different records may be interleaved.
+#[derive(Debug)]
+pub struct SegmentJoiner<T> {
+    open: HashMap<String, (SegmentInfo, T)>,
+}
+
+impl<T> SegmentJoiner<T> {
+    pub fn new() -> Self {
+        Self {
+            open: HashMap::new(),
+        }
+    }
+
+    /// Stores the state after processing a segment that is not the last
+    /// segment.
+    pub fn suspend(&mut self, segment: &SegmentInfo, state: T) {
+        self.open
+            .insert(segment.origin_id.clone(), (segment.clone(), state));
+    }
+
+    /// Removes and returns the state of the segmented record that is
+    /// continued by the given segment.
+    ///
+    /// Returns `None` if the previous segment was not processed.
+    pub fn resume(&mut self, segment: &SegmentInfo) -> Option<T> {
+        let (previous, _) = self.open.get(&segment.origin_id)?;
+
+        if previous.is_continued_by(segment) {
+            self.open.remove(&segment.origin_id).map(|(_, state)| state)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of segmented records waiting for segments.
+    pub fn len(&self) -> usize {
+        self.open.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.open.is_empty()
+    }
+
+    /// Removes and returns the states of segmented records waiting for
+    /// segments.
+    pub fn drain(&mut self) -> impl Iterator<Item = (SegmentInfo, T)> + '_ {
+        self.open.drain().map(|(_, value)| value)
+    }
+}
+
+impl<T> Default for SegmentJoiner<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(fields: &[(&str, &str)]) -> WarcHeader {
+        let mut header = WarcHeader::new(0, "continuation");
+
+        for (name, value) in fields {
+            header.fields.insert(name.to_string(), value.to_string());
+        }
+
+        header
+    }
+
+    #[test]
+    fn test_segment_info() {
+        assert_eq!(SegmentInfo::from_header(&header(&[])).unwrap(), None);
+
+        let first = SegmentInfo::from_header(&header(&[
+            ("WARC-Record-ID", "<urn:example:1>"),
+            ("WARC-Segment-Number", "1"),
+        ]))
+        .unwrap()
+        .unwrap();
+        let last = SegmentInfo::from_header(&header(&[
+            ("WARC-Record-ID", "<urn:example:2>"),
+            ("WARC-Segment-Origin-ID", "<urn:example:1>"),
+            ("WARC-Segment-Number", "2"),
+            ("WARC-Segment-Total-Length", "10"),
+        ]))
+        .unwrap()
+        .unwrap();
+
+        assert!(first.is_first());
+        assert!(!first.is_last());
+        assert_eq!(first.origin_id, "<urn:example:1>");
+        assert!(last.is_last());
+        assert_eq!(last.origin_id, "<urn:example:1>");
+        assert!(first.is_continued_by(&last));
+        assert!(!last.is_continued_by(&first));
+
+        assert!(SegmentInfo::from_header(&header(&[("WARC-Segment-Number", "2")])).is_err());
+        assert!(SegmentInfo::from_header(&header(&[
+            ("WARC-Record-ID", "<urn:example:1>"),
+            ("WARC-Segment-Number", "a"),
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_segment_joiner() {
+        let segment = |number| SegmentInfo {
+            origin_id: "<urn:example:1>".to_string(),
+            number,
+            total_length: None,
+        };
+        let mut joiner = SegmentJoiner::new();
+
+        joiner.suspend(&segment(1), "a");
+        assert_eq!(joiner.len(), 1);
+        assert_eq!(joiner.resume(&segment(3)), None);
+        assert_eq!(joiner.resume(&segment(2)), Some("a"));
+        assert!(joiner.is_empty());
+        assert_eq!(joiner.resume(&segment(2)), None);
+
+        joiner.suspend(&segment(2), "b");
+        assert_eq!(joiner.drain().collect::<Vec<_>>(), vec![(segment(2), "b")]);
+        assert!(joiner.is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+//! Helpers for testing that WARC processing is lossless.
+//!
+//! [`assert_round_trip()`] decodes a WARC file, encodes each record again,
+//! and compares the encoded record with the original bytes. It is intended
+//! for the test suites of this library and of tools built with it that need
+//! to ensure records pass through unchanged.
+//!
+//! Compression is not part of the comparison: the file is decompressed and
+//! the records are encoded without compression. Files are read entirely into
+//! memory, so they should be test fixtures rather than full size archives.
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufRead, BufReader, Cursor, Write},
+    path::Path,
+};
+
+use crate::{
+    compress::{DecompressorConfig, Dictionary, Format, PushDecompressor},
+    error::GeneralError,
+    header::fields::FieldsExt,
+    warc::{Decoder, DecoderConfig, Encoder, EncoderConfig},
+};
+
+/// Result of [`check_round_trip()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundTripReport {
+    /// Number of records that were encoded identically.
+    pub record_count: u64,
+    /// The first difference, if any.
+    pub mismatch: Option<RoundTripMismatch>,
+}
+
+/// A record that was not encoded identically to the original.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundTripMismatch {
+    /// Index of the record starting from 0.
+    pub index: u64,
+    /// WARC-Record-ID of the record.
+    pub record_id: String,
+    /// Position of the first differing byte in the decompressed file.
+    pub position: u64,
+}
+
+impl Display for RoundTripMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "record {} ({}) differs at decompressed position {}",
+            self.index, self.record_id, self.position
+        )
+    }
+}
+
+/// Decodes and encodes the records of the WARC file and compares them with
+/// the original.
+///
+/// Checking stops at the first difference. Data after the last record,
+/// such as extra line endings, is reported as a difference of the record
+/// index after the last record.
+pub fn check_round_trip<R: BufRead>(
+    input: R,
+    format: Format,
+) -> Result<RoundTripReport, GeneralError> {
+    let original = decompress(input, format)?;
+
+    let mut decoder = Decoder::new(Cursor::new(original.as_slice()), DecoderConfig::default())?;
+    let mut position = 0usize;
+    let mut record_count = 0;
+
+    while decoder.has_next_record()? {
+        let (header, mut block_decoder) = decoder.read_header()?;
+        let record_id = header.fields.get_or_default("WARC-Record-ID").to_string();
+
+        let mut encoder =
+            Encoder::new(Vec::new(), EncoderConfig::default()).write_header(&header)?;
+        std::io::copy(&mut block_decoder, &mut encoder)?;
+        let encoded = encoder.finish_block()?.finish()?;
+
+        decoder = block_decoder.finish_block()?;
+
+        let original_record = &original[position..original.len().min(position + encoded.len())];
+
+        if original_record != encoded.as_slice() {
+            let offset = original_record
+                .iter()
+                .zip(&encoded)
+                .position(|(a, b)| a != b)
+                .unwrap_or(original_record.len());
+
+            return Ok(RoundTripReport {
+                record_count,
+                mismatch: Some(RoundTripMismatch {
+                    index: record_count,
+                    record_id,
+                    position: (position + offset) as u64,
+                }),
+            });
+        }
+
+        position += encoded.len();
+        record_count += 1;
+    }
+
+    let mismatch = if position != original.len() {
+        Some(RoundTripMismatch {
+            index: record_count,
+            record_id: String::new(),
+            position: position as u64,
+        })
+    } else {
+        None
+    };
+
+    Ok(RoundTripReport {
+        record_count,
+        mismatch,
+    })
+}
+
+/// Panics if the records of the WARC file are not encoded identically.
+///
+/// The compression format is determined from the filename extension
+/// (`.gz` or `.zst`).
+pub fn assert_round_trip<P: AsRef<Path>>(path: P) {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .unwrap_or_else(|error| panic!("could not open {}: {}", path.display(), error));
+
+    let report = check_round_trip(BufReader::new(file), format_from_path(path))
+        .unwrap_or_else(|error| panic!("could not process {}: {}", path.display(), error));
+
+    if let Some(mismatch) = report.mismatch {
+        panic!("round trip of {} failed: {}", path.display(), mismatch);
+    }
+}
+
+fn format_from_path(path: &Path) -> Format {
+    match path.extension().and_then(|value| value.to_str()) {
+        Some("gz") => Format::Gzip,
+        #[cfg(feature = "zstd")]
+        Some("zst") => Format::Zstandard,
+        _ => Format::Identity,
+    }
+}
+
+fn decompress<R: BufRead>(mut input: R, format: Format) -> std::io::Result<Vec<u8>> {
+    let dictionary = match format {
+        #[cfg(feature = "zstd")]
+        Format::Zstandard => Dictionary::WarcZstd(Vec::new()),
+        _ => Dictionary::None,
+    };
+    let mut decompressor = PushDecompressor::with_config(
+        Vec::new(),
+        DecompressorConfig {
+            format,
+            dictionary,
+            ..Default::default()
+        },
+    )?;
+    let mut compressed = Vec::new();
+    input.read_to_end(&mut compressed)?;
+
+    let mut buf = compressed.as_slice();
+    let mut is_segment_end = false;
+
+    while !buf.is_empty() {
+        let write_len = decompressor.write(buf)?;
+
+        if write_len == 0 {
+            if is_segment_end {
+                return Err(std::io::ErrorKind::InvalidData.into());
+            }
+
+            decompressor.start_next_segment()?;
+        }
+
+        is_segment_end = write_len == 0;
+        buf = &buf[write_len..];
+    }
+
+    decompressor.flush()?;
+    decompressor.into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORD: &[u8] = b"WARC/1.1\r\n\
+        WARC-Record-ID: <urn:uuid:1>\r\n\
+        WARC-Type: resource\r\n\
+        Content-Length: 5\r\n\
+        \r\n\
+        hello\r\n\r\n";
+
+    #[test]
+    fn test_check_round_trip() {
+        let input = [RECORD, RECORD].concat();
+        let report = check_round_trip(input.as_slice(), Format::Identity).unwrap();
+
+        assert_eq!(report.record_count, 2);
+        assert_eq!(report.mismatch, None);
+    }
+
+    #[test]
+    fn test_check_round_trip_mismatch() {
+        // Line folding is not preserved.
+        let folded = b"WARC/1.1\r\n\
+            WARC-Record-ID: <urn:uuid:2>\r\n\
+            WARC-Type: resource\r\n\
+            X-Folded: a\r\n b\r\n\
+            Content-Length: 5\r\n\
+            \r\n\
+            hello\r\n\r\n";
+        let input = [RECORD, folded].concat();
+        let report = check_round_trip(input.as_slice(), Format::Identity).unwrap();
+
+        assert_eq!(report.record_count, 1);
+        assert_eq!(
+            report.mismatch,
+            Some(RoundTripMismatch {
+                index: 1,
+                record_id: "<urn:uuid:2>".to_string(),
+                position: RECORD.len() as u64 + 72,
+            })
+        );
+    }
+}
@@ -6,19 +6,24 @@ use std::{
     str::FromStr,
 };
 
+use chrono::{DateTime, FixedOffset, SecondsFormat, TimeDelta, Utc};
 use data_encoding::HEXLOWER;
-use redb::{backends::InMemoryBackend, Database, MultimapTableDefinition, TableDefinition};
+use redb::{
+    backends::InMemoryBackend, Database, MultimapTableDefinition, ReadableMultimapTable,
+    ReadableTable, TableDefinition,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     digest::{AlgorithmName, Digest, Hasher},
     error::StorageError,
     extract::WarcExtractor,
-    header::{fields::FieldsExt, WarcHeader},
+    header::{fields::FieldsExt, protocol::Protocol, record_id::RecordId, WarcHeader},
+    segment::{SegmentInfo, SegmentJoiner},
 };
 
-// mapping of record ID => ()
-const RECORDS_TABLE: TableDefinition<&str, ()> = TableDefinition::new("records");
+// mapping of record ID => sequence number of the first record with the ID
+const RECORDS_TABLE: TableDefinition<&str, u64> = TableDefinition::new("records");
 // mapping of record ID => (reference target record ID, type of reference)
 const ID_REFERENCES_TABLE: MultimapTableDefinition<&str, (&str, &str)> =
     MultimapTableDefinition::new("id_references");
@@ -26,6 +31,31 @@ const ID_REFERENCES_TABLE: MultimapTableDefinition<&str, (&str, &str)> =
 const SEGMENT_ID_TABLE: TableDefinition<(&str, u64), u64> = TableDefinition::new("segments");
 // mapping of origin record ID => total length
 const SEGMENT_LENGTH_TABLE: TableDefinition<&str, u64> = TableDefinition::new("segment_lengths");
+// mapping of record ID => WARC-Payload-Digest
+const PAYLOAD_DIGEST_TABLE: TableDefinition<&str, &str> = TableDefinition::new("payload_digests");
+// mapping of (target URI, date) => record ID
+const CAPTURE_TABLE: TableDefinition<(&str, &str), &str> = TableDefinition::new("captures");
+// mapping of revisit record ID => (WARC-Refers-To, target URI, date, WARC-Payload-Digest)
+const REVISIT_TABLE: TableDefinition<&str, (&str, &str, &str, &str)> =
+    TableDefinition::new("revisits");
+// mapping of file => (position, record sequence number, is done)
+const CHECKPOINT_TABLE: TableDefinition<&str, (u64, u64, bool)> =
+    TableDefinition::new("checkpoints");
+
+const IDENTICAL_PAYLOAD_DIGEST_PROFILES: &[&str] = &[
+    "http://netpreserve.org/warc/1.1/revisit/identical-payload-digest",
+    "http://netpreserve.org/warc/1.0/revisit/identical-payload-digest",
+];
+const SERVER_NOT_MODIFIED_PROFILES: &[&str] = &[
+    "http://netpreserve.org/warc/1.1/revisit/server-not-modified",
+    "http://netpreserve.org/warc/1.0/revisit/server-not-modified",
+];
+/// Dates before this are not plausible for web captures.
+const MIN_DATE: &str = "1993-01-01T00:00:00Z";
+/// Default limit of the length of a header field value.
+pub const DEFAULT_MAX_FIELD_LENGTH: u64 = 8192;
+/// Default limit of the length of a serialized header.
+pub const DEFAULT_MAX_HEADER_LENGTH: u64 = 16384;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Check {
@@ -47,6 +77,15 @@ pub enum Check {
     // IdentifiedPayloadType,
     Segment,
     RecordAtTimeCompression,
+    Protocol,
+    ContentLengthActual,
+    DuplicateRecordId,
+    Revisit,
+    DuplicateField,
+    Date,
+    HeaderLength,
+    FieldEncoding,
+    LineFolding,
 }
 
 impl Check {
@@ -70,11 +109,21 @@ impl Check {
             // Self::IdentifiedPayloadType,
             Self::Segment,
             Self::RecordAtTimeCompression,
+            Self::Protocol,
+            Self::ContentLengthActual,
+            Self::DuplicateRecordId,
+            Self::Revisit,
+            Self::DuplicateField,
+            Self::Date,
+            Self::HeaderLength,
+            Self::FieldEncoding,
+            Self::LineFolding,
         ]
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[non_exhaustive]
 pub enum ProblemKind {
     UnknownRecordType(String),
@@ -107,21 +156,184 @@ pub enum ProblemKind {
     },
     ParsePayload(String),
     NotRecordAtTimeCompression,
+    ContainerChecksumMissing,
+    ContainerChecksumMismatch {
+        algorithm: String,
+        expected: String,
+        actual: String,
+    },
+    InvalidRecordId(String),
+    TruncatedFile,
+    UnknownProtocol(String),
+    InvalidCipherSuite(String),
+    ContentLengthMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    DuplicateRecordId,
+    UnknownProfile(String),
+    /// The payload digest of a revisit record does not match the record it
+    /// refers to.
+    RevisitDigestMismatch(String),
+    /// A field that must be unique is repeated.
+    DuplicateField(String),
+    /// The record is not listed in the CDX index.
+    CdxRecordMissing,
+    /// A CDX entry does not point at the start of a record.
+    CdxEntryNotFound {
+        url: String,
+        offset: u64,
+    },
+    /// The length in a CDX entry does not match the record.
+    CdxLengthMismatch {
+        expected: u64,
+        actual: u64,
+    },
+    /// The digest in a CDX entry does not match the payload digest of the
+    /// record.
+    CdxDigestMismatch {
+        expected: String,
+        actual: String,
+    },
+    /// The WARC-Date is in the future, before 1993, or outside the
+    /// configured range.
+    DateOutOfRange(String),
+    /// The WARC-Date is earlier than the latest date of the previous
+    /// records in the file by more than the configured tolerance.
+    DateOutOfOrder(String),
+    /// The value of a header field is longer than the configured limit.
+    FieldTooLong {
+        name: String,
+        length: u64,
+    },
+    /// The serialized header is longer than the configured limit.
+    HeaderTooLong {
+        length: u64,
+    },
+    /// The value of a header field is not valid UTF-8.
+    NonUtf8Field(String),
+    /// The value of a header field continues on folded lines.
+    ///
+    /// Folded lines are only found in headers parsed with
+    /// [`LineFolding::Preserve`](crate::header::LineFolding::Preserve).
+    FoldedField(String),
+}
+
+impl ProblemKind {
+    /// Returns how serious the problem is.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::RequiredFieldMissing(_)
+            | Self::ParseInt(_)
+            | Self::InvalidDate(_)
+            | Self::InvalidSegment
+            | Self::MissingSegment(_)
+            | Self::MismatchedSegmentLength { .. }
+            | Self::DigestMismatch { .. }
+            | Self::PayloadDigestMismatch { .. }
+            | Self::ContainerChecksumMismatch { .. }
+            | Self::InvalidRecordId(_)
+            | Self::TruncatedFile
+            | Self::ContentLengthMismatch { .. }
+            | Self::DuplicateRecordId
+            | Self::RevisitDigestMismatch(_)
+            | Self::DuplicateField(_)
+            | Self::CdxEntryNotFound { .. }
+            | Self::CdxLengthMismatch { .. }
+            | Self::CdxDigestMismatch { .. } => Severity::Error,
+            Self::UnknownRecordType(_)
+            | Self::ProhibitedField(_)
+            | Self::ReferencedRecordMissing(_)
+            | Self::UnknownDigest(_)
+            | Self::InvalidUrl(_)
+            | Self::InvalidIpAddress(_)
+            | Self::InvalidMediaType(_)
+            | Self::InvalidTruncatedReason
+            | Self::ParsePayload(_)
+            | Self::NotRecordAtTimeCompression
+            | Self::UnknownProtocol(_)
+            | Self::InvalidCipherSuite(_)
+            | Self::UnknownProfile(_)
+            | Self::CdxRecordMissing
+            | Self::DateOutOfRange(_)
+            | Self::DateOutOfOrder(_)
+            | Self::FieldTooLong { .. }
+            | Self::HeaderTooLong { .. }
+            | Self::NonUtf8Field(_)
+            | Self::FoldedField(_) => Severity::Warning,
+            Self::BadSpecUri(_) | Self::ContainerChecksumMissing => Severity::Info,
+        }
+    }
+}
+
+/// How serious a problem is.
+///
+/// Severities are ordered from the least to the most serious.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The file is valid but could be improved, such as for compatibility.
+    Info,
+    /// The file is questionable but its data is likely intact.
+    Warning,
+    /// The file is invalid or its data is damaged.
+    Error,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Problem {
     record_id: String,
     kind: ProblemKind,
+    severity: Severity,
 }
 
 impl Problem {
     pub fn new<I: Into<String>>(record_id: I, kind: ProblemKind) -> Self {
         Self {
             record_id: record_id.into(),
+            severity: kind.severity(),
             kind,
         }
     }
+
+    pub fn record_id(&self) -> &str {
+        &self.record_id
+    }
+
+    pub fn kind(&self) -> &ProblemKind {
+        &self.kind
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+/// A reference from a record to another record by ID.
+///
+/// See [`Verifier::find_references()`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordReference {
+    /// ID of the record containing the reference.
+    pub source: String,
+    /// ID of the referenced record.
+    pub target: String,
+    /// Name of the field without the `WARC-` prefix, such as `Refers-To`.
+    pub kind: String,
+}
+
+/// Progress of verifying a file saved in the database.
+///
+/// See [`Verifier::save_checkpoint()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Position of the record boundary where verification can continue.
+    pub position: u64,
+    /// Whether the file was verified to the end.
+    pub is_done: bool,
+    record_seq: u64,
 }
 
 /// Checks WARCs for specification conformance and integrity.
@@ -129,15 +341,28 @@ pub struct Verifier {
     checks: HashSet<Check>,
     db: Database,
     problems: Vec<Problem>,
+    record_seq: u64,
     id_references_cursor: Option<String>,
     segment_length_cursor: Option<String>,
+    revisit_cursor: Option<String>,
     header: WarcHeader,
+    is_record_open: bool,
+    block_length: u64,
+    task: Option<DigestTask>,
+    segment: Option<SegmentInfo>,
+    payload_segments: SegmentJoiner<PayloadState>,
+    date_range: (Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+    date_order_tolerance: Option<TimeDelta>,
+    latest_date: Option<DateTime<FixedOffset>>,
+    max_field_length: u64,
+    max_header_length: u64,
+}
+
+/// Payload digest state of a segmented record between segments.
+struct PayloadState {
+    extractor: WarcExtractor,
     digests: HashMap<AlgorithmName, Digest>,
     hashers: Vec<Hasher>,
-    payload_extractor: Option<WarcExtractor>,
-    payload_extractor_buf: Vec<u8>,
-    payload_digests: HashMap<AlgorithmName, Digest>,
-    payload_hashers: Vec<Hasher>,
 }
 
 impl Verifier {
@@ -162,21 +387,31 @@ impl Verifier {
         txn.open_multimap_table(ID_REFERENCES_TABLE)?;
         txn.open_table(SEGMENT_ID_TABLE)?;
         txn.open_table(SEGMENT_LENGTH_TABLE)?;
+        txn.open_table(PAYLOAD_DIGEST_TABLE)?;
+        txn.open_table(CAPTURE_TABLE)?;
+        txn.open_table(REVISIT_TABLE)?;
+        txn.open_table(CHECKPOINT_TABLE)?;
         txn.commit()?;
 
         Ok(Self {
             checks: HashSet::from_iter(Check::all().iter().cloned()),
             db,
             problems: Vec::new(),
+            record_seq: 0,
             id_references_cursor: Some(String::new()),
             segment_length_cursor: Some(String::new()),
+            revisit_cursor: Some(String::new()),
             header: WarcHeader::empty(),
-            digests: HashMap::new(),
-            hashers: Vec::new(),
-            payload_extractor: None,
-            payload_extractor_buf: Vec::new(),
-            payload_digests: HashMap::new(),
-            payload_hashers: Vec::new(),
+            is_record_open: false,
+            block_length: 0,
+            task: None,
+            segment: None,
+            payload_segments: SegmentJoiner::new(),
+            date_range: (None, None),
+            date_order_tolerance: None,
+            latest_date: None,
+            max_field_length: DEFAULT_MAX_FIELD_LENGTH,
+            max_header_length: DEFAULT_MAX_HEADER_LENGTH,
         })
     }
 
@@ -196,22 +431,172 @@ impl Verifier {
         &mut self.problems
     }
 
+    /// Sets the range of WARC-Date values accepted by the date check.
+    ///
+    /// Dates before 1993 and dates in the future are always reported.
+    pub fn set_date_range(&mut self, start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) {
+        self.date_range = (start, end);
+    }
+
+    /// Sets how much earlier a WARC-Date value can be than the latest date
+    /// of the previous records in the file.
+    ///
+    /// The chronological order is not checked by default.
+    pub fn set_date_order_tolerance(&mut self, value: Option<TimeDelta>) {
+        self.date_order_tolerance = value;
+    }
+
+    /// Sets the lengths of a header field value and of the serialized header
+    /// above which the header length check reports a problem.
+    ///
+    /// The limits are independent of the maximum header length accepted by
+    /// the [decoder](crate::warc::Decoder).
+    pub fn set_header_length_limits(&mut self, max_field_length: u64, max_header_length: u64) {
+        self.max_field_length = max_field_length;
+        self.max_header_length = max_header_length;
+    }
+
+    /// Starts verifying the records of another file.
+    ///
+    /// The chronological order of the records is checked within each file.
+    pub fn begin_file(&mut self) {
+        self.latest_date = None;
+    }
+
     /// Starts verifying a record.
     ///
     /// After calling this function, call [`block_data()`](Self::block_data).
     pub fn begin_record(&mut self, header: &WarcHeader) -> Result<(), StorageError> {
         self.header = header.clone();
-        self.digests.clear();
-        self.hashers.clear();
-        self.payload_extractor = None;
-        self.payload_digests.clear();
-        self.payload_hashers.clear();
+        self.is_record_open = true;
+        self.block_length = 0;
+        self.task = Some(DigestTask::new(self.record_id()));
+        self.segment = SegmentInfo::from_header(header).ok().flatten();
 
         self.process_header()?;
 
         Ok(())
     }
 
+    /// Removes and returns the digest checks of the current record so they
+    /// can be done on another thread.
+    ///
+    /// Returns `None` if the record does not have digests to check or if
+    /// the record is segmented. Segmented records depend on the state of
+    /// their previous segments, so their digests are checked by
+    /// [`block_data()`](Self::block_data) as usual.
+    ///
+    /// The problems returned by [`DigestTask::finish()`] should be treated
+    /// as problems of the current record.
+    pub fn take_digest_task(&mut self) -> Option<DigestTask> {
+        if self.segment.is_some() || self.task.as_ref().is_some_and(DigestTask::is_empty) {
+            None
+        } else {
+            self.task.take()
+        }
+    }
+
+    /// Removes the digest checks of the current record.
+    ///
+    /// This is for checking the digests of only a sample of the records.
+    /// Segmented records are not affected because the digests of the
+    /// following segments depend on the state of the previous segments.
+    pub fn skip_digests(&mut self) {
+        if self.segment.is_none() {
+            self.task = None;
+        }
+    }
+
+    /// Saves the progress of verifying a file into the database.
+    ///
+    /// The position is the record boundary after the last record whose
+    /// problems were handled. With a database opened by
+    /// [`open()`](Self::open), an interrupted verification can be continued
+    /// with [`resume()`](Self::resume).
+    pub fn save_checkpoint(
+        &mut self,
+        file: &str,
+        position: u64,
+        is_done: bool,
+    ) -> Result<(), StorageError> {
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(CHECKPOINT_TABLE)?;
+            table.insert(file, (position, self.record_seq, is_done))?;
+        }
+        txn.commit()?;
+
+        Ok(())
+    }
+
+    /// Returns the saved progress of verifying a file.
+    pub fn checkpoint(&self, file: &str) -> Result<Option<Checkpoint>, StorageError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(CHECKPOINT_TABLE)?;
+
+        Ok(table.get(file)?.map(|value| {
+            let (position, record_seq, is_done) = value.value();
+
+            Checkpoint {
+                position,
+                is_done,
+                record_seq,
+            }
+        }))
+    }
+
+    /// Returns the references of the verified records that point from or to
+    /// any of the given records.
+    ///
+    /// References are recorded from the WARC-Concurrent-To, WARC-Refers-To,
+    /// WARC-Warcinfo-ID, and WARC-Segment-Origin-ID fields by the checks of
+    /// the same names. All references are read on each call.
+    pub fn find_references(
+        &self,
+        record_ids: &HashSet<String>,
+    ) -> Result<Vec<RecordReference>, StorageError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_multimap_table(ID_REFERENCES_TABLE)?;
+        let mut references = Vec::new();
+
+        for item in table.iter()? {
+            let (key, values) = item?;
+            let source = key.value();
+            let is_source = record_ids.contains(source);
+
+            for item in values {
+                let item = item?;
+                let (target, kind) = item.value();
+
+                if is_source || record_ids.contains(target) {
+                    references.push(RecordReference {
+                        source: source.to_string(),
+                        target: target.to_string(),
+                        kind: kind.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(references)
+    }
+
+    /// Returns whether a record with the ID was verified.
+    pub fn has_record(&self, record_id: &str) -> Result<bool, StorageError> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(RECORDS_TABLE)?;
+
+        Ok(table.get(record_id)?.is_some())
+    }
+
+    /// Continues from a checkpoint.
+    ///
+    /// The records after the checkpoint position must be verified again
+    /// in the same order.
+    pub fn resume(&mut self, checkpoint: &Checkpoint) {
+        self.record_seq = self.record_seq.max(checkpoint.record_seq);
+    }
+
     /// Finish processing any remaining verification.
     ///
     /// This function should be repeated called until [`VerifyStatus::Done`]
@@ -219,8 +604,12 @@ impl Verifier {
     pub fn verify_end(&mut self) -> Result<VerifyStatus, StorageError> {
         self.check_references()?;
         self.check_segments()?;
+        self.check_revisits()?;
 
-        if self.id_references_cursor.is_none() && self.segment_length_cursor.is_none() {
+        if self.id_references_cursor.is_none()
+            && self.segment_length_cursor.is_none()
+            && self.revisit_cursor.is_none()
+        {
             Ok(VerifyStatus::Done)
         } else {
             Ok(VerifyStatus::HasMore)
@@ -248,7 +637,12 @@ impl Verifier {
 
                 for item in values {
                     let item = item?;
-                    let (target_id, _target_type) = item.value();
+                    let (target_id, target_type) = item.value();
+
+                    // Missing origin records are reported as missing segments.
+                    if target_type == "Segment-Origin-ID" {
+                        continue;
+                    }
 
                     if records_table.get(target_id)?.is_none() {
                         self.problems.push(Problem::new(
@@ -318,6 +712,63 @@ impl Verifier {
         Ok(())
     }
 
+    fn check_revisits(&mut self) -> Result<(), StorageError> {
+        tracing::trace!("check revisits");
+
+        let txn = self.db.begin_read()?;
+        let payload_digest_table = txn.open_table(PAYLOAD_DIGEST_TABLE)?;
+        let capture_table = txn.open_table(CAPTURE_TABLE)?;
+        let revisit_table = txn.open_table(REVISIT_TABLE)?;
+
+        if let Some(cursor) = self.revisit_cursor.take() {
+            let cursor = cursor.as_str();
+
+            for (index, item) in revisit_table.range(cursor..)?.enumerate() {
+                let (key, value) = item?;
+                let record_id = key.value();
+                let (refers_to, target_uri, date, payload_digest) = value.value();
+
+                if index == 1025 {
+                    self.revisit_cursor = Some(record_id.to_string());
+                    break;
+                }
+
+                let target_id = if !refers_to.is_empty() {
+                    Some(refers_to.to_string())
+                } else {
+                    capture_table
+                        .get((target_uri, date))?
+                        .map(|value| value.value().to_string())
+                };
+
+                // A referenced record that is not in the input is reported by
+                // the refers-to check if it is referenced by ID.
+                let Some(target_id) = target_id else {
+                    continue;
+                };
+                let Some(target_digest) = payload_digest_table.get(target_id.as_str())? else {
+                    continue;
+                };
+
+                if let (Ok(digest), Ok(target_digest)) = (
+                    Digest::from_str(payload_digest),
+                    Digest::from_str(target_digest.value()),
+                ) {
+                    if digest.algorithm() == target_digest.algorithm()
+                        && digest.value() != target_digest.value()
+                    {
+                        self.problems.push(Problem::new(
+                            record_id.to_string(),
+                            ProblemKind::RevisitDigestMismatch(target_id),
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn record_id(&self) -> &str {
         self.header.fields.get_or_default("WARC-Record-ID")
     }
@@ -395,19 +846,58 @@ impl Verifier {
         if self.checks.contains(&Check::Segment) {
             self.segment()?;
         }
+        if self.checks.contains(&Check::Protocol) {
+            self.protocol();
+        }
         if self.checks.contains(&Check::BlockDigest) {
             self.block_digest();
         }
         if self.checks.contains(&Check::PayloadDigest) {
             self.payload_digest();
         }
+        if self.checks.contains(&Check::Revisit) {
+            self.revisit()?;
+        }
+        if self.checks.contains(&Check::DuplicateField) {
+            self.duplicate_field();
+        }
+        if self.checks.contains(&Check::Date) {
+            self.date();
+        }
+        if self.checks.contains(&Check::HeaderLength) {
+            self.header_length();
+        }
+        if self.checks.contains(&Check::FieldEncoding) {
+            self.field_encoding();
+        }
+        if self.checks.contains(&Check::LineFolding) {
+            self.line_folding();
+        }
 
+        // Records that are verified again after resuming from a checkpoint
+        // have the same sequence number as before and are not duplicates.
         let txn = self.db.begin_write()?;
-        {
+        let is_duplicate = {
             let mut table = txn.open_table(RECORDS_TABLE)?;
-            table.insert(self.record_id(), ())?;
-        }
+            let previous = table.get(self.record_id())?.map(|value| value.value());
+            let is_duplicate = previous.is_some_and(|seq| seq < self.record_seq);
+
+            if !is_duplicate {
+                table.insert(self.record_id(), self.record_seq)?;
+            }
+
+            is_duplicate
+        };
         txn.commit()?;
+        self.record_seq += 1;
+
+        if is_duplicate
+            && self.checks.contains(&Check::DuplicateRecordId)
+            && self.header.fields.contains_name("WARC-Record-ID")
+        {
+            self.add_problem(ProblemKind::DuplicateRecordId);
+        }
+
         Ok(())
     }
 
@@ -416,16 +906,7 @@ impl Verifier {
 
         self.require_fields(&["WARC-Record-ID", "Content-Length", "WARC-Date", "WARC-Type"]);
 
-        if !self.is_any_record_type(&[
-            "warcinfo",
-            "response",
-            "resource",
-            "request",
-            "metadata",
-            "revisit",
-            "conversion",
-            "continuation",
-        ]) {
+        if !self.header.warc_type().is_some_and(|value| value.is_known()) {
             self.add_problem(ProblemKind::UnknownRecordType(
                 self.record_type().to_string(),
             ));
@@ -434,6 +915,108 @@ impl Verifier {
         if let Some(Err(_error)) = self.header.fields.get_date("WARC-Date") {
             self.add_problem(ProblemKind::InvalidDate("WARC-Date".to_string()));
         }
+
+        if let Some(value) = self.header.fields.get("WARC-Record-ID") {
+            if RecordId::parse_strict(value).is_err() {
+                self.add_problem(ProblemKind::InvalidRecordId("WARC-Record-ID".to_string()));
+            }
+        }
+    }
+
+    fn duplicate_field(&mut self) {
+        tracing::trace!("check duplicate field");
+
+        for name in self.header.duplicate_fields() {
+            self.add_problem(ProblemKind::DuplicateField(name.to_string()));
+        }
+    }
+
+    fn date(&mut self) {
+        tracing::trace!("check date");
+
+        let Some(Ok(date)) = self.header.fields.get_date("WARC-Date") else {
+            return;
+        };
+        let date_utc = date.to_utc();
+        let min_date = DateTime::parse_from_rfc3339(MIN_DATE).unwrap().to_utc();
+        let (start, end) = self.date_range;
+
+        if date_utc < min_date
+            || date_utc > Utc::now()
+            || start.is_some_and(|start| date_utc < start)
+            || end.is_some_and(|end| date_utc > end)
+        {
+            let value = self.header.fields.get_or_default("WARC-Date").to_string();
+            self.add_problem(ProblemKind::DateOutOfRange(value));
+
+            // A wrong date would cause the following records to be reported
+            // as out of order.
+            return;
+        }
+
+        if let (Some(tolerance), Some(latest_date)) = (self.date_order_tolerance, self.latest_date)
+        {
+            if date < latest_date - tolerance {
+                self.add_problem(ProblemKind::DateOutOfOrder(
+                    latest_date.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                ));
+            }
+        }
+
+        self.latest_date = Some(self.latest_date.map_or(date, |value| value.max(date)));
+    }
+
+    fn header_length(&mut self) {
+        tracing::trace!("check header length");
+
+        let header_length = self.header.serialized_len();
+        let long_fields = self
+            .header
+            .fields
+            .iter()
+            .filter(|(_name, value)| value.len() as u64 > self.max_field_length)
+            .map(|(name, value)| (name.to_string(), value.len() as u64))
+            .collect::<Vec<_>>();
+
+        for (name, length) in long_fields {
+            self.add_problem(ProblemKind::FieldTooLong { name, length });
+        }
+
+        if header_length > self.max_header_length {
+            self.add_problem(ProblemKind::HeaderTooLong {
+                length: header_length,
+            });
+        }
+    }
+
+    fn field_encoding(&mut self) {
+        tracing::trace!("check field encoding");
+
+        let names = self
+            .header
+            .opaque_field_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        for name in names {
+            self.add_problem(ProblemKind::NonUtf8Field(name));
+        }
+    }
+
+    fn line_folding(&mut self) {
+        tracing::trace!("check line folding");
+
+        let names = self
+            .header
+            .folded_field_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        for name in names {
+            self.add_problem(ProblemKind::FoldedField(name));
+        }
     }
 
     fn content_type(&mut self) {
@@ -620,11 +1203,122 @@ impl Verifier {
     fn profile(&mut self) {
         tracing::trace!("check profile");
 
-        if self.is_any_record_type(&["profile"]) {
+        if self.is_any_record_type(&["revisit"]) {
             self.require_field("WARC-Profile");
+
+            if let Some(profile) = self.header.fields.get_url_str("WARC-Profile") {
+                if !IDENTICAL_PAYLOAD_DIGEST_PROFILES.contains(&profile)
+                    && !SERVER_NOT_MODIFIED_PROFILES.contains(&profile)
+                {
+                    self.add_problem(ProblemKind::UnknownProfile(profile.to_string()));
+                }
+            }
         }
         if self.header.fields.is_formatted_bad_spec_url("WARC-Profile") {
-            self.add_problem(ProblemKind::BadSpecUri("WARC-Target-URI".to_string()));
+            self.add_problem(ProblemKind::BadSpecUri("WARC-Profile".to_string()));
+        }
+    }
+
+    fn revisit(&mut self) -> Result<(), StorageError> {
+        tracing::trace!("check revisit");
+
+        let is_revisit = self.is_any_record_type(&["revisit"]);
+        let (uri_name, date_name) = if is_revisit {
+            ("WARC-Refers-To-Target-URI", "WARC-Refers-To-Date")
+        } else {
+            ("WARC-Target-URI", "WARC-Date")
+        };
+        let target_uri = self
+            .header
+            .fields
+            .get_url_str(uri_name)
+            .unwrap_or_default()
+            .to_string();
+        let date = normalize_date(self.header.fields.get(date_name));
+
+        if !is_revisit {
+            // Remember the payload digest for the revisit records that refer
+            // to this record.
+            if let Some(payload_digest) = self.header.fields.get("WARC-Payload-Digest") {
+                let txn = self.db.begin_write()?;
+                {
+                    let mut table = txn.open_table(PAYLOAD_DIGEST_TABLE)?;
+                    table.insert(self.record_id(), payload_digest.as_str())?;
+
+                    if !target_uri.is_empty() && !date.is_empty() {
+                        let mut table = txn.open_table(CAPTURE_TABLE)?;
+                        table.insert((target_uri.as_str(), date.as_str()), self.record_id())?;
+                    }
+                }
+                txn.commit()?;
+            }
+
+            return Ok(());
+        }
+
+        let profile = self
+            .header
+            .fields
+            .get_url_str("WARC-Profile")
+            .unwrap_or_default();
+
+        if !IDENTICAL_PAYLOAD_DIGEST_PROFILES.contains(&profile) {
+            return Ok(());
+        }
+
+        self.require_fields(&[
+            "WARC-Refers-To-Target-URI",
+            "WARC-Refers-To-Date",
+            "WARC-Payload-Digest",
+        ]);
+
+        if let Some(payload_digest) = self.header.fields.get("WARC-Payload-Digest") {
+            let refers_to = self.header.fields.get_or_default("WARC-Refers-To");
+
+            let txn = self.db.begin_write()?;
+            {
+                let mut table = txn.open_table(REVISIT_TABLE)?;
+                table.insert(
+                    self.record_id(),
+                    (
+                        refers_to,
+                        target_uri.as_str(),
+                        date.as_str(),
+                        payload_digest.as_str(),
+                    ),
+                )?;
+            }
+            txn.commit()?;
+        }
+
+        Ok(())
+    }
+
+    fn protocol(&mut self) {
+        tracing::trace!("check protocol");
+
+        let values = self
+            .header
+            .fields
+            .get_all("WARC-Protocol")
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for value in values {
+            match Protocol::from_str(&value) {
+                Ok(protocol) if protocol.is_known() => {}
+                _ => self.add_problem(ProblemKind::UnknownProtocol(value)),
+            }
+        }
+
+        match self.header.fields.get_cipher_suite() {
+            Some(Ok(_)) => self.require_field("WARC-Protocol"),
+            Some(Err(_error)) => {
+                self.add_problem(ProblemKind::InvalidCipherSuite(
+                    "WARC-Cipher-Suite".to_string(),
+                ));
+            }
+            None => {}
         }
     }
 
@@ -692,6 +1386,9 @@ impl Verifier {
         {
             let mut table = txn.open_table(SEGMENT_ID_TABLE)?;
             table.insert((origin_id, number), self.header.content_length().unwrap())?;
+
+            let mut table = txn.open_multimap_table(ID_REFERENCES_TABLE)?;
+            table.insert(self.record_id(), (origin_id, "Segment-Origin-ID"))?;
         }
         txn.commit()?;
 
@@ -729,11 +1426,12 @@ impl Verifier {
         tracing::trace!("check block-digest");
 
         let mut pending_problems = Vec::new();
+        let task = self.task.as_mut().unwrap();
 
         for value in self.header.fields.get_all("WARC-Block-Digest") {
             if let Ok(digest) = Digest::from_str(value) {
-                self.hashers.push(Hasher::new(digest.algorithm()));
-                self.digests.insert(digest.algorithm(), digest);
+                task.hashers.push(Hasher::new(digest.algorithm()));
+                task.digests.insert(digest.algorithm(), digest);
             } else {
                 pending_problems.push(ProblemKind::UnknownDigest(value.to_string()));
             }
@@ -747,6 +1445,18 @@ impl Verifier {
     fn payload_digest(&mut self) {
         tracing::trace!("check payload-digest");
 
+        if let Some(segment) = self.segment.as_ref().filter(|segment| !segment.is_first()) {
+            // The payload digest of the first segment covers all the segments.
+            if let Some(state) = self.payload_segments.resume(segment) {
+                let task = self.task.as_mut().unwrap();
+                task.payload_extractor = Some(state.extractor);
+                task.payload_digests = state.digests;
+                task.payload_hashers = state.hashers;
+            }
+
+            return;
+        }
+
         if self.header.fields.contains_name("WARC-Payload-Digest") {
             let mut extractor = WarcExtractor::new();
             if let Err(error) = extractor.read_header(&self.header) {
@@ -756,18 +1466,19 @@ impl Verifier {
             }
 
             if extractor.has_content() {
-                self.payload_extractor = Some(extractor);
+                self.task.as_mut().unwrap().payload_extractor = Some(extractor);
             } else {
                 return;
             }
         }
 
         let mut pending_problems = Vec::new();
+        let task = self.task.as_mut().unwrap();
 
         for value in self.header.fields.get_all("WARC-Payload-Digest") {
             if let Ok(digest) = Digest::from_str(value) {
-                self.payload_hashers.push(Hasher::new(digest.algorithm()));
-                self.payload_digests.insert(digest.algorithm(), digest);
+                task.payload_hashers.push(Hasher::new(digest.algorithm()));
+                task.payload_digests.insert(digest.algorithm(), digest);
             } else {
                 pending_problems.push(ProblemKind::UnknownDigest(value.to_string()));
             }
@@ -782,6 +1493,117 @@ impl Verifier {
     ///
     /// This function should be called until there is no more block data.
     /// Then, call [`end_record()`](Self::end_record).
+    pub fn block_data(&mut self, data: &[u8]) {
+        self.block_length += data.len() as u64;
+
+        if let Some(task) = &mut self.task {
+            task.block_data(data);
+        }
+    }
+
+    /// Indicate the end of the record.
+    ///
+    /// Call [`begin_record()`](Self::begin_record) or [`verify_end()`](Self::verify_end) next.
+    pub fn end_record(&mut self) {
+        self.is_record_open = false;
+
+        if self.checks.contains(&Check::ContentLengthActual) {
+            self.content_length_actual();
+        }
+
+        let Some(mut task) = self.task.take() else {
+            return;
+        };
+
+        task.verify_block_digests();
+
+        if let Some(segment) = self.segment.as_ref().filter(|segment| !segment.is_last()) {
+            let hashers = std::mem::take(&mut task.payload_hashers);
+            let digests = std::mem::take(&mut task.payload_digests);
+
+            if let Some(extractor) = task.payload_extractor.take() {
+                self.payload_segments.suspend(
+                    segment,
+                    PayloadState {
+                        extractor,
+                        digests,
+                        hashers,
+                    },
+                );
+            }
+        } else {
+            task.verify_payload_digests();
+        }
+
+        self.problems.append(&mut task.problems);
+    }
+
+    pub fn add_not_record_at_time_compression(&mut self) {
+        self.add_problem(ProblemKind::NotRecordAtTimeCompression);
+    }
+
+    /// Adds a problem that the file ended within the current record.
+    pub fn add_truncated_file(&mut self) {
+        if self.is_record_open && self.checks.contains(&Check::ContentLengthActual) {
+            self.is_record_open = false;
+            self.content_length_actual();
+        }
+
+        self.add_problem(ProblemKind::TruncatedFile);
+    }
+
+    fn content_length_actual(&mut self) {
+        tracing::trace!("check content-length-actual");
+
+        // An invalid value is reported by the mandatory fields check.
+        if let Ok(expected) = self.header.content_length() {
+            if expected != self.block_length {
+                self.add_problem(ProblemKind::ContentLengthMismatch {
+                    expected,
+                    actual: self.block_length,
+                });
+            }
+        }
+    }
+}
+
+/// Block and payload digest checks of a record.
+///
+/// See [`Verifier::take_digest_task()`].
+pub struct DigestTask {
+    record_id: String,
+    digests: HashMap<AlgorithmName, Digest>,
+    hashers: Vec<Hasher>,
+    payload_extractor: Option<WarcExtractor>,
+    payload_extractor_buf: Vec<u8>,
+    payload_digests: HashMap<AlgorithmName, Digest>,
+    payload_hashers: Vec<Hasher>,
+    problems: Vec<Problem>,
+}
+
+impl DigestTask {
+    fn new(record_id: &str) -> Self {
+        Self {
+            record_id: record_id.to_string(),
+            digests: HashMap::new(),
+            hashers: Vec::new(),
+            payload_extractor: None,
+            payload_extractor_buf: Vec::new(),
+            payload_digests: HashMap::new(),
+            payload_hashers: Vec::new(),
+            problems: Vec::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.hashers.is_empty() && self.payload_extractor.is_none()
+    }
+
+    fn add_problem(&mut self, kind: ProblemKind) {
+        self.problems.push(Problem::new(&self.record_id, kind));
+    }
+
+    /// Process the block data of the record.
     pub fn block_data(&mut self, data: &[u8]) {
         for hasher in &mut self.hashers {
             hasher.update(data);
@@ -807,10 +1629,14 @@ impl Verifier {
         }
     }
 
-    /// Indicate the end of the record.
-    ///
-    /// Call [`begin_record()`](Self::begin_record) or [`verify_end()`](Self::verify_end) next.
-    pub fn end_record(&mut self) {
+    /// Indicate the end of the record and return the problems.
+    pub fn finish(mut self) -> Vec<Problem> {
+        self.verify_block_digests();
+        self.verify_payload_digests();
+        self.problems
+    }
+
+    fn verify_block_digests(&mut self) {
         let mut hashers = std::mem::take(&mut self.hashers);
 
         tracing::trace!(hashers_len = hashers.len(), "verify block digests");
@@ -828,9 +1654,9 @@ impl Verifier {
                 });
             }
         }
+    }
 
-        self.hashers = hashers;
-
+    fn verify_payload_digests(&mut self) {
         let mut payload_hashers = std::mem::take(&mut self.payload_hashers);
 
         tracing::trace!(hashers_len = payload_hashers.len(), "verify payload digests");
@@ -848,12 +1674,16 @@ impl Verifier {
                 });
             }
         }
-
-        self.payload_hashers = payload_hashers;
     }
+}
 
-    pub fn add_not_record_at_time_compression(&mut self) {
-        self.add_problem(ProblemKind::NotRecordAtTimeCompression);
+/// Returns the date in a consistent format so that dates written with
+/// different precisions can be compared.
+fn normalize_date(value: Option<&String>) -> String {
+    match value.map(|value| DateTime::parse_from_rfc3339(value)) {
+        Some(Ok(date)) => date.to_utc().to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        Some(Err(_)) => value.cloned().unwrap_or_default(),
+        None => String::new(),
     }
 }
 
@@ -868,3 +1698,299 @@ pub enum VerifyStatus {
     HasMore,
     Done,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::header::LineFolding;
+
+    use super::*;
+
+    fn verify_headers(headers: &[WarcHeader]) -> Vec<Problem> {
+        let mut verifier = Verifier::new();
+        verifier.checks_mut().clear();
+        verifier.checks_mut().insert(Check::Profile);
+        verifier.checks_mut().insert(Check::Revisit);
+
+        for header in headers {
+            verifier.begin_record(header).unwrap();
+            verifier.end_record();
+        }
+
+        while verifier.verify_end().unwrap() == VerifyStatus::HasMore {}
+
+        verifier.problems().to_vec()
+    }
+
+    fn header(id: &str, record_type: &str, fields: &[(&str, &str)]) -> WarcHeader {
+        let mut header = WarcHeader::new(0, record_type);
+        header
+            .fields
+            .insert("WARC-Record-ID".to_string(), id.to_string());
+
+        for (name, value) in fields {
+            header.fields.insert(name.to_string(), value.to_string());
+        }
+
+        header
+    }
+
+    #[test]
+    fn test_resume() {
+        let headers = [
+            header("<urn:example:1>", "resource", &[]),
+            header("<urn:example:2>", "resource", &[]),
+            header("<urn:example:1>", "resource", &[]),
+        ];
+        let is_duplicate = |verifier: &Verifier| {
+            verifier
+                .problems()
+                .iter()
+                .any(|problem| matches!(problem.kind(), ProblemKind::DuplicateRecordId))
+        };
+
+        let mut verifier = Verifier::new();
+        verifier.checks_mut().clear();
+        verifier.checks_mut().insert(Check::DuplicateRecordId);
+
+        verifier.begin_record(&headers[0]).unwrap();
+        verifier.end_record();
+        verifier.save_checkpoint("a.warc", 100, false).unwrap();
+
+        // Interrupted after the checkpoint.
+        for header in &headers[1..] {
+            verifier.begin_record(header).unwrap();
+            verifier.end_record();
+        }
+        assert!(is_duplicate(&verifier));
+
+        let checkpoint = verifier.checkpoint("a.warc").unwrap().unwrap();
+        assert_eq!(checkpoint.position, 100);
+        assert!(!checkpoint.is_done);
+        assert_eq!(verifier.checkpoint("b.warc").unwrap(), None);
+
+        // Continue with the same database.
+        verifier.record_seq = 0;
+        verifier.problems_mut().clear();
+        verifier.resume(&checkpoint);
+
+        verifier.begin_record(&headers[1]).unwrap();
+        verifier.end_record();
+        assert!(!is_duplicate(&verifier));
+
+        verifier.begin_record(&headers[2]).unwrap();
+        verifier.end_record();
+        assert!(is_duplicate(&verifier));
+    }
+
+    #[test]
+    fn test_revisit() {
+        let response = header(
+            "<urn:example:1>",
+            "response",
+            &[
+                ("WARC-Target-URI", "http://example.com/"),
+                ("WARC-Date", "2024-01-02T03:04:05Z"),
+                (
+                    "WARC-Payload-Digest",
+                    "sha1:3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ",
+                ),
+            ],
+        );
+        let revisit = |id: &str, digest: &str| {
+            header(
+                id,
+                "revisit",
+                &[
+                    ("WARC-Profile", IDENTICAL_PAYLOAD_DIGEST_PROFILES[0]),
+                    ("WARC-Refers-To-Target-URI", "http://example.com/"),
+                    ("WARC-Refers-To-Date", "2024-01-02T03:04:05.000Z"),
+                    ("WARC-Payload-Digest", digest),
+                ],
+            )
+        };
+
+        let problems = verify_headers(&[
+            response,
+            revisit("<urn:example:2>", "sha1:3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ"),
+            revisit("<urn:example:3>", "sha1:LRPJEK4G7TIGBBHGD5NBDEWCVNMDVNCU"),
+            header(
+                "<urn:example:4>",
+                "revisit",
+                &[("WARC-Profile", "http://example.com/profile")],
+            ),
+            header(
+                "<urn:example:5>",
+                "revisit",
+                &[("WARC-Profile", SERVER_NOT_MODIFIED_PROFILES[0])],
+            ),
+        ]);
+
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].record_id(), "<urn:example:4>");
+        assert!(matches!(
+            problems[0].kind(),
+            ProblemKind::UnknownProfile(profile) if profile == "http://example.com/profile"
+        ));
+        assert_eq!(problems[1].record_id(), "<urn:example:3>");
+        assert!(matches!(
+            problems[1].kind(),
+            ProblemKind::RevisitDigestMismatch(id) if id == "<urn:example:1>"
+        ));
+
+        let problems = verify_headers(&[header(
+            "<urn:example:6>",
+            "revisit",
+            &[("WARC-Profile", IDENTICAL_PAYLOAD_DIGEST_PROFILES[1])],
+        )]);
+
+        assert_eq!(problems.len(), 3);
+        assert!(problems
+            .iter()
+            .all(|problem| matches!(problem.kind(), ProblemKind::RequiredFieldMissing(_))));
+    }
+
+    #[test]
+    fn test_date() {
+        let headers = [
+            ("<urn:example:1>", "2024-01-02T03:04:05Z"),
+            ("<urn:example:2>", "1992-12-31T23:59:59Z"),
+            ("<urn:example:3>", "9999-01-01T00:00:00Z"),
+            ("<urn:example:4>", "2024-01-02T03:00:00Z"),
+            ("<urn:example:5>", "2023-06-01T00:00:00Z"),
+            ("<urn:example:6>", "2025-01-01T00:00:00Z"),
+        ]
+        .map(|(id, date)| header(id, "resource", &[("WARC-Date", date)]));
+
+        let end = DateTime::parse_from_rfc3339("2024-12-31T00:00:00Z").unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.checks_mut().clear();
+        verifier.checks_mut().insert(Check::Date);
+        verifier.set_date_range(None, Some(end.to_utc()));
+        verifier.set_date_order_tolerance(Some(TimeDelta::hours(1)));
+
+        for header in &headers {
+            verifier.begin_record(header).unwrap();
+            verifier.end_record();
+        }
+
+        let problems = verifier
+            .problems()
+            .iter()
+            .map(|problem| (problem.record_id(), problem.kind().clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(problems.len(), 4);
+        assert!(matches!(
+            &problems[0],
+            ("<urn:example:2>", ProblemKind::DateOutOfRange(value)) if value == "1992-12-31T23:59:59Z"
+        ));
+        assert!(matches!(
+            &problems[1],
+            ("<urn:example:3>", ProblemKind::DateOutOfRange(_))
+        ));
+        assert!(matches!(
+            &problems[2],
+            ("<urn:example:5>", ProblemKind::DateOutOfOrder(value)) if value == "2024-01-02T03:04:05Z"
+        ));
+        assert!(matches!(
+            &problems[3],
+            ("<urn:example:6>", ProblemKind::DateOutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_header_length() {
+        let long_value = "a".repeat(100);
+        let headers = [
+            header("<urn:example:1>", "metadata", &[("WARC-Target-URI", "a")]),
+            header(
+                "<urn:example:2>",
+                "metadata",
+                &[("X-Crawler-Data", &long_value)],
+            ),
+        ];
+
+        let mut verifier = Verifier::new();
+        verifier.checks_mut().clear();
+        verifier.checks_mut().insert(Check::HeaderLength);
+        verifier.set_header_length_limits(50, 200);
+
+        for header in &headers {
+            verifier.begin_record(header).unwrap();
+            verifier.end_record();
+        }
+
+        let mut buf = Vec::new();
+        headers[1].serialize(&mut buf).unwrap();
+        let problems = verifier
+            .problems()
+            .iter()
+            .map(|problem| (problem.record_id(), problem.kind().clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(problems.len(), 2);
+        assert!(matches!(
+            &problems[0],
+            ("<urn:example:2>", ProblemKind::FieldTooLong { name, length: 100 }) if name == "X-Crawler-Data"
+        ));
+        assert!(matches!(
+            &problems[1],
+            ("<urn:example:2>", ProblemKind::HeaderTooLong { length }) if *length == buf.len() as u64
+        ));
+    }
+
+    #[test]
+    fn test_field_encoding() {
+        let header = WarcHeader::parse(
+            b"WARC/1.1\r\n\
+            WARC-Record-ID: <urn:example:1>\r\n\
+            WARC-Type: response\r\n\
+            WARC-Target-URI: http://example.com/caf\xe9\r\n\
+            Content-Length: 0\r\n\
+            \r\n",
+        )
+        .unwrap();
+
+        let mut verifier = Verifier::new();
+        verifier.checks_mut().clear();
+        verifier.checks_mut().insert(Check::FieldEncoding);
+        verifier.begin_record(&header).unwrap();
+        verifier.end_record();
+
+        assert_eq!(verifier.problems().len(), 1);
+        assert!(matches!(
+            verifier.problems()[0].kind(),
+            ProblemKind::NonUtf8Field(name) if name == "WARC-Target-URI"
+        ));
+    }
+
+    #[test]
+    fn test_line_folding() {
+        let data = b"WARC/1.1\r\n\
+            WARC-Record-ID: <urn:example:1>\r\n\
+            X-Note: a\r\n b\r\n\
+            Content-Length: 0\r\n\
+            \r\n";
+
+        let mut verifier = Verifier::new();
+        verifier.checks_mut().clear();
+        verifier.checks_mut().insert(Check::LineFolding);
+
+        let header = WarcHeader::parse(data).unwrap();
+        verifier.begin_record(&header).unwrap();
+        verifier.end_record();
+
+        assert!(verifier.problems().is_empty());
+
+        let header = WarcHeader::parse_with_line_folding(data, LineFolding::Preserve).unwrap();
+        verifier.begin_record(&header).unwrap();
+        verifier.end_record();
+
+        assert_eq!(verifier.problems().len(), 1);
+        assert!(matches!(
+            verifier.problems()[0].kind(),
+            ProblemKind::FoldedField(name) if name == "X-Note"
+        ));
+    }
+}
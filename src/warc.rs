@@ -1,6 +1,25 @@
 //! WARC file format
+pub use builder::*;
 pub use decode::*;
 pub use encode::*;
+pub use iter::*;
+pub use multi::*;
+#[cfg(feature = "parallel-decode")]
+pub use parallel::*;
+pub use rotate::*;
+pub use slice::*;
 
+mod builder;
 mod decode;
 mod encode;
+mod iter;
+mod multi;
+#[cfg(feature = "parallel-decode")]
+mod parallel;
+mod rotate;
+mod slice;
+
+/// Returns the error for a method called in a state that does not allow it.
+fn state_error(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
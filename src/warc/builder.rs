@@ -0,0 +1,243 @@
+use std::{io::Write, net::IpAddr};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::{
+    error::GeneralError,
+    header::{fields::FieldsExt, WarcHeader, WarcType},
+};
+
+use super::{EncStateHeader, Encoder};
+
+/// Content-Type of a block containing an HTTP request.
+pub const HTTP_REQUEST_CONTENT_TYPE: &str = "application/http;msgtype=request";
+/// Content-Type of a block containing an HTTP response.
+pub const HTTP_RESPONSE_CONTENT_TYPE: &str = "application/http;msgtype=response";
+/// Content-Type of a block containing named fields.
+pub const WARC_FIELDS_CONTENT_TYPE: &str = "application/warc-fields";
+
+/// Helper for constructing records of common types.
+///
+/// The WARC-Record-ID and WARC-Date fields are generated when the builder
+/// is created. The Content-Length field is set from the block when the
+/// record is written.
+///
+/// ```
+/// use warcat::warc::{Encoder, EncoderConfig, RecordBuilder};
+///
+/// let encoder = Encoder::new(Vec::new(), EncoderConfig::default());
+///
+/// let request = RecordBuilder::request("http://example.com/");
+/// let response = RecordBuilder::response("http://example.com/")
+///     .with_concurrent_to(request.record_id());
+///
+/// let encoder = request.write(encoder, b"GET / HTTP/1.1\r\n\r\n")?;
+/// let encoder = response.write(encoder, b"HTTP/1.1 204 No Content\r\n\r\n")?;
+///
+/// encoder.finish()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct RecordBuilder {
+    header: WarcHeader,
+}
+
+impl RecordBuilder {
+    /// Creates a record of the given type.
+    pub fn new<T: Into<String>>(warc_type: T) -> Self {
+        let warc_type = WarcType::from(warc_type.into().as_str());
+
+        Self {
+            header: WarcHeader::builder(warc_type).build(0),
+        }
+    }
+
+    /// Creates a `response` record containing an HTTP response.
+    pub fn response<U: Into<String>>(target_uri: U) -> Self {
+        Self::new("response")
+            .with_target_uri(target_uri)
+            .with_content_type(HTTP_RESPONSE_CONTENT_TYPE)
+    }
+
+    /// Creates a `request` record containing an HTTP request.
+    pub fn request<U: Into<String>>(target_uri: U) -> Self {
+        Self::new("request")
+            .with_target_uri(target_uri)
+            .with_content_type(HTTP_REQUEST_CONTENT_TYPE)
+    }
+
+    /// Creates a `resource` record containing a document of the given media
+    /// type.
+    pub fn resource<U: Into<String>, C: Into<String>>(target_uri: U, content_type: C) -> Self {
+        Self::new("resource")
+            .with_target_uri(target_uri)
+            .with_content_type(content_type)
+    }
+
+    /// Creates a `metadata` record containing named fields about the
+    /// resource.
+    pub fn metadata<U: Into<String>>(target_uri: U) -> Self {
+        Self::new("metadata")
+            .with_target_uri(target_uri)
+            .with_content_type(WARC_FIELDS_CONTENT_TYPE)
+    }
+
+    /// Creates a `warcinfo` record containing named fields about the
+    /// records following it.
+    pub fn warcinfo() -> Self {
+        Self::new("warcinfo").with_content_type(WARC_FIELDS_CONTENT_TYPE)
+    }
+
+    /// Returns the WARC-Record-ID of the record.
+    pub fn record_id(&self) -> &str {
+        self.header.fields.get_or_default("WARC-Record-ID")
+    }
+
+    /// Returns the header as built so far.
+    pub fn header(&self) -> &WarcHeader {
+        &self.header
+    }
+
+    /// Sets a field, replacing any fields of the same name.
+    pub fn with_field<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.header.fields.insert(name.into(), value.into());
+        self
+    }
+
+    /// Sets the WARC-Date field.
+    pub fn with_date(self, date: DateTime<Utc>) -> Self {
+        self.with_field(
+            "WARC-Date",
+            date.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        )
+    }
+
+    /// Sets the WARC-Target-URI field.
+    pub fn with_target_uri<U: Into<String>>(self, target_uri: U) -> Self {
+        self.with_field("WARC-Target-URI", target_uri)
+    }
+
+    /// Sets the Content-Type field.
+    pub fn with_content_type<C: Into<String>>(self, content_type: C) -> Self {
+        self.with_field("Content-Type", content_type)
+    }
+
+    /// Sets the WARC-IP-Address field.
+    pub fn with_ip_address(self, address: IpAddr) -> Self {
+        self.with_field("WARC-IP-Address", address.to_string())
+    }
+
+    /// Sets the WARC-Warcinfo-ID field to the record ID of a warcinfo record.
+    pub fn with_warcinfo_id<I: Into<String>>(self, record_id: I) -> Self {
+        self.with_field("WARC-Warcinfo-ID", record_id)
+    }
+
+    /// Adds a WARC-Concurrent-To field with the record ID of a record
+    /// created in the same capture event.
+    ///
+    /// The field may be added several times to link several records.
+    pub fn with_concurrent_to<I: Into<String>>(mut self, record_id: I) -> Self {
+        self.header
+            .fields
+            .append("WARC-Concurrent-To".to_string(), record_id.into());
+        self
+    }
+
+    /// Returns the header of a record with a block of the given length.
+    pub fn build(mut self, block_length: u64) -> WarcHeader {
+        self.header.set_content_length(block_length);
+        self.header
+    }
+
+    /// Writes the record with the block using the encoder.
+    pub fn write<W: Write>(
+        self,
+        encoder: Encoder<EncStateHeader, W>,
+        block: &[u8],
+    ) -> Result<Encoder<EncStateHeader, W>, GeneralError> {
+        let header = self.build(block.len() as u64);
+        let mut encoder = encoder.write_header(&header)?;
+        encoder.write_all(block)?;
+
+        Ok(encoder.finish_block()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use crate::{
+        verify::{Verifier, VerifyStatus},
+        warc::{Decoder, DecoderConfig, EncoderConfig},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_record_builder() {
+        let encoder = Encoder::new(Vec::new(), EncoderConfig::default());
+
+        let warcinfo = RecordBuilder::warcinfo();
+        let warcinfo_id = warcinfo.record_id().to_string();
+        let encoder = warcinfo.write(encoder, b"software: test\r\n").unwrap();
+
+        let request = RecordBuilder::request("http://example.com/")
+            .with_warcinfo_id(&warcinfo_id)
+            .with_ip_address("192.0.2.1".parse().unwrap());
+        let response = RecordBuilder::response("http://example.com/")
+            .with_warcinfo_id(&warcinfo_id)
+            .with_concurrent_to(request.record_id());
+        let metadata = RecordBuilder::metadata("http://example.com/")
+            .with_warcinfo_id(&warcinfo_id)
+            .with_concurrent_to(response.record_id());
+        let resource = RecordBuilder::resource("http://example.com/a.txt", "text/plain")
+            .with_warcinfo_id(&warcinfo_id)
+            .with_date("2020-01-02T03:04:05Z".parse().unwrap());
+        let response_id = response.record_id().to_string();
+
+        let encoder = request
+            .write(encoder, b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n")
+            .unwrap();
+        let encoder = response
+            .write(encoder, b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nhi")
+            .unwrap();
+        let encoder = metadata.write(encoder, b"fetchTimeMs: 1\r\n").unwrap();
+        let encoder = resource.write(encoder, b"Hello world!").unwrap();
+        let buf = encoder.finish().unwrap();
+
+        let mut reader = Decoder::new(buf.as_slice(), DecoderConfig::default()).unwrap();
+        let mut verifier = Verifier::new();
+        let mut headers = Vec::new();
+
+        while reader.has_next_record().unwrap() {
+            let (header, mut block_reader) = reader.read_header().unwrap();
+            let mut data = Vec::new();
+            block_reader.read_to_end(&mut data).unwrap();
+
+            verifier.begin_record(&header).unwrap();
+            verifier.block_data(&data);
+            verifier.end_record();
+
+            headers.push(header);
+            reader = block_reader.finish_block().unwrap();
+        }
+
+        while verifier.verify_end().unwrap() == VerifyStatus::HasMore {}
+
+        assert!(verifier.problems().is_empty(), "{:?}", verifier.problems());
+        assert_eq!(headers.len(), 5);
+        assert_eq!(
+            headers[2].fields.get_or_default("Content-Type"),
+            HTTP_RESPONSE_CONTENT_TYPE
+        );
+        assert_eq!(
+            headers[3].fields.get_or_default("WARC-Concurrent-To"),
+            response_id
+        );
+        assert_eq!(
+            headers[4].fields.get_or_default("WARC-Date"),
+            "2020-01-02T03:04:05Z"
+        );
+    }
+}
@@ -5,21 +5,120 @@ use std::{
 };
 
 use crate::{
-    compress::{DecompressorConfig, PushDecompressor},
+    compress::{DecompressorConfig, Dictionary, Format, PushDecompressor},
     error::{GeneralError, ProtocolError, ProtocolErrorKind},
-    header::WarcHeader,
+    header::{DuplicateFieldPolicy, LineFolding, WarcHeader},
     io::LogicalPosition,
+    warning::{Warning, WarningSink},
 };
 
 const BUFFER_LENGTH: usize = crate::io::IO_BUFFER_LENGTH;
-const MAX_HEADER_LENGTH: usize = 32768;
+/// Default value of [`DecoderConfig::max_header_length`].
+pub const DEFAULT_HEADER_LENGTH_LIMIT: usize = 32768;
 
 /// Configuration for a [`Decoder`]
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 #[non_exhaustive]
 pub struct DecoderConfig {
     /// Compression configuration of the file to be read
     pub decompressor: DecompressorConfig,
+    /// Receiver of non-fatal observations about the file
+    pub warnings: WarningSink,
+    /// Handling of header fields that are repeated but must be unique
+    pub duplicate_fields: DuplicateFieldPolicy,
+    /// Handling of field values continued on folded lines
+    pub line_folding: LineFolding,
+    /// Maximum length of a header in bytes
+    ///
+    /// Longer headers are rejected with [`ProtocolErrorKind::HeaderTooBig`].
+    pub max_header_length: usize,
+    /// Maximum value of the Content-Length field
+    ///
+    /// Records with a longer block are rejected with
+    /// [`ProtocolErrorKind::ContentTooBig`]. By default, there is no limit.
+    pub max_content_length: Option<u64>,
+    /// Keep a copy of the compressed data of each record
+    ///
+    /// The data can be taken with [`Decoder::take_raw_record()`] and
+    /// written to another file with [`Encoder::write_raw_record()`](crate::warc::Encoder::write_raw_record).
+    pub keep_raw_records: bool,
+    /// Keep a copy of the header of each record as it appears in the file
+    ///
+    /// The header can be read with [`Decoder::raw_header()`] and written
+    /// verbatim to another file with [`Encoder::write_raw_header()`](crate::warc::Encoder::write_raw_header)
+    /// so that the order, case, and formatting of the fields are preserved.
+    pub keep_raw_header: bool,
+    /// Accept records that deviate from the specification in ways commonly
+    /// written by other software
+    ///
+    /// Header lines may end with LF instead of CRLF, and the line endings
+    /// following a block may be missing or repeated. Any other data
+    /// between records is skipped up to the next `WARC/` version line. The
+    /// deviations are reported as warnings instead of
+    /// [`ProtocolErrorKind::InvalidRecordBoundary`] errors.
+    pub lenient: bool,
+    /// Skip corrupt data to the next record instead of returning an error
+    ///
+    /// When a header cannot be parsed or the compressed data cannot be
+    /// decompressed, the data is skipped up to the next compression member
+    /// or frame, or up to the next `WARC/` version line. The skipped data
+    /// is reported as a [`Warning::SkippedData`]. A record with corrupt
+    /// data in its block ends early.
+    pub recover: bool,
+}
+
+impl DecoderConfig {
+    pub(super) fn check_content_length(&self, length: u64) -> Result<(), ProtocolError> {
+        match self.max_content_length {
+            Some(max_length) if length > max_length => Err(ProtocolError::new(
+                ProtocolErrorKind::ContentTooBig,
+            )
+            .with_source(format!(
+                "Content-Length {} exceeds the maximum of {}",
+                length, max_length
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for DecoderConfig {
+    fn default() -> Self {
+        Self {
+            decompressor: DecompressorConfig::default(),
+            warnings: WarningSink::default(),
+            duplicate_fields: DuplicateFieldPolicy::default(),
+            line_folding: LineFolding::default(),
+            max_header_length: DEFAULT_HEADER_LENGTH_LIMIT,
+            max_content_length: None,
+            keep_raw_records: false,
+            keep_raw_header: false,
+            lenient: false,
+            recover: false,
+        }
+    }
+}
+
+/// Compressed data of a whole record as it appears in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawRecord {
+    /// Compression member or frame containing only the record.
+    pub data: Vec<u8>,
+    /// Length of the record when decompressed.
+    pub decompressed_len: u64,
+}
+
+/// Whether the input ended at a record boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndState {
+    /// The end of the input has not been reached.
+    NotReached,
+    /// The input ended at a record boundary.
+    Clean,
+    /// The input ended within a record.
+    ///
+    /// The file is likely truncated.
+    Truncated,
 }
 
 #[derive(Debug)]
@@ -37,6 +136,7 @@ pub struct Decoder<S, R: Read> {
     push_decoder: PushDecoder,
     logical_position: u64,
     buf: Vec<u8>,
+    is_eof: bool,
 }
 
 impl<S, R: Read> Decoder<S, R> {
@@ -55,6 +155,23 @@ impl<S, R: Read> Decoder<S, R> {
         self.push_decoder.record_boundary_position()
     }
 
+    /// Returns whether the end of the input was reached and whether it
+    /// ended at a record boundary.
+    ///
+    /// This function is intended to be used after
+    /// [`has_next_record()`](Decoder::has_next_record) returns false or
+    /// after an error to distinguish a clean end of the file from a
+    /// truncated file.
+    pub fn end_state(&self) -> EndState {
+        if !self.is_eof {
+            EndState::NotReached
+        } else if self.push_decoder.is_ready() {
+            EndState::Clean
+        } else {
+            EndState::Truncated
+        }
+    }
+
     fn read_into_push_decoder(&mut self) -> std::io::Result<usize> {
         tracing::trace!("read into push decoder");
 
@@ -69,6 +186,7 @@ impl<S, R: Read> Decoder<S, R> {
         self.push_decoder.write_all(&self.buf)?;
 
         if read_length == 0 {
+            self.is_eof = true;
             self.push_decoder.write_eof();
         }
 
@@ -102,6 +220,7 @@ impl<S, R: Read> Decoder<S, R> {
             push_decoder: self.push_decoder,
             logical_position: self.logical_position,
             buf: self.buf,
+            is_eof: false,
         })
     }
 }
@@ -117,6 +236,7 @@ impl<R: Read> Decoder<DecStateHeader, R> {
             push_decoder,
             logical_position: 0,
             buf: Vec::with_capacity(BUFFER_LENGTH),
+            is_eof: false,
         })
     }
 
@@ -134,6 +254,16 @@ impl<R: Read> Decoder<DecStateHeader, R> {
         self.push_decoder.has_record_at_time_compression_fault()
     }
 
+    /// Removes and returns the compressed data of the record that was
+    /// finished last.
+    ///
+    /// Returns `None` unless [`DecoderConfig::keep_raw_records`] is enabled
+    /// and the record was compressed in its own member or frame. Records of
+    /// files compressed with a dictionary are not kept.
+    pub fn take_raw_record(&mut self) -> Option<RawRecord> {
+        self.push_decoder.take_raw_record()
+    }
+
     /// Returns whether there is another WARC record to be read.
     pub fn has_next_record(&mut self) -> std::io::Result<bool> {
         if self.push_decoder.is_ready() {
@@ -165,6 +295,7 @@ impl<R: Read> Decoder<DecStateHeader, R> {
                             push_decoder: self.push_decoder,
                             buf: self.buf,
                             logical_position: self.logical_position,
+                            is_eof: self.is_eof,
                         },
                     ));
                 }
@@ -176,6 +307,26 @@ impl<R: Read> Decoder<DecStateHeader, R> {
 }
 
 impl<R: Read + Seek> Decoder<DecStateHeader, R> {
+    /// Creates a decoder that reads from the record at the given position
+    /// of the source.
+    ///
+    /// The position is the start of a record in the file, such as one
+    /// from a CDX file or [`record_boundary_position()`](Self::record_boundary_position).
+    /// The source is prepared with [`prepare_for_seek()`](Self::prepare_for_seek)
+    /// and seeked to the position. The record boundary positions of the
+    /// decoder are positions in the file.
+    pub fn open_at(input: R, position: u64, config: DecoderConfig) -> Result<Self, GeneralError> {
+        let mut decoder = Self::new(input, config)?;
+
+        if position != 0 {
+            decoder.prepare_for_seek()?;
+            decoder.input.seek(std::io::SeekFrom::Start(position))?;
+            decoder.push_decoder.set_position(position);
+        }
+
+        Ok(decoder)
+    }
+
     /// Prepare the internal decompressor to be ready for the source to be seeked.
     ///
     /// For Zstandard, this may load an embedded dictionary.
@@ -204,13 +355,59 @@ impl<R: Read + Seek> Decoder<DecStateHeader, R> {
 
             self.input.seek(std::io::SeekFrom::Start(0))?;
             self.push_decoder.reset()?;
+            self.is_eof = false;
         }
 
         Ok(())
     }
+
+    /// Seeks the source to the record at the position in the decompressed
+    /// data.
+    ///
+    /// For Zstandard, the frame of the record is found with the seek table
+    /// at the end of the file (see [`CompressorConfig::seek_table`]).
+    /// Uncompressed files are seeked to the position directly. An error
+    /// with [`ProtocolErrorKind::NotFound`] is returned if the file has no
+    /// seek table or no frame starts at the position.
+    ///
+    /// [`CompressorConfig::seek_table`]: crate::compress::CompressorConfig::seek_table
+    pub fn seek_decompressed(&mut self, position: u64) -> Result<(), GeneralError> {
+        let file_position = match self.push_decoder.config.decompressor.format {
+            Format::Identity => position,
+            #[cfg(feature = "zstd")]
+            Format::Zstandard => {
+                let frame = crate::compress::zstd::SeekTable::read(&mut self.input)?
+                    .and_then(|table| table.find_decompressed(position))
+                    .filter(|frame| frame.decompressed_position == position)
+                    .ok_or_else(|| ProtocolError::new(ProtocolErrorKind::NotFound))?;
+                self.input.seek(std::io::SeekFrom::Start(0))?;
+
+                frame.compressed_position
+            }
+            _ => {
+                return Err(
+                    ProtocolError::new(ProtocolErrorKind::UnsupportedCompressionFormat).into(),
+                )
+            }
+        };
+
+        self.prepare_for_seek()?;
+        self.input.seek(std::io::SeekFrom::Start(file_position))?;
+
+        Ok(())
+    }
 }
 
 impl<R: Read> Decoder<DecStateBlock, R> {
+    /// Returns the header of the current record as it appears in the file.
+    ///
+    /// The data includes the version line and the blank line ending the
+    /// header. Returns `None` unless [`DecoderConfig::keep_raw_header`] is
+    /// enabled.
+    pub fn raw_header(&self) -> Option<&[u8]> {
+        self.push_decoder.raw_header()
+    }
+
     fn read_block_impl(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         if self.state.is_end {
             return Ok(0);
@@ -274,6 +471,7 @@ impl<R: Read> Decoder<DecStateBlock, R> {
             push_decoder: self.push_decoder,
             logical_position: self.logical_position,
             buf: self.buf,
+            is_eof: self.is_eof,
         })
     }
 
@@ -388,6 +586,7 @@ enum PushDecoderState {
     Block,
     RecordBoundary,
     EndOfSegment,
+    Skip,
 }
 
 /// WARC format decoder push-style.
@@ -417,6 +616,23 @@ pub struct PushDecoder {
     buf_output_reference_len: usize,
     /// Detected a compressed file that can't be randomly accessed
     has_rat_comp_fault: bool,
+    /// Length of the current record when decompressed.
+    record_decompressed_len: u64,
+    /// Input data of the current record when keeping raw records.
+    raw_buf: Vec<u8>,
+    /// Whether the current record started at a compression segment.
+    is_raw_record_aligned: bool,
+    raw_record: Option<RawRecord>,
+    /// Header of the current record when keeping raw headers.
+    raw_header: Vec<u8>,
+    /// Position of the corrupt data being skipped.
+    skip_position: u64,
+    /// Whether the corrupt data is within a record that has not ended.
+    skip_ends_record: bool,
+    /// Whether the input is being scanned for the next compression segment.
+    is_scanning_input: bool,
+    /// Whether the decompressed data starts at a segment found while skipping.
+    is_skip_segment_start: bool,
 }
 
 impl PushDecoder {
@@ -439,6 +655,15 @@ impl PushDecoder {
             buf_output_max_len: BUFFER_LENGTH,
             buf_output_reference_len: 0,
             has_rat_comp_fault: false,
+            record_decompressed_len: 0,
+            raw_buf: Vec::new(),
+            is_raw_record_aligned: true,
+            raw_record: None,
+            raw_header: Vec::new(),
+            skip_position: 0,
+            skip_ends_record: false,
+            is_scanning_input: false,
+            is_skip_segment_start: false,
         })
     }
 
@@ -449,6 +674,36 @@ impl PushDecoder {
         self.record_boundary_position
     }
 
+    /// Removes and returns the compressed data of the record that ended at
+    /// the last [`PushDecoderEvent::EndRecord`].
+    ///
+    /// See [`Decoder::take_raw_record()`].
+    pub fn take_raw_record(&mut self) -> Option<RawRecord> {
+        self.raw_record.take()
+    }
+
+    /// Returns the header of the record at the last
+    /// [`PushDecoderEvent::Header`] as it appears in the file.
+    ///
+    /// See [`Decoder::raw_header()`].
+    pub fn raw_header(&self) -> Option<&[u8]> {
+        self.config
+            .keep_raw_header
+            .then_some(self.raw_header.as_slice())
+    }
+
+    fn keeps_raw_records(&self) -> bool {
+        self.config.keep_raw_records
+            && self.config.decompressor.format.supports_concatenation()
+            && matches!(self.config.decompressor.dictionary, Dictionary::None)
+    }
+
+    /// Sets the position of the input at the start of a record.
+    fn set_position(&mut self, position: u64) {
+        self.bytes_consumed = position;
+        self.record_boundary_position = position;
+    }
+
     /// Returns whether internal buffer contains unused bytes that can be
     /// used to decode the next record.
     pub fn has_next_record(&self) -> bool {
@@ -502,6 +757,7 @@ impl PushDecoder {
             PushDecoderState::Block => self.process_block(),
             PushDecoderState::RecordBoundary => self.process_record_boundary(),
             PushDecoderState::EndOfSegment => self.process_end_of_segment(),
+            PushDecoderState::Skip => self.process_skip(),
         }
     }
 
@@ -516,6 +772,10 @@ impl PushDecoder {
         self.decompressor.get_mut().clear();
         self.unused_input_buf.clear();
         self.decompressor.start_next_segment()?;
+        self.raw_buf.clear();
+        self.is_raw_record_aligned = true;
+        self.raw_record = None;
+        self.is_scanning_input = false;
         Ok(())
     }
 
@@ -523,23 +783,48 @@ impl PushDecoder {
         let buf = self.decompressor.get_mut().make_contiguous();
 
         if let Some(index) = crate::parse::scan_header_deliminator(buf) {
-            let header = self.process_decodable_header(index)?;
+            if index > self.config.max_header_length {
+                return self.skip_or_error(ProtocolError::new(ProtocolErrorKind::HeaderTooBig));
+            }
 
-            return Ok(PushDecoderEvent::Header { header });
+            return match self.process_decodable_header(index) {
+                Ok(header) => Ok(PushDecoderEvent::Header { header }),
+                Err(error) => self.skip_or_error(error),
+            };
         }
 
-        self.check_max_header_length()?;
+        if let Err(error) = self.check_max_header_length() {
+            return self.skip_or_error(error);
+        }
 
-        Ok(PushDecoderEvent::WantData)
+        self.want_data()
     }
 
     fn process_decodable_header(&mut self, index: usize) -> Result<WarcHeader, GeneralError> {
+        let _span = tracing::trace_span!("parse_header", header_len = index).entered();
+
         // Okay to discard slice1 because we called make_contiguous() earlier.
         let (buf, _slice1) = self.decompressor.get_ref().as_slices();
 
         let header_bytes = &buf[0..index];
-        let header = WarcHeader::parse(header_bytes)?;
+
+        if self.config.lenient && crate::parse::has_bare_line_feed(header_bytes) {
+            self.config.warnings.emit(Warning::BareLineFeed {
+                position: self.record_boundary_position,
+            });
+        }
+
+        let mut header =
+            WarcHeader::parse_with_line_folding(header_bytes, self.config.line_folding)?;
+        header.apply_duplicate_field_policy(self.config.duplicate_fields)?;
         let length = header.content_length()?;
+        self.config.check_content_length(length)?;
+
+        if self.config.keep_raw_header {
+            self.raw_header.clear();
+            self.raw_header.extend_from_slice(header_bytes);
+        }
+
         let record_id = header.fields.get("WARC-Record-ID");
         let warc_type = header.fields.get("WARC-Type");
         self.decompressor.get_mut().drain(0..index);
@@ -553,6 +838,7 @@ impl PushDecoder {
 
         self.block_current_position = 0;
         self.block_length = length;
+        self.record_decompressed_len = index as u64 + length + 4;
 
         tracing::trace!("Header -> Block");
         self.state = PushDecoderState::Block;
@@ -563,7 +849,7 @@ impl PushDecoder {
     fn check_max_header_length(&self) -> Result<(), ProtocolError> {
         tracing::trace!("check max header length");
 
-        if self.decompressor.get_ref().len() > MAX_HEADER_LENGTH {
+        if self.decompressor.get_ref().len() > self.config.max_header_length {
             Err(ProtocolError::new(ProtocolErrorKind::HeaderTooBig))
         } else {
             Ok(())
@@ -585,7 +871,7 @@ impl PushDecoder {
             self.state = PushDecoderState::RecordBoundary;
             Ok(PushDecoderEvent::Continue)
         } else if self.decompressor.get_ref().is_empty() {
-            Ok(PushDecoderEvent::WantData)
+            self.want_data()
         } else {
             // Okay to discard slice1 because the caller will continually poll
             // until the buffer is empty.
@@ -611,6 +897,10 @@ impl PushDecoder {
             "process record boundary"
         );
 
+        if self.config.lenient {
+            return self.process_lenient_record_boundary();
+        }
+
         if self.decompressor.get_ref().len() >= 4 {
             let mut buf = [0u8; 4];
             let mut iter = self.decompressor.get_ref().range(0..4).copied();
@@ -620,13 +910,63 @@ impl PushDecoder {
             buf[3] = iter.next().unwrap();
 
             if !buf.starts_with(b"\r\n\r\n") {
-                Err(ProtocolError::new(ProtocolErrorKind::InvalidRecordBoundary).into())
+                self.skip_or_error(ProtocolError::new(ProtocolErrorKind::InvalidRecordBoundary))
             } else {
                 self.decompressor.get_mut().drain(0..4);
 
                 self.state = PushDecoderState::EndOfSegment;
                 Ok(PushDecoderEvent::Continue)
             }
+        } else {
+            self.want_data()
+        }
+    }
+
+    /// Skips the data following a block up to the next record.
+    ///
+    /// The record boundary ends at a `WARC/` version line or at the end of
+    /// the compression segment or input.
+    fn process_lenient_record_boundary(&mut self) -> Result<PushDecoderEvent<'_>, GeneralError> {
+        let is_end = self.decompressor_eof || self.input_eof;
+        let buf = self.decompressor.get_mut().make_contiguous();
+
+        let boundary_len = match buf.windows(5).position(|window| window == b"WARC/") {
+            Some(index) => index,
+            None if is_end => buf.len(),
+            None if buf.len() > self.config.max_header_length => {
+                return self
+                    .skip_or_error(ProtocolError::new(ProtocolErrorKind::InvalidRecordBoundary));
+            }
+            None => return Ok(PushDecoderEvent::WantDataOrEof),
+        };
+
+        if &buf[0..boundary_len] != b"\r\n\r\n" {
+            self.config.warnings.emit(Warning::InvalidRecordBoundary {
+                position: self.record_boundary_position,
+                length: boundary_len as u64,
+            });
+        }
+
+        self.decompressor.get_mut().drain(0..boundary_len);
+
+        self.state = PushDecoderState::EndOfSegment;
+        Ok(PushDecoderEvent::Continue)
+    }
+
+    /// Returns the event for when the current record needs more data.
+    ///
+    /// If the compression segment has ended, the record continues in the
+    /// next segment, such as a record compressed as several Zstandard
+    /// frames.
+    fn want_data(&mut self) -> Result<PushDecoderEvent<'_>, GeneralError> {
+        if self.decompressor_eof && self.config.decompressor.format.supports_concatenation() {
+            tracing::trace!("continue record in next segment");
+
+            self.start_next_segment()?;
+            self.decompressor_eof = false;
+            self.consume_unused_input()?;
+
+            Ok(PushDecoderEvent::Continue)
         } else {
             Ok(PushDecoderEvent::WantData)
         }
@@ -657,15 +997,40 @@ impl PushDecoder {
         // dbg!(String::from_utf8_lossy(self.decompressor.get_ref().as_slices().0));
         // dbg!(String::from_utf8_lossy(self.decompressor.get_ref().as_slices().1));
 
-        if self.config.decompressor.format.supports_concatenation()
-            && self.decompressor.get_ref().is_empty()
-        {
-            self.decompressor.start_next_segment()?;
-        } else if self.config.decompressor.format.supports_concatenation()
-            && !self.has_rat_comp_fault
-        {
-            tracing::warn!("file is not using Record-at-time compression");
-            self.has_rat_comp_fault = true;
+        let is_segment_end = self.config.decompressor.format.supports_concatenation()
+            && self.decompressor.get_ref().is_empty();
+
+        if self.keeps_raw_records() {
+            let data = std::mem::take(&mut self.raw_buf);
+
+            let record = RawRecord {
+                data,
+                decompressed_len: self.record_decompressed_len,
+            };
+            self.raw_record = (self.is_raw_record_aligned && is_segment_end).then_some(record);
+            self.is_raw_record_aligned = is_segment_end;
+        }
+
+        // The record has ended, so an error in the segment skips only the
+        // following data.
+        self.state = PushDecoderState::Header;
+
+        if is_segment_end {
+            self.start_next_segment()?;
+        } else {
+            // The decompression limits apply to each record.
+            let buffered_len = self.decompressor.get_ref().len() as u64;
+            self.decompressor.reset_limits(buffered_len);
+
+            if self.config.decompressor.format.supports_concatenation() && !self.has_rat_comp_fault
+            {
+                self.config
+                    .warnings
+                    .emit(Warning::NotRecordAtTimeCompression {
+                        position: self.record_boundary_position,
+                    });
+                self.has_rat_comp_fault = true;
+            }
         }
 
         self.record_boundary_position = self.bytes_consumed;
@@ -674,29 +1039,264 @@ impl PushDecoder {
 
         self.consume_unused_input()?;
 
-        if self.decompressor.get_ref().is_empty() {
+        if self.state == PushDecoderState::Skip {
+            tracing::trace!("RecordBoundary -> Skip");
+        } else if self.decompressor.get_ref().is_empty() {
             tracing::trace!("RecordBoundary -> PendingHeader");
             self.state = PushDecoderState::PendingHeader;
         } else {
             tracing::trace!("RecordBoundary -> Header");
-            self.state = PushDecoderState::Header;
         }
 
         Ok(())
     }
 
+    /// Starts skipping corrupt data if recovering from errors, otherwise
+    /// returns the error.
+    fn skip_or_error<E: Into<GeneralError>>(
+        &mut self,
+        error: E,
+    ) -> Result<PushDecoderEvent<'static>, GeneralError> {
+        let error = error.into();
+
+        if !self.config.recover {
+            return Err(error);
+        }
+
+        self.start_skip(&error);
+
+        // Skip the start of the data so the same record is not found again.
+        let len = self.decompressor.get_ref().len().min(1);
+        self.decompressor.get_mut().drain(0..len);
+
+        Ok(PushDecoderEvent::Continue)
+    }
+
+    fn start_skip(&mut self, error: &dyn std::fmt::Display) {
+        tracing::debug!(%error, "skip corrupt data");
+
+        if self.state == PushDecoderState::Skip {
+            return;
+        }
+
+        self.skip_position = self.buffer_position();
+        self.skip_ends_record = matches!(
+            self.state,
+            PushDecoderState::Block
+                | PushDecoderState::RecordBoundary
+                | PushDecoderState::EndOfSegment
+        );
+        self.is_skip_segment_start = false;
+
+        tracing::trace!("{:?} -> Skip", self.state);
+        self.state = PushDecoderState::Skip;
+    }
+
+    fn can_scan_input(&self) -> bool {
+        self.config.recover && self.config.decompressor.format.segment_magic().is_some()
+    }
+
+    fn start_input_scan(&mut self, error: std::io::Error) {
+        self.start_skip(&error);
+        self.is_scanning_input = true;
+    }
+
+    /// Returns the position in the file of the start of the decompressed
+    /// data.
+    ///
+    /// For compressed files, this is the start of the segment.
+    fn buffer_position(&self) -> u64 {
+        if self.config.decompressor.format == Format::Identity {
+            self.bytes_consumed - self.decompressor.get_ref().len() as u64
+        } else {
+            self.record_boundary_position
+        }
+    }
+
+    /// Discards data up to the next `WARC/` version line.
+    fn process_skip(&mut self) -> Result<PushDecoderEvent<'_>, GeneralError> {
+        if self.is_scanning_input {
+            return self.scan_input();
+        }
+
+        let buf = self.decompressor.get_mut().make_contiguous();
+        let buf_len = buf.len();
+
+        if let Some(index) = buf.windows(5).position(|window| window == b"WARC/") {
+            if index != 0 {
+                self.is_skip_segment_start = false;
+            }
+
+            self.decompressor.get_mut().drain(0..index);
+
+            return Ok(self.end_skip());
+        }
+
+        if self.decompressor_eof && self.config.decompressor.format.supports_concatenation() {
+            tracing::trace!("skip to next segment");
+
+            self.decompressor.get_mut().clear();
+            self.start_next_segment()?;
+            self.start_skip_segment();
+            self.consume_unused_input()?;
+
+            return Ok(PushDecoderEvent::Continue);
+        }
+
+        // Keep the end of the data that may be the start of a version line.
+        let len = buf_len.saturating_sub(4);
+
+        if len != 0 {
+            self.is_skip_segment_start = false;
+        }
+
+        if self.input_eof {
+            self.decompressor.get_mut().clear();
+            Ok(self.end_skip_at_eof())
+        } else {
+            self.decompressor.get_mut().drain(0..len);
+            Ok(self.skip_want_data())
+        }
+    }
+
+    /// Discards input up to the next compression segment.
+    fn scan_input(&mut self) -> Result<PushDecoderEvent<'_>, GeneralError> {
+        let magic = self.config.decompressor.format.segment_magic().unwrap();
+        let buf = self.unused_input_buf.make_contiguous();
+
+        // The segment at the start is the corrupt one.
+        if let Some(index) = buf
+            .windows(magic.len())
+            .skip(1)
+            .position(|window| window == magic)
+        {
+            self.unused_input_buf.drain(0..index + 1);
+            self.bytes_consumed += index as u64 + 1;
+            self.is_scanning_input = false;
+
+            tracing::trace!("found next segment");
+
+            self.decompressor.get_mut().clear();
+            self.decompressor.discard_segment()?;
+            self.start_skip_segment();
+            self.consume_unused_input()?;
+
+            Ok(PushDecoderEvent::Continue)
+        } else if self.input_eof {
+            self.bytes_consumed += buf.len() as u64;
+            self.unused_input_buf.clear();
+            self.decompressor.get_mut().clear();
+            Ok(self.end_skip_at_eof())
+        } else {
+            let len = buf.len().saturating_sub(magic.len() - 1);
+            self.unused_input_buf.drain(0..len);
+            self.bytes_consumed += len as u64;
+            Ok(self.skip_want_data())
+        }
+    }
+
+    /// Starts decompressing the next segment.
+    ///
+    /// If recovering from errors, a segment that ends with an error, such as
+    /// a checksum mismatch, is skipped.
+    fn start_next_segment(&mut self) -> Result<(), GeneralError> {
+        match self.decompressor.start_next_segment() {
+            Ok(()) => Ok(()),
+            Err(error) if self.config.recover => {
+                self.start_skip(&error);
+                self.decompressor.discard_segment()?;
+                self.start_skip_segment();
+                Ok(())
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn start_skip_segment(&mut self) {
+        self.decompressor_eof = false;
+        self.record_boundary_position = self.bytes_consumed;
+        self.raw_buf.clear();
+        self.is_skip_segment_start = true;
+    }
+
+    fn skip_want_data(&self) -> PushDecoderEvent<'static> {
+        if self.skip_ends_record {
+            PushDecoderEvent::WantDataOrEof
+        } else {
+            PushDecoderEvent::WantData
+        }
+    }
+
+    fn emit_skipped_data(&self, end_position: u64) {
+        self.config.warnings.emit(Warning::SkippedData {
+            position: self.skip_position,
+            length: end_position.saturating_sub(self.skip_position),
+        });
+    }
+
+    fn end_skip(&mut self) -> PushDecoderEvent<'static> {
+        let position = self.buffer_position();
+        self.emit_skipped_data(position);
+
+        self.record_boundary_position = position;
+        self.is_raw_record_aligned = self.is_skip_segment_start;
+        self.raw_record = None;
+
+        if !self.is_raw_record_aligned {
+            self.raw_buf.clear();
+        }
+
+        tracing::trace!("Skip -> Header");
+        self.state = PushDecoderState::Header;
+
+        if self.skip_ends_record {
+            PushDecoderEvent::EndRecord
+        } else {
+            PushDecoderEvent::Continue
+        }
+    }
+
+    fn end_skip_at_eof(&mut self) -> PushDecoderEvent<'static> {
+        self.emit_skipped_data(self.bytes_consumed);
+
+        self.record_boundary_position = self.bytes_consumed;
+        self.raw_buf.clear();
+        self.raw_record = None;
+
+        tracing::trace!("Skip -> PendingHeader");
+        self.state = PushDecoderState::PendingHeader;
+
+        if self.skip_ends_record {
+            PushDecoderEvent::EndRecord
+        } else {
+            PushDecoderEvent::Ready
+        }
+    }
+
     fn consume_unused_input(&mut self) -> Result<(), GeneralError> {
         tracing::trace!(len = self.unused_input_buf.len(), "consume unused input");
 
         while !self.unused_input_buf.is_empty() {
             let (slice0, _slice1) = self.unused_input_buf.as_slices();
-            let write_len = self.decompressor.write(slice0)?;
+            let write_len = match self.decompressor.write(slice0) {
+                Ok(write_len) => write_len,
+                Err(error) if self.can_scan_input() => {
+                    self.start_input_scan(error);
+                    break;
+                }
+                Err(error) => return Err(error.into()),
+            };
             tracing::trace!(write_len, "consume unused input");
 
             if write_len == 0 {
+                self.decompressor_eof = true;
                 break;
             }
 
+            if self.keeps_raw_records() {
+                self.raw_buf.extend_from_slice(&slice0[0..write_len]);
+            }
+
             self.bytes_consumed += write_len as u64;
             self.unused_input_buf.drain(..write_len);
         }
@@ -721,14 +1321,30 @@ impl Write for PushDecoder {
             self.state = PushDecoderState::Header;
         }
 
-        let write_len = self.decompressor.write(buf)?;
+        if self.is_scanning_input {
+            self.unused_input_buf.write_all(buf)?;
+            return Ok(buf.len());
+        }
+
+        let write_len = match self.decompressor.write(buf) {
+            Ok(write_len) => write_len,
+            Err(error) if self.can_scan_input() => {
+                self.start_input_scan(error);
+                self.unused_input_buf.write_all(buf)?;
+                return Ok(buf.len());
+            }
+            Err(error) => return Err(error),
+        };
 
         tracing::trace!(buf_len = buf.len(), write_len, "push decoder write");
 
         if write_len != 0 {
-            // FIXME: handle the case where a single record is compressed as
-            // several zstd frames
             self.bytes_consumed += write_len as u64;
+
+            if self.keeps_raw_records() {
+                self.raw_buf.extend_from_slice(&buf[0..write_len]);
+            }
+
             Ok(write_len)
         } else {
             self.decompressor_eof = true;
@@ -744,7 +1360,12 @@ impl Write for PushDecoder {
 
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
+    use std::{
+        io::Cursor,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::compress::Compressor;
 
     use super::*;
 
@@ -776,10 +1397,589 @@ mod tests {
         let mut reader = reader.finish_block().unwrap();
 
         assert!(!reader.has_next_record().unwrap());
+        assert_eq!(reader.end_state(), EndState::Clean);
 
         reader.into_inner();
     }
 
+    #[test]
+    fn test_reader_truncated() {
+        let data = b"WARC/1.1\r\n\
+            Content-Length: 12\r\n\
+            \r\n\
+            Hello";
+
+        let reader = Decoder::new(Cursor::new(data), DecoderConfig::default()).unwrap();
+        assert_eq!(reader.end_state(), EndState::NotReached);
+
+        let (_header, mut reader) = reader.read_header().unwrap();
+        let mut block = Vec::new();
+        let error = reader.read_to_end(&mut block).unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(reader.end_state(), EndState::Truncated);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_seek_decompressed() {
+        use crate::{
+            compress::{zstd::SeekTable, CompressorConfig},
+            warc::{Encoder, EncoderConfig},
+        };
+
+        let encode = |compressor: CompressorConfig| {
+            let mut encoder = Encoder::new(
+                Vec::new(),
+                EncoderConfig {
+                    compressor,
+                    ..Default::default()
+                },
+            );
+
+            for (record_type, body) in [("resource", b"hello"), ("metadata", b"world")] {
+                let mut header = WarcHeader::empty();
+                header.version = "WARC/1.1".to_string();
+                header
+                    .fields
+                    .insert("WARC-Type".to_string(), record_type.to_string());
+                header.set_content_length(body.len() as u64);
+
+                let mut block_encoder = encoder.write_header(&header).unwrap();
+                block_encoder.write_all(body).unwrap();
+                encoder = block_encoder.finish_block().unwrap();
+            }
+
+            encoder.finish().unwrap()
+        };
+        let uncompressed = encode(CompressorConfig::default());
+        let compressed = encode(CompressorConfig {
+            format: Format::Zstandard,
+            seek_table: true,
+            ..Default::default()
+        });
+        let second_position = uncompressed
+            .windows(8)
+            .rposition(|window| window == b"WARC/1.1")
+            .unwrap() as u64;
+
+        let table = SeekTable::read(Cursor::new(&compressed)).unwrap().unwrap();
+        let frame = table.find_decompressed(second_position).unwrap();
+        assert_eq!(frame.decompressed_position, second_position);
+
+        let config = DecoderConfig {
+            decompressor: DecompressorConfig {
+                format: Format::Zstandard,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut reader = Decoder::new(Cursor::new(&compressed), config.clone()).unwrap();
+        reader.seek_decompressed(second_position).unwrap();
+        let (header, reader) = reader.read_header().unwrap();
+        assert_eq!(header.fields.get("WARC-Type").unwrap(), "metadata");
+        let mut reader = reader.finish_block().unwrap();
+        assert!(!reader.has_next_record().unwrap());
+        assert_eq!(reader.end_state(), EndState::Clean);
+
+        let mut reader = Decoder::new(Cursor::new(&compressed), config).unwrap();
+        assert!(reader.seek_decompressed(second_position + 1).is_err());
+    }
+
+    #[test]
+    fn test_reader_record_across_segments() {
+        /// Reader that returns one byte at a time.
+        struct ByteReader<'a>(&'a [u8]);
+
+        impl Read for ByteReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = self.0.len().min(buf.len()).min(1);
+                buf[0..len].copy_from_slice(&self.0[0..len]);
+                self.0 = &self.0[len..];
+                Ok(len)
+            }
+        }
+
+        // The first record is split within its header and within its block.
+        let parts: [&[u8]; 4] = [
+            b"WARC/1.1\r\nContent-",
+            b"Length: 12\r\n\r\nHello ",
+            b"world!\r\n\r\n",
+            b"WARC/1.1\r\nContent-Length: 3\r\n\r\nabc\r\n\r\n",
+        ];
+
+        let mut formats = vec![Format::Gzip];
+        #[cfg(feature = "zstd")]
+        formats.push(Format::Zstandard);
+
+        for format in formats {
+            let mut data = Vec::new();
+
+            for part in parts {
+                let mut compressor = crate::compress::Compressor::new(Vec::new(), format);
+                compressor.write_all(part).unwrap();
+                data.extend(compressor.finish().unwrap());
+            }
+
+            let config = DecoderConfig {
+                decompressor: DecompressorConfig {
+                    format,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+
+            for one_byte in [false, true] {
+                let input: Box<dyn Read> = if one_byte {
+                    Box::new(ByteReader(&data))
+                } else {
+                    Box::new(data.as_slice())
+                };
+                let mut reader = Decoder::new(input, config.clone()).unwrap();
+                let mut blocks = Vec::new();
+
+                while reader.has_next_record().unwrap() {
+                    let (_header, mut block_reader) = reader.read_header().unwrap();
+                    let mut block = Vec::new();
+                    block_reader.read_to_end(&mut block).unwrap();
+                    blocks.push(block);
+                    reader = block_reader.finish_block().unwrap();
+                }
+
+                assert_eq!(
+                    blocks,
+                    [b"Hello world!".to_vec(), b"abc".to_vec()],
+                    "{format:?} {one_byte}"
+                );
+                assert_eq!(reader.end_state(), EndState::Clean);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reader_lenient() {
+        let records: [&[u8]; 4] = [
+            b"WARC/1.1\nContent-Length: 12\n\nHello world!\n\n",
+            b"WARC/1.1\r\nContent-Length: 3\r\n\r\nabc",
+            b"WARC/1.1\r\nContent-Length: 3\r\n\r\ndef\r\n\r\n\r\njunk",
+            b"WARC/1.1\r\nContent-Length: 3\r\n\r\nghi\r\n",
+        ];
+
+        let mut formats = vec![Format::Identity, Format::Gzip];
+        #[cfg(feature = "zstd")]
+        formats.push(Format::Zstandard);
+
+        for format in formats {
+            let mut data = Vec::new();
+
+            for record in records {
+                let mut compressor = crate::compress::Compressor::new(Vec::new(), format);
+                compressor.write_all(record).unwrap();
+                data.extend(compressor.finish().unwrap());
+            }
+
+            let warnings = Arc::new(Mutex::new(Vec::new()));
+            let config = DecoderConfig {
+                decompressor: DecompressorConfig {
+                    format,
+                    ..Default::default()
+                },
+                warnings: {
+                    let warnings = warnings.clone();
+                    WarningSink::new(move |warning| warnings.lock().unwrap().push(warning.clone()))
+                },
+                lenient: true,
+                ..Default::default()
+            };
+
+            let mut reader = Decoder::new(data.as_slice(), config.clone()).unwrap();
+            let mut blocks = Vec::new();
+
+            while reader.has_next_record().unwrap() {
+                let (_header, mut block_reader) = reader.read_header().unwrap();
+                let mut block = Vec::new();
+                block_reader.read_to_end(&mut block).unwrap();
+                blocks.push(block);
+                reader = block_reader.finish_block().unwrap();
+            }
+
+            assert_eq!(
+                blocks,
+                [
+                    b"Hello world!".to_vec(),
+                    b"abc".to_vec(),
+                    b"def".to_vec(),
+                    b"ghi".to_vec()
+                ],
+                "{format:?}"
+            );
+            assert_eq!(reader.end_state(), EndState::Clean);
+
+            // Boundary lengths of the records, or `None` for a bare LF header
+            let warnings = warnings
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|warning| match warning {
+                    Warning::BareLineFeed { .. } => None,
+                    Warning::InvalidRecordBoundary { length, .. } => Some(*length),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                warnings,
+                [None, Some(2), Some(0), Some(10), Some(2)],
+                "{format:?}"
+            );
+
+            let config = DecoderConfig {
+                lenient: false,
+                ..config
+            };
+            let reader = Decoder::new(data.as_slice(), config).unwrap();
+            let (_header, block_reader) = reader.read_header().unwrap();
+            assert!(block_reader.finish_block().is_err());
+        }
+    }
+
+    #[test]
+    fn test_reader_recover() {
+        let parts: [(&[u8], bool); 4] = [
+            (b"WARC/1.1\r\nContent-Length: 3\r\n\r\nabc\r\n\r\n", true),
+            (b"junk", false),
+            (b"WARC/1.1\r\nContent-Length: x\r\n\r\ndef\r\n\r\n", true),
+            (b"WARC/1.1\r\nContent-Length: 3\r\n\r\nghi\r\n\r\n", true),
+        ];
+
+        let mut formats = vec![Format::Identity, Format::Gzip];
+        #[cfg(feature = "zstd")]
+        formats.push(Format::Zstandard);
+
+        for format in formats {
+            let mut data = Vec::new();
+            let mut positions = Vec::new();
+
+            for (part, compress) in parts {
+                positions.push(data.len() as u64);
+
+                if compress {
+                    let mut compressor = crate::compress::Compressor::new(Vec::new(), format);
+                    compressor.write_all(part).unwrap();
+                    data.extend(compressor.finish().unwrap());
+                } else {
+                    data.extend_from_slice(part);
+                }
+            }
+
+            let warnings = Arc::new(Mutex::new(Vec::new()));
+            let config = DecoderConfig {
+                decompressor: DecompressorConfig {
+                    format,
+                    ..Default::default()
+                },
+                warnings: {
+                    let warnings = warnings.clone();
+                    WarningSink::new(move |warning| warnings.lock().unwrap().push(warning.clone()))
+                },
+                recover: true,
+                ..Default::default()
+            };
+
+            let mut reader = Decoder::new(data.as_slice(), config.clone()).unwrap();
+            let mut blocks = Vec::new();
+
+            while reader.has_next_record().unwrap() {
+                let (_header, mut block_reader) = reader.read_header().unwrap();
+                let mut block = Vec::new();
+                block_reader.read_to_end(&mut block).unwrap();
+                blocks.push(block);
+                reader = block_reader.finish_block().unwrap();
+            }
+
+            assert_eq!(blocks, [b"abc".to_vec(), b"ghi".to_vec()], "{format:?}");
+            assert_eq!(reader.end_state(), EndState::Clean);
+
+            assert_eq!(
+                *warnings.lock().unwrap(),
+                [
+                    Warning::SkippedData {
+                        position: positions[1],
+                        length: positions[2] - positions[1],
+                    },
+                    Warning::SkippedData {
+                        position: positions[2],
+                        length: positions[3] - positions[2],
+                    },
+                ],
+                "{format:?}"
+            );
+
+            let config = DecoderConfig {
+                recover: false,
+                ..config
+            };
+            let reader = Decoder::new(data.as_slice(), config).unwrap();
+            let (_header, block_reader) = reader.read_header().unwrap();
+            let result = block_reader
+                .finish_block()
+                .and_then(|reader| reader.read_header());
+            assert!(result.is_err(), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_reader_recover_corrupt_block() {
+        let mut formats = vec![Format::Gzip];
+        #[cfg(feature = "zstd")]
+        formats.push(Format::Zstandard);
+
+        for format in formats {
+            let mut data = Vec::new();
+
+            let warnings = Arc::new(Mutex::new(Vec::new()));
+            let mut compressor = crate::compress::Compressor::new(Vec::new(), format);
+            compressor
+                .write_all(b"WARC/1.1\r\nContent-Length: 100000\r\n\r\n")
+                .unwrap();
+            // Data that does not compress well
+            let mut value = 1u32;
+            for _ in 0..100000 {
+                value ^= value << 13;
+                value ^= value >> 17;
+                value ^= value << 5;
+                compressor.write_all(&[value as u8]).unwrap();
+            }
+            compressor.write_all(b"\r\n\r\n").unwrap();
+            let mut member = compressor.finish().unwrap();
+            let middle = member.len() / 2;
+            member[middle..middle + 16].fill(0xff);
+            let member_len = member.len() as u64;
+            data.extend(member);
+
+            let mut compressor = crate::compress::Compressor::new(Vec::new(), format);
+            compressor
+                .write_all(b"WARC/1.1\r\nContent-Length: 3\r\n\r\nabc\r\n\r\n")
+                .unwrap();
+            data.extend(compressor.finish().unwrap());
+
+            let config = DecoderConfig {
+                decompressor: DecompressorConfig {
+                    format,
+                    ..Default::default()
+                },
+                warnings: {
+                    let warnings = warnings.clone();
+                    WarningSink::new(move |warning| warnings.lock().unwrap().push(warning.clone()))
+                },
+                recover: true,
+                ..Default::default()
+            };
+
+            let mut reader = Decoder::new(data.as_slice(), config).unwrap();
+            let mut blocks = Vec::new();
+
+            while reader.has_next_record().unwrap() {
+                let (_header, mut block_reader) = reader.read_header().unwrap();
+                let mut block = Vec::new();
+                block_reader.read_to_end(&mut block).unwrap();
+                blocks.push(block);
+                reader = block_reader.finish_block().unwrap();
+            }
+
+            assert_eq!(blocks.len(), 2, "{format:?}");
+            assert_eq!(blocks[1], b"abc", "{format:?}");
+            assert_eq!(
+                *warnings.lock().unwrap(),
+                [Warning::SkippedData {
+                    position: 0,
+                    length: member_len
+                }],
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_open_at() {
+        use crate::{
+            compress::CompressorConfig,
+            warc::{Encoder, EncoderConfig},
+        };
+
+        let mut encoder = Encoder::new(
+            Vec::new(),
+            EncoderConfig {
+                compressor: CompressorConfig {
+                    format: Format::Gzip,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        for record_type in ["resource", "metadata", "revisit"] {
+            let header = WarcHeader::new(5, record_type);
+            let mut block_encoder = encoder.write_header(&header).unwrap();
+            block_encoder.write_all(b"hello").unwrap();
+            encoder = block_encoder.finish_block().unwrap();
+        }
+
+        let data = encoder.finish().unwrap();
+        let config = DecoderConfig {
+            decompressor: DecompressorConfig {
+                format: Format::Gzip,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let reader = Decoder::new(Cursor::new(&data), config.clone()).unwrap();
+        let (_header, reader) = reader.read_header().unwrap();
+        let reader = reader.finish_block().unwrap();
+        let second_position = reader.record_boundary_position();
+        assert_ne!(second_position, 0);
+
+        let mut reader = Decoder::open_at(Cursor::new(&data), second_position, config).unwrap();
+        assert_eq!(reader.record_boundary_position(), second_position);
+        assert!(reader.has_next_record().unwrap());
+
+        let (header, reader) = reader.read_header().unwrap();
+        assert_eq!(header.fields.get("WARC-Type").unwrap(), "metadata");
+        let reader = reader.finish_block().unwrap();
+        assert!(reader.record_boundary_position() > second_position);
+
+        let (header, reader) = reader.read_header().unwrap();
+        assert_eq!(header.fields.get("WARC-Type").unwrap(), "revisit");
+        let mut reader = reader.finish_block().unwrap();
+        assert!(!reader.has_next_record().unwrap());
+        assert_eq!(reader.end_state(), EndState::Clean);
+    }
+
+    #[test]
+    fn test_reader_max_header_length() {
+        let mut data = b"WARC/1.1\r\nContent-Length: 0\r\nX-Long: ".to_vec();
+        data.resize(data.len() + DEFAULT_HEADER_LENGTH_LIMIT, b'a');
+        data.extend_from_slice(b"\r\n\r\n\r\n\r\n");
+
+        let reader = Decoder::new(Cursor::new(&data), DecoderConfig::default()).unwrap();
+        assert!(reader.read_header().is_err());
+
+        let config = DecoderConfig {
+            max_header_length: DEFAULT_HEADER_LENGTH_LIMIT * 2,
+            ..Default::default()
+        };
+        let reader = Decoder::new(Cursor::new(&data), config).unwrap();
+        let (header, reader) = reader.read_header().unwrap();
+
+        assert_eq!(
+            header.fields.get("X-Long").unwrap().len(),
+            DEFAULT_HEADER_LENGTH_LIMIT
+        );
+
+        let mut reader = reader.finish_block().unwrap();
+        assert!(!reader.has_next_record().unwrap());
+    }
+
+    #[test]
+    fn test_reader_max_content_length() {
+        let data = b"WARC/1.1\r\n\
+            Content-Length: 1000000000000\r\n\
+            \r\n\
+            Hello";
+        let config = DecoderConfig {
+            max_content_length: Some(1000),
+            ..Default::default()
+        };
+
+        let reader = Decoder::new(Cursor::new(data), config).unwrap();
+        assert!(matches!(
+            reader.read_header(),
+            Err(GeneralError::Protocol(error))
+                if matches!(error.kind(), ProtocolErrorKind::ContentTooBig)
+        ));
+
+        let reader = Decoder::new(Cursor::new(data), DecoderConfig::default()).unwrap();
+        assert!(reader.read_header().is_ok());
+    }
+
+    #[test]
+    fn test_reader_max_segment_length() {
+        // Incompressible data so the limit is not reached by data read ahead.
+        let mut state = 1u32;
+        let mut random_block = |len: usize| {
+            (0..len)
+                .map(|_| {
+                    state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                    b'a' + (state >> 16) as u8 % 26
+                })
+                .collect::<Vec<u8>>()
+        };
+        let mut record = |len: usize| {
+            let block = random_block(len);
+            let mut data = format!("WARC/1.1\r\nContent-Length: {}\r\n\r\n", len).into_bytes();
+            data.extend_from_slice(&block);
+            data.extend_from_slice(b"\r\n\r\n");
+            data
+        };
+        let read_all = |data: &[u8], format: Format| -> Result<(), GeneralError> {
+            let mut compressor = Compressor::new(Vec::new(), format);
+            compressor.write_all(data)?;
+            let data = compressor.finish()?;
+            let config = DecoderConfig {
+                decompressor: DecompressorConfig {
+                    format,
+                    max_segment_length: Some(512 * 1024),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let mut reader = Decoder::new(Cursor::new(data), config)?;
+
+            while reader.has_next_record()? {
+                let (_header, mut block_reader) = reader.read_header()?;
+                block_reader.read_to_end(&mut Vec::new())?;
+                reader = block_reader.finish_block()?;
+            }
+
+            Ok(())
+        };
+
+        // The limit applies to each record of a single gzip member.
+        let data = (0..20).flat_map(|_| record(100_000)).collect::<Vec<u8>>();
+        assert!(read_all(&data, Format::Gzip).is_ok());
+
+        let data = [record(100), record(600_000)].concat();
+        assert!(read_all(&data, Format::Gzip).is_err());
+
+        // Uncompressed data is not limited.
+        assert!(read_all(&data, Format::Identity).is_ok());
+    }
+
+    #[test]
+    fn test_reader_line_folding() {
+        let data = b"WARC/1.1\r\n\
+            X-Note: a\r\n\tb\r\n\
+            Content-Length: 0\r\n\
+            \r\n\
+            \r\n\r\n";
+        let config = |line_folding| DecoderConfig {
+            line_folding,
+            ..Default::default()
+        };
+
+        let reader = Decoder::new(Cursor::new(data), config(LineFolding::Unfold)).unwrap();
+        let (header, _reader) = reader.read_header().unwrap();
+        assert_eq!(header.fields.get("X-Note").unwrap(), "a b");
+
+        let reader = Decoder::new(Cursor::new(data), config(LineFolding::Preserve)).unwrap();
+        let (header, _reader) = reader.read_header().unwrap();
+        assert_eq!(header.fields.get("X-Note").unwrap(), "a\r\n\tb");
+
+        let reader = Decoder::new(Cursor::new(data), config(LineFolding::Reject)).unwrap();
+        assert!(matches!(reader.read_header(), Err(GeneralError::Parse(_))));
+    }
+
     #[tracing_test::traced_test]
     #[test]
     fn test_push_reader() {
@@ -1,24 +1,266 @@
 //! WARC file writing
-use std::io::{BufWriter, Write};
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    io::{BufWriter, Read, Write},
+};
 
 use crate::{
     compress::{Compressor, CompressorConfig},
-    error::GeneralError,
-    header::WarcHeader,
+    digest::{AlgorithmName, Digest, Hasher},
+    error::{GeneralError, ProtocolError, ProtocolErrorKind},
+    extract::WarcExtractor,
+    header::{fields::FieldsExt, WarcHeader},
+    io::SpooledBuffer,
+    verify::{Problem, Severity, Verifier},
+    warc::{state_error, RawRecord},
+    warning::{Warning, WarningSink},
 };
 
+/// Default value of [`EncoderConfig::spool_memory_limit`].
+pub const DEFAULT_SPOOL_MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+
 /// Configuration for a [`Encoder`].
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 #[non_exhaustive]
 pub struct EncoderConfig {
     /// Configuration for compressing the written file
     pub compressor: CompressorConfig,
+    /// Receiver of non-fatal observations about the written records
+    pub warnings: WarningSink,
+    /// Buffer each block and set the Content-Length field from its length
+    ///
+    /// The Content-Length of the header given to
+    /// [`write_header()`](Encoder::write_header) is ignored and the
+    /// header is written by [`finish_block()`](Encoder::finish_block).
+    pub spool_block: bool,
+    /// Maximum number of bytes of a spooled block kept in memory before it
+    /// is moved to a temporary file
+    pub spool_memory_limit: usize,
+    /// Algorithms of the digests computed from each block and added to the
+    /// header
+    ///
+    /// The WARC-Block-Digest fields are replaced with the digests of the
+    /// block. Records containing an HTTP message also have their
+    /// WARC-Payload-Digest fields replaced with the digests of the HTTP
+    /// body. If not empty, blocks are spooled as with `spool_block`.
+    pub digest_algorithms: Vec<AlgorithmName>,
+    /// Maximum length of a block written in a single record
+    ///
+    /// A longer block is split into a record containing the first segment
+    /// and `continuation` records containing the remaining segments. The
+    /// WARC-Block-Digest fields of a split record are removed because they
+    /// apply to the whole block.
+    pub max_segment_length: Option<u64>,
+    /// Check each header with a [`Verifier`] before it is written
+    ///
+    /// A header with problems of [`Severity::Error`] is rejected with
+    /// [`ProtocolErrorKind::InvalidHeader`] and its source is a
+    /// [`HeaderProblems`]. Other problems are sent to the warning sink.
+    pub validate_headers: bool,
+}
+
+impl Default for EncoderConfig {
+    fn default() -> Self {
+        Self {
+            compressor: Default::default(),
+            warnings: Default::default(),
+            spool_block: false,
+            spool_memory_limit: DEFAULT_SPOOL_MEMORY_LIMIT,
+            digest_algorithms: Vec::new(),
+            max_segment_length: None,
+            validate_headers: false,
+        }
+    }
 }
 
 pub struct EncStateHeader;
 pub struct EncStateBlock {
     length: u64,
     written: u64,
+    spool: Option<Box<SpooledBlock>>,
+    segmenter: Option<Box<BlockSegmenter>>,
+}
+
+/// Fields of the first segment copied to the continuation records.
+const CONTINUATION_FIELDS: [&str; 3] = ["WARC-Date", "WARC-Target-URI", "WARC-Warcinfo-ID"];
+
+/// Block split into segments that are written as separate records.
+struct BlockSegmenter {
+    header: WarcHeader,
+    max_length: u64,
+    total_length: u64,
+    /// Sum of the lengths of the segments that have been written.
+    finished_length: u64,
+    number: u64,
+}
+
+impl BlockSegmenter {
+    fn new(header: &WarcHeader, max_length: u64, total_length: u64) -> Self {
+        Self {
+            header: header.clone(),
+            max_length,
+            total_length,
+            finished_length: 0,
+            number: 1,
+        }
+    }
+
+    /// Returns the header of the first segment.
+    fn first_header(&self) -> WarcHeader {
+        let mut header = self.header.clone();
+        header.set_content_length(self.max_length);
+        header
+            .fields
+            .insert("WARC-Segment-Number".to_string(), "1".to_string());
+        header.fields.remove("WARC-Block-Digest");
+        header
+    }
+
+    /// Returns the header and block length of the next continuation record
+    /// or `None` if the whole block has been written.
+    fn next_header(&mut self) -> Option<(WarcHeader, u64)> {
+        let remaining_length = self.total_length - self.finished_length;
+
+        if remaining_length == 0 {
+            return None;
+        }
+
+        self.number += 1;
+        let length = remaining_length.min(self.max_length);
+
+        let mut header = WarcHeader::new(length, "continuation");
+        header.version.clone_from(&self.header.version);
+
+        for name in CONTINUATION_FIELDS {
+            if let Some(value) = self.header.fields.get(name) {
+                header.fields.insert(name.to_string(), value.clone());
+            }
+        }
+
+        header.fields.insert(
+            "WARC-Segment-Origin-ID".to_string(),
+            self.header
+                .fields
+                .get_or_default("WARC-Record-ID")
+                .to_string(),
+        );
+        header
+            .fields
+            .insert("WARC-Segment-Number".to_string(), self.number.to_string());
+
+        if length == remaining_length {
+            header.fields.insert(
+                "WARC-Segment-Total-Length".to_string(),
+                self.total_length.to_string(),
+            );
+        }
+
+        Some((header, length))
+    }
+}
+
+/// Block buffered until its length and digests are known.
+struct SpooledBlock {
+    header: WarcHeader,
+    buffer: SpooledBuffer,
+    block_hashers: Vec<Hasher>,
+    payload_extractor: Option<WarcExtractor>,
+    payload_hashers: Vec<Hasher>,
+    payload_buf: Vec<u8>,
+}
+
+impl SpooledBlock {
+    fn new(header: &WarcHeader, config: &EncoderConfig) -> Self {
+        let mut header = header.clone();
+        // The length is not known, so the block must not be seen as empty.
+        header.fields.remove("Content-Length");
+
+        let payload_extractor = if config.digest_algorithms.is_empty() {
+            None
+        } else {
+            let mut extractor = WarcExtractor::new().with_requests(true);
+            extractor
+                .read_header(&header)
+                .ok()
+                .filter(|_| extractor.has_content())
+                .map(|_| extractor)
+        };
+        let new_hashers = || {
+            config
+                .digest_algorithms
+                .iter()
+                .map(|&algorithm| Hasher::new(algorithm))
+                .collect()
+        };
+
+        Self {
+            header,
+            buffer: SpooledBuffer::new(config.spool_memory_limit),
+            block_hashers: new_hashers(),
+            payload_extractor,
+            payload_hashers: new_hashers(),
+            payload_buf: Vec::new(),
+        }
+    }
+
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let write_length = self.buffer.write(buf)?;
+        let buf = &buf[0..write_length];
+
+        for hasher in &mut self.block_hashers {
+            hasher.update(buf);
+        }
+
+        if let Some(extractor) = &mut self.payload_extractor {
+            if extractor.extract_data(buf, &mut self.payload_buf).is_err() {
+                // The block is written as is without a payload digest.
+                self.payload_extractor = None;
+            } else {
+                for hasher in &mut self.payload_hashers {
+                    hasher.update(&self.payload_buf);
+                }
+            }
+
+            self.payload_buf.clear();
+        }
+
+        Ok(write_length)
+    }
+
+    /// Sets the Content-Length and digest fields of the header.
+    fn finish_header(&mut self) -> &WarcHeader {
+        self.header.set_content_length(self.buffer.len());
+
+        if !self.block_hashers.is_empty() {
+            self.header.fields.remove("WARC-Block-Digest");
+
+            for hasher in &mut self.block_hashers {
+                let digest = Digest::new(hasher.algorithm(), hasher.finish());
+                self.header
+                    .fields
+                    .append("WARC-Block-Digest".to_string(), digest.to_string());
+            }
+        }
+
+        let is_http = self
+            .payload_extractor
+            .as_ref()
+            .is_some_and(|extractor| extractor.http_header().is_some());
+
+        if is_http {
+            self.header.fields.remove("WARC-Payload-Digest");
+
+            for hasher in &mut self.payload_hashers {
+                let digest = Digest::new(hasher.algorithm(), hasher.finish());
+                self.header
+                    .fields
+                    .append("WARC-Payload-Digest".to_string(), digest.to_string());
+            }
+        }
+
+        &self.header
+    }
 }
 
 /// WARC format writer
@@ -26,6 +268,7 @@ pub struct Encoder<S, W: Write> {
     state: S,
     output: BufWriter<Compressor<W>>,
     config: EncoderConfig,
+    verifier: Option<Box<Verifier>>,
 }
 
 impl<S, W: Write> Encoder<S, W> {
@@ -45,14 +288,20 @@ impl<W: Write> Encoder<EncStateHeader, W> {
     /// compression, you must configure it with [`EncoderConfig`].
     pub fn new(dest: W, config: EncoderConfig) -> Self {
         let output = Compressor::with_config(dest, config.compressor.clone());
+        let verifier = config.validate_headers.then(|| Box::new(Verifier::new()));
 
         Self {
             state: EncStateHeader,
             output: BufWriter::new(output),
             config,
+            verifier,
         }
     }
 
+    fn is_spooling(&self) -> bool {
+        self.config.spool_block || !self.config.digest_algorithms.is_empty()
+    }
+
     /// Start a new WARC record with a given header.
     ///
     /// The validation function will be called on the header before
@@ -64,16 +313,174 @@ impl<W: Write> Encoder<EncStateHeader, W> {
         mut self,
         header: &WarcHeader,
     ) -> Result<Encoder<EncStateBlock, W>, GeneralError> {
+        self.check_header(header)?;
+        self.write_checked_header(header)
+    }
+
+    /// Returns an error if the header cannot be written.
+    pub(super) fn check_header(&mut self, header: &WarcHeader) -> Result<(), GeneralError> {
         header.validate()?;
-        header.serialize(&mut self.output)?;
+
+        let is_spooling = self.is_spooling();
+
+        if !is_spooling {
+            header.content_length()?;
+        }
+
+        if let Some(verifier) = &mut self.verifier {
+            // The Content-Length of a spooled block is set later.
+            let mut header = Cow::Borrowed(header);
+
+            if is_spooling && !header.fields.contains_name("Content-Length") {
+                header.to_mut().set_content_length(0);
+            }
+
+            verifier.problems_mut().clear();
+            verifier.begin_record(&header)?;
+
+            let (errors, others): (Vec<Problem>, Vec<Problem>) =
+                std::mem::take(verifier.problems_mut())
+                    .into_iter()
+                    .partition(|problem| problem.severity() == Severity::Error);
+
+            for problem in others {
+                self.config.warnings.emit(Warning::HeaderProblem {
+                    record_id: problem.record_id().to_string(),
+                    problem: format!("{:?}", problem.kind()),
+                });
+            }
+
+            if !errors.is_empty() {
+                return Err(ProtocolError::new(ProtocolErrorKind::InvalidHeader)
+                    .with_source(HeaderProblems { problems: errors })
+                    .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a header that has been checked by
+    /// [`check_header()`](Self::check_header).
+    pub(super) fn write_checked_header(
+        self,
+        header: &WarcHeader,
+    ) -> Result<Encoder<EncStateBlock, W>, GeneralError> {
+        let _span = tracing::trace_span!("serialize_header", header_len = header.serialized_len())
+            .entered();
+
+        self.check_mandatory_fields(header);
+
+        if self.is_spooling() {
+            let spool = SpooledBlock::new(header, &self.config);
+
+            return Ok(Encoder {
+                state: EncStateBlock {
+                    length: u64::MAX,
+                    written: 0,
+                    spool: Some(Box::new(spool)),
+                    segmenter: None,
+                },
+                output: self.output,
+                config: self.config,
+                verifier: self.verifier,
+            });
+        }
 
         let length = header.content_length()?;
 
-        Ok(Encoder {
-            state: EncStateBlock { length, written: 0 },
+        let mut encoder = Encoder {
+            state: EncStateBlock {
+                length: 0,
+                written: 0,
+                spool: None,
+                segmenter: None,
+            },
             output: self.output,
             config: self.config,
-        })
+            verifier: self.verifier,
+        };
+        encoder.begin_block(header, length)?;
+
+        Ok(encoder)
+    }
+
+    /// Start a new WARC record with a header that is already serialized.
+    ///
+    /// The header, such as one from
+    /// [`Decoder::raw_header()`](crate::warc::Decoder::raw_header), is
+    /// copied verbatim so that the order, case, and formatting of the fields
+    /// are preserved. It must end with the blank line of the header. It is
+    /// parsed to check it and to get the Content-Length.
+    ///
+    /// Options that change the header, such as spooling, digests, and
+    /// segmenting, cannot be used and return an error.
+    pub fn write_raw_header(
+        mut self,
+        header: &[u8],
+    ) -> Result<Encoder<EncStateBlock, W>, GeneralError> {
+        if crate::parse::scan_header_deliminator(header) != Some(header.len()) {
+            return Err(ProtocolError::new(ProtocolErrorKind::InvalidHeader)
+                .with_source("data is not exactly one header")
+                .into());
+        }
+
+        let parsed_header = WarcHeader::parse(header)?;
+        let length = parsed_header.content_length()?;
+        let is_segmented = self
+            .config
+            .max_segment_length
+            .is_some_and(|max_length| max_length > 0 && length > max_length);
+
+        if self.is_spooling() || is_segmented {
+            return Err(state_error("encoder configured to change headers").into());
+        }
+
+        self.check_header(&parsed_header)?;
+        self.check_mandatory_fields(&parsed_header);
+
+        let _span = tracing::trace_span!("write_raw_header", header_len = header.len()).entered();
+
+        let mut encoder = Encoder {
+            state: EncStateBlock {
+                length,
+                written: 0,
+                spool: None,
+                segmenter: None,
+            },
+            output: self.output,
+            config: self.config,
+            verifier: self.verifier,
+        };
+        encoder.output.write_all(header)?;
+
+        Ok(encoder)
+    }
+
+    /// Writes a record that is already compressed.
+    ///
+    /// The record, such as one from
+    /// [`Decoder::take_raw_record()`](crate::warc::Decoder::take_raw_record),
+    /// is copied verbatim without being decompressed and compressed again.
+    /// It must be compressed in the format of the encoder. Options that
+    /// change records, such as digests and validation, are not applied.
+    pub fn write_raw_record(&mut self, record: &RawRecord) -> std::io::Result<()> {
+        debug_assert!(self.output.buffer().is_empty());
+
+        self.output
+            .get_mut()
+            .write_raw_segment(&record.data, record.decompressed_len)
+    }
+
+    fn check_mandatory_fields(&self, header: &WarcHeader) {
+        for name in ["WARC-Record-ID", "WARC-Type", "WARC-Date"] {
+            if !header.fields.contains_name(name) {
+                self.config.warnings.emit(Warning::MissingField {
+                    record_id: header.fields.get_or_default("WARC-Record-ID").to_string(),
+                    name: name.to_string(),
+                });
+            }
+        }
     }
 
     /// Flushes any buffered data and returns the underlying stream.
@@ -86,7 +493,59 @@ impl<W: Write> Encoder<EncStateHeader, W> {
 }
 
 impl<W: Write> Encoder<EncStateBlock, W> {
+    /// Writes the header and starts a block of the given length, splitting
+    /// it into segments if it is too long.
+    fn begin_block(&mut self, header: &WarcHeader, length: u64) -> std::io::Result<()> {
+        let max_length = self
+            .config
+            .max_segment_length
+            .filter(|&max_length| max_length > 0 && length > max_length);
+
+        if let Some(max_length) = max_length {
+            let segmenter = BlockSegmenter::new(header, max_length, length);
+            segmenter.first_header().serialize(&mut self.output)?;
+
+            self.state.length = max_length;
+            self.state.segmenter = Some(Box::new(segmenter));
+        } else {
+            header.serialize(&mut self.output)?;
+
+            self.state.length = length;
+            self.state.segmenter = None;
+        }
+
+        self.state.written = 0;
+
+        Ok(())
+    }
+
+    /// Writes the header of the next continuation record if the segmented
+    /// block has not been fully written.
+    fn begin_next_segment(&mut self) -> std::io::Result<()> {
+        let Some(segmenter) = &mut self.state.segmenter else {
+            return Ok(());
+        };
+
+        segmenter.finished_length += self.state.length;
+
+        if let Some((header, length)) = segmenter.next_header() {
+            header.serialize(&mut self.output)?;
+
+            self.state.length = length;
+            self.state.written = 0;
+        }
+
+        Ok(())
+    }
+
     fn write_block_impl(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Some(spool) = &mut self.state.spool {
+            let write_length = spool.write(buf)?;
+            self.state.written += write_length as u64;
+
+            return Ok(write_length);
+        }
+
         let remain_length = self.state.length - self.state.written;
         let buf_upper = buf
             .len()
@@ -98,8 +557,9 @@ impl<W: Write> Encoder<EncStateBlock, W> {
 
         debug_assert!(self.state.length >= self.state.written);
 
-        if self.state.length == self.state.written {
+        if write_length > 0 && self.state.length == self.state.written {
             self.write_finish_block()?;
+            self.begin_next_segment()?;
         }
 
         Ok(write_length)
@@ -112,22 +572,49 @@ impl<W: Write> Encoder<EncStateBlock, W> {
         Ok(())
     }
 
+    /// Returns an error if the length of the written data does not match
+    /// the Content-Length of a block that is not spooled.
+    fn check_length(&self) -> std::io::Result<()> {
+        if self.state.spool.is_some() || self.state.length == self.state.written {
+            return Ok(());
+        }
+
+        let (expect, actual) = match &self.state.segmenter {
+            Some(segmenter) => (
+                segmenter.total_length,
+                segmenter.finished_length + self.state.written,
+            ),
+            None => (self.state.length, self.state.written),
+        };
+
+        Err(std::io::Error::other(ContentLengthMismatch::new(
+            expect, actual,
+        )))
+    }
+
     /// Indicate writing the block portion of a WARC record has completed.
     ///
     /// Consumes the writer and returns a typestate transitioned
     /// writer for writing a new record.
-    pub fn finish_block(self) -> std::io::Result<Encoder<EncStateHeader, W>> {
-        if self.state.length != self.state.written {
-            return Err(std::io::Error::other(ContentLengthMismatch::new(
-                self.state.length,
-                self.state.written,
-            )));
+    pub fn finish_block(mut self) -> std::io::Result<Encoder<EncStateHeader, W>> {
+        if let Some(mut spool) = self.state.spool.take() {
+            let length = spool.buffer.len();
+            self.begin_block(spool.finish_header(), length)?;
+            spool.buffer.copy_to(&mut self)?;
+        }
+
+        self.check_length()?;
+
+        // An empty block is not finished by a write.
+        if self.state.length == 0 {
+            self.write_finish_block()?;
         }
 
         Ok(Encoder {
             state: EncStateHeader,
             output: self.output,
             config: self.config,
+            verifier: self.verifier,
         })
     }
 }
@@ -142,6 +629,146 @@ impl<W: Write> Write for Encoder<EncStateBlock, W> {
     }
 }
 
+enum PushEncoderState {
+    Header(Encoder<EncStateHeader, VecDeque<u8>>),
+    Block(Encoder<EncStateBlock, VecDeque<u8>>),
+    Finished(VecDeque<u8>),
+    Failed,
+}
+
+/// WARC format encoder push-style.
+///
+/// This is similar to [`Encoder`] but the encoded data is buffered in the
+/// struct and read by the caller. This push-style method can be used for
+/// sans-IO implementations.
+///
+/// For each record, call [`write_header()`](Self::write_header), write the
+/// block data using the [`Write`] trait, and call
+/// [`end_record()`](Self::end_record). Call [`finish()`](Self::finish)
+/// after the last record. The output can be read using the [`Read`] trait
+/// at any time.
+pub struct PushEncoder {
+    state: PushEncoderState,
+}
+
+impl PushEncoder {
+    /// Creates a new encoder.
+    pub fn new(config: EncoderConfig) -> Self {
+        Self {
+            state: PushEncoderState::Header(Encoder::new(VecDeque::new(), config)),
+        }
+    }
+
+    /// Returns the number of bytes of output that can be read.
+    pub fn output_len(&self) -> usize {
+        self.output().map(VecDeque::len).unwrap_or_default()
+    }
+
+    fn output(&self) -> Option<&VecDeque<u8>> {
+        match &self.state {
+            PushEncoderState::Header(encoder) => Some(encoder.get_ref()),
+            PushEncoderState::Block(encoder) => Some(encoder.get_ref()),
+            PushEncoderState::Finished(output) => Some(output),
+            PushEncoderState::Failed => None,
+        }
+    }
+
+    fn output_mut(&mut self) -> Option<&mut VecDeque<u8>> {
+        match &mut self.state {
+            PushEncoderState::Header(encoder) => Some(encoder.get_mut()),
+            PushEncoderState::Block(encoder) => Some(encoder.get_mut()),
+            PushEncoderState::Finished(output) => Some(output),
+            PushEncoderState::Failed => None,
+        }
+    }
+
+    /// Starts a new record with the given header.
+    ///
+    /// The block data of the record is written next.
+    pub fn write_header(&mut self, header: &WarcHeader) -> Result<(), GeneralError> {
+        let PushEncoderState::Header(encoder) = &mut self.state else {
+            return Err(state_error("not expecting a header").into());
+        };
+
+        // Check the header first so the encoder is kept on error.
+        encoder.check_header(header)?;
+
+        let PushEncoderState::Header(encoder) =
+            std::mem::replace(&mut self.state, PushEncoderState::Failed)
+        else {
+            unreachable!()
+        };
+
+        self.state = PushEncoderState::Block(encoder.write_checked_header(header)?);
+
+        Ok(())
+    }
+
+    /// Indicates the block data of the current record has been written.
+    pub fn end_record(&mut self) -> std::io::Result<()> {
+        let PushEncoderState::Block(encoder) = &self.state else {
+            return Err(state_error("not expecting the end of a record"));
+        };
+
+        // Check the length first so more data can be written on error.
+        encoder.check_length()?;
+
+        let PushEncoderState::Block(encoder) =
+            std::mem::replace(&mut self.state, PushEncoderState::Failed)
+        else {
+            unreachable!()
+        };
+
+        self.state = PushEncoderState::Header(encoder.finish_block()?);
+
+        Ok(())
+    }
+
+    /// Writes ending compressor data.
+    ///
+    /// The remaining output can be read afterwards.
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        let PushEncoderState::Header(_) = &self.state else {
+            return Err(state_error("not expecting the end of the file"));
+        };
+
+        let PushEncoderState::Header(encoder) =
+            std::mem::replace(&mut self.state, PushEncoderState::Failed)
+        else {
+            unreachable!()
+        };
+
+        self.state = PushEncoderState::Finished(encoder.finish()?);
+
+        Ok(())
+    }
+}
+
+impl Write for PushEncoder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.state {
+            PushEncoderState::Block(encoder) => encoder.write(buf),
+            _ => Err(state_error("not expecting block data")),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.state {
+            PushEncoderState::Block(encoder) => encoder.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Read for PushEncoder {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.output_mut() {
+            Some(output) => output.read(buf),
+            None => Ok(0),
+        }
+    }
+}
+
 /// Error for a block size mismatch in a WARC record.
 #[derive(Debug, Default, thiserror::Error)]
 #[error("content length mismatch: expected {expected}, got {expected}")]
@@ -156,8 +783,29 @@ impl ContentLengthMismatch {
     }
 }
 
+/// Error for a header rejected by [`EncoderConfig::validate_headers`].
+#[derive(Debug, thiserror::Error)]
+#[error("header has {} problems", problems.len())]
+pub struct HeaderProblems {
+    problems: Vec<Problem>,
+}
+
+impl HeaderProblems {
+    /// Returns the problems of [`Severity::Error`] found in the header.
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::{
+        io::Read,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::{compress::Format, verify::ProblemKind};
+
     use super::*;
 
     #[tracing_test::traced_test]
@@ -176,8 +824,491 @@ mod tests {
         writer.write_all(b"").unwrap();
         let writer = writer.finish_block().unwrap();
 
+        let header = WarcHeader::new(0, "a");
+        let writer = writer.write_header(&header).unwrap();
+        let writer = writer.finish_block().unwrap();
+
         let buf = writer.finish().unwrap();
 
         assert!(buf.starts_with(b"WARC/1.1\r\n"));
+        assert!(buf.ends_with(b"\r\n\r\n\r\n\r\n"));
+
+        let mut reader =
+            crate::warc::Decoder::new(buf.as_slice(), crate::warc::DecoderConfig::default())
+                .unwrap();
+        let mut count = 0;
+
+        while reader.has_next_record().unwrap() {
+            let (_header, block_reader) = reader.read_header().unwrap();
+            reader = block_reader.finish_block().unwrap();
+            count += 1;
+        }
+
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_writer_missing_field_warning() {
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let warnings = warnings.clone();
+            WarningSink::new(move |warning| warnings.lock().unwrap().push(warning.clone()))
+        };
+        let config = EncoderConfig {
+            warnings: sink,
+            ..Default::default()
+        };
+        let writer = Encoder::new(Vec::new(), config);
+
+        let mut header = WarcHeader::new(0, "a");
+        header.fields.remove("WARC-Date");
+        let writer = writer.write_header(&header).unwrap();
+        writer.finish_block().unwrap();
+
+        let warnings = warnings.lock().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            &warnings[0],
+            Warning::MissingField { name, .. } if name == "WARC-Date"
+        ));
+    }
+
+    #[test]
+    fn test_writer_raw_record() {
+        let formats = [
+            Format::Identity,
+            Format::Gzip,
+            #[cfg(feature = "zstd")]
+            Format::Zstandard,
+        ];
+
+        for format in formats {
+            let compressor = CompressorConfig {
+                format,
+                seek_table: true,
+                ..Default::default()
+            };
+            let config = EncoderConfig {
+                compressor: compressor.clone(),
+                ..Default::default()
+            };
+            let mut writer = Encoder::new(Vec::new(), config.clone());
+
+            for index in 0..3 {
+                let mut block_writer = writer
+                    .write_header(&WarcHeader::new(12, "resource"))
+                    .unwrap();
+                block_writer.write_all(&[b'a' + index; 12]).unwrap();
+                writer = block_writer.finish_block().unwrap();
+            }
+
+            let input = writer.finish().unwrap();
+
+            let decoder_config = crate::warc::DecoderConfig {
+                decompressor: crate::compress::DecompressorConfig {
+                    format,
+                    ..Default::default()
+                },
+                keep_raw_records: true,
+                ..Default::default()
+            };
+            let mut reader =
+                crate::warc::Decoder::new(input.as_slice(), decoder_config.clone()).unwrap();
+            let mut writer = Encoder::new(Vec::new(), config);
+            let mut index = 0;
+
+            while reader.has_next_record().unwrap() {
+                let (header, mut block_reader) = reader.read_header().unwrap();
+                let mut data = Vec::new();
+                block_reader.read_to_end(&mut data).unwrap();
+                reader = block_reader.finish_block().unwrap();
+
+                let record = reader.take_raw_record();
+                assert_eq!(record.is_some(), format != Format::Identity);
+
+                // Raw records are mixed with records that are encoded again.
+                match record {
+                    Some(record) if index % 2 == 0 => {
+                        writer.write_raw_record(&record).unwrap();
+                    }
+                    _ => {
+                        let mut block_writer = writer.write_header(&header).unwrap();
+                        block_writer.write_all(&data).unwrap();
+                        writer = block_writer.finish_block().unwrap();
+                    }
+                }
+
+                index += 1;
+            }
+
+            let output = writer.finish().unwrap();
+
+            assert_eq!(output, input);
+        }
+    }
+
+    #[test]
+    fn test_writer_raw_header() {
+        let input = b"WARC/1.1\r\n\
+            warc-type:   resource\r\n\
+            WARC-Record-ID: <urn:uuid:1>\r\n\
+            WARC-Date: 2024-01-02T03:04:05Z\r\n\
+            X-Folded: a\r\n \
+            b\r\n\
+            Content-Length: 12\r\n\
+            \r\n\
+            Hello world!\
+            \r\n\r\n\
+            WARC/1.1\r\n\
+            Content-Length:0\r\n\
+            \r\n\
+            \r\n\r\n";
+
+        let config = crate::warc::DecoderConfig {
+            keep_raw_header: true,
+            ..Default::default()
+        };
+        let mut reader = crate::warc::Decoder::new(input.as_slice(), config).unwrap();
+        let mut writer = Encoder::new(Vec::new(), EncoderConfig::default());
+
+        while reader.has_next_record().unwrap() {
+            let (_header, mut block_reader) = reader.read_header().unwrap();
+            let raw_header = block_reader.raw_header().unwrap().to_vec();
+            let mut data = Vec::new();
+            block_reader.read_to_end(&mut data).unwrap();
+            reader = block_reader.finish_block().unwrap();
+
+            let mut block_writer = writer.write_raw_header(&raw_header).unwrap();
+            block_writer.write_all(&data).unwrap();
+            writer = block_writer.finish_block().unwrap();
+        }
+
+        let output = writer.finish().unwrap();
+
+        assert_eq!(output, input);
+
+        let writer = Encoder::new(Vec::new(), EncoderConfig::default());
+        let result = writer.write_raw_header(b"WARC/1.1\r\nContent-Length: 0\r\n");
+        assert!(matches!(
+            result,
+            Err(GeneralError::Protocol(error))
+                if matches!(error.kind(), ProtocolErrorKind::InvalidHeader)
+        ));
+
+        let writer = Encoder::new(
+            Vec::new(),
+            EncoderConfig {
+                spool_block: true,
+                ..Default::default()
+            },
+        );
+        assert!(writer
+            .write_raw_header(b"WARC/1.1\r\nContent-Length: 0\r\n\r\n")
+            .is_err());
+    }
+
+    #[test]
+    fn test_push_encoder_validate_headers() {
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let warnings = warnings.clone();
+            WarningSink::new(move |warning| warnings.lock().unwrap().push(warning.clone()))
+        };
+        let config = EncoderConfig {
+            warnings: sink,
+            validate_headers: true,
+            ..Default::default()
+        };
+        let mut encoder = PushEncoder::new(config);
+
+        let header = WarcHeader::new(0, "continuation");
+        let error = encoder.write_header(&header).unwrap_err();
+        let error = error.as_protocol().unwrap();
+        assert!(matches!(error.kind(), ProtocolErrorKind::InvalidHeader));
+
+        let problems = std::error::Error::source(error)
+            .unwrap()
+            .downcast_ref::<HeaderProblems>()
+            .unwrap()
+            .problems();
+        assert!(problems.iter().any(|problem| matches!(
+            problem.kind(),
+            ProblemKind::RequiredFieldMissing(name) if name == "WARC-Segment-Origin-ID"
+        )));
+
+        let mut header = WarcHeader::new(5, "resource");
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "http://example.com/".to_string(),
+        );
+        header
+            .fields
+            .insert("WARC-IP-Address".to_string(), "example".to_string());
+        encoder.write_header(&header).unwrap();
+        encoder.write_all(b"Hello").unwrap();
+        encoder.end_record().unwrap();
+        encoder.finish().unwrap();
+
+        let mut output = Vec::new();
+        encoder.read_to_end(&mut output).unwrap();
+        assert!(output.starts_with(b"WARC/1.1\r\n"));
+
+        let warnings = warnings.lock().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(&warnings[0], Warning::HeaderProblem { .. }));
+    }
+
+    #[test]
+    fn test_writer_spool_block() {
+        let config = EncoderConfig {
+            spool_block: true,
+            spool_memory_limit: 10,
+            ..Default::default()
+        };
+        let blocks: [&[u8]; 3] = [b"Hello", b"", b"Hello world!"];
+        let mut writer = Encoder::new(Vec::new(), config);
+
+        for block in blocks {
+            let header = WarcHeader::new(0, "a");
+            let mut block_writer = writer.write_header(&header).unwrap();
+            block_writer.write_all(block).unwrap();
+            writer = block_writer.finish_block().unwrap();
+        }
+
+        let buf = writer.finish().unwrap();
+
+        let mut reader =
+            crate::warc::Decoder::new(buf.as_slice(), crate::warc::DecoderConfig::default())
+                .unwrap();
+
+        for block in blocks {
+            assert!(reader.has_next_record().unwrap());
+
+            let (header, mut block_reader) = reader.read_header().unwrap();
+            assert_eq!(header.content_length().unwrap(), block.len() as u64);
+
+            let mut data = Vec::new();
+            block_reader.read_to_end(&mut data).unwrap();
+            assert_eq!(data, block);
+
+            reader = block_reader.finish_block().unwrap();
+        }
+
+        assert!(!reader.has_next_record().unwrap());
+    }
+
+    #[test]
+    fn test_writer_digests() {
+        let config = EncoderConfig {
+            digest_algorithms: vec![AlgorithmName::Sha1, AlgorithmName::Sha256],
+            ..Default::default()
+        };
+        let writer = Encoder::new(Vec::new(), config);
+
+        let mut header = WarcHeader::new(0, "response");
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "http://example.com/".to_string(),
+        );
+        header.fields.insert(
+            "Content-Type".to_string(),
+            "application/http;msgtype=response".to_string(),
+        );
+        header.fields.insert(
+            "WARC-Block-Digest".to_string(),
+            "sha1:AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA".to_string(),
+        );
+        let mut writer = writer.write_header(&header).unwrap();
+        writer
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 12\r\n\r\nHello world!")
+            .unwrap();
+        let writer = writer.finish_block().unwrap();
+
+        let mut header = WarcHeader::new(0, "resource");
+        header.fields.insert(
+            "WARC-Target-URI".to_string(),
+            "http://example.com/a.txt".to_string(),
+        );
+        let mut writer = writer.write_header(&header).unwrap();
+        writer.write_all(b"Hello world!").unwrap();
+        let writer = writer.finish_block().unwrap();
+
+        let buf = writer.finish().unwrap();
+
+        let mut reader =
+            crate::warc::Decoder::new(buf.as_slice(), crate::warc::DecoderConfig::default())
+                .unwrap();
+        let mut verifier = crate::verify::Verifier::new();
+        let mut headers = Vec::new();
+
+        while reader.has_next_record().unwrap() {
+            let (header, mut block_reader) = reader.read_header().unwrap();
+            let mut data = Vec::new();
+            block_reader.read_to_end(&mut data).unwrap();
+
+            verifier.begin_record(&header).unwrap();
+            verifier.block_data(&data);
+            verifier.end_record();
+
+            headers.push(header);
+            reader = block_reader.finish_block().unwrap();
+        }
+
+        assert!(verifier.problems().is_empty(), "{:?}", verifier.problems());
+        assert_eq!(headers[0].fields.get_all("WARC-Block-Digest").count(), 2);
+        assert_eq!(
+            headers[0]
+                .fields
+                .get("WARC-Payload-Digest")
+                .map(String::as_str),
+            Some("sha1:2NEGV2ITNZ4FNPCCEERYL2TZOCKEOWAC")
+        );
+        assert_eq!(headers[1].fields.get_all("WARC-Block-Digest").count(), 2);
+        assert!(!headers[1].fields.contains_name("WARC-Payload-Digest"));
+    }
+
+    #[test]
+    fn test_writer_segments() {
+        for spool_block in [false, true] {
+            let config = EncoderConfig {
+                spool_block,
+                max_segment_length: Some(10),
+                ..Default::default()
+            };
+            let writer = Encoder::new(Vec::new(), config);
+
+            let mut header = WarcHeader::new(25, "resource");
+            header.fields.insert(
+                "WARC-Target-URI".to_string(),
+                "http://example.com/".to_string(),
+            );
+            let mut writer = writer.write_header(&header).unwrap();
+            writer.write_all(b"0123456789abcdefghijABCDE").unwrap();
+            let writer = writer.finish_block().unwrap();
+
+            let mut writer = writer
+                .write_header(&WarcHeader::new(5, "resource"))
+                .unwrap();
+            writer.write_all(b"Hello").unwrap();
+            let writer = writer.finish_block().unwrap();
+
+            let buf = writer.finish().unwrap();
+
+            let mut reader =
+                crate::warc::Decoder::new(buf.as_slice(), crate::warc::DecoderConfig::default())
+                    .unwrap();
+            let mut records = Vec::new();
+
+            while reader.has_next_record().unwrap() {
+                let (header, mut block_reader) = reader.read_header().unwrap();
+                let mut data = Vec::new();
+                block_reader.read_to_end(&mut data).unwrap();
+                reader = block_reader.finish_block().unwrap();
+
+                let segment = crate::segment::SegmentInfo::from_header(&header).unwrap();
+                records.push((header, segment, data));
+            }
+
+            assert_eq!(records.len(), 4);
+
+            let origin_id = records[0].0.fields.get_or_default("WARC-Record-ID");
+            let data = records[0..3]
+                .iter()
+                .flat_map(|(_, _, data)| data.clone())
+                .collect::<Vec<u8>>();
+            assert_eq!(data, b"0123456789abcdefghijABCDE");
+
+            for (index, (header, segment, _)) in records[0..3].iter().enumerate() {
+                let segment = segment.as_ref().unwrap();
+                assert_eq!(segment.origin_id, origin_id);
+                assert_eq!(segment.number, index as u64 + 1);
+                assert_eq!(
+                    header.fields.get_or_default("WARC-Target-URI"),
+                    "http://example.com/"
+                );
+
+                if index > 0 {
+                    assert_eq!(header.fields.get_or_default("WARC-Type"), "continuation");
+                }
+            }
+
+            assert_eq!(records[1].1.as_ref().unwrap().total_length, None);
+            assert_eq!(records[2].1.as_ref().unwrap().total_length, Some(25));
+            assert!(records[3].1.is_none());
+            assert_eq!(records[3].2, b"Hello");
+        }
+    }
+
+    #[test]
+    fn test_writer_segments_length_mismatch() {
+        let config = EncoderConfig {
+            max_segment_length: Some(10),
+            ..Default::default()
+        };
+        let writer = Encoder::new(Vec::new(), config);
+
+        let mut writer = writer
+            .write_header(&WarcHeader::new(25, "resource"))
+            .unwrap();
+        writer.write_all(b"0123456789abcdefghij").unwrap();
+        let error = writer.finish_block().err().unwrap();
+        let error = error
+            .get_ref()
+            .unwrap()
+            .downcast_ref::<ContentLengthMismatch>()
+            .unwrap();
+
+        assert_eq!(error.expected, 25);
+        assert_eq!(error.actual, 20);
+    }
+
+    #[test]
+    fn test_push_encoder() {
+        let mut encoder = PushEncoder::new(EncoderConfig {
+            compressor: CompressorConfig {
+                format: crate::compress::Format::Gzip,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let mut output = Vec::new();
+
+        assert!(encoder.write_all(b"Hello").is_err());
+        assert!(encoder.write_header(&WarcHeader::empty()).is_err());
+
+        encoder.write_header(&WarcHeader::new(12, "a")).unwrap();
+        encoder.write_all(b"Hello").unwrap();
+        assert!(encoder.end_record().is_err());
+        encoder.write_all(b" world!").unwrap();
+        encoder.end_record().unwrap();
+
+        assert!(encoder.output_len() > 0);
+        encoder.read_to_end(&mut output).unwrap();
+        assert_eq!(encoder.output_len(), 0);
+
+        encoder.write_header(&WarcHeader::new(0, "a")).unwrap();
+        encoder.end_record().unwrap();
+        encoder.finish().unwrap();
+        encoder.read_to_end(&mut output).unwrap();
+
+        let config = crate::warc::DecoderConfig {
+            decompressor: crate::compress::DecompressorConfig {
+                format: crate::compress::Format::Gzip,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut reader = crate::warc::Decoder::new(output.as_slice(), config).unwrap();
+        let mut blocks = Vec::new();
+
+        while reader.has_next_record().unwrap() {
+            let (_header, mut block_reader) = reader.read_header().unwrap();
+            let mut data = Vec::new();
+            block_reader.read_to_end(&mut data).unwrap();
+            blocks.push(data);
+            reader = block_reader.finish_block().unwrap();
+        }
+
+        assert_eq!(blocks, vec![b"Hello world!".to_vec(), Vec::new()]);
     }
 }
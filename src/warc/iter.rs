@@ -0,0 +1,150 @@
+use std::io::Read;
+
+use crate::{error::GeneralError, header::WarcHeader};
+
+use super::{DecStateHeader, Decoder};
+
+/// WARC record with its block read into memory.
+#[derive(Debug, Clone)]
+pub struct Record {
+    header: WarcHeader,
+    block: Vec<u8>,
+}
+
+impl Record {
+    pub(super) fn new(header: WarcHeader, block: Vec<u8>) -> Self {
+        Self { header, block }
+    }
+
+    /// Returns the header of the record.
+    pub fn header(&self) -> &WarcHeader {
+        &self.header
+    }
+
+    /// Returns the block of the record.
+    ///
+    /// The returned slice implements [`Read`].
+    pub fn body(&self) -> &[u8] {
+        &self.block
+    }
+
+    /// Returns the header and the block.
+    pub fn into_parts(self) -> (WarcHeader, Vec<u8>) {
+        (self.header, self.block)
+    }
+}
+
+/// Iterator over the records of a [`Decoder`].
+///
+/// Each block is read into memory. To process records with large blocks,
+/// use the decoder directly.
+///
+/// The iteration stops after the first error.
+pub struct RecordIter<R: Read> {
+    decoder: Option<Decoder<DecStateHeader, R>>,
+}
+
+impl<R: Read> RecordIter<R> {
+    /// Creates an iterator over the remaining records of the decoder.
+    pub fn new(decoder: Decoder<DecStateHeader, R>) -> Self {
+        Self {
+            decoder: Some(decoder),
+        }
+    }
+
+    /// Returns the decoder positioned at the next record.
+    ///
+    /// Returns `None` if the iteration was stopped by an error.
+    pub fn into_decoder(self) -> Option<Decoder<DecStateHeader, R>> {
+        self.decoder
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>, GeneralError> {
+        let Some(decoder) = &mut self.decoder else {
+            return Ok(None);
+        };
+
+        if !decoder.has_next_record()? {
+            return Ok(None);
+        }
+
+        let decoder = self.decoder.take().unwrap();
+        let (header, mut block_reader) = decoder.read_header()?;
+        let mut block = Vec::new();
+        block_reader.read_to_end(&mut block)?;
+        self.decoder = Some(block_reader.finish_block()?);
+
+        Ok(Some(Record::new(header, block)))
+    }
+}
+
+impl<R: Read> Iterator for RecordIter<R> {
+    type Item = Result<Record, GeneralError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_record() {
+            Ok(record) => record.map(Ok),
+            Err(error) => {
+                self.decoder = None;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+impl<R: Read> Decoder<DecStateHeader, R> {
+    /// Returns an iterator over the remaining records.
+    ///
+    /// See [`RecordIter`].
+    pub fn records(self) -> RecordIter<R> {
+        RecordIter::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{header::fields::FieldsExt, warc::DecoderConfig};
+
+    use super::*;
+
+    #[test]
+    fn test_record_iter() {
+        let data = b"WARC/1.1\r\n\
+            WARC-Record-ID: <urn:uuid:1>\r\n\
+            Content-Length: 12\r\n\
+            \r\n\
+            Hello world!\
+            \r\n\r\n\
+            WARC/1.1\r\n\
+            WARC-Record-ID: <urn:uuid:2>\r\n\
+            Content-Length: 0\r\n\
+            \r\n\
+            \r\n\r\n";
+
+        let decoder = Decoder::new(data.as_slice(), DecoderConfig::default()).unwrap();
+        let records = decoder.records().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0].header().fields.get_or_default("WARC-Record-ID"),
+            "<urn:uuid:1>"
+        );
+        assert_eq!(records[0].body(), b"Hello world!");
+        assert_eq!(records[1].body(), b"");
+    }
+
+    #[test]
+    fn test_record_iter_truncated() {
+        let data = b"WARC/1.1\r\n\
+            Content-Length: 12\r\n\
+            \r\n\
+            Hello";
+
+        let decoder = Decoder::new(data.as_slice(), DecoderConfig::default()).unwrap();
+        let mut records = decoder.records();
+
+        assert!(records.next().unwrap().is_err());
+        assert!(records.next().is_none());
+        assert!(records.into_decoder().is_none());
+    }
+}
@@ -0,0 +1,324 @@
+use std::{collections::VecDeque, io::Read};
+
+use crate::{error::GeneralError, header::WarcHeader, io::LogicalPosition};
+
+use super::{state_error, DecStateBlock, DecStateHeader, Decoder, DecoderConfig};
+
+/// Name and position of a file read by a [`MultiDecoder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileBoundary {
+    /// Name given when the input was added.
+    pub name: String,
+    /// Position of the start of the file in the combined input.
+    pub position: u64,
+}
+
+enum MultiDecoderState<R: Read> {
+    None,
+    Header(Decoder<DecStateHeader, R>),
+    Block(Decoder<DecStateBlock, R>),
+}
+
+/// WARC format reader that reads several files as one stream.
+///
+/// Each input is decoded with its own configuration, so files with
+/// different compression formats can be combined. Positions of records are
+/// relative to the start of their file. The position of each file in the
+/// combined input is recorded so that a position can be attributed to a
+/// file with [`find_file()`](Self::find_file).
+///
+/// For each record, call [`read_header()`](Self::read_header), read the
+/// block data using the [`Read`] trait, and call
+/// [`finish_block()`](Self::finish_block). No more records are read after
+/// an error.
+pub struct MultiDecoder<R: Read> {
+    inputs: VecDeque<(String, R, DecoderConfig)>,
+    files: Vec<FileBoundary>,
+    position: u64,
+    state: MultiDecoderState<R>,
+}
+
+impl<R: Read> MultiDecoder<R> {
+    /// Creates a reader without inputs.
+    pub fn new() -> Self {
+        Self {
+            inputs: VecDeque::new(),
+            files: Vec::new(),
+            position: 0,
+            state: MultiDecoderState::None,
+        }
+    }
+
+    /// Adds an input to be read after the previous inputs.
+    pub fn with_input<N: Into<String>>(mut self, name: N, input: R, config: DecoderConfig) -> Self {
+        self.push_input(name, input, config);
+        self
+    }
+
+    /// Adds an input to be read after the previous inputs.
+    pub fn push_input<N: Into<String>>(&mut self, name: N, input: R, config: DecoderConfig) {
+        self.inputs.push_back((name.into(), input, config));
+    }
+
+    /// Returns the files that have been started so far.
+    pub fn files(&self) -> &[FileBoundary] {
+        &self.files
+    }
+
+    /// Returns the index of the current file.
+    pub fn file_index(&self) -> Option<usize> {
+        self.files.len().checked_sub(1)
+    }
+
+    /// Returns the name of the current file.
+    pub fn file_name(&self) -> Option<&str> {
+        self.files.last().map(|file| file.name.as_str())
+    }
+
+    /// Returns the file containing the position in the combined input.
+    pub fn find_file(&self, position: u64) -> Option<&FileBoundary> {
+        let index = self
+            .files
+            .partition_point(|file| file.position <= position)
+            .checked_sub(1)?;
+
+        Some(&self.files[index])
+    }
+
+    /// Returns the position of the beginning of the current WARC record in
+    /// the current file.
+    ///
+    /// See [`Decoder::record_boundary_position()`].
+    pub fn record_boundary_position(&self) -> u64 {
+        match &self.state {
+            MultiDecoderState::None => 0,
+            MultiDecoderState::Header(decoder) => decoder.record_boundary_position(),
+            MultiDecoderState::Block(decoder) => decoder.record_boundary_position(),
+        }
+    }
+
+    /// Returns whether there is another WARC record to be read.
+    ///
+    /// Inputs are started as needed.
+    pub fn has_next_record(&mut self) -> Result<bool, GeneralError> {
+        self.has_next_record_impl().inspect_err(|_| self.stop())
+    }
+
+    fn has_next_record_impl(&mut self) -> Result<bool, GeneralError> {
+        loop {
+            match &mut self.state {
+                MultiDecoderState::Header(decoder) => {
+                    if decoder.has_next_record()? {
+                        return Ok(true);
+                    }
+
+                    self.position += decoder.logical_position();
+                    self.state = MultiDecoderState::None;
+                }
+                MultiDecoderState::Block(_) => return Err(state_error("block not finished").into()),
+                MultiDecoderState::None => {
+                    let Some((name, input, config)) = self.inputs.pop_front() else {
+                        return Ok(false);
+                    };
+
+                    tracing::debug!(name, position = self.position, "start file");
+
+                    self.files.push(FileBoundary {
+                        name,
+                        position: self.position,
+                    });
+                    self.state = MultiDecoderState::Header(Decoder::new(input, config)?);
+                }
+            }
+        }
+    }
+
+    /// Reads the header portion of the next WARC record.
+    pub fn read_header(&mut self) -> Result<WarcHeader, GeneralError> {
+        if !self.has_next_record()? {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let MultiDecoderState::Header(decoder) =
+            std::mem::replace(&mut self.state, MultiDecoderState::None)
+        else {
+            unreachable!()
+        };
+
+        let (header, decoder) = decoder.read_header().inspect_err(|_| self.stop())?;
+        self.state = MultiDecoderState::Block(decoder);
+
+        Ok(header)
+    }
+
+    /// Indicates that reading the block portion of the WARC record has
+    /// completed.
+    ///
+    /// See [`Decoder::finish_block()`].
+    pub fn finish_block(&mut self) -> Result<(), GeneralError> {
+        let MultiDecoderState::Block(_) = &self.state else {
+            self.stop();
+            return Err(state_error("not expecting the end of a block").into());
+        };
+
+        let MultiDecoderState::Block(decoder) =
+            std::mem::replace(&mut self.state, MultiDecoderState::None)
+        else {
+            unreachable!()
+        };
+
+        let decoder = decoder.finish_block().inspect_err(|_| self.stop())?;
+        self.state = MultiDecoderState::Header(decoder);
+
+        Ok(())
+    }
+
+    /// Stops reading after an error.
+    fn stop(&mut self) {
+        self.inputs.clear();
+        self.state = MultiDecoderState::None;
+    }
+}
+
+impl<R: Read> Default for MultiDecoder<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Read> Read for MultiDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match &mut self.state {
+            MultiDecoderState::Block(decoder) => decoder.read(buf),
+            _ => Err(state_error("not expecting block data")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::{
+        compress::{Compressor, DecompressorConfig, Format},
+        header::fields::FieldsExt,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_multi_decoder() {
+        let record = |id: &str| {
+            format!(
+                "WARC/1.1\r\nWARC-Record-ID: <urn:uuid:{}>\r\nContent-Length: 3\r\n\r\nabc\r\n\r\n",
+                id
+            )
+            .into_bytes()
+        };
+
+        let file1 = [record("1"), record("2")].concat();
+        let mut compressor = Compressor::new(Vec::new(), Format::Gzip);
+        compressor.write_all(&record("3")).unwrap();
+        let file2 = compressor.finish().unwrap();
+
+        let gzip_config = DecoderConfig {
+            decompressor: DecompressorConfig {
+                format: Format::Gzip,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut decoder = MultiDecoder::new()
+            .with_input("a.warc", file1.as_slice(), DecoderConfig::default())
+            .with_input("empty.warc", [].as_slice(), DecoderConfig::default())
+            .with_input("b.warc.gz", file2.as_slice(), gzip_config);
+        let mut records = Vec::new();
+
+        while decoder.has_next_record().unwrap() {
+            let header = decoder.read_header().unwrap();
+            let mut block = Vec::new();
+            decoder.read_to_end(&mut block).unwrap();
+            decoder.finish_block().unwrap();
+
+            assert_eq!(block, b"abc");
+            records.push((
+                header.fields.get_or_default("WARC-Record-ID").to_string(),
+                decoder.file_name().unwrap().to_string(),
+            ));
+        }
+
+        assert_eq!(
+            records,
+            [
+                ("<urn:uuid:1>".to_string(), "a.warc".to_string()),
+                ("<urn:uuid:2>".to_string(), "a.warc".to_string()),
+                ("<urn:uuid:3>".to_string(), "b.warc.gz".to_string()),
+            ]
+        );
+
+        let file1_len = file1.len() as u64;
+        assert_eq!(
+            decoder.files(),
+            [
+                FileBoundary {
+                    name: "a.warc".to_string(),
+                    position: 0
+                },
+                FileBoundary {
+                    name: "empty.warc".to_string(),
+                    position: file1_len
+                },
+                FileBoundary {
+                    name: "b.warc.gz".to_string(),
+                    position: file1_len
+                },
+            ]
+        );
+        assert_eq!(decoder.find_file(10).unwrap().name, "a.warc");
+        assert_eq!(decoder.find_file(file1_len).unwrap().name, "b.warc.gz");
+        assert_eq!(decoder.file_index(), Some(2));
+    }
+
+    #[test]
+    fn test_multi_decoder_truncated() {
+        let mut decoder = MultiDecoder::new()
+            .with_input(
+                "a.warc",
+                b"WARC/1.1\r\nContent-Length: 3\r\n\r\nabc\r\n\r\nWARC/1.1\r\n".as_slice(),
+                DecoderConfig::default(),
+            )
+            .with_input("b.warc", [].as_slice(), DecoderConfig::default());
+
+        assert!(decoder.has_next_record().unwrap());
+        decoder.read_header().unwrap();
+        decoder.finish_block().unwrap();
+        assert!(decoder.has_next_record().unwrap());
+        assert!(decoder.read_header().is_err());
+        assert!(!decoder.has_next_record().unwrap());
+        assert_eq!(decoder.file_name(), Some("a.warc"));
+    }
+
+    #[test]
+    fn test_multi_decoder_error_stops() {
+        let gzip_config = DecoderConfig {
+            decompressor: DecompressorConfig {
+                format: Format::Gzip,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut decoder = MultiDecoder::new()
+            .with_input("a.warc.gz", b"not gzip data".as_slice(), gzip_config)
+            .with_input(
+                "b.warc",
+                b"WARC/1.1\r\nContent-Length: 3\r\n\r\nabc\r\n\r\n".as_slice(),
+                DecoderConfig::default(),
+            );
+
+        assert!(decoder.has_next_record().is_err());
+        assert!(!decoder.has_next_record().unwrap());
+        assert!(decoder.read_header().is_err());
+        assert_eq!(decoder.file_name(), Some("a.warc.gz"));
+    }
+}
@@ -0,0 +1,367 @@
+use std::{collections::VecDeque, io::Read, ops::Range};
+
+use crate::{compress::ParallelGzipDecoder, error::GeneralError};
+
+use super::{DecoderConfig, EndState, Record, SliceDecoder};
+
+/// WARC format reader of gzip files that decompresses members in parallel.
+///
+/// The members are decompressed on a pool of threads by a
+/// [`ParallelGzipDecoder`] and the records are returned in order with their
+/// blocks read into memory. Members are only independent with
+/// record-at-time compression, so this is intended for such files. Records
+/// spanning several members are also read, but each member is held in
+/// memory.
+///
+/// Records are parsed as with [`SliceDecoder`]. The lenient and recovery
+/// modes are not supported.
+#[derive(Debug)]
+pub struct ParallelDecoder<R: Read> {
+    decompressor: ParallelGzipDecoder<R>,
+    config: DecoderConfig,
+    buf: Vec<u8>,
+    /// Index in the buffer of the next record.
+    buf_index: usize,
+    /// Index in the buffer and position in the file of each segment in the
+    /// buffer.
+    segments: VecDeque<(usize, u64)>,
+    /// Range in the buffer of the header of the record that was read last.
+    raw_header_range: Range<usize>,
+    /// Position in the file after the last segment.
+    end_position: u64,
+    record_boundary_position: u64,
+    end_state: EndState,
+    has_rat_comp_fault: bool,
+}
+
+impl<R: Read> ParallelDecoder<R> {
+    /// Creates a reader with the given number of worker threads.
+    ///
+    /// The input must be compressed with gzip. The format of the
+    /// decompressor configuration is not used.
+    pub fn new(input: R, config: DecoderConfig, threads: usize) -> Self {
+        Self {
+            decompressor: ParallelGzipDecoder::new(input, config.decompressor.clone(), threads),
+            config,
+            buf: Vec::new(),
+            buf_index: 0,
+            segments: VecDeque::new(),
+            raw_header_range: 0..0,
+            end_position: 0,
+            record_boundary_position: 0,
+            end_state: EndState::NotReached,
+            has_rat_comp_fault: false,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        self.decompressor.get_ref()
+    }
+
+    /// Returns the underlying reader.
+    ///
+    /// Seeking the reader is only valid before the first record is read.
+    /// Positions are relative to the position of the reader at that time.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.decompressor.get_mut()
+    }
+
+    /// Returns the position of the beginning of the WARC record that was
+    /// read last.
+    ///
+    /// For records that do not start a member, this is the position of the
+    /// member containing the start of the record.
+    pub fn record_boundary_position(&self) -> u64 {
+        self.record_boundary_position
+    }
+
+    /// Returns the position of the next record boundary.
+    ///
+    /// See [`record_boundary_position()`](Self::record_boundary_position).
+    pub fn position(&self) -> u64 {
+        if self.buf_index == self.buf.len() {
+            self.end_position
+        } else {
+            self.segment_position(self.buf_index)
+        }
+    }
+
+    /// Returns whether the end of the input was reached and whether it
+    /// ended at a record boundary.
+    pub fn end_state(&self) -> EndState {
+        self.end_state
+    }
+
+    /// Returns whether a record was found that was not compressed in its
+    /// own member.
+    ///
+    /// A false value is not guaranteed to be false unless the entire file has
+    /// been read.
+    pub fn has_record_at_time_compression_fault(&self) -> bool {
+        self.has_rat_comp_fault
+    }
+
+    /// Returns the header of the record that was read last as it appears
+    /// in the file.
+    ///
+    /// Returns `None` unless [`DecoderConfig::keep_raw_header`] is enabled.
+    pub fn raw_header(&self) -> Option<&[u8]> {
+        self.config
+            .keep_raw_header
+            .then(|| &self.buf[self.raw_header_range.clone()])
+    }
+
+    /// Reads the next WARC record.
+    ///
+    /// Returns `None` at the end of the input.
+    pub fn read_record(&mut self) -> Result<Option<Record>, GeneralError> {
+        self.raw_header_range = 0..0;
+
+        loop {
+            if self.buf_index == self.buf.len() {
+                self.buf.clear();
+                self.buf_index = 0;
+                self.segments.clear();
+
+                if !self.push_segment()? {
+                    self.end_state = EndState::Clean;
+                    return Ok(None);
+                }
+
+                continue;
+            }
+
+            let mut decoder = SliceDecoder::new(&self.buf[self.buf_index..], self.config.clone());
+
+            match decoder.read_record() {
+                Ok((header, block)) => {
+                    let record = Record::new(header, block.to_vec());
+                    let start = self.buf_index;
+                    let end = start + decoder.position() as usize;
+
+                    self.raw_header_range = start..end - block.len() - 4;
+
+                    self.record_boundary_position = self.segment_position(start);
+                    self.buf_index = end;
+
+                    if !self.is_segment_aligned(start, end) {
+                        tracing::debug!(
+                            position = self.record_boundary_position,
+                            "record not compressed in its own member"
+                        );
+                        self.has_rat_comp_fault = true;
+                    }
+
+                    return Ok(Some(record));
+                }
+                Err(error) if decoder.end_state() == EndState::Truncated => {
+                    self.discard_read_segments();
+
+                    if !self.push_segment()? {
+                        self.end_state = EndState::Truncated;
+                        return Err(error);
+                    }
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Appends the next decompressed segment to the buffer.
+    fn push_segment(&mut self) -> std::io::Result<bool> {
+        let segment = self.decompressor.next_segment().inspect_err(|error| {
+            if error.kind() == std::io::ErrorKind::UnexpectedEof {
+                self.end_state = EndState::Truncated;
+            }
+        })?;
+
+        let Some(segment) = segment else {
+            return Ok(false);
+        };
+
+        self.segments.push_back((self.buf.len(), segment.position));
+        self.end_position = segment.position + segment.compressed_len;
+        self.buf.extend_from_slice(&segment.data);
+
+        Ok(true)
+    }
+
+    /// Removes the data before the next record from the buffer.
+    fn discard_read_segments(&mut self) {
+        while self
+            .segments
+            .get(1)
+            .is_some_and(|(index, _)| *index <= self.buf_index)
+        {
+            self.segments.pop_front();
+        }
+
+        for (index, _) in &mut self.segments {
+            *index = index.saturating_sub(self.buf_index);
+        }
+
+        self.buf.drain(0..self.buf_index);
+        self.buf_index = 0;
+    }
+
+    /// Returns the position of the segment containing the index.
+    fn segment_position(&self, index: usize) -> u64 {
+        self.segments
+            .iter()
+            .rev()
+            .find(|(segment_index, _)| *segment_index <= index)
+            .map_or(self.end_position, |(_, position)| *position)
+    }
+
+    /// Returns whether the range of the buffer is exactly one segment.
+    fn is_segment_aligned(&self, start: usize, end: usize) -> bool {
+        let is_start = self.segments.iter().any(|(index, _)| *index == start);
+        let is_end = end == self.buf.len() || self.segments.iter().any(|(index, _)| *index == end);
+        let is_split = self
+            .segments
+            .iter()
+            .any(|(index, _)| start < *index && *index < end);
+
+        is_start && is_end && !is_split
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::{
+        compress::{Compressor, CompressorConfig, DecompressorConfig, Format},
+        header::{fields::FieldsExt, WarcHeader},
+        warc::{Decoder, Encoder, EncoderConfig},
+    };
+
+    use super::*;
+
+    fn gzip_config() -> DecoderConfig {
+        DecoderConfig {
+            decompressor: DecompressorConfig {
+                format: Format::Gzip,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parallel_decoder() {
+        let mut encoder = Encoder::new(
+            Vec::new(),
+            EncoderConfig {
+                compressor: CompressorConfig {
+                    format: Format::Gzip,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        for index in 0..100 {
+            let block = format!("record {}", index).repeat(index * 10);
+            let mut header = WarcHeader::new(block.len() as u64, "resource");
+            header.fields.insert(
+                "WARC-Record-ID".to_string(),
+                format!("<urn:uuid:{}>", index),
+            );
+
+            let mut block_encoder = encoder.write_header(&header).unwrap();
+            block_encoder.write_all(block.as_bytes()).unwrap();
+            encoder = block_encoder.finish_block().unwrap();
+        }
+
+        let buf = encoder.finish().unwrap();
+
+        let mut positions = Vec::new();
+        let mut reader = Decoder::new(buf.as_slice(), gzip_config()).unwrap();
+
+        while reader.has_next_record().unwrap() {
+            let (_header, mut block_reader) = reader.read_header().unwrap();
+            positions.push(block_reader.record_boundary_position());
+            block_reader.read_to_end(&mut Vec::new()).unwrap();
+            reader = block_reader.finish_block().unwrap();
+        }
+
+        let mut reader = ParallelDecoder::new(buf.as_slice(), gzip_config(), 4);
+
+        for (index, position) in positions.iter().enumerate() {
+            let record = reader.read_record().unwrap().unwrap();
+
+            assert_eq!(
+                record.header().fields.get_or_default("WARC-Record-ID"),
+                format!("<urn:uuid:{}>", index)
+            );
+            assert_eq!(
+                record.body(),
+                format!("record {}", index).repeat(index * 10).as_bytes()
+            );
+            assert_eq!(reader.record_boundary_position(), *position);
+        }
+
+        assert!(reader.read_record().unwrap().is_none());
+        assert_eq!(reader.position(), buf.len() as u64);
+        assert_eq!(reader.end_state(), EndState::Clean);
+        assert!(!reader.has_record_at_time_compression_fault());
+    }
+
+    #[test]
+    fn test_parallel_decoder_not_record_at_time() {
+        let record = |id: &str| {
+            format!(
+                "WARC/1.1\r\nWARC-Record-ID: <urn:uuid:{}>\r\nContent-Length: 3\r\n\r\nabc\r\n\r\n",
+                id
+            )
+        };
+        let data = [record("1"), record("2"), record("3")].concat();
+
+        // The second member contains the end of the first record and the
+        // second record.
+        let mut compressor = Compressor::new(Vec::new(), Format::Gzip);
+        compressor.write_all(&data.as_bytes()[..20]).unwrap();
+        compressor.start_new_segment().unwrap();
+        let second_position = compressor.get_ref().len() as u64;
+        compressor
+            .write_all(&data.as_bytes()[20..record("1").len() * 2])
+            .unwrap();
+        compressor.start_new_segment().unwrap();
+        let third_position = compressor.get_ref().len() as u64;
+        compressor
+            .write_all(&data.as_bytes()[record("1").len() * 2..])
+            .unwrap();
+        let buf = compressor.finish().unwrap();
+
+        let config = DecoderConfig {
+            keep_raw_header: true,
+            ..gzip_config()
+        };
+        let mut reader = ParallelDecoder::new(buf.as_slice(), config, 2);
+        let mut positions = Vec::new();
+        let mut raw_headers = Vec::new();
+
+        while let Some(record) = reader.read_record().unwrap() {
+            assert_eq!(record.body(), b"abc");
+            positions.push(reader.record_boundary_position());
+            raw_headers.push(reader.raw_header().unwrap().to_vec());
+        }
+
+        assert_eq!(reader.raw_header(), Some(b"".as_slice()));
+
+        assert_eq!(positions, [0, second_position, third_position]);
+        assert_eq!(
+            raw_headers,
+            ["1", "2", "3"].map(|id| record(id).replace("abc\r\n\r\n", "").into_bytes())
+        );
+        assert!(reader.has_record_at_time_compression_fault());
+
+        let mut reader = ParallelDecoder::new(&buf[..buf.len() - 1], gzip_config(), 2);
+
+        assert!(reader.read_record().is_ok());
+        assert!(reader.read_record().is_ok());
+        assert!(reader.read_record().is_err());
+        assert_eq!(reader.end_state(), EndState::Truncated);
+    }
+}
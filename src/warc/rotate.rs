@@ -0,0 +1,339 @@
+use std::{
+    borrow::Cow,
+    fs::{File, OpenOptions},
+    io::{Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    compress::Format,
+    error::GeneralError,
+    header::{record_id::RecordId, WarcHeader},
+};
+
+use super::{state_error, EncStateBlock, EncStateHeader, Encoder, EncoderConfig};
+
+enum RotatingEncoderState {
+    Closed,
+    Header(Encoder<EncStateHeader, File>),
+    Block(Encoder<EncStateBlock, File>),
+}
+
+/// WARC format writer that splits the output into numbered files.
+///
+/// The files are named `PREFIX-00001.warc.gz`, `PREFIX-00002.warc.gz`, and
+/// so on, with the extension of the compression format. When a record ends
+/// and the size of the current file has reached the maximum, the file is
+/// closed and the next file is created for the next record. An optional
+/// warcinfo record is written at the start of each file.
+///
+/// For each record, call [`write_header()`](Self::write_header), write the
+/// block data using the [`Write`] trait, and call
+/// [`end_record()`](Self::end_record). Call [`finish()`](Self::finish)
+/// after the last record.
+pub struct RotatingEncoder {
+    prefix: PathBuf,
+    config: EncoderConfig,
+    max_output_size: u64,
+    warcinfo: Option<(WarcHeader, Vec<u8>)>,
+    warcinfo_id: Option<String>,
+    file_number: u64,
+    paths: Vec<PathBuf>,
+    state: RotatingEncoderState,
+}
+
+impl RotatingEncoder {
+    /// Creates a writer of files starting with the given path prefix.
+    ///
+    /// No file is created until the first record is written.
+    pub fn new<P: Into<PathBuf>>(prefix: P, config: EncoderConfig, max_output_size: u64) -> Self {
+        Self {
+            prefix: prefix.into(),
+            config,
+            max_output_size,
+            warcinfo: None,
+            warcinfo_id: None,
+            file_number: 0,
+            paths: Vec::new(),
+            state: RotatingEncoderState::Closed,
+        }
+    }
+
+    /// Writes a warcinfo record with the given header and block at the
+    /// start of each file.
+    ///
+    /// For each file, the WARC-Record-ID is replaced with a new ID and the
+    /// WARC-Filename field is set to the name of the file.
+    pub fn with_warcinfo(mut self, header: WarcHeader, block: Vec<u8>) -> Self {
+        self.warcinfo = Some((header, block));
+        self.warcinfo_id = Some(RecordId::new().to_string());
+        self
+    }
+
+    /// Returns the WARC-Record-ID of the warcinfo record of the file that
+    /// the next record is written to.
+    ///
+    /// The value can be used for the WARC-Warcinfo-ID field of the next
+    /// record.
+    pub fn warcinfo_id(&self) -> Option<&str> {
+        self.warcinfo_id.as_deref()
+    }
+
+    /// Returns the path of the current file.
+    pub fn path(&self) -> Option<&Path> {
+        match self.state {
+            RotatingEncoderState::Closed => None,
+            _ => self.paths.last().map(PathBuf::as_path),
+        }
+    }
+
+    /// Returns the paths of the files created so far.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Starts a new record with the given header.
+    ///
+    /// A new file is created if there is no open file.
+    pub fn write_header(&mut self, header: &WarcHeader) -> Result<(), GeneralError> {
+        match self.state {
+            RotatingEncoderState::Closed => self.open_next_file()?,
+            RotatingEncoderState::Header(_) => {}
+            RotatingEncoderState::Block(_) => {
+                return Err(state_error("not expecting a header").into())
+            }
+        }
+
+        let RotatingEncoderState::Header(encoder) = &mut self.state else {
+            unreachable!()
+        };
+
+        // Check the header first so the file is kept open on error.
+        encoder.check_header(header)?;
+
+        let RotatingEncoderState::Header(encoder) =
+            std::mem::replace(&mut self.state, RotatingEncoderState::Closed)
+        else {
+            unreachable!()
+        };
+
+        self.state = RotatingEncoderState::Block(encoder.write_checked_header(header)?);
+
+        Ok(())
+    }
+
+    /// Indicates the block data of the current record has been written.
+    ///
+    /// The file is closed if it has reached the maximum size.
+    pub fn end_record(&mut self) -> std::io::Result<()> {
+        let RotatingEncoderState::Block(_) = &self.state else {
+            return Err(state_error("not expecting the end of a record"));
+        };
+
+        let RotatingEncoderState::Block(encoder) =
+            std::mem::replace(&mut self.state, RotatingEncoderState::Closed)
+        else {
+            unreachable!()
+        };
+
+        let mut encoder = encoder.finish_block()?;
+
+        if encoder.get_mut().stream_position()? >= self.max_output_size {
+            self.close_file(encoder)?;
+        } else {
+            self.state = RotatingEncoderState::Header(encoder);
+        }
+
+        Ok(())
+    }
+
+    /// Closes the current file.
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        if let RotatingEncoderState::Block(_) = &self.state {
+            return Err(state_error("not expecting the end of a file"));
+        }
+
+        match std::mem::replace(&mut self.state, RotatingEncoderState::Closed) {
+            RotatingEncoderState::Header(encoder) => self.close_file(encoder),
+            _ => Ok(()),
+        }
+    }
+
+    fn open_next_file(&mut self) -> Result<(), GeneralError> {
+        self.file_number += 1;
+
+        let mut filename = self
+            .prefix
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        filename.push_str(&format!(
+            "-{:05}{}",
+            self.file_number,
+            file_extension(self.config.compressor.format)
+        ));
+
+        let path = self.prefix.with_file_name(&filename);
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        tracing::debug!(?path, "opened rotated file");
+
+        self.paths.push(path);
+
+        let mut encoder = Encoder::new(file, self.config.clone());
+
+        if let Some((header, block)) = &self.warcinfo {
+            let mut header = header.clone();
+            let record_id = self.warcinfo_id.take().unwrap();
+            header
+                .fields
+                .insert("WARC-Record-ID".to_string(), record_id);
+            header.fields.insert("WARC-Filename".to_string(), filename);
+            header.set_content_length(block.len() as u64);
+
+            let mut block_encoder = encoder.write_header(&header)?;
+            block_encoder.write_all(block)?;
+            encoder = block_encoder.finish_block()?;
+        }
+
+        self.state = RotatingEncoderState::Header(encoder);
+
+        Ok(())
+    }
+
+    fn close_file(&mut self, encoder: Encoder<EncStateHeader, File>) -> std::io::Result<()> {
+        let mut file = encoder.finish()?;
+        file.flush()?;
+
+        if self.warcinfo.is_some() {
+            self.warcinfo_id = Some(RecordId::new().to_string());
+        }
+
+        tracing::debug!(path = ?self.paths.last(), "closed rotated file");
+
+        Ok(())
+    }
+}
+
+impl Write for RotatingEncoder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.state {
+            RotatingEncoderState::Block(encoder) => encoder.write(buf),
+            _ => Err(state_error("not expecting block data")),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.state {
+            RotatingEncoderState::Block(encoder) => encoder.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the filename extension of a WARC file with the compression
+/// format.
+fn file_extension(format: Format) -> Cow<'static, str> {
+    match format {
+        Format::Identity => ".warc".into(),
+        Format::Deflate => ".warc.zz".into(),
+        Format::Gzip => ".warc.gz".into(),
+        Format::Brotli => ".warc.br".into(),
+        #[cfg(feature = "zstd")]
+        Format::Zstandard => ".warc.zst".into(),
+        #[cfg(feature = "codec-registry")]
+        Format::Custom(id) => format!(".warc{}", id.codec().file_extension()).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use crate::{
+        compress::{CompressorConfig, DecompressorConfig},
+        header::fields::FieldsExt,
+        warc::{Decoder, DecoderConfig},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_rotating_encoder() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = EncoderConfig {
+            compressor: CompressorConfig {
+                format: Format::Gzip,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut encoder = RotatingEncoder::new(dir.path().join("crawl"), config, 300)
+            .with_warcinfo(WarcHeader::new(0, "warcinfo"), b"software: test".to_vec());
+
+        for index in 0..5 {
+            let mut header = WarcHeader::new(100, "resource");
+            header.fields.insert(
+                "WARC-Warcinfo-ID".to_string(),
+                encoder.warcinfo_id().unwrap().to_string(),
+            );
+            encoder.write_header(&header).unwrap();
+            encoder.write_all(&[index; 100]).unwrap();
+            encoder.end_record().unwrap();
+        }
+
+        encoder.finish().unwrap();
+
+        let paths = encoder.paths().to_vec();
+        assert!(paths.len() > 1);
+        assert_eq!(paths[0], dir.path().join("crawl-00001.warc.gz"));
+
+        let mut record_count = 0;
+        let mut warcinfo_ids = Vec::new();
+
+        for path in &paths {
+            let config = DecoderConfig {
+                decompressor: DecompressorConfig {
+                    format: Format::Gzip,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let mut reader = Decoder::new(File::open(path).unwrap(), config).unwrap();
+            let mut warcinfo_id = None;
+
+            while reader.has_next_record().unwrap() {
+                let (header, mut block_reader) = reader.read_header().unwrap();
+                block_reader.read_to_end(&mut Vec::new()).unwrap();
+                reader = block_reader.finish_block().unwrap();
+
+                let record_id = header.fields.get_or_default("WARC-Record-ID");
+
+                match warcinfo_id {
+                    None => {
+                        assert_eq!(header.fields.get_or_default("WARC-Type"), "warcinfo");
+                        assert_eq!(
+                            header.fields.get_or_default("WARC-Filename"),
+                            path.file_name().unwrap().to_str().unwrap()
+                        );
+                        warcinfo_id = Some(record_id.to_string());
+                    }
+                    Some(ref id) => {
+                        assert_eq!(header.fields.get_or_default("WARC-Warcinfo-ID"), id);
+                        record_count += 1;
+                    }
+                }
+            }
+
+            warcinfo_ids.extend(warcinfo_id);
+        }
+
+        assert_eq!(record_count, 5);
+        warcinfo_ids.dedup();
+        assert_eq!(warcinfo_ids.len(), paths.len());
+    }
+}
@@ -0,0 +1,201 @@
+use std::ops::Range;
+
+use crate::{
+    error::{GeneralError, ProtocolError, ProtocolErrorKind},
+    header::WarcHeader,
+};
+
+use super::{DecoderConfig, EndState};
+
+/// WARC format reader of uncompressed data in memory.
+///
+/// Unlike [`Decoder`](super::Decoder), the data is not copied into buffers:
+/// headers are parsed directly from the input and blocks are returned as
+/// slices of the input. This is intended for files mapped into memory, such
+/// as with [`MappedFile`](crate::io::MappedFile).
+///
+/// The decompressor configuration is not used.
+#[derive(Debug)]
+pub struct SliceDecoder<'a> {
+    input: &'a [u8],
+    position: usize,
+    /// Range in the input of the header of the record that was read last.
+    raw_header_range: Range<usize>,
+    config: DecoderConfig,
+    end_state: EndState,
+}
+
+impl<'a> SliceDecoder<'a> {
+    /// Creates a reader of the records in the data.
+    pub fn new(input: &'a [u8], config: DecoderConfig) -> Self {
+        Self {
+            input,
+            position: 0,
+            raw_header_range: 0..0,
+            config,
+            end_state: EndState::NotReached,
+        }
+    }
+
+    /// Returns the position of the next record boundary.
+    pub fn position(&self) -> u64 {
+        self.position as u64
+    }
+
+    /// Sets the position of the next record.
+    ///
+    /// The position must be a record boundary.
+    pub fn set_position(&mut self, position: u64) {
+        self.position = usize::try_from(position)
+            .unwrap_or(usize::MAX)
+            .min(self.input.len());
+        self.end_state = EndState::NotReached;
+    }
+
+    /// Returns whether the input ended at a record boundary.
+    pub fn end_state(&self) -> EndState {
+        if self.end_state == EndState::NotReached && self.position == self.input.len() {
+            EndState::Clean
+        } else {
+            self.end_state
+        }
+    }
+
+    /// Returns whether there is another WARC record to be read.
+    pub fn has_next_record(&self) -> bool {
+        self.position < self.input.len()
+    }
+
+    /// Returns the header of the record that was read last as it appears
+    /// in the input.
+    ///
+    /// Returns `None` unless [`DecoderConfig::keep_raw_header`] is enabled.
+    pub fn raw_header(&self) -> Option<&'a [u8]> {
+        self.config
+            .keep_raw_header
+            .then(|| &self.input[self.raw_header_range.clone()])
+    }
+
+    /// Reads a WARC record and returns the header and block.
+    pub fn read_record(&mut self) -> Result<(WarcHeader, &'a [u8]), GeneralError> {
+        let input = &self.input[self.position..];
+        let max_header_length = self.config.max_header_length;
+        let search_len = input.len().min(max_header_length);
+
+        let Some(header_len) = crate::parse::scan_header_deliminator(&input[0..search_len]) else {
+            return if search_len == max_header_length {
+                Err(ProtocolError::new(ProtocolErrorKind::HeaderTooBig).into())
+            } else {
+                Err(self.truncated())
+            };
+        };
+
+        let mut header = {
+            let _span = tracing::trace_span!("parse_header", header_len).entered();
+            WarcHeader::parse_with_line_folding(&input[0..header_len], self.config.line_folding)?
+        };
+        header.apply_duplicate_field_policy(self.config.duplicate_fields)?;
+
+        let block_len = header.content_length()?;
+        self.config.check_content_length(block_len)?;
+        let block_len = usize::try_from(block_len).unwrap_or(usize::MAX);
+        let block_end = header_len.saturating_add(block_len);
+        let record_len = block_end.saturating_add(4);
+
+        if record_len > input.len() {
+            return Err(self.truncated());
+        }
+
+        if &input[block_end..record_len] != b"\r\n\r\n" {
+            return Err(ProtocolError::new(ProtocolErrorKind::InvalidRecordBoundary).into());
+        }
+
+        self.raw_header_range = self.position..self.position + header_len;
+        self.position += record_len;
+
+        Ok((header, &input[header_len..block_end]))
+    }
+
+    fn truncated(&mut self) -> GeneralError {
+        self.end_state = EndState::Truncated;
+
+        std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_decoder() {
+        let data = b"WARC/1.1\r\n\
+            WARC-Record-ID: <urn:example:1>\r\n\
+            Content-Length: 5\r\n\
+            \r\n\
+            hello\r\n\r\n\
+            WARC/1.1\r\n\
+            WARC-Record-ID: <urn:example:2>\r\n\
+            Content-Length: 0\r\n\
+            \r\n\
+            \r\n\r\n";
+        let mut decoder = SliceDecoder::new(data, DecoderConfig::default());
+
+        assert!(decoder.has_next_record());
+        let (header, block) = decoder.read_record().unwrap();
+        assert_eq!(
+            header.fields.get("WARC-Record-ID").unwrap(),
+            "<urn:example:1>"
+        );
+        assert_eq!(block, b"hello");
+        assert_eq!(decoder.end_state(), EndState::NotReached);
+
+        let position = decoder.position();
+        let (_header, block) = decoder.read_record().unwrap();
+        assert_eq!(block, b"");
+        assert!(!decoder.has_next_record());
+        assert_eq!(decoder.end_state(), EndState::Clean);
+
+        decoder.set_position(position);
+        let (header, _block) = decoder.read_record().unwrap();
+        assert_eq!(
+            header.fields.get("WARC-Record-ID").unwrap(),
+            "<urn:example:2>"
+        );
+
+        let config = DecoderConfig {
+            keep_raw_header: true,
+            ..Default::default()
+        };
+        let mut decoder = SliceDecoder::new(data, config);
+        decoder.read_record().unwrap();
+        assert_eq!(
+            decoder.raw_header().unwrap(),
+            b"WARC/1.1\r\nWARC-Record-ID: <urn:example:1>\r\nContent-Length: 5\r\n\r\n"
+        );
+
+        let mut decoder = SliceDecoder::new(&data[0..50], DecoderConfig::default());
+        assert!(decoder.read_record().is_err());
+        assert_eq!(decoder.end_state(), EndState::Truncated);
+
+        let config = DecoderConfig {
+            max_content_length: Some(4),
+            ..Default::default()
+        };
+        let mut decoder = SliceDecoder::new(data, config);
+        assert!(matches!(
+            decoder.read_record(),
+            Err(GeneralError::Protocol(error))
+                if matches!(error.kind(), ProtocolErrorKind::ContentTooBig)
+        ));
+
+        let mut data = data.to_vec();
+        data[69] = b'x';
+        let mut decoder = SliceDecoder::new(&data, DecoderConfig::default());
+        assert!(matches!(
+            decoder.read_record(),
+            Err(GeneralError::Protocol(error))
+                if matches!(error.kind(), ProtocolErrorKind::InvalidRecordBoundary)
+        ));
+    }
+}
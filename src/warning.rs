@@ -0,0 +1,142 @@
+//! Non-fatal diagnostics
+//!
+//! Warnings are observations that do not stop processing, such as a file
+//! that is not compressed per record. They are logged and also sent to a
+//! [`WarningSink`] that can be given to the decoder, extractor, and encoder
+//! through their configuration.
+use std::{
+    fmt::{Debug, Display},
+    sync::Arc,
+};
+
+/// A non-fatal observation about a file or record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// The file is compressed without a separate compression member or frame
+    /// for each record.
+    ///
+    /// The position is the start of the record that does not end at a
+    /// compression boundary.
+    NotRecordAtTimeCompression { position: u64 },
+    /// The WARC-Target-URI of a record could not be parsed as a URL.
+    InvalidTargetUri { record_id: String },
+    /// A record header is missing a field required by the specification.
+    MissingField { record_id: String, name: String },
+    /// A problem found in a record header by the verifier before it was
+    /// written.
+    HeaderProblem { record_id: String, problem: String },
+    /// The lines of a record header end with LF instead of CRLF.
+    BareLineFeed { position: u64 },
+    /// The data following the block of a record is not two CRLF.
+    ///
+    /// The length is the number of bytes between the block and the next
+    /// record.
+    InvalidRecordBoundary { position: u64, length: u64 },
+    /// Corrupt data was skipped to the next record.
+    ///
+    /// The position and length are of the skipped data in the file. For
+    /// compressed files, the range starts and ends at compression members
+    /// or frames.
+    SkippedData { position: u64, length: u64 },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotRecordAtTimeCompression { position } => write!(
+                f,
+                "file is not using Record-at-time compression (record at position {})",
+                position
+            ),
+            Self::InvalidTargetUri { record_id } => {
+                write!(f, "invalid WARC-Target-URI in record {}", record_id)
+            }
+            Self::MissingField { record_id, name } => {
+                write!(f, "missing field {} in record {}", name, record_id)
+            }
+            Self::HeaderProblem { record_id, problem } => {
+                write!(f, "header problem {} in record {}", problem, record_id)
+            }
+            Self::BareLineFeed { position } => write!(
+                f,
+                "header lines end with LF instead of CRLF (record at position {})",
+                position
+            ),
+            Self::InvalidRecordBoundary { position, length } => write!(
+                f,
+                "invalid record boundary of {} bytes (record at position {})",
+                length, position
+            ),
+            Self::SkippedData { position, length } => write!(
+                f,
+                "skipped {} bytes of corrupt data at position {}",
+                length, position
+            ),
+        }
+    }
+}
+
+type Callback = Arc<dyn Fn(&Warning) + Send + Sync>;
+
+/// Receiver of [`Warning`]s.
+///
+/// The default sink only logs the warnings.
+#[derive(Clone, Default)]
+pub struct WarningSink {
+    callback: Option<Callback>,
+}
+
+impl WarningSink {
+    /// Creates a sink that calls the function for each warning.
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&Warning) + Send + Sync + 'static,
+    {
+        Self {
+            callback: Some(Arc::new(callback)),
+        }
+    }
+
+    /// Logs the warning and passes it to the callback.
+    pub fn emit(&self, warning: Warning) {
+        tracing::warn!("{}", warning);
+
+        if let Some(callback) = &self.callback {
+            callback(&warning);
+        }
+    }
+}
+
+impl Debug for WarningSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WarningSink")
+            .field("has_callback", &self.callback.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn test_warning_sink() {
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let warnings = warnings.clone();
+            WarningSink::new(move |warning| warnings.lock().unwrap().push(warning.clone()))
+        };
+
+        sink.clone()
+            .emit(Warning::NotRecordAtTimeCompression { position: 10 });
+        WarningSink::default().emit(Warning::NotRecordAtTimeCompression { position: 20 });
+
+        assert_eq!(
+            *warnings.lock().unwrap(),
+            vec![Warning::NotRecordAtTimeCompression { position: 10 }]
+        );
+    }
+}
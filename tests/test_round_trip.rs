@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+mod warc_generator;
+
+fn write_temp_file(name: &str, data: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("warcat-test-{}-{}", std::process::id(), name));
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+#[test]
+fn test_round_trip_gzip() {
+    let path = write_temp_file("round-trip.warc.gz", &warc_generator::generate_warc_gzip());
+
+    warcat::testing::assert_round_trip(&path);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_round_trip_zst() {
+    let path = write_temp_file(
+        "round-trip.warc.zst",
+        &warc_generator::generate_warc_zst(false),
+    );
+
+    warcat::testing::assert_round_trip(&path);
+
+    std::fs::remove_file(path).unwrap();
+}